@@ -0,0 +1,847 @@
+//! Encrypted full-app backups.
+//!
+//! A backup is a single file: [`MAGIC`], a format byte, then a gzipped tar,
+//! encrypted as a [`crypto::EncryptWriter`] stream, holding
+//!
+//! - `manifest.json`: what the backup contains ([`BackupManifest`])
+//! - `data.json`: every row of the app tables, keyed by column name
+//! - `node/<datapath>/..`: optionally, each account's node data directory
+//!
+//! Rows are stored by column name rather than as a DB file so a backup taken
+//! at an older schema version restores into a newer one: columns the backup
+//! lacks get their defaults. Backups from a newer schema are refused.
+//!
+//! The archive is streamed to and from the file, so node data directories
+//! are never held in memory. Format 1 backups, one [`crypto::encrypt_bytes`]
+//! envelope, are still read.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zeroize::Zeroizing;
+
+use crate::crypto::{DecryptReader, EncryptWriter, KdfParams};
+use crate::{crypto, db, migrations};
+
+const MAGIC: &[u8; 6] = b"KSBAK\0";
+const FORMAT_VERSION: u8 = 2;
+/// The format that encrypted the whole archive as one envelope.
+const FORMAT_ENVELOPE: u8 = 1;
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// Backed-up tables, parents first.
+const TABLES: &[&str] = &[
+    "Accounts",
+    "AppSettings",
+    "ChannelOrders",
+    "DcaOrders",
+    "DcaExecutions",
+    "LimitOrders",
+    "NwcConnections",
+    "Trades",
+];
+/// Tables whose rows belong to an account through `account_id`.
+const ACCOUNT_TABLES: &[&str] = &[
+    "ChannelOrders",
+    "DcaOrders",
+    "DcaExecutions",
+    "LimitOrders",
+    "NwcConnections",
+    "Trades",
+];
+/// AppSettings key prefixes that are caches rather than state.
+const CACHE_SETTINGS: &[&str] = &["dca_price_history:"];
+
+type Row = serde_json::Map<String, Value>;
+type TarWriter = tar::Builder<GzEncoder<EncryptWriter<BufWriter<File>>>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format: u8,
+    pub created_at: i64,
+    pub app_version: String,
+    pub schema_version: u32,
+    pub accounts: Vec<String>,
+    /// Rows per table.
+    pub counts: BTreeMap<String, usize>,
+    /// Node data directories included, by account datapath.
+    pub node_dirs: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupData {
+    tables: BTreeMap<String, Vec<Row>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Add the backup's accounts that don't exist here, with their orders,
+    /// connections and settings. Existing accounts and settings are kept.
+    Merge,
+    /// Delete all app data and restore the backup as it was.
+    Replace,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub manifest: BackupManifest,
+    pub mode: ImportMode,
+    /// False for a dry run: nothing was written.
+    pub applied: bool,
+    /// Backup accounts that already exist here. Merging skips them.
+    pub conflicts: Vec<String>,
+    /// Rows written (or that would be written), per table.
+    pub imported: BTreeMap<String, usize>,
+    /// Node data directories restored (or that would be restored).
+    pub node_dirs: Vec<String>,
+    /// Node data directories left alone because the target already exists.
+    pub skipped_node_dirs: Vec<String>,
+}
+
+/// A decrypted backup. Node data stays in the file and is read again when
+/// restored.
+pub struct Archive {
+    pub manifest: BackupManifest,
+    data: BackupData,
+    path: PathBuf,
+    password: Zeroizing<String>,
+}
+
+fn latest_schema_version() -> u32 {
+    migrations::MIGRATIONS.last().map_or(0, |m| m.id)
+}
+
+/// A datapath is restored as a directory under the node data root, so it
+/// must be a plain relative path.
+fn is_safe_datapath(datapath: &str) -> bool {
+    !datapath.is_empty()
+        && Path::new(datapath)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn to_json(value: ValueRef) -> Result<Value, String> {
+    Ok(match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(_) => return Err("Unexpected BLOB column".to_string()),
+    })
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn dump_table(conn: &Connection, table: &str) -> Result<Vec<Row>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT * FROM \"{}\" ORDER BY rowid", table))
+        .map_err(|e| e.to_string())?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+    let mut out = Vec::new();
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let mut map = Row::new();
+        for (i, column) in columns.iter().enumerate() {
+            map.insert(
+                column.clone(),
+                to_json(row.get_ref(i).map_err(|e| e.to_string())?)?,
+            );
+        }
+        out.push(map);
+    }
+    Ok(out)
+}
+
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM pragma_table_info(?1)")
+        .map_err(|e| e.to_string())?;
+    let columns = stmt
+        .query_map([table], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<String>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(columns)
+}
+
+/// Insert the columns of `row` that `table` has here. Returns whether a row
+/// was written (`INSERT OR IGNORE` skips unique conflicts).
+fn insert_row(
+    conn: &Connection,
+    table: &str,
+    columns: &[String],
+    row: &Row,
+) -> Result<bool, String> {
+    let names: Vec<&String> = row.keys().filter(|k| columns.contains(k)).collect();
+    let sql = format!(
+        "INSERT OR IGNORE INTO \"{}\" ({}) VALUES ({})",
+        table,
+        names
+            .iter()
+            .map(|n| format!("\"{}\"", n))
+            .collect::<Vec<_>>()
+            .join(", "),
+        (1..=names.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    conn.execute(
+        &sql,
+        rusqlite::params_from_iter(names.iter().map(|n| to_sql(&row[n.as_str()]))),
+    )
+    .map(|n| n > 0)
+    .map_err(|e| format!("Failed to restore {} row: {}", table, e))
+}
+
+fn account_datapaths(rows: &[Row]) -> Vec<(String, String)> {
+    rows.iter()
+        .filter_map(|row| {
+            let name = row.get("name")?.as_str()?;
+            let datapath = row.get("datapath")?.as_str()?;
+            Some((name.to_string(), datapath.to_string()))
+        })
+        .collect()
+}
+
+fn append_json<T: Serialize, W: Write>(
+    tar: &mut tar::Builder<W>,
+    name: &str,
+    value: &T,
+    mtime: u64,
+) -> Result<(), String> {
+    let bytes = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(mtime);
+    header.set_cksum();
+    tar.append_data(&mut header, name, bytes.as_slice())
+        .map_err(|e| format!("Failed to write {}: {}", name, e))
+}
+
+/// Stream the archive `build` writes, encrypted, to `target`. It is written
+/// aside and renamed, so a failed export never leaves a truncated file under
+/// the chosen name.
+fn write_archive(
+    target: &Path,
+    password: &str,
+    build: impl FnOnce(&mut TarWriter) -> Result<(), String>,
+) -> Result<(), String> {
+    let partial = PathBuf::from(format!("{}.partial", target.display()));
+    let result = (|| {
+        let mut file = BufWriter::new(
+            File::create(&partial).map_err(|e| format!("Failed to write backup: {}", e))?,
+        );
+        file.write_all(MAGIC)
+            .and_then(|_| file.write_all(&[FORMAT_VERSION]))
+            .map_err(|e| format!("Failed to write backup: {}", e))?;
+        let encrypted =
+            EncryptWriter::new(file, password, KdfParams::current()).map_err(|e| e.to_string())?;
+        let mut tar = tar::Builder::new(GzEncoder::new(encrypted, Compression::default()));
+        build(&mut tar)?;
+        tar.into_inner()
+            .and_then(|gz| gz.finish())
+            .and_then(|encrypted| encrypted.finish())
+            .and_then(|file| file.into_inner().map_err(|e| e.into_error()))
+            .and_then(|file| file.sync_all())
+            .map_err(|e| format!("Failed to write backup: {}", e))?;
+        fs::rename(&partial, target).map_err(|e| format!("Failed to write backup: {}", e))
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+/// Open the tar inside the backup at `path`, decrypting as it is read.
+fn open_tar(path: &Path, password: &str) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let mut file =
+        BufReader::new(File::open(path).map_err(|e| format!("Failed to read backup: {}", e))?);
+    let mut head = [0u8; MAGIC.len() + 1];
+    if file.read_exact(&mut head).is_err() || &head[..MAGIC.len()] != MAGIC {
+        return Err("Not a Kaleidoswap backup file".to_string());
+    }
+    let wrong_password = |_| "Wrong backup password or corrupted file".to_string();
+    let tar_gz: Box<dyn Read> = match head[MAGIC.len()] {
+        FORMAT_ENVELOPE => {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)
+                .map_err(|e| format!("Failed to read backup: {}", e))?;
+            let tar_gz = crypto::decrypt_bytes(&data, password).map_err(wrong_password)?;
+            Box::new(std::io::Cursor::new(tar_gz))
+        }
+        FORMAT_VERSION => Box::new(DecryptReader::new(file, password).map_err(wrong_password)?),
+        format if format > FORMAT_VERSION => {
+            return Err(format!(
+                "Backup format {} is newer than this version supports",
+                format
+            ))
+        }
+        _ => return Err("Not a Kaleidoswap backup file".to_string()),
+    };
+    Ok(tar::Archive::new(Box::new(GzDecoder::new(tar_gz))))
+}
+
+/// Write an encrypted backup of every app table to `target`. With
+/// `node_root`, each account's node data directory under it is included.
+pub fn export(
+    conn: &Connection,
+    target: &Path,
+    password: &str,
+    node_root: Option<&Path>,
+) -> Result<BackupManifest, String> {
+    if password.len() < MIN_PASSWORD_LEN {
+        return Err(format!(
+            "Backup password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        ));
+    }
+
+    let mut tables = BTreeMap::new();
+    for table in TABLES {
+        let mut rows = dump_table(conn, table)?;
+        if *table == "AppSettings" {
+            rows.retain(|row| {
+                let key = row.get("key").and_then(Value::as_str).unwrap_or_default();
+                !CACHE_SETTINGS.iter().any(|prefix| key.starts_with(prefix))
+            });
+        }
+        tables.insert(table.to_string(), rows);
+    }
+
+    let accounts = account_datapaths(&tables["Accounts"]);
+    let node_dirs: Vec<String> = match node_root {
+        Some(root) => accounts
+            .iter()
+            .map(|(_, datapath)| datapath.clone())
+            .filter(|datapath| is_safe_datapath(datapath) && root.join(datapath).is_dir())
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let created_at = chrono::Utc::now().timestamp();
+    let manifest = BackupManifest {
+        format: FORMAT_VERSION,
+        created_at,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: latest_schema_version(),
+        accounts: tables["Accounts"]
+            .iter()
+            .filter_map(|row| row.get("name")?.as_str().map(String::from))
+            .collect(),
+        counts: tables
+            .iter()
+            .map(|(t, rows)| (t.clone(), rows.len()))
+            .collect(),
+        node_dirs,
+    };
+
+    let mtime = created_at.max(0) as u64;
+    write_archive(target, password, |tar| {
+        append_json(tar, "manifest.json", &manifest, mtime)?;
+        append_json(tar, "data.json", &BackupData { tables }, mtime)?;
+        if let Some(root) = node_root {
+            for datapath in &manifest.node_dirs {
+                tar.append_dir_all(format!("node/{}", datapath), root.join(datapath))
+                    .map_err(|e| format!("Failed to add node data '{}': {}", datapath, e))?;
+            }
+        }
+        Ok(())
+    })?;
+
+    log::info!(
+        "[Backup] Exported {} accounts ({} node dirs) to {}",
+        manifest.accounts.len(),
+        manifest.node_dirs.len(),
+        target.display()
+    );
+    Ok(manifest)
+}
+
+/// Decrypt and validate the backup at `path`.
+pub fn read(path: &Path, password: &str) -> Result<Archive, String> {
+    let mut archive = open_tar(path, password)?;
+    let mut manifest = None;
+    let mut data = None;
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let name = entry.path().map_err(|e| e.to_string())?.into_owned();
+        if name == Path::new("manifest.json") || name == Path::new("data.json") {
+            let mut json = String::new();
+            entry.read_to_string(&mut json).map_err(|e| e.to_string())?;
+            if name == Path::new("manifest.json") {
+                manifest = Some(
+                    serde_json::from_str::<BackupManifest>(&json)
+                        .map_err(|e| format!("Invalid backup manifest: {}", e))?,
+                );
+            } else {
+                data = Some(
+                    serde_json::from_str::<BackupData>(&json)
+                        .map_err(|e| format!("Invalid backup data: {}", e))?,
+                );
+            }
+        }
+    }
+    let manifest = manifest.ok_or("Backup has no manifest")?;
+    let data = data.ok_or("Backup has no data")?;
+
+    if manifest.schema_version > latest_schema_version() {
+        return Err(format!(
+            "Backup was made by a newer version (schema {}, this version has {}); update the app first",
+            manifest.schema_version,
+            latest_schema_version()
+        ));
+    }
+    if let Some(table) = data.tables.keys().find(|t| !TABLES.contains(&t.as_str())) {
+        return Err(format!("Backup has unknown table '{}'", table));
+    }
+
+    Ok(Archive {
+        manifest,
+        data,
+        path: path.to_path_buf(),
+        password: Zeroizing::new(password.to_string()),
+    })
+}
+
+/// Restore `archive` into the database, and its node data under `node_root`
+/// when given. With `dry_run` everything is checked and counted, then rolled
+/// back, and no files are touched.
+pub fn import(
+    conn: &Connection,
+    archive: &Archive,
+    mode: ImportMode,
+    node_root: Option<&Path>,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
+    let empty = Vec::new();
+    let rows = |table: &str| archive.data.tables.get(table).unwrap_or(&empty);
+
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    let existing: Vec<String> = tx
+        .prepare("SELECT name FROM Accounts")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<Result<Vec<String>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+    let conflicts: Vec<String> = archive
+        .manifest
+        .accounts
+        .iter()
+        .filter(|name| existing.contains(name))
+        .cloned()
+        .collect();
+
+    if mode == ImportMode::Replace {
+        for table in TABLES.iter().rev() {
+            tx.execute(&format!("DELETE FROM \"{}\"", table), [])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let mut imported = BTreeMap::new();
+    // Backup account id -> id here
+    let mut account_ids: HashMap<i64, i64> = HashMap::new();
+    let mut restored_accounts = Vec::new();
+
+    let columns = table_columns(&tx, "Accounts")?;
+    let mut count = 0;
+    for row in rows("Accounts") {
+        let old_id = row.get("id").and_then(Value::as_i64);
+        let name = row.get("name").and_then(Value::as_str).unwrap_or_default();
+        if mode == ImportMode::Merge && existing.iter().any(|n| n == name) {
+            continue;
+        }
+        let mut row = row.clone();
+        if mode == ImportMode::Merge {
+            row.remove("id");
+        }
+        if insert_row(&tx, "Accounts", &columns, &row)? {
+            count += 1;
+            if let Some(old_id) = old_id {
+                account_ids.insert(old_id, tx.last_insert_rowid());
+            }
+            restored_accounts.push(name.to_string());
+        }
+    }
+    imported.insert("Accounts".to_string(), count);
+
+    let columns = table_columns(&tx, "AppSettings")?;
+    let mut count = 0;
+    for row in rows("AppSettings") {
+        let mut row = row.clone();
+        let key = row.get("key").and_then(Value::as_str).unwrap_or_default();
+        // Per-account settings follow their account's new id
+        if let Some(old_id) = key
+            .strip_prefix("nwc_service_secret_")
+            .and_then(|id| id.parse::<i64>().ok())
+        {
+            match account_ids.get(&old_id) {
+                Some(new_id) => {
+                    row.insert("key".into(), db::nwc_service_secret_key(*new_id).into());
+                }
+                None => continue,
+            }
+        }
+        if insert_row(&tx, "AppSettings", &columns, &row)? {
+            count += 1;
+        }
+    }
+    imported.insert("AppSettings".to_string(), count);
+
+    for table in ACCOUNT_TABLES {
+        let columns = table_columns(&tx, table)?;
+        let mut count = 0;
+        for row in rows(table) {
+            let Some(account_id) = row
+                .get("account_id")
+                .and_then(Value::as_i64)
+                .and_then(|id| account_ids.get(&id))
+            else {
+                continue;
+            };
+            let mut row = row.clone();
+            row.insert("account_id".into(), (*account_id).into());
+            if mode == ImportMode::Merge {
+                row.remove("id");
+            }
+            if insert_row(&tx, table, &columns, &row)? {
+                count += 1;
+            }
+        }
+        imported.insert(table.to_string(), count);
+    }
+
+    // Node data goes to the accounts restored above; an existing directory
+    // is never overwritten when merging, and moved aside when replacing
+    let mut node_dirs = Vec::new();
+    let mut skipped_node_dirs = Vec::new();
+    if let Some(root) = node_root {
+        for (name, datapath) in account_datapaths(rows("Accounts")) {
+            if !restored_accounts.contains(&name)
+                || !archive.manifest.node_dirs.contains(&datapath)
+                || !is_safe_datapath(&datapath)
+            {
+                continue;
+            }
+            if mode == ImportMode::Merge && root.join(&datapath).exists() {
+                skipped_node_dirs.push(datapath);
+            } else {
+                node_dirs.push(datapath);
+            }
+        }
+    }
+
+    let report = ImportReport {
+        manifest: archive.manifest.clone(),
+        mode,
+        applied: !dry_run,
+        conflicts,
+        imported,
+        node_dirs,
+        skipped_node_dirs,
+    };
+    if dry_run {
+        return Ok(report);
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    if let Some(root) = node_root {
+        restore_node_dirs(archive, root, &report.node_dirs)?;
+    }
+    log::info!(
+        "[Backup] Restored {} accounts ({:?}), {} node dirs",
+        report.imported["Accounts"],
+        mode,
+        report.node_dirs.len()
+    );
+    Ok(report)
+}
+
+/// Unpack `datapaths` from the archive into `root`. Entries are unpacked into
+/// a staging directory first (`unpack_in` refuses paths escaping it), then
+/// each directory is moved into place.
+fn restore_node_dirs(archive: &Archive, root: &Path, datapaths: &[String]) -> Result<(), String> {
+    if datapaths.is_empty() {
+        return Ok(());
+    }
+    let stamp = chrono::Utc::now().format("%Y%m%d%H%M%S").to_string();
+    let staging = root.join(format!(".restore-{}", stamp));
+    fs::create_dir_all(&staging).map_err(|e| e.to_string())?;
+
+    let result = (|| {
+        let mut tar = open_tar(&archive.path, &archive.password)?;
+        for entry in tar.entries().map_err(|e| e.to_string())? {
+            let mut entry = entry.map_err(|e| e.to_string())?;
+            let path = entry.path().map_err(|e| e.to_string())?.into_owned();
+            let wanted = datapaths
+                .iter()
+                .any(|d| path.starts_with(Path::new("node").join(d)));
+            if wanted {
+                entry
+                    .unpack_in(&staging)
+                    .map_err(|e| format!("Failed to unpack {}: {}", path.display(), e))?;
+            }
+        }
+        for datapath in datapaths {
+            let target = root.join(datapath);
+            if target.exists() {
+                let aside = root.join(format!("{}.pre-restore-{}", datapath, stamp));
+                fs::rename(&target, &aside).map_err(|e| e.to_string())?;
+                log::info!(
+                    "[Backup] Moved existing {} to {}",
+                    datapath,
+                    aside.display()
+                );
+            }
+            fs::rename(staging.join("node").join(datapath), &target)
+                .map_err(|e| format!("Failed to restore node data '{}': {}", datapath, e))?;
+        }
+        Ok(())
+    })();
+
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("kaleido-backup-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn test_db(name: &str) -> (db::DbPool, PathBuf) {
+        let path = temp_path(&format!("{}.sqlite", name));
+        let pool = db::open_pool(&path, None).unwrap();
+        migrations::run(&mut pool.get().unwrap()).unwrap();
+        (pool, path)
+    }
+
+    fn add_account(conn: &Connection, name: &str, datapath: Option<&str>) -> i32 {
+        db::insert_account(
+            conn,
+            name.to_string(),
+            "Regtest".to_string(),
+            datapath.map(String::from),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            "3001".to_string(),
+            "9735".to_string(),
+            Some("token".to_string()),
+            None,
+        )
+        .unwrap();
+        db::get_account_by_name(conn, name).unwrap().unwrap().id
+    }
+
+    fn cleanup(paths: &[&Path]) {
+        for path in paths {
+            let _ = fs::remove_dir_all(path);
+            let _ = fs::remove_file(path);
+            for suffix in ["-wal", "-shm"] {
+                let _ = fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+        }
+    }
+
+    #[test]
+    fn test_export_and_merge() {
+        let (source, source_path) = test_db("source");
+        let conn = source.get().unwrap();
+        add_account(&conn, "shared", None);
+        let solo = add_account(&conn, "solo", None);
+        db::upsert_dca_order(&conn, solo, "d1".into(), "{}".into()).unwrap();
+        let limit = db::LimitOrderRow {
+            order_id: "l1".into(),
+            order_type: "limit".into(),
+            linked_order_id: None,
+            payload: "{}".into(),
+        };
+        db::upsert_limit_order(&conn, solo, &limit).unwrap();
+        db::set_nwc_service_secret(&conn, solo, "abcd").unwrap();
+        db::set_app_setting(&conn, "theme", "dark").unwrap();
+        db::set_app_setting(&conn, "dca_price_history:x:30", "[]").unwrap();
+
+        let file = temp_path("merge.ksbak");
+        let manifest = export(&conn, &file, "backup-pass", None).unwrap();
+        assert_eq!(manifest.accounts, vec!["shared", "solo"]);
+        assert_eq!(manifest.counts["AppSettings"], 2);
+        assert!(read(&file, "wrong-pass").is_err());
+
+        let (target, target_path) = test_db("target");
+        let conn = target.get().unwrap();
+        add_account(&conn, "other", None);
+        add_account(&conn, "shared", None);
+        db::set_app_setting(&conn, "theme", "light").unwrap();
+
+        let archive = read(&file, "backup-pass").unwrap();
+        let preview = import(&conn, &archive, ImportMode::Merge, None, true).unwrap();
+        assert!(!preview.applied);
+        assert_eq!(preview.conflicts, vec!["shared"]);
+        assert_eq!(preview.imported["Accounts"], 1);
+        assert!(!db::check_account_exists(&conn, "solo").unwrap());
+
+        let report = import(&conn, &archive, ImportMode::Merge, None, false).unwrap();
+        assert_eq!(report.imported["DcaOrders"], 1);
+        let solo_here = db::get_account_by_name(&conn, "solo").unwrap().unwrap().id;
+        assert_ne!(solo_here, solo);
+        assert_eq!(db::get_dca_orders(&conn, solo_here).unwrap().len(), 1);
+        assert_eq!(db::get_limit_orders(&conn, solo_here).unwrap().len(), 1);
+        assert_eq!(
+            db::get_nwc_service_secret(&conn, solo_here).unwrap(),
+            Some("abcd".to_string())
+        );
+        // Local settings win when merging
+        assert_eq!(
+            db::get_app_setting(&conn, "theme").unwrap(),
+            Some("light".to_string())
+        );
+
+        drop((conn, source, target));
+        cleanup(&[&source_path, &target_path, &file]);
+    }
+
+    #[test]
+    fn test_replace_with_node_data() {
+        let node_root = temp_path("nodes");
+        fs::create_dir_all(node_root.join("main-node/wallet")).unwrap();
+        fs::write(node_root.join("main-node/wallet/seed"), "secret").unwrap();
+
+        let (pool, db_path) = test_db("replace");
+        let conn = pool.get().unwrap();
+        let main = add_account(&conn, "main", Some("main-node"));
+        db::upsert_dca_order(&conn, main, "d1".into(), "{}".into()).unwrap();
+
+        let file = temp_path("replace.ksbak");
+        let manifest = export(&conn, &file, "backup-pass", Some(&node_root)).unwrap();
+        assert_eq!(manifest.node_dirs, vec!["main-node"]);
+
+        // Diverge: another account, and the node data changes
+        add_account(&conn, "later", None);
+        fs::write(node_root.join("main-node/wallet/seed"), "changed").unwrap();
+
+        let archive = read(&file, "backup-pass").unwrap();
+        let report = import(
+            &conn,
+            &archive,
+            ImportMode::Replace,
+            Some(&node_root),
+            false,
+        )
+        .unwrap();
+        assert_eq!(report.node_dirs, vec!["main-node"]);
+        let accounts = db::get_accounts(&conn).unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].id, main);
+        assert_eq!(accounts[0].bearer_token.as_deref(), Some("token"));
+        assert_eq!(db::get_dca_orders(&conn, main).unwrap().len(), 1);
+        assert_eq!(
+            fs::read_to_string(node_root.join("main-node/wallet/seed")).unwrap(),
+            "secret"
+        );
+        // The replaced directory is kept aside, and staging is cleaned up
+        let names: Vec<String> = fs::read_dir(&node_root)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names
+            .iter()
+            .any(|n| n.starts_with("main-node.pre-restore-")));
+
+        drop((conn, pool));
+        cleanup(&[&db_path, &file, &node_root]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_backups() {
+        let (pool, db_path) = test_db("invalid");
+        let conn = pool.get().unwrap();
+        let file = temp_path("invalid.ksbak");
+
+        assert!(export(&conn, &file, "short", None).is_err());
+        export(&conn, &file, "backup-pass", None).unwrap();
+        let bytes = fs::read(&file).unwrap();
+        fs::write(&file, &bytes[..bytes.len() - 10]).unwrap();
+        assert!(read(&file, "backup-pass").is_err());
+        assert_eq!(
+            read(&file, "wrong-pass").err().unwrap(),
+            "Wrong backup password or corrupted file"
+        );
+        fs::write(&file, b"not a backup").unwrap();
+        assert_eq!(
+            read(&file, "backup-pass").err().unwrap(),
+            "Not a Kaleidoswap backup file"
+        );
+
+        let manifest = BackupManifest {
+            format: FORMAT_VERSION,
+            created_at: 0,
+            app_version: "99.0.0".into(),
+            schema_version: latest_schema_version() + 1,
+            accounts: Vec::new(),
+            counts: BTreeMap::new(),
+            node_dirs: Vec::new(),
+        };
+        let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        append_json(&mut tar, "manifest.json", &manifest, 0).unwrap();
+        let data = BackupData {
+            tables: BTreeMap::new(),
+        };
+        append_json(&mut tar, "data.json", &data, 0).unwrap();
+        let tar_gz = tar.into_inner().unwrap().finish().unwrap();
+        // Written as format 1, which is still read
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(FORMAT_ENVELOPE);
+        bytes.extend(crypto::encrypt_bytes(&tar_gz, "backup-pass").unwrap());
+        fs::write(&file, bytes).unwrap();
+        assert!(read(&file, "backup-pass")
+            .err()
+            .unwrap()
+            .starts_with("Backup was made by a newer version"));
+
+        assert!(is_safe_datapath("node-1"));
+        assert!(!is_safe_datapath("../etc"));
+        assert!(!is_safe_datapath("/abs"));
+        assert!(!is_safe_datapath(""));
+
+        drop((conn, pool));
+        cleanup(&[&db_path, &file]);
+    }
+}
@@ -0,0 +1,457 @@
+//! Channel-order lifecycle.
+//!
+//! A channel bought from an LSP (LSPS1) is created, paid, and then completed,
+//! failed or left to expire on the LSP's side. [`ChannelOrderPoller`] asks the
+//! LSP for every pending order of the current account, stores what changed
+//! and emits `channel-order:updated`. Orders that start waiting for payment
+//! also raise `channel-order:payment-required`, and orders whose payment
+//! window closed unpaid `channel-order:expired`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use crate::db::{self, ChannelOrder, ChannelOrderUpdate};
+
+const POLL_INTERVAL_SECS: u64 = 30;
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+
+pub const UPDATED_EVENT: &str = "channel-order:updated";
+pub const PAYMENT_REQUIRED_EVENT: &str = "channel-order:payment-required";
+pub const EXPIRED_EVENT: &str = "channel-order:expired";
+
+const EXPECT_PAYMENT: &str = "EXPECT_PAYMENT";
+const PAYMENT_RECEIVED_STATES: &[&str] = &["HOLD", "PAID"];
+const PAYMENT_EXPIRED_STATES: &[&str] = &["TIMEOUT", "EXPIRED"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Lsps1Payment {
+    #[serde(default)]
+    state: Option<String>,
+    #[serde(default)]
+    expires_at: Option<String>,
+    #[serde(default)]
+    fee_total_sat: Option<i64>,
+    #[serde(default)]
+    order_total_sat: Option<i64>,
+}
+
+impl Lsps1Payment {
+    fn state(&self) -> String {
+        self.state.as_deref().unwrap_or_default().to_uppercase()
+    }
+
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        PAYMENT_EXPIRED_STATES.contains(&self.state().as_str())
+            || self
+                .expires_at
+                .as_deref()
+                .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                .is_some_and(|at| at < now)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Lsps1PaymentInfo {
+    #[serde(default)]
+    bolt11: Option<Lsps1Payment>,
+    #[serde(default)]
+    onchain: Option<Lsps1Payment>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Lsps1Channel {
+    #[serde(default)]
+    funding_outpoint: Option<String>,
+}
+
+/// The fields of an LSPS1 `get_order` response the poller uses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Lsps1Order {
+    order_state: String,
+    #[serde(default)]
+    payment: Option<Lsps1PaymentInfo>,
+    #[serde(default)]
+    channel: Option<Lsps1Channel>,
+}
+
+/// The order's state as it should be stored. The payment leg that was paid
+/// wins over the other; an unpaid order whose every payment leg expired is
+/// `EXPIRED`.
+pub fn order_update(order: &Lsps1Order, now: DateTime<Utc>) -> ChannelOrderUpdate {
+    let legs: Vec<&Lsps1Payment> = order
+        .payment
+        .iter()
+        .flat_map(|p| [p.bolt11.as_ref(), p.onchain.as_ref()])
+        .flatten()
+        .collect();
+    let paid = legs
+        .iter()
+        .find(|leg| PAYMENT_RECEIVED_STATES.contains(&leg.state().as_str()));
+    let leg = paid.or(legs.first());
+
+    let mut status = order.order_state.to_uppercase();
+    if status == "CREATED"
+        && paid.is_none()
+        && !legs.is_empty()
+        && legs.iter().all(|leg| leg.is_expired(now))
+    {
+        status = "EXPIRED".to_string();
+    }
+    ChannelOrderUpdate {
+        status,
+        payment_state: leg.map(|leg| leg.state()).filter(|s| !s.is_empty()),
+        fee_total_sat: leg.and_then(|leg| leg.fee_total_sat),
+        order_total_sat: leg.and_then(|leg| leg.order_total_sat),
+        expires_at: leg.and_then(|leg| leg.expires_at.clone()),
+        channel_id: order
+            .channel
+            .as_ref()
+            .and_then(|c| c.funding_outpoint.clone()),
+    }
+}
+
+/// Whether `update` changes what is stored for `order`.
+fn is_change(order: &ChannelOrder, update: &ChannelOrderUpdate) -> bool {
+    fn differs<T: PartialEq>(new: &Option<T>, old: &Option<T>) -> bool {
+        new.is_some() && new != old
+    }
+    update.status != order.status
+        || differs(&update.payment_state, &order.payment_state)
+        || differs(&update.fee_total_sat, &order.fee_total_sat)
+        || differs(&update.order_total_sat, &order.order_total_sat)
+        || differs(&update.expires_at, &order.expires_at)
+        || differs(&update.channel_id, &order.channel_id)
+}
+
+/// The lifecycle event `update` raises for `order`, if any.
+pub fn lifecycle_event(order: &ChannelOrder, update: &ChannelOrderUpdate) -> Option<&'static str> {
+    if update.status == "EXPIRED" && order.status != "EXPIRED" {
+        return Some(EXPIRED_EVENT);
+    }
+    let awaits_payment = |status: &str, payment: Option<&str>| {
+        status == "CREATED" && payment == Some(EXPECT_PAYMENT)
+    };
+    if awaits_payment(&update.status, update.payment_state.as_deref())
+        && !awaits_payment(&order.status.to_uppercase(), order.payment_state.as_deref())
+    {
+        return Some(PAYMENT_REQUIRED_EVENT);
+    }
+    None
+}
+
+/// Payload of the channel-order events: the order as now stored.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChannelOrderEvent {
+    pub order_id: String,
+    pub order: ChannelOrder,
+}
+
+/// Which account's orders to watch, and the LSP to ask for orders stored
+/// without one.
+#[derive(Debug, Clone)]
+pub struct PollerConfig {
+    pub account_id: i32,
+    pub lsp_url: String,
+}
+
+struct RunningPoller {
+    account_id: i32,
+    cancel: CancellationToken,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+/// Refreshes the current account's pending channel orders from the LSP.
+/// Managed in Tauri state behind an `Arc`, like `LimitOrderEngine`.
+pub struct ChannelOrderPoller {
+    app_handle: Mutex<Option<AppHandle>>,
+    http: reqwest::Client,
+    wake: Notify,
+    task: tokio::sync::Mutex<Option<RunningPoller>>,
+}
+
+impl ChannelOrderPoller {
+    pub fn new() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        ChannelOrderPoller {
+            app_handle: Mutex::new(None),
+            http,
+            wake: Notify::new(),
+            task: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn set_app_handle(&self, handle: AppHandle) {
+        *self.app_handle.lock().unwrap() = Some(handle);
+    }
+
+    /// Check the pending orders now (after one was stored or paid).
+    pub fn refresh(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Start polling for an account. A no-op if it already polls for it; a
+    /// poller for another account is stopped first.
+    pub async fn start(self: &Arc<Self>, config: PollerConfig) {
+        let mut task = self.task.lock().await;
+        if let Some(running) = task.take() {
+            if running.account_id == config.account_id {
+                *task = Some(running);
+                return;
+            }
+            running.cancel.cancel();
+            let _ = running.task.await;
+        }
+        log::info!(
+            "[ChannelOrders] poller starting for account {}",
+            config.account_id
+        );
+        let cancel = CancellationToken::new();
+        let poller = Arc::clone(self);
+        let token = cancel.clone();
+        let account_id = config.account_id;
+        let handle = tauri::async_runtime::spawn(async move { poller.run(config, token).await });
+        *task = Some(RunningPoller {
+            account_id,
+            cancel,
+            task: handle,
+        });
+    }
+
+    /// Stop polling. Safe to call when not running.
+    pub async fn stop(&self) {
+        let mut task = self.task.lock().await;
+        if let Some(running) = task.take() {
+            running.cancel.cancel();
+            if let Err(e) = running.task.await {
+                log::error!("[ChannelOrders] poller task failed: {:?}", e);
+            }
+        }
+    }
+
+    async fn run(self: Arc<Self>, config: PollerConfig, cancel: CancellationToken) {
+        loop {
+            let account_id = config.account_id;
+            let orders = tauri::async_runtime::spawn_blocking(move || {
+                db::with(|conn| db::get_pending_channel_orders(conn, account_id))
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.map_err(|e| e.to_string()));
+            match orders {
+                Ok(orders) => {
+                    for order in orders {
+                        if cancel.is_cancelled() {
+                            break;
+                        }
+                        if let Err(e) = self.check(&config, order).await {
+                            log::warn!("[ChannelOrders] {}", e);
+                        }
+                    }
+                }
+                Err(e) => log::error!("[ChannelOrders] failed to load orders: {}", e),
+            }
+
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = self.wake.notified() => {}
+                _ = tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+            }
+        }
+        log::info!("[ChannelOrders] poller stopped");
+    }
+
+    /// Ask the LSP for one order and store and announce what changed.
+    async fn check(&self, config: &PollerConfig, order: ChannelOrder) -> Result<(), String> {
+        let lsp_url = order
+            .lsp_url
+            .clone()
+            .filter(|u| !u.is_empty())
+            .unwrap_or_else(|| config.lsp_url.clone());
+        if lsp_url.is_empty() {
+            return Err(format!("no LSP known for order {}", order.order_id));
+        }
+        let url = format!("{}/api/v1/lsps1/get_order", lsp_url.trim_end_matches('/'));
+        let body = serde_json::json!({
+            "order_id": order.order_id,
+            "access_token": order.access_token,
+        });
+        let resp = self
+            .http
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(format!("{} returned {}: {}", url, status, text));
+        }
+        let lsp_order: Lsps1Order = resp
+            .json()
+            .await
+            .map_err(|e| format!("Invalid response from {}: {}", url, e))?;
+
+        let update = order_update(&lsp_order, Utc::now());
+        if !is_change(&order, &update) {
+            return Ok(());
+        }
+        let event = lifecycle_event(&order, &update);
+        log::info!(
+            "[ChannelOrders] order {} {} -> {} (payment {:?})",
+            order.order_id,
+            order.status,
+            update.status,
+            update.payment_state
+        );
+        let account_id = config.account_id;
+        let order_id = order.order_id.clone();
+        let stored = tauri::async_runtime::spawn_blocking(move || {
+            db::with(|conn| {
+                db::update_channel_order_status(conn, account_id, &order_id, &update, unix_now())?;
+                db::get_channel_order(conn, account_id, &order_id)
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+        if let Some(stored) = stored {
+            self.emit(UPDATED_EVENT, &stored);
+            if let Some(event) = event {
+                self.emit(event, &stored);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn emit(&self, event: &str, order: &ChannelOrder) {
+        if let Some(handle) = self.app_handle.lock().unwrap().as_ref() {
+            let payload = ChannelOrderEvent {
+                order_id: order.order_id.clone(),
+                order: order.clone(),
+            };
+            if let Err(e) = handle.emit(event, payload) {
+                log::error!("[ChannelOrders] failed to emit {}: {}", event, e);
+            }
+        }
+    }
+}
+
+pub fn unix_now() -> i64 {
+    Utc::now().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(status: &str, payment_state: Option<&str>) -> ChannelOrder {
+        ChannelOrder {
+            id: 1,
+            account_id: 1,
+            order_id: "o1".into(),
+            created_at: "2026-01-01T00:00:00Z".into(),
+            status: status.into(),
+            payload: "{}".into(),
+            lsp_url: None,
+            access_token: Some("tok".into()),
+            client_balance_sat: None,
+            lsp_balance_sat: None,
+            asset_id: None,
+            client_asset_amount: None,
+            lsp_asset_amount: None,
+            payment_state: payment_state.map(Into::into),
+            fee_total_sat: None,
+            order_total_sat: None,
+            expires_at: None,
+            channel_id: None,
+            updated_at: None,
+        }
+    }
+
+    fn lsp_order(value: serde_json::Value) -> Lsps1Order {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_awaiting_payment() {
+        let order = lsp_order(serde_json::json!({
+            "order_state": "CREATED",
+            "payment": {
+                "bolt11": {
+                    "state": "EXPECT_PAYMENT",
+                    "expires_at": "2026-01-01T13:00:00Z",
+                    "fee_total_sat": 1200,
+                    "order_total_sat": 101200
+                },
+                "onchain": { "state": "EXPECT_PAYMENT", "expires_at": "2026-01-01T11:00:00Z" }
+            },
+            "channel": null
+        }));
+        let update = order_update(&order, now());
+        assert_eq!(update.status, "CREATED");
+        assert_eq!(update.payment_state.as_deref(), Some("EXPECT_PAYMENT"));
+        assert_eq!(update.fee_total_sat, Some(1200));
+        assert_eq!(update.order_total_sat, Some(101200));
+
+        let before = stored("CREATED", None);
+        assert!(is_change(&before, &update));
+        assert_eq!(
+            lifecycle_event(&before, &update),
+            Some(PAYMENT_REQUIRED_EVENT)
+        );
+        // Raised once
+        let before = stored("CREATED", Some("EXPECT_PAYMENT"));
+        assert_eq!(lifecycle_event(&before, &update), None);
+    }
+
+    #[test]
+    fn test_expired_and_completed() {
+        let order = lsp_order(serde_json::json!({
+            "order_state": "CREATED",
+            "payment": {
+                "bolt11": { "state": "EXPECT_PAYMENT", "expires_at": "2026-01-01T11:00:00Z" },
+                "onchain": { "state": "TIMEOUT" }
+            }
+        }));
+        let update = order_update(&order, now());
+        assert_eq!(update.status, "EXPIRED");
+        let before = stored("CREATED", Some("EXPECT_PAYMENT"));
+        assert_eq!(lifecycle_event(&before, &update), Some(EXPIRED_EVENT));
+
+        let order = lsp_order(serde_json::json!({
+            "order_state": "COMPLETED",
+            "payment": {
+                "bolt11": { "state": "EXPECT_PAYMENT", "expires_at": "2026-01-01T11:00:00Z" },
+                "onchain": { "state": "PAID", "fee_total_sat": 900 }
+            },
+            "channel": { "funding_outpoint": "txid:0" }
+        }));
+        let update = order_update(&order, now());
+        assert_eq!(update.status, "COMPLETED");
+        assert_eq!(update.payment_state.as_deref(), Some("PAID"));
+        assert_eq!(update.fee_total_sat, Some(900));
+        assert_eq!(update.channel_id.as_deref(), Some("txid:0"));
+        assert_eq!(lifecycle_event(&stored("paid", None), &update), None);
+
+        let mut done = stored("COMPLETED", Some("PAID"));
+        done.fee_total_sat = Some(900);
+        done.channel_id = Some("txid:0".into());
+        assert!(!is_change(&done, &update));
+    }
+}
@@ -1,13 +1,56 @@
+//! Password-based encryption of secrets (mnemonics, backup archives).
+//!
+//! Everything is sealed in a self-describing envelope:
+//!
+//! ```text
+//! "KENC" | version (1) | kdf (1) | m_cost (4) | t_cost (4) | p_cost (4)
+//!        | salt_len (1) | salt | nonce (12) | ciphertext
+//! ```
+//!
+//! Integers are little-endian, and the header is authenticated along with
+//! the ciphertext. The key comes from Argon2id with the parameters in the
+//! header, calibrated to the machine when the envelope is created, so older
+//! envelopes still open after the parameters change. Data written before
+//! the envelope (salt, nonce and ciphertext without a header) used the
+//! argon2 crate's defaults, [`KdfParams::LEGACY`].
+//!
+//! Data too large to hold in memory (backup archives) is encrypted as a
+//! stream: the envelope header without ciphertext, then chunks of
+//!
+//! ```text
+//! last (1) | len (4) | ciphertext
+//! ```
+//!
+//! each sealed under the same key, with the chunk counter mixed into the
+//! nonce and the header and `last` flag authenticated, so chunks can't be
+//! reordered, dropped or the stream cut short.
+
 use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
+    aead::{Aead, KeyInit, OsRng, Payload},
     Aes256Gcm, Nonce,
 };
-use argon2::{
-    password_hash::{rand_core::RngCore, SaltString},
-    Argon2, PasswordHasher,
-};
+use argon2::{password_hash::rand_core::RngCore, Algorithm, Argon2, Params, Version};
 use std::error::Error;
 use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
+
+/// Current envelope version; envelopes of an older one get re-encrypted.
+pub const ENVELOPE_VERSION: u8 = 1;
+const MAGIC: &[u8; 4] = b"KENC";
+const KDF_ARGON2ID: u8 = 1;
+const SALT_LEN: usize = 16;
+/// Salt lengths an envelope may have (argon2 requires at least 8 bytes).
+const SALT_LENS: std::ops::RangeInclusive<usize> = 8..=64;
+const NONCE_LEN: usize = 12;
+/// Plaintext bytes per chunk of a stream.
+const STREAM_CHUNK: usize = 64 * 1024;
+/// AES-GCM tag length.
+const TAG_LEN: usize = 16;
+/// Time one key derivation should take on this machine.
+const CALIBRATION_TARGET: Duration = Duration::from_millis(500);
 
 #[derive(Debug)]
 pub enum CryptoError {
@@ -30,167 +73,615 @@ impl fmt::Display for CryptoError {
 
 impl Error for CryptoError {}
 
-/// Derives a 32-byte encryption key from a password using Argon2id
-///
-/// Uses Argon2id with secure parameters:
-/// - Memory: 64 MB
-/// - Iterations: 3
-/// - Parallelism: 4 threads
-fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32], CryptoError> {
-    // Configure Argon2 with secure parameters
-    let argon2 = Argon2::default();
-
-    // Create a password hash
-    let salt_string = SaltString::encode_b64(salt)
-        .map_err(|e| CryptoError::KeyDerivationFailed(format!("Failed to encode salt: {}", e)))?;
-
-    let password_hash = argon2
-        .hash_password(password.as_bytes(), &salt_string)
-        .map_err(|e| CryptoError::KeyDerivationFailed(format!("Failed to hash password: {}", e)))?;
+/// Argon2id cost parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory in KiB.
+    pub m_cost: u32,
+    /// Iterations.
+    pub t_cost: u32,
+    /// Lanes.
+    pub p_cost: u32,
+}
 
-    // Extract the raw hash bytes (32 bytes for Argon2)
-    let hash_bytes = password_hash
-        .hash
-        .ok_or_else(|| CryptoError::KeyDerivationFailed("No hash produced".to_string()))?;
+impl KdfParams {
+    /// `Argon2::default()` of argon2 0.5, used before the envelope.
+    pub const LEGACY: KdfParams = KdfParams {
+        m_cost: Params::DEFAULT_M_COST,
+        t_cost: Params::DEFAULT_T_COST,
+        p_cost: Params::DEFAULT_P_COST,
+    };
+    /// Memory calibration starts from (64 MB).
+    const MAX_M_COST: u32 = 64 * 1024;
+    const MAX_T_COST: u32 = 10;
+    const MAX_P_COST: u32 = 4;
+
+    /// Parameters that make one derivation take about `target` here: 64 MB
+    /// (halved while a single pass is already too slow, down to the legacy
+    /// memory) and as many iterations as fit, at least the legacy two. One
+    /// lane, as the argon2 crate computes lanes one after the other.
+    pub fn calibrate(target: Duration) -> KdfParams {
+        let mut params = KdfParams {
+            m_cost: Self::MAX_M_COST,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        loop {
+            let started = Instant::now();
+            if derive_key("calibration", &[0u8; SALT_LEN], &params).is_err() {
+                return Self::LEGACY;
+            }
+            let elapsed = started.elapsed().max(Duration::from_millis(1));
+            if elapsed > target && params.m_cost / 2 >= Self::LEGACY.m_cost {
+                params.m_cost /= 2;
+                continue;
+            }
+            let fits = (target.as_micros() / elapsed.as_micros()) as u32;
+            params.t_cost = fits.clamp(Self::LEGACY.t_cost, Self::MAX_T_COST);
+            return params;
+        }
+    }
 
-    let hash_slice = hash_bytes.as_bytes();
-    if hash_slice.len() < 32 {
-        return Err(CryptoError::KeyDerivationFailed(
-            "Derived key is too short".to_string(),
-        ));
+    /// The parameters new envelopes use, calibrated once per run.
+    pub fn current() -> KdfParams {
+        static CURRENT: OnceLock<KdfParams> = OnceLock::new();
+        *CURRENT.get_or_init(|| {
+            let params = Self::calibrate(CALIBRATION_TARGET);
+            log::info!(
+                "[Crypto] Calibrated Argon2id: m={} KiB, t={}, p={}",
+                params.m_cost,
+                params.t_cost,
+                params.p_cost
+            );
+            params
+        })
     }
 
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&hash_slice[..32]);
-    Ok(key)
+    /// Whether these are parameters this app could have written. Envelopes
+    /// are read from files (backups) that may be corrupt or crafted, and
+    /// deriving a key with unbounded costs would exhaust memory or hang.
+    fn is_supported(&self) -> bool {
+        (Self::LEGACY.m_cost..=Self::MAX_M_COST).contains(&self.m_cost)
+            && (1..=Self::MAX_T_COST).contains(&self.t_cost)
+            && (1..=Self::MAX_P_COST).contains(&self.p_cost)
+    }
+
+    fn cost(&self) -> u64 {
+        self.m_cost as u64 * self.t_cost as u64 * self.p_cost as u64
+    }
 }
 
-/// Encrypts a mnemonic phrase using AES-256-GCM
-///
-/// Returns (encrypted_data_hex, salt_hex, nonce_hex)
-pub fn encrypt_mnemonic(
-    mnemonic: &str,
+/// Derives a 32-byte key from a password with Argon2id.
+fn derive_key(
     password: &str,
-) -> Result<(String, String, String), CryptoError> {
-    if mnemonic.is_empty() {
-        return Err(CryptoError::InvalidInput(
-            "Mnemonic cannot be empty".to_string(),
-        ));
-    }
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| CryptoError::KeyDerivationFailed(format!("Invalid parameters: {}", e)))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, key.as_mut())
+        .map_err(|e| CryptoError::KeyDerivationFailed(format!("Failed to hash password: {}", e)))?;
+    Ok(key)
+}
+
+fn cipher(key: &[u8; 32]) -> Aes256Gcm {
+    Aes256Gcm::new(key.into())
+}
+
+fn wrong_password() -> CryptoError {
+    CryptoError::DecryptionFailed(
+        "Decryption failed: Invalid password or corrupted data".to_string(),
+    )
+}
+
+fn check_password(password: &str) -> Result<(), CryptoError> {
     if password.is_empty() {
         return Err(CryptoError::InvalidInput(
             "Password cannot be empty".to_string(),
         ));
     }
+    Ok(())
+}
+
+/// Encrypted data with everything needed to decrypt it but the password.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    /// 0 for data from before the envelope.
+    pub version: u8,
+    pub params: KdfParams,
+    salt: Vec<u8>,
+    nonce: [u8; NONCE_LEN],
+    ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    /// Encrypts `plaintext` with AES-256-GCM under a key derived from
+    /// `password` with `params`.
+    pub fn seal(plaintext: &[u8], password: &str, params: KdfParams) -> Result<Self, CryptoError> {
+        check_password(password)?;
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let mut envelope = Envelope {
+            version: ENVELOPE_VERSION,
+            params,
+            salt,
+            nonce,
+            ciphertext: Vec::new(),
+        };
+        let key = derive_key(password, &envelope.salt, &params)?;
+        envelope.ciphertext = cipher(&key)
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &envelope.header(),
+                },
+            )
+            .map_err(|e| CryptoError::EncryptionFailed(format!("Encryption failed: {}", e)))?;
+        Ok(envelope)
+    }
+
+    /// Decrypts the envelope; fails on a wrong password or tampered data.
+    pub fn open(&self, password: &str) -> Result<Vec<u8>, CryptoError> {
+        let key = self.key(password)?;
+        self.open_with(&key)
+    }
 
-    // Generate a random salt (16 bytes)
-    let mut salt = [0u8; 16];
-    OsRng.fill_bytes(&mut salt);
+    /// The key `password` derives for this envelope, for opening it again
+    /// without the password.
+    pub fn key(&self, password: &str) -> Result<Zeroizing<[u8; 32]>, CryptoError> {
+        check_password(password)?;
+        derive_key(password, &self.salt, &self.params)
+    }
 
-    // Derive encryption key from password
-    let key = derive_key(password, &salt)?;
+    /// Decrypts the envelope with its [`key`](Self::key).
+    pub fn open_with(&self, key: &[u8; 32]) -> Result<Vec<u8>, CryptoError> {
+        let nonce = Nonce::from_slice(&self.nonce);
+        let result = if self.version == 0 {
+            cipher(key).decrypt(nonce, self.ciphertext.as_ref())
+        } else {
+            cipher(key).decrypt(
+                nonce,
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &self.header(),
+                },
+            )
+        };
+        result.map_err(|_| wrong_password())
+    }
 
-    // Create cipher instance
-    let cipher = Aes256Gcm::new_from_slice(&key)
-        .map_err(|e| CryptoError::EncryptionFailed(format!("Failed to create cipher: {}", e)))?;
+    /// Whether the data should be sealed again: it predates the current
+    /// envelope version, or this machine affords at least twice the work.
+    pub fn is_outdated(&self) -> bool {
+        self.version < ENVELOPE_VERSION || self.params.cost() * 2 <= KdfParams::current().cost()
+    }
 
-    // Generate a random nonce (12 bytes for GCM)
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
+    /// Data written before the envelope: its salt, nonce and ciphertext.
+    pub fn legacy(salt: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<Self, CryptoError> {
+        let nonce: [u8; NONCE_LEN] = nonce
+            .try_into()
+            .map_err(|_| CryptoError::DecryptionFailed("Invalid nonce length".to_string()))?;
+        Ok(Envelope {
+            version: 0,
+            params: KdfParams::LEGACY,
+            salt: salt.to_vec(),
+            nonce,
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
 
-    // Encrypt the mnemonic
-    let ciphertext = cipher
-        .encrypt(nonce, mnemonic.as_bytes())
-        .map_err(|e| CryptoError::EncryptionFailed(format!("Encryption failed: {}", e)))?;
+    /// Everything up to the ciphertext.
+    fn header(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(19 + self.salt.len() + NONCE_LEN);
+        out.extend_from_slice(MAGIC);
+        out.push(self.version);
+        out.push(KDF_ARGON2ID);
+        out.extend_from_slice(&self.params.m_cost.to_le_bytes());
+        out.extend_from_slice(&self.params.t_cost.to_le_bytes());
+        out.extend_from_slice(&self.params.p_cost.to_le_bytes());
+        out.push(self.salt.len() as u8);
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&self.nonce);
+        out
+    }
 
-    // Return hex-encoded values
-    Ok((
-        hex::encode(ciphertext),
-        hex::encode(salt),
-        hex::encode(nonce_bytes),
-    ))
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.header();
+        out.extend_from_slice(&self.ciphertext);
+        out
+    }
+
+    /// Parses an envelope; `None` if `data` isn't one, or has parameters or
+    /// a salt length outside what this app writes.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let rest = data.strip_prefix(MAGIC)?;
+        let (&[version, kdf], rest) = rest.split_first_chunk::<2>()?;
+        if version == 0 || version > ENVELOPE_VERSION || kdf != KDF_ARGON2ID {
+            return None;
+        }
+        let (costs, rest) = rest.split_first_chunk::<12>()?;
+        let cost = |i: usize| u32::from_le_bytes(costs[i * 4..i * 4 + 4].try_into().unwrap());
+        let params = KdfParams {
+            m_cost: cost(0),
+            t_cost: cost(1),
+            p_cost: cost(2),
+        };
+        let (&salt_len, rest) = rest.split_first()?;
+        let salt_len = salt_len as usize;
+        if !params.is_supported() || !SALT_LENS.contains(&salt_len) {
+            return None;
+        }
+        if rest.len() < salt_len + NONCE_LEN {
+            return None;
+        }
+        let (salt, rest) = rest.split_at(salt_len);
+        let (nonce, ciphertext) = rest.split_first_chunk::<NONCE_LEN>()?;
+        Some(Envelope {
+            version,
+            params,
+            salt: salt.to_vec(),
+            nonce: *nonce,
+            ciphertext: ciphertext.to_vec(),
+        })
+    }
 }
 
-/// Decrypts a mnemonic phrase using AES-256-GCM
+/// Encrypts a mnemonic phrase with the current parameters.
 ///
-/// Takes hex-encoded encrypted_data, salt, and nonce
-pub fn decrypt_mnemonic(
-    encrypted_hex: &str,
-    password: &str,
-    salt_hex: &str,
-    nonce_hex: &str,
-) -> Result<String, CryptoError> {
-    if password.is_empty() {
+/// Returns the hex-encoded envelope.
+pub fn encrypt_mnemonic(mnemonic: &str, password: &str) -> Result<String, CryptoError> {
+    if mnemonic.is_empty() {
         return Err(CryptoError::InvalidInput(
-            "Password cannot be empty".to_string(),
+            "Mnemonic cannot be empty".to_string(),
         ));
     }
+    let envelope = Envelope::seal(mnemonic.as_bytes(), password, KdfParams::current())?;
+    Ok(hex::encode(envelope.to_bytes()))
+}
 
-    // Decode hex values
+/// The envelope of a stored mnemonic: a hex-encoded envelope, or with
+/// `legacy` (hex salt and nonce) a mnemonic stored before the envelope.
+pub fn mnemonic_envelope(
+    encrypted_hex: &str,
+    legacy: Option<(&str, &str)>,
+) -> Result<Envelope, CryptoError> {
     let encrypted = hex::decode(encrypted_hex)
         .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid encrypted data: {}", e)))?;
+    match legacy {
+        Some((salt_hex, nonce_hex)) => {
+            let salt = hex::decode(salt_hex)
+                .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid salt: {}", e)))?;
+            let nonce = hex::decode(nonce_hex)
+                .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid nonce: {}", e)))?;
+            Envelope::legacy(&salt, &nonce, &encrypted)
+        }
+        None => Envelope::from_bytes(&encrypted)
+            .ok_or_else(|| CryptoError::DecryptionFailed("Unknown data format".to_string())),
+    }
+}
+
+/// The plaintext of an opened mnemonic envelope.
+pub fn mnemonic_text(plaintext: Vec<u8>) -> Result<Zeroizing<String>, CryptoError> {
+    String::from_utf8(plaintext)
+        .map(Zeroizing::new)
+        .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid UTF-8: {}", e)))
+}
 
-    let salt = hex::decode(salt_hex)
-        .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid salt: {}", e)))?;
+/// Encrypts a stored mnemonic (see [`mnemonic_envelope`]) under
+/// `new_password`, with the current parameters. Fails if `old_password`
+/// doesn't decrypt it.
+pub fn reencrypt_mnemonic(
+    encrypted_hex: &str,
+    legacy: Option<(&str, &str)>,
+    old_password: &str,
+    new_password: &str,
+) -> Result<String, CryptoError> {
+    let envelope = mnemonic_envelope(encrypted_hex, legacy)?;
+    let mnemonic = mnemonic_text(envelope.open(old_password)?)?;
+    encrypt_mnemonic(&mnemonic, new_password)
+}
 
-    let nonce_bytes = hex::decode(nonce_hex)
-        .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid nonce: {}", e)))?;
+/// Encrypts a BIP39 passphrase like a mnemonic (see [`encrypt_mnemonic`]).
+pub fn encrypt_passphrase(passphrase: &str, password: &str) -> Result<String, CryptoError> {
+    let envelope = Envelope::seal(passphrase.as_bytes(), password, KdfParams::current())?;
+    Ok(hex::encode(envelope.to_bytes()))
+}
 
-    if nonce_bytes.len() != 12 {
+/// Encrypts a stored passphrase under `new_password`, like
+/// [`reencrypt_mnemonic`].
+pub fn reencrypt_passphrase(
+    encrypted_hex: &str,
+    old_password: &str,
+    new_password: &str,
+) -> Result<String, CryptoError> {
+    let envelope = mnemonic_envelope(encrypted_hex, None)?;
+    let passphrase = mnemonic_text(envelope.open(old_password)?)?;
+    encrypt_passphrase(&passphrase, new_password)
+}
+
+/// Encrypts arbitrary bytes with a key derived from `password`.
+///
+/// Returns the envelope, as format 1 backups were written; new ones use
+/// [`EncryptWriter`].
+#[cfg(test)]
+pub fn encrypt_bytes(plaintext: &[u8], password: &str) -> Result<Vec<u8>, CryptoError> {
+    Ok(Envelope::seal(plaintext, password, KdfParams::current())?.to_bytes())
+}
+
+/// Decrypts the output of [`encrypt_bytes`], including the
+/// `salt (16) | nonce (12) | ciphertext` it wrote before the envelope.
+pub fn decrypt_bytes(data: &[u8], password: &str) -> Result<Vec<u8>, CryptoError> {
+    check_password(password)?;
+    if data.starts_with(MAGIC) {
+        return Envelope::from_bytes(data)
+            .ok_or_else(|| {
+                CryptoError::DecryptionFailed("Unsupported or corrupt envelope".to_string())
+            })?
+            .open(password);
+    }
+    if data.len() < SALT_LEN + NONCE_LEN {
         return Err(CryptoError::DecryptionFailed(
-            "Invalid nonce length".to_string(),
+            "Encrypted data is too short".to_string(),
         ));
     }
+    let (salt, rest) = data.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    Envelope::legacy(salt, nonce, ciphertext)?.open(password)
+}
 
-    // Derive the same key from password and salt
-    let key = derive_key(password, &salt)?;
+/// The nonce of chunk `counter` of a stream.
+fn chunk_nonce(base: &[u8; NONCE_LEN], counter: u64) -> [u8; NONCE_LEN] {
+    let mut nonce = *base;
+    for (n, c) in nonce[NONCE_LEN - 8..].iter_mut().zip(counter.to_be_bytes()) {
+        *n ^= c;
+    }
+    nonce
+}
 
-    // Create cipher instance
-    let cipher = Aes256Gcm::new_from_slice(&key)
-        .map_err(|e| CryptoError::DecryptionFailed(format!("Failed to create cipher: {}", e)))?;
+fn chunk_aad(header: &[u8], last: bool) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.push(last as u8);
+    aad
+}
 
-    let nonce = Nonce::from_slice(&nonce_bytes);
+/// Encrypts everything written to it into `inner` as a stream. Call
+/// [`finish`](Self::finish) to seal the last chunk; a stream dropped
+/// without it fails to decrypt.
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    cipher: Aes256Gcm,
+    header: Vec<u8>,
+    nonce: [u8; NONCE_LEN],
+    counter: u64,
+    buf: Zeroizing<Vec<u8>>,
+}
 
-    // Decrypt the mnemonic
-    let plaintext = cipher.decrypt(nonce, encrypted.as_ref()).map_err(|_| {
-        CryptoError::DecryptionFailed(
-            "Decryption failed: Invalid password or corrupted data".to_string(),
-        )
-    })?;
+impl<W: Write> EncryptWriter<W> {
+    /// Derives a key from `password` with `params` and writes the header.
+    pub fn new(mut inner: W, password: &str, params: KdfParams) -> Result<Self, CryptoError> {
+        check_password(password)?;
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let key = derive_key(password, &salt, &params)?;
+        let header = Envelope {
+            version: ENVELOPE_VERSION,
+            params,
+            salt,
+            nonce,
+            ciphertext: Vec::new(),
+        }
+        .header();
+        inner
+            .write_all(&header)
+            .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
+        Ok(EncryptWriter {
+            inner,
+            cipher: cipher(&key),
+            header,
+            nonce,
+            counter: 0,
+            buf: Zeroizing::new(Vec::with_capacity(STREAM_CHUNK)),
+        })
+    }
 
-    // Convert bytes to string
-    String::from_utf8(plaintext)
-        .map_err(|e| CryptoError::DecryptionFailed(format!("Invalid UTF-8: {}", e)))
+    fn write_chunk(&mut self, plaintext: &[u8], last: bool) -> io::Result<()> {
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&chunk_nonce(&self.nonce, self.counter)),
+                Payload {
+                    msg: plaintext,
+                    aad: &chunk_aad(&self.header, last),
+                },
+            )
+            .map_err(|e| io::Error::other(format!("Encryption failed: {}", e)))?;
+        self.counter += 1;
+        self.inner.write_all(&[last as u8])?;
+        self.inner
+            .write_all(&(ciphertext.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&ciphertext)
+    }
+
+    /// Seals the buffered data as the last chunk and returns `inner`.
+    pub fn finish(mut self) -> io::Result<W> {
+        let buf = std::mem::take(&mut *self.buf);
+        self.write_chunk(&Zeroizing::new(buf), true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // A full chunk is only written once more data follows, so the last
+        // one is never empty unless the whole stream is
+        let take = data.len().min(STREAM_CHUNK + 1 - self.buf.len());
+        self.buf.extend_from_slice(&data[..take]);
+        if self.buf.len() > STREAM_CHUNK {
+            let rest = self.buf.split_off(STREAM_CHUNK);
+            let chunk = std::mem::replace(&mut *self.buf, rest);
+            self.write_chunk(&Zeroizing::new(chunk), false)?;
+        }
+        Ok(take)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts a stream written by [`EncryptWriter`].
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    cipher: Aes256Gcm,
+    header: Vec<u8>,
+    nonce: [u8; NONCE_LEN],
+    counter: u64,
+    buf: Zeroizing<Vec<u8>>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Reads the header and decrypts the first chunk, so a wrong password
+    /// fails here rather than partway through the data.
+    pub fn new(mut inner: R, password: &str) -> Result<Self, CryptoError> {
+        check_password(password)?;
+        let corrupt =
+            || CryptoError::DecryptionFailed("Unsupported or corrupt envelope".to_string());
+        // Magic, version, kdf, costs and salt length, then salt and nonce
+        let mut header = vec![0u8; 19];
+        inner.read_exact(&mut header).map_err(|_| corrupt())?;
+        let salt_len = header[18] as usize;
+        if !SALT_LENS.contains(&salt_len) {
+            return Err(corrupt());
+        }
+        header.resize(19 + salt_len + NONCE_LEN, 0);
+        inner.read_exact(&mut header[19..]).map_err(|_| corrupt())?;
+        let envelope = Envelope::from_bytes(&header).ok_or_else(corrupt)?;
+        let key = envelope.key(password)?;
+        let mut reader = DecryptReader {
+            inner,
+            cipher: cipher(&key),
+            header,
+            nonce: envelope.nonce,
+            counter: 0,
+            buf: Zeroizing::new(Vec::new()),
+            pos: 0,
+            done: false,
+        };
+        reader.read_chunk().map_err(|_| wrong_password())?;
+        Ok(reader)
+    }
+
+    fn read_chunk(&mut self) -> io::Result<()> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let mut frame = [0u8; 5];
+        self.inner
+            .read_exact(&mut frame)
+            .map_err(|_| invalid("Encrypted stream is truncated"))?;
+        let last = match frame[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(invalid("Encrypted stream is corrupt")),
+        };
+        let len = u32::from_le_bytes(frame[1..].try_into().unwrap()) as usize;
+        if !(TAG_LEN..=STREAM_CHUNK + TAG_LEN).contains(&len) {
+            return Err(invalid("Encrypted stream is corrupt"));
+        }
+        let mut ciphertext = vec![0u8; len];
+        self.inner
+            .read_exact(&mut ciphertext)
+            .map_err(|_| invalid("Encrypted stream is truncated"))?;
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&chunk_nonce(&self.nonce, self.counter)),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &chunk_aad(&self.header, last),
+                },
+            )
+            .map_err(|_| invalid("Invalid password or corrupted data"))?;
+        self.counter += 1;
+        self.buf = Zeroizing::new(plaintext);
+        self.pos = 0;
+        self.done = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.read_chunk()?;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn decrypt_mnemonic(
+        encrypted_hex: &str,
+        password: &str,
+        legacy: Option<(&str, &str)>,
+    ) -> Result<(String, bool), CryptoError> {
+        let envelope = mnemonic_envelope(encrypted_hex, legacy)?;
+        let mnemonic = mnemonic_text(envelope.open(password)?)?;
+        Ok((mnemonic.to_string(), envelope.is_outdated()))
+    }
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const CHEAP: KdfParams = KdfParams {
+        m_cost: KdfParams::LEGACY.m_cost,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    /// `MNEMONIC` as stored before the envelope (ciphertext, salt, nonce),
+    /// under "password", written by the code of that time.
+    const LEGACY_MNEMONIC: (&str, &str, &str) = (
+        "bb4c29611fa56568fa3cbc252594f92bacf5ec3d7a1b6d7fb7aa8ded3912170dd65171b258c05c48f079ed1a4f42a9c3500376b6e3559e595037fbe38a019f86879ed2cf8a29a588705b120b4a774d7f3eddfa676cac0b3c45bc15a498332df773b6f6e602d41a3679ad018edf",
+        "f6984ef18b2270307b843d24678b3b7e",
+        "1c47ca87eca92b21edc25ef2",
+    );
+    /// `LEGACY_DATA` as `encrypt_bytes` wrote it before the envelope, under
+    /// "password".
+    const LEGACY_BYTES: &str = "b9c900e0e8313b4b1eecd4a4147bc340221b59c57879d70aabb8f0b5b908d353668ae51dfecf347df5cffc49c103d3a7c39431c59340a8a746cd3f";
+    const LEGACY_DATA: &[u8] = b"\x00binary\xffpayload";
+
     #[test]
     fn test_encrypt_decrypt() {
-        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
         let password = "test_password_123";
 
-        // Encrypt
-        let (encrypted, salt, nonce) = encrypt_mnemonic(mnemonic, password).unwrap();
+        let encrypted = encrypt_mnemonic(MNEMONIC, password).unwrap();
+        let (decrypted, outdated) = decrypt_mnemonic(&encrypted, password, None).unwrap();
 
-        // Decrypt
-        let decrypted = decrypt_mnemonic(&encrypted, password, &salt, &nonce).unwrap();
-
-        assert_eq!(mnemonic, decrypted);
+        assert_eq!(MNEMONIC, decrypted);
+        assert!(!outdated);
     }
 
     #[test]
     fn test_wrong_password() {
-        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
-        let password = "correct_password";
-        let wrong_password = "wrong_password";
-
-        let (encrypted, salt, nonce) = encrypt_mnemonic(mnemonic, password).unwrap();
+        let encrypted = encrypt_mnemonic(MNEMONIC, "correct_password").unwrap();
 
-        let result = decrypt_mnemonic(&encrypted, wrong_password, &salt, &nonce);
+        let result = decrypt_mnemonic(&encrypted, "wrong_password", None);
         assert!(result.is_err());
     }
 
@@ -199,4 +690,133 @@ mod tests {
         assert!(encrypt_mnemonic("", "password").is_err());
         assert!(encrypt_mnemonic("mnemonic", "").is_err());
     }
+
+    #[test]
+    fn test_legacy_mnemonic() {
+        let (ciphertext, salt, nonce) = LEGACY_MNEMONIC;
+        assert!(decrypt_mnemonic(ciphertext, "wrong", Some((salt, nonce))).is_err());
+        let (decrypted, outdated) =
+            decrypt_mnemonic(ciphertext, "password", Some((salt, nonce))).unwrap();
+        assert_eq!(decrypted, MNEMONIC);
+        assert!(outdated);
+    }
+
+    #[test]
+    fn test_reencrypt_mnemonic() {
+        let (stored, salt, nonce) = LEGACY_MNEMONIC;
+        let legacy = Some((salt, nonce));
+        assert!(reencrypt_mnemonic(stored, legacy, "wrong", "new").is_err());
+
+        let encrypted = reencrypt_mnemonic(stored, legacy, "password", "new").unwrap();
+        assert!(decrypt_mnemonic(&encrypted, "password", None).is_err());
+        let (decrypted, outdated) = decrypt_mnemonic(&encrypted, "new", None).unwrap();
+        assert_eq!(decrypted, MNEMONIC);
+        assert!(!outdated);
+    }
+
+    #[test]
+    fn test_envelope() {
+        let envelope = Envelope::seal(b"secret", "password", CHEAP).unwrap();
+        let bytes = envelope.to_bytes();
+        let parsed = Envelope::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, envelope);
+        assert_eq!(parsed.params, CHEAP);
+        assert_eq!(parsed.open("password").unwrap(), b"secret");
+        assert!(parsed.open("wrong").is_err());
+        assert!(parsed.is_outdated());
+
+        // Changed parameters don't open it
+        let mut tampered = bytes.clone();
+        tampered[MAGIC.len() + 2] ^= 1;
+        let tampered = Envelope::from_bytes(&tampered).unwrap();
+        assert!(tampered.open("password").is_err());
+
+        assert!(Envelope::from_bytes(&bytes[..30]).is_none());
+        assert!(Envelope::from_bytes(b"not an envelope").is_none());
+    }
+
+    #[test]
+    fn test_oversized_header() {
+        let bytes = Envelope::seal(b"secret", "password", CHEAP)
+            .unwrap()
+            .to_bytes();
+        let m_cost = MAGIC.len() + 2;
+        let salt_len = m_cost + 12;
+        let with = |at: usize, value: &[u8]| {
+            let mut bytes = bytes.clone();
+            bytes[at..at + value.len()].copy_from_slice(value);
+            bytes
+        };
+        for crafted in [
+            with(m_cost, &u32::MAX.to_le_bytes()),
+            with(m_cost, &1024u32.to_le_bytes()),
+            with(m_cost + 4, &u32::MAX.to_le_bytes()),
+            with(m_cost + 4, &0u32.to_le_bytes()),
+            with(m_cost + 8, &u32::MAX.to_le_bytes()),
+            with(salt_len, &[0]),
+            with(salt_len, &[255]),
+        ] {
+            assert!(Envelope::from_bytes(&crafted).is_none());
+            // Rejected before any key derivation, not read as legacy data
+            let err = decrypt_bytes(&crafted, "password").unwrap_err();
+            assert!(err.to_string().contains("corrupt"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_bytes() {
+        let data = b"\x00binary\xffpayload";
+        let encrypted = encrypt_bytes(data, "password").unwrap();
+        assert_eq!(decrypt_bytes(&encrypted, "password").unwrap(), data);
+        assert!(decrypt_bytes(&encrypted, "wrong").is_err());
+        assert!(decrypt_bytes(&encrypted[..20], "password").is_err());
+
+        let legacy = hex::decode(LEGACY_BYTES).unwrap();
+        assert_eq!(decrypt_bytes(&legacy, "password").unwrap(), LEGACY_DATA);
+    }
+
+    #[test]
+    fn test_stream() {
+        let data: Vec<u8> = (0..STREAM_CHUNK * 2 + 100).map(|i| i as u8).collect();
+        let encrypt = |data: &[u8]| {
+            let mut writer = EncryptWriter::new(Vec::new(), "password", CHEAP).unwrap();
+            for part in data.chunks(1000) {
+                writer.write_all(part).unwrap();
+            }
+            writer.finish().unwrap()
+        };
+        let decrypt = |encrypted: &[u8], password: &str| -> Result<Vec<u8>, String> {
+            let mut reader = DecryptReader::new(encrypted, password).map_err(|e| e.to_string())?;
+            let mut out = Vec::new();
+            reader.read_to_end(&mut out).map_err(|e| e.to_string())?;
+            Ok(out)
+        };
+
+        let encrypted = encrypt(&data);
+        assert_eq!(decrypt(&encrypted, "password").unwrap(), data);
+        assert!(decrypt(&encrypted, "wrong").is_err());
+        assert_eq!(decrypt(&encrypt(b""), "password").unwrap(), b"");
+        // An exact multiple of the chunk size still ends in a last chunk
+        let full = &data[..STREAM_CHUNK * 2];
+        assert_eq!(decrypt(&encrypt(full), "password").unwrap(), full);
+
+        // Cut at a chunk boundary, and with a chunk flipped to last
+        let first_chunk = encrypted.len() - 2 * (5 + STREAM_CHUNK + TAG_LEN) - (5 + 100 + TAG_LEN);
+        let cut = first_chunk + 5 + STREAM_CHUNK + TAG_LEN;
+        assert!(decrypt(&encrypted[..cut], "password").is_err());
+        let mut flipped = encrypted[..cut].to_vec();
+        flipped[first_chunk] = 1;
+        assert!(decrypt(&flipped, "password").is_err());
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&tampered, "password").is_err());
+    }
+
+    #[test]
+    fn test_calibrate() {
+        let params = KdfParams::calibrate(Duration::from_millis(1));
+        assert_eq!(params.t_cost, KdfParams::LEGACY.t_cost);
+        assert!(params.m_cost >= KdfParams::LEGACY.m_cost);
+        assert_eq!(params.p_cost, 1);
+    }
 }
@@ -0,0 +1,524 @@
+//! Where the app keeps its data.
+//!
+//! Everything the app writes (the database, each account's node data and the
+//! node logs) lives under one data root, resolved once at startup from, in
+//! order:
+//!
+//!   1. `--data-dir <path>` on the command line
+//!   2. `$KALEIDO_DATA_DIR`
+//!   3. portable mode (`--portable`, `$KALEIDO_PORTABLE=1` or a `portable`
+//!      file next to the executable): `kaleidoswap-data/` next to the
+//!      executable
+//!   4. the location recorded in the default root when the data was moved
+//!      ([`switch_to`])
+//!   5. the per-OS default (`~/.local/share/com.kaleidoswap.dev`, ...), or
+//!      the source tree in debug builds
+//!
+//! A root given by 1–4 is laid out as `db/database.sqlite`, `data/` and
+//! `logs/`. The default root keeps the historical per-OS locations.
+
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const DATA_DIR_ENV: &str = "KALEIDO_DATA_DIR";
+pub const PORTABLE_ENV: &str = "KALEIDO_PORTABLE";
+const DATA_DIR_FLAG: &str = "--data-dir";
+const PORTABLE_FLAG: &str = "--portable";
+/// File next to the executable that turns on portable mode.
+const PORTABLE_MARKER: &str = "portable";
+const PORTABLE_DIR: &str = "kaleidoswap-data";
+/// File in the default root holding the path data was moved to.
+const LOCATION_FILE: &str = "data-location";
+const APP_ID: &str = "com.kaleidoswap.dev";
+
+static LAYOUT: RwLock<Option<DataLayout>> = RwLock::new(None);
+
+/// What chose the data root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataRootSource {
+    CliFlag,
+    Env,
+    Portable,
+    Moved,
+    Default,
+}
+
+impl DataRootSource {
+    /// Roots chosen by a flag, variable or portable mode are re-chosen on
+    /// every launch and can't be moved from inside the app. Neither can the
+    /// source tree of a debug build, whose node directory holds the binary.
+    pub fn is_movable(self) -> bool {
+        match self {
+            DataRootSource::Moved => true,
+            DataRootSource::Default => !cfg!(debug_assertions),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DataLayout {
+    pub source: DataRootSource,
+    pub root: PathBuf,
+    pub db_path: PathBuf,
+    /// Directory holding each account's node data (the account `datapath`
+    /// is relative to it).
+    pub node_dir: PathBuf,
+    pub log_dir: PathBuf,
+}
+
+impl DataLayout {
+    /// The layout of a data root other than the default one.
+    pub fn under(root: PathBuf, source: DataRootSource) -> Self {
+        DataLayout {
+            source,
+            db_path: root.join("db/database.sqlite"),
+            node_dir: root.join("data"),
+            log_dir: root.join("logs"),
+            root,
+        }
+    }
+
+    /// Whether the app logs go to [`DataLayout::log_dir`] rather than the
+    /// OS log directory.
+    pub fn is_custom(&self) -> bool {
+        self.source != DataRootSource::Default
+    }
+
+    /// The database directory, with its pre-migration backups.
+    pub fn db_dir(&self) -> &Path {
+        self.db_path.parent().unwrap_or(&self.root)
+    }
+}
+
+fn home() -> Result<PathBuf, String> {
+    env::var("HOME")
+        .map(PathBuf::from)
+        .map_err(|e| format!("Failed to get HOME directory: {}", e))
+}
+
+/// The per-OS layout used when nothing overrides it.
+pub fn default_layout() -> Result<DataLayout, String> {
+    let layout = |root: PathBuf, log_dir: PathBuf| DataLayout {
+        source: DataRootSource::Default,
+        db_path: root.join("db/database.sqlite"),
+        node_dir: root.join("data"),
+        log_dir,
+        root,
+    };
+    if cfg!(debug_assertions) {
+        // During development, keep everything in the source tree
+        let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        Ok(DataLayout {
+            node_dir: manifest.join("../bin"),
+            ..layout(manifest.clone(), manifest.join("logs"))
+        })
+    } else if cfg!(target_os = "macos") {
+        let home = home()?;
+        Ok(layout(
+            home.join("Library/Application Support").join(APP_ID),
+            home.join("Library/Logs").join(APP_ID),
+        ))
+    } else if cfg!(target_os = "windows") {
+        let local_app_data =
+            env::var("LOCALAPPDATA").map_err(|e| format!("Failed to get LOCALAPPDATA: {}", e))?;
+        let app_data =
+            env::var("APPDATA").map_err(|e| format!("Failed to get APPDATA directory: {}", e))?;
+        Ok(layout(
+            PathBuf::from(local_app_data).join(APP_ID),
+            PathBuf::from(app_data).join(APP_ID).join("logs"),
+        ))
+    } else {
+        let root = home()?.join(".local/share").join(APP_ID);
+        Ok(layout(root.clone(), root.join("logs")))
+    }
+}
+
+/// Overrides given on the command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct CliArgs {
+    pub data_dir: Option<PathBuf>,
+    pub portable: bool,
+}
+
+/// Read `--data-dir <path>`, `--data-dir=<path>` and `--portable`; other
+/// arguments are left to whoever else reads them.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> CliArgs {
+    let mut parsed = CliArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == DATA_DIR_FLAG {
+            parsed.data_dir = args.next().map(PathBuf::from);
+        } else if let Some(dir) = arg.strip_prefix("--data-dir=") {
+            parsed.data_dir = Some(PathBuf::from(dir));
+        } else if arg == PORTABLE_FLAG {
+            parsed.portable = true;
+        }
+    }
+    parsed
+}
+
+/// Everything the data root is resolved from, gathered by [`init`].
+#[derive(Debug, Default)]
+struct Inputs {
+    cli: CliArgs,
+    env_dir: Option<String>,
+    env_portable: bool,
+    exe_dir: Option<PathBuf>,
+    /// Contents of the default root's location file.
+    moved_to: Option<String>,
+}
+
+fn resolve(inputs: Inputs, default: DataLayout) -> DataLayout {
+    if let Some(dir) = inputs.cli.data_dir {
+        return DataLayout::under(dir, DataRootSource::CliFlag);
+    }
+    if let Some(dir) = inputs.env_dir.filter(|d| !d.trim().is_empty()) {
+        return DataLayout::under(PathBuf::from(dir.trim()), DataRootSource::Env);
+    }
+    if let Some(exe_dir) = inputs.exe_dir {
+        let portable =
+            inputs.cli.portable || inputs.env_portable || exe_dir.join(PORTABLE_MARKER).is_file();
+        if portable {
+            return DataLayout::under(exe_dir.join(PORTABLE_DIR), DataRootSource::Portable);
+        }
+    }
+    if let Some(dir) = inputs.moved_to.filter(|d| !d.trim().is_empty()) {
+        return DataLayout::under(PathBuf::from(dir.trim()), DataRootSource::Moved);
+    }
+    default
+}
+
+/// Resolve the data root from the command line, the environment and the
+/// default root. Called once at startup, before anything touches the disk.
+pub fn init(args: impl IntoIterator<Item = String>) -> Result<DataLayout, String> {
+    let default = default_layout()?;
+    let inputs = Inputs {
+        cli: parse_args(args),
+        env_dir: env::var(DATA_DIR_ENV).ok(),
+        env_portable: env::var(PORTABLE_ENV).is_ok_and(|v| v == "1" || v == "true"),
+        exe_dir: env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf)),
+        moved_to: fs::read_to_string(default.root.join(LOCATION_FILE)).ok(),
+    };
+    let layout = absolute(resolve(inputs, default))?;
+    *LAYOUT.write().unwrap() = Some(layout.clone());
+    Ok(layout)
+}
+
+fn absolute(mut layout: DataLayout) -> Result<DataLayout, String> {
+    if layout.root.is_relative() {
+        let cwd = env::current_dir().map_err(|e| e.to_string())?;
+        layout = DataLayout::under(cwd.join(&layout.root), layout.source);
+    }
+    Ok(layout)
+}
+
+/// The current layout. Resolved without command-line overrides if [`init`]
+/// hasn't run.
+pub fn layout() -> Result<DataLayout, String> {
+    if let Some(layout) = LAYOUT.read().unwrap().as_ref() {
+        return Ok(layout.clone());
+    }
+    init(std::iter::empty())
+}
+
+/// Copy the contents of `from` into `to`, creating it.
+pub fn copy_dir(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn is_empty_dir(dir: &Path) -> bool {
+    fs::read_dir(dir).map_or(true, |mut entries| entries.next().is_none())
+}
+
+/// Data copied to a new root by [`copy_data`].
+#[derive(Debug, Clone)]
+pub struct MovedData {
+    pub layout: DataLayout,
+    /// The files and directories copied, which [`remove_moved_data`]
+    /// deletes. The log file still being written isn't among them.
+    copied: Vec<PathBuf>,
+}
+
+/// Copy each entry of `from` into `to`, creating it, and record what was
+/// copied.
+fn copy_entries(from: &Path, to: &Path, copied: &mut Vec<PathBuf>) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+        copied.push(entry.path());
+    }
+    Ok(())
+}
+
+/// The log file the app is writing to: the most recently modified one.
+fn live_log(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            path.extension()
+                .is_some_and(|ext| ext == "log")
+                .then_some((modified, path))
+        })
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// Copy the data of `from` into the layout of `target`, leaving the original
+/// in place. The database must be closed. Nothing of `target` is
+/// overwritten; on failure what was copied is removed again.
+pub fn copy_data(from: &DataLayout, target: &Path) -> Result<MovedData, String> {
+    if !from.source.is_movable() {
+        return Err(format!(
+            "The data directory is set by {} and can't be moved from the app",
+            match from.source {
+                DataRootSource::CliFlag => DATA_DIR_FLAG,
+                DataRootSource::Env => DATA_DIR_ENV,
+                DataRootSource::Portable => "portable mode",
+                _ => "the development build",
+            }
+        ));
+    }
+    if target.is_relative() {
+        return Err("The new data directory must be an absolute path".to_string());
+    }
+    let to = DataLayout::under(target.to_path_buf(), DataRootSource::Moved);
+    let overlaps = |a: &Path, b: &Path| a.starts_with(b) || b.starts_with(a);
+    if [from.db_dir(), &from.node_dir, &from.log_dir]
+        .iter()
+        .any(|dir| overlaps(dir, target))
+    {
+        return Err("The new data directory overlaps the current one".to_string());
+    }
+    if to.db_path.exists() || !is_empty_dir(&to.node_dir) {
+        return Err(format!("{} already holds app data", target.display()));
+    }
+
+    let mut copied = Vec::new();
+    let mut copy = || -> io::Result<()> {
+        copy_entries(from.db_dir(), to.db_dir(), &mut copied)?;
+        if from.node_dir.exists() {
+            copy_entries(&from.node_dir, &to.node_dir, &mut copied)?;
+        }
+        if from.log_dir.exists() {
+            copy_entries(&from.log_dir, &to.log_dir, &mut copied)?;
+        }
+        Ok(())
+    };
+    if let Err(e) = copy() {
+        for dir in [to.db_dir(), &to.node_dir, &to.log_dir] {
+            let _ = fs::remove_dir_all(dir);
+        }
+        return Err(format!(
+            "Failed to copy data to {}: {}",
+            target.display(),
+            e
+        ));
+    }
+    // The live log keeps being written until the next launch
+    if let Some(live) = live_log(&from.log_dir) {
+        copied.retain(|path| *path != live);
+    }
+    Ok(MovedData { layout: to, copied })
+}
+
+/// Use `layout`, copied by [`copy_data`], from now on and on later launches.
+pub fn switch_to(layout: DataLayout) -> Result<(), String> {
+    let default = default_layout()?;
+    fs::create_dir_all(&default.root)
+        .and_then(|_| {
+            fs::write(
+                default.root.join(LOCATION_FILE),
+                layout.root.to_string_lossy().as_bytes(),
+            )
+        })
+        .map_err(|e| format!("Failed to record the new data directory: {}", e))?;
+    *LAYOUT.write().unwrap() = Some(layout);
+    Ok(())
+}
+
+/// Delete what [`copy_data`] copied out of `old`, then its database and
+/// node directories if that left them empty. Anything else under them, and
+/// the log directory itself (the OS one for the default layout), stays.
+pub fn remove_moved_data(old: &DataLayout, moved: &MovedData) {
+    for path in &moved.copied {
+        let result = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        if let Err(e) = result {
+            if e.kind() != io::ErrorKind::NotFound {
+                log::warn!("[DataDir] Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+    for dir in [old.db_dir(), &old.node_dir] {
+        if dir != old.root && is_empty_dir(dir) {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("kaleido-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(args(&["app"])), CliArgs::default());
+        let parsed = parse_args(args(&["app", "--data-dir", "/d", "--portable"]));
+        assert_eq!(parsed.data_dir, Some(PathBuf::from("/d")));
+        assert!(parsed.portable);
+        let parsed = parse_args(args(&["app", "-psn_0_1", "--data-dir=/e"]));
+        assert_eq!(parsed.data_dir, Some(PathBuf::from("/e")));
+    }
+
+    #[test]
+    fn test_resolve_order() {
+        let default = DataLayout::under(PathBuf::from("/default"), DataRootSource::Default);
+        let exe_dir = temp_dir("exe");
+        let inputs = || Inputs {
+            cli: CliArgs {
+                data_dir: Some(PathBuf::from("/cli")),
+                portable: false,
+            },
+            env_dir: Some("/env".into()),
+            env_portable: true,
+            exe_dir: Some(exe_dir.clone()),
+            moved_to: Some("/moved\n".into()),
+        };
+
+        let layout = resolve(inputs(), default.clone());
+        assert_eq!(layout.source, DataRootSource::CliFlag);
+        assert_eq!(layout.db_path, PathBuf::from("/cli/db/database.sqlite"));
+
+        let layout = resolve(
+            Inputs {
+                cli: CliArgs::default(),
+                ..inputs()
+            },
+            default.clone(),
+        );
+        assert_eq!(layout.root, PathBuf::from("/env"));
+
+        let layout = resolve(
+            Inputs {
+                cli: CliArgs::default(),
+                env_dir: None,
+                ..inputs()
+            },
+            default.clone(),
+        );
+        assert_eq!(layout.source, DataRootSource::Portable);
+        assert_eq!(layout.node_dir, exe_dir.join("kaleidoswap-data/data"));
+
+        let no_overrides = || Inputs {
+            cli: CliArgs::default(),
+            env_dir: None,
+            env_portable: false,
+            ..inputs()
+        };
+        let layout = resolve(no_overrides(), default.clone());
+        assert_eq!(layout.source, DataRootSource::Moved);
+        assert_eq!(layout.root, PathBuf::from("/moved"));
+
+        // A marker file next to the executable also turns on portable mode
+        fs::write(exe_dir.join(PORTABLE_MARKER), "").unwrap();
+        let layout = resolve(no_overrides(), default.clone());
+        assert_eq!(layout.source, DataRootSource::Portable);
+
+        let layout = resolve(Inputs::default(), default.clone());
+        assert_eq!(layout, default);
+        fs::remove_dir_all(&exe_dir).unwrap();
+    }
+
+    #[test]
+    fn test_copy_data() {
+        let dir = temp_dir("move");
+        let from = DataLayout::under(dir.join("old"), DataRootSource::Moved);
+        fs::create_dir_all(from.node_dir.join("alice")).unwrap();
+        fs::create_dir_all(from.db_dir()).unwrap();
+        fs::write(&from.db_path, "db").unwrap();
+        fs::write(from.node_dir.join("alice/wallet"), "w").unwrap();
+
+        let pinned = DataLayout::under(dir.join("old"), DataRootSource::Env);
+        assert!(copy_data(&pinned, &dir.join("new")).is_err());
+        assert!(copy_data(&from, Path::new("relative")).is_err());
+        assert!(copy_data(&from, &from.node_dir.join("nested")).is_err());
+
+        let taken = dir.join("taken");
+        fs::create_dir_all(taken.join("db")).unwrap();
+        fs::write(taken.join("db/database.sqlite"), "other").unwrap();
+        assert!(copy_data(&from, &taken).is_err());
+
+        // The log written to right now is the newest
+        fs::create_dir_all(&from.log_dir).unwrap();
+        let old_log = from.log_dir.join("app_2026-01-01.log");
+        fs::write(&old_log, "old").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&old_log)
+            .unwrap()
+            .set_modified(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap();
+        fs::write(from.log_dir.join("app.log"), "live").unwrap();
+
+        let moved = copy_data(&from, &dir.join("new")).unwrap();
+        let to = &moved.layout;
+        assert_eq!(to.source, DataRootSource::Moved);
+        assert_eq!(fs::read_to_string(&to.db_path).unwrap(), "db");
+        assert_eq!(
+            fs::read_to_string(to.node_dir.join("alice/wallet")).unwrap(),
+            "w"
+        );
+        assert!(to.log_dir.join("app.log").exists());
+
+        // Written after the copy, so not moved
+        fs::write(from.node_dir.join("bob"), "b").unwrap();
+        remove_moved_data(&from, &moved);
+        assert!(!from.db_path.exists() && !from.db_dir().exists());
+        assert!(!from.node_dir.join("alice").exists());
+        assert!(from.node_dir.join("bob").exists());
+        assert!(!old_log.exists());
+        assert!(from.log_dir.join("app.log").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
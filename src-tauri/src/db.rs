@@ -1,7 +1,14 @@
+use crate::data_dir;
+use crate::migrations;
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{Connection, OptionalExtension};
-use serde::Serialize;
-use std::path::{Path, PathBuf};
-use std::{env, fs};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
 
 #[derive(Debug, Serialize, Clone)]
 pub struct Account {
@@ -25,6 +32,12 @@ pub struct Account {
     pub language: Option<String>,
 }
 
+/// A channel bought from an LSP (LSPS1 order).
+///
+/// `status` is the LSP's order state (`CREATED`, `COMPLETED`, `FAILED`), or
+/// `EXPIRED` once the payment window passed without a payment. `payload` is
+/// the order request as sent; the typed fields are filled from it and from
+/// the LSP's order responses.
 #[derive(Debug, Serialize, Clone)]
 pub struct ChannelOrder {
     pub id: i32,
@@ -33,8 +46,40 @@ pub struct ChannelOrder {
     pub created_at: String,
     pub status: String,
     pub payload: String,
+    pub lsp_url: Option<String>,
+    pub access_token: Option<String>,
+    pub client_balance_sat: Option<i64>,
+    pub lsp_balance_sat: Option<i64>,
+    pub asset_id: Option<String>,
+    pub client_asset_amount: Option<i64>,
+    pub lsp_asset_amount: Option<i64>,
+    /// State of the payment (`EXPECT_PAYMENT`, `HOLD`, `PAID`, ...).
+    pub payment_state: Option<String>,
+    pub fee_total_sat: Option<i64>,
+    pub order_total_sat: Option<i64>,
+    /// When the payment window closes (RFC 3339).
+    pub expires_at: Option<String>,
+    /// Funding outpoint of the opened channel.
+    pub channel_id: Option<String>,
+    /// Unix seconds of the last status change.
+    pub updated_at: Option<i64>,
+}
+
+/// New state of a channel order, as reported by the LSP. Fields left `None`
+/// keep their stored value.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct ChannelOrderUpdate {
+    pub status: String,
+    pub payment_state: Option<String>,
+    pub fee_total_sat: Option<i64>,
+    pub order_total_sat: Option<i64>,
+    pub expires_at: Option<String>,
+    pub channel_id: Option<String>,
 }
 
+/// Order states after which an order no longer changes.
+pub const CHANNEL_ORDER_FINAL_STATES: &[&str] = &["COMPLETED", "FAILED", "EXPIRED"];
+
 /// A Nostr Wallet Connect (NIP-47) app connection.
 ///
 /// Each connected app gets its own randomly generated `client_secret` (the key
@@ -65,141 +110,372 @@ pub struct NwcConnection {
     pub last_used_at: Option<i64>,
 }
 
-// Check if a database file exists, and create one if it does not.
-pub fn init() {
-    // Create database file if it doesn't exist
-    if !db_file_exists() {
+/// One attempt at executing a DCA order, successful or not.
+///
+/// `amount_in` is the spent amount in `spend_asset` (the quote asset for buys,
+/// the base asset for sells); `amount_out` and `fee` are in the smallest unit
+/// of the received asset. `quoted_price` is the pair's price (quote asset per
+/// base asset) the swap was quoted at, 0 when no quote was obtained.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DcaExecution {
+    pub id: i64,
+    pub account_id: i32,
+    pub order_id: String,
+    pub executed_at: i64,
+    pub quoted_price: f64,
+    pub amount_in: f64,
+    pub amount_out: i64,
+    pub fee: i64,
+    pub swap_id: Option<String>,
+    /// "success" | "failed" | "missed"
+    pub status: String,
+    pub error: Option<String>,
+    /// Ticker of the asset spent; None for rows recorded before it was stored.
+    #[serde(default)]
+    pub spend_asset: Option<String>,
+}
+
+/// One successful execution, as needed for DCA spend limits.
+#[derive(Debug, Clone)]
+pub struct DcaSpendRow {
+    pub order_id: String,
+    pub spend_asset: Option<String>,
+    pub executed_at: i64,
+    pub amount_in: f64,
+}
+
+/// Shared pool of connections to the app database.
+pub type DbPool = Pool<SqliteConnectionManager>;
+
+// The open database and, in encrypted mode, its key. `None` until `init` has
+// run, which for an encrypted database means until it was unlocked.
+struct OpenDb {
+    pool: DbPool,
+    key: Option<String>,
+}
+
+static DB: RwLock<Option<OpenDb>> = RwLock::new(None);
+
+// Why the database could not be reopened after a re-key, which left it
+// closed. Cleared when it is opened again.
+static REOPEN_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Each connection derives the SQLCipher key from the password, so keep the
+// pool small.
+const POOL_SIZE: u32 = 4;
+
+// First bytes of every plaintext SQLite file. SQLCipher files are random from
+// the first byte on.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+// Per-connection settings. SQLite leaves foreign keys off by default, which
+// would make every ON DELETE CASCADE a no-op. The key has to come first.
+fn configure(conn: &mut Connection, key: Option<&str>) -> Result<(), rusqlite::Error> {
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", key)?;
+    }
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update(None, "foreign_keys", true)
+}
+
+/// Open a pool on the database at `path`, decrypting it with `key` when
+/// given, in WAL mode so readers don't block the writer.
+pub fn open_pool(path: impl AsRef<Path>, key: Option<&str>) -> Result<DbPool, String> {
+    // A wrong key only shows on the first read; check it once up front.
+    let mut probe =
+        Connection::open(&path).map_err(|e| format!("Failed to open database: {}", e))?;
+    configure(&mut probe, key).map_err(|e| e.to_string())?;
+    probe
+        .query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map_err(|e| match e.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::NotADatabase) if key.is_some() => {
+                "Wrong database password".to_string()
+            }
+            Some(rusqlite::ErrorCode::NotADatabase) => "Database is encrypted".to_string(),
+            _ => format!("Failed to open database: {}", e),
+        })?;
+    drop(probe);
+
+    let key = key.map(str::to_owned);
+    let manager =
+        SqliteConnectionManager::file(path).with_init(move |conn| configure(conn, key.as_deref()));
+    let pool = Pool::builder()
+        .max_size(POOL_SIZE)
+        .min_idle(Some(1))
+        .build(manager)
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to enable WAL: {}", e))?;
+    Ok(pool)
+}
+
+/// Run `f` on a connection from the app pool. [`init`] must have run.
+pub fn with<T>(
+    f: impl FnOnce(&Connection) -> Result<T, rusqlite::Error>,
+) -> Result<T, rusqlite::Error> {
+    // Held for the duration of `f` so a re-key can't swap the file under it.
+    let db = DB.read().unwrap();
+    let db = db.as_ref().ok_or_else(|| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+            Some("Database is locked or not initialized".to_string()),
+        )
+    })?;
+    let conn = db.pool.get().map_err(|e| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            Some(format!("No database connection available: {}", e)),
+        )
+    })?;
+    f(&conn)
+}
+
+/// Whether the file at `path` is an encrypted (SQLCipher) database. Missing
+/// and empty files are not.
+pub fn is_encrypted_file(path: impl AsRef<Path>) -> bool {
+    let mut header = [0u8; 16];
+    match fs::File::open(path).and_then(|mut f| f.read(&mut header)) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => &header != SQLITE_HEADER,
+    }
+}
+
+pub fn is_encrypted() -> bool {
+    is_encrypted_file(get_db_path())
+}
+
+/// Whether `init` has run, i.e. the database is open.
+pub fn is_open() -> bool {
+    DB.read().unwrap().is_some()
+}
+
+/// Why a re-key left the database closed, until it is opened again.
+pub fn reopen_error() -> Option<String> {
+    REOPEN_ERROR.lock().unwrap().clone()
+}
+
+// Create the database file if needed, bring its schema up to date and open
+// the app pool. `key` is the password of an encrypted database; a plaintext
+// one is opened with `None`.
+//
+// Pending migrations run after a backup of the existing file; any failure is
+// returned so startup stops instead of running against a half-migrated DB.
+pub fn init(key: Option<&str>) -> Result<(), String> {
+    let mut db = DB.write().unwrap();
+    if db.is_some() {
+        return Err("Database is already initialized".to_string());
+    }
+
+    let existed = db_file_exists();
+    if !existed {
         create_db_file();
     }
 
-    // Create/verify tables regardless of whether the file existed
     let path = get_db_path();
-    let conn = Connection::open(path).unwrap();
+    let pool = open_pool(&path, key)?;
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+
+    let pending = migrations::pending_count(&conn)?;
+    if pending > 0 {
+        if existed {
+            let backup = backup_db(&conn, &path, key)?;
+            log::info!(
+                "[DB] {} pending migration(s), backed up database to {}",
+                pending,
+                backup
+            );
+        }
+        migrations::run(&mut conn)?;
+    }
+    drop(conn);
+
+    *db = Some(OpenDb {
+        pool,
+        key: key.map(str::to_owned),
+    });
+    *REOPEN_ERROR.lock().unwrap() = None;
+    Ok(())
+}
 
+// Write a copy of the database `conn` is open on to `target`, encrypted with
+// `key` (plaintext for `None`).
+fn export_db(conn: &Connection, target: &str, key: Option<&str>) -> Result<(), rusqlite::Error> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS accounts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            network TEXT NOT NULL,
-            datapath TEXT,
-            rpc_connection_url TEXT NOT NULL,
-            node_url TEXT NOT NULL,
-            indexer_url TEXT NOT NULL,
-            proxy_endpoint TEXT NOT NULL,
-            default_lsp_url TEXT NOT NULL,
-            maker_urls TEXT NOT NULL,
-            default_maker_url TEXT NOT NULL,
-            daemon_listening_port TEXT NOT NULL,
-            ldk_peer_listening_port TEXT NOT NULL,
-            bearer_token TEXT,
-            terms_accepted INTEGER DEFAULT 0,
-            encrypted_mnemonic TEXT,
-            mnemonic_salt TEXT,
-            mnemonic_nonce TEXT,
-            language TEXT DEFAULT 'en'
-        )",
-        [],
-    )
-    .unwrap();
+        "ATTACH DATABASE ?1 AS export KEY ?2",
+        [target, key.unwrap_or("")],
+    )?;
+    let exported = conn.query_row("SELECT sqlcipher_export('export')", [], |_| Ok(()));
+    conn.execute("DETACH DATABASE export", [])?;
+    exported
+}
 
-    // Add mnemonic encryption columns if they don't exist (migration)
-    let _ = conn.execute(
-        "ALTER TABLE Accounts ADD COLUMN encrypted_mnemonic TEXT",
-        (),
-    );
-    let _ = conn.execute("ALTER TABLE Accounts ADD COLUMN mnemonic_salt TEXT", ());
-    let _ = conn.execute("ALTER TABLE Accounts ADD COLUMN mnemonic_nonce TEXT", ());
+// Copy the database next to itself before migrating it, under the same key.
+// The name carries the schema version it was taken at, so earlier backups are
+// kept.
+fn backup_db(conn: &Connection, path: &str, key: Option<&str>) -> Result<String, String> {
+    let version: u32 = conn
+        .query_row(
+            "SELECT COALESCE(MAX(id), 0) FROM schema_version",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let backup = format!("{}.v{}.bak", path, version);
+    if Path::new(&backup).exists() {
+        fs::remove_file(&backup).map_err(|e| format!("Failed to replace {}: {}", backup, e))?;
+    }
+    export_db(conn, &backup, key)
+        .map_err(|e| format!("Failed to back up database before migrating: {}", e))?;
+    Ok(backup)
+}
 
-    // Add language column if it doesn't exist (migration)
-    let _ = conn.execute(
-        "ALTER TABLE Accounts ADD COLUMN language TEXT DEFAULT 'en'",
-        (),
-    );
-    // Add ChannelOrders table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS 'ChannelOrders' (
-            'id' INTEGER PRIMARY KEY AUTOINCREMENT,
-            'account_id' INTEGER NOT NULL,
-            'order_id' TEXT NOT NULL,
-            'created_at' TEXT NOT NULL,
-            'status' TEXT NOT NULL,
-            'payload' TEXT NOT NULL,
-            FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
-        );",
-        (),
-    )
-    .unwrap();
+fn remove_wal_files(path: &str) {
+    for suffix in ["-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", path, suffix));
+    }
+}
 
-    // Add account_id column to existing ChannelOrders table if it doesn't exist
-    let _ = conn.execute(
-        "ALTER TABLE ChannelOrders ADD COLUMN account_id INTEGER",
-        (),
-    );
-    // Note: We ignore the error if the column already exists
+// Re-write the database at `path` under `new_key` and swap it in place of the
+// original. `pool` is closed in the process, whatever the outcome.
+fn rekey_file(pool: DbPool, path: &str, new_key: Option<&str>) -> Result<(), String> {
+    let tmp = format!("{}.rekey", path);
+    let _ = fs::remove_file(&tmp);
+    {
+        let conn = pool.get().map_err(|e| e.to_string())?;
+        export_db(&conn, &tmp, new_key).map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            format!("Failed to re-encrypt database: {}", e)
+        })?;
+    }
+    // Closing the last connection checkpoints the WAL. What is left of it
+    // belongs to the old file and must not be replayed onto the new one.
+    drop(pool);
+    remove_wal_files(path);
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace database: {}", e))
+}
 
-    // Migrate existing orders without account_id to the first available account
-    migrate_existing_orders(&conn);
+/// Encrypt, re-key or decrypt the open database. `current` must match the
+/// key it was opened with (`None` for plaintext); `new` is the key to use
+/// from now on (`None` to store it in plaintext).
+///
+/// Backups taken before migrations are deleted when encrypting, as they are
+/// plaintext copies of the same data.
+pub fn change_key(current: Option<&str>, new: Option<&str>) -> Result<(), String> {
+    let mut db = DB.write().unwrap();
+    let open = db.take().ok_or("Database is locked or not initialized")?;
+    if open.key.as_deref() != current {
+        let error = if current.is_some() {
+            "Wrong database password"
+        } else {
+            "Database is already encrypted"
+        };
+        *db = Some(open);
+        return Err(error.to_string());
+    }
 
-    // Add DcaOrders table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS 'DcaOrders' (
-            'id' INTEGER PRIMARY KEY AUTOINCREMENT,
-            'account_id' INTEGER NOT NULL,
-            'order_id' TEXT NOT NULL,
-            'payload' TEXT NOT NULL,
-            UNIQUE(account_id, order_id),
-            FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
-        );",
-        (),
-    )
-    .unwrap();
+    let path = get_db_path();
+    let (reopened, result) = rekey(open.pool, &path, current, new, |path, key| {
+        open_pool(path, key)
+    });
+    match reopened {
+        Ok(open) => *db = Some(open),
+        Err(e) => {
+            // Stay closed: `db_status` reports it and the database can be
+            // unlocked again, or opened on the next start.
+            log::error!("[DB] Failed to reopen database after re-key: {}", e);
+            *REOPEN_ERROR.lock().unwrap() = Some(e.clone());
+            return Err(format!("Database is locked: {}", e));
+        }
+    }
+    result?;
 
-    // Add LimitOrders table
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS 'LimitOrders' (
-            'id' INTEGER PRIMARY KEY AUTOINCREMENT,
-            'account_id' INTEGER NOT NULL,
-            'order_id' TEXT NOT NULL,
-            'payload' TEXT NOT NULL,
-            UNIQUE(account_id, order_id),
-            FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
-        );",
-        (),
-    )
-    .unwrap();
+    if current.is_none() && new.is_some() {
+        remove_plaintext_backups(&path);
+    }
+    log::info!(
+        "[DB] Database is now {}",
+        if new.is_some() {
+            "encrypted"
+        } else {
+            "in plaintext"
+        }
+    );
+    Ok(())
+}
 
-    // Add AppSettings table for key-value config (e.g. node backend type)
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS 'AppSettings' (
-            'key' TEXT PRIMARY KEY NOT NULL,
-            'value' TEXT NOT NULL
-        );",
-        (),
-    )
-    .unwrap();
+// Re-key the file at `path` from `current` to `new`, then reopen it with
+// `open` under whichever key it is now in: `new` once re-keyed, else
+// `current`. Should that fail, the other key is tried too, in case the file
+// is not in the state the re-key reported. Returns the reopened database
+// and the outcome of the re-key.
+fn rekey(
+    pool: DbPool,
+    path: &str,
+    current: Option<&str>,
+    new: Option<&str>,
+    open: impl Fn(&str, Option<&str>) -> Result<DbPool, String>,
+) -> (Result<OpenDb, String>, Result<(), String>) {
+    let result = rekey_file(pool, path, new);
+    let (key, other) = if result.is_ok() {
+        (new, current)
+    } else {
+        (current, new)
+    };
+    let reopened = open(path, key)
+        .map(|pool| (pool, key))
+        .or_else(|e| {
+            log::warn!(
+                "[DB] Failed to reopen database: {}; trying the other key",
+                e
+            );
+            open(path, other).map(|pool| (pool, other)).map_err(|_| e)
+        })
+        .map(|(pool, key)| OpenDb {
+            pool,
+            key: key.map(str::to_owned),
+        });
+    (reopened, result)
+}
 
-    // Add NwcConnections table (Nostr Wallet Connect app connections)
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS 'NwcConnections' (
-            'id' INTEGER PRIMARY KEY AUTOINCREMENT,
-            'account_id' INTEGER NOT NULL,
-            'name' TEXT NOT NULL,
-            'client_pubkey' TEXT NOT NULL,
-            'client_secret' TEXT NOT NULL,
-            'relays_json' TEXT NOT NULL,
-            'methods_json' TEXT NOT NULL,
-            'budget_msat' INTEGER,
-            'spent_msat' INTEGER NOT NULL DEFAULT 0,
-            'budget_renews_at' INTEGER,
-            'enabled' INTEGER NOT NULL DEFAULT 1,
-            'created_at' INTEGER NOT NULL,
-            'last_used_at' INTEGER,
-            UNIQUE(account_id, client_pubkey),
-            FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
-        );",
-        (),
-    )
-    .unwrap();
+/// Close the database, run `f`, and reopen it at [`get_db_path`], which `f`
+/// may have changed, under the same key.
+pub fn with_closed<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let mut db = DB.write().unwrap();
+    let open = db.take().ok_or("Database is locked or not initialized")?;
+    // Closing the last connection checkpoints the WAL into the file.
+    drop(open.pool);
+    let result = f();
+    let pool = open_pool(get_db_path(), open.key.as_deref())
+        .map_err(|e| format!("Failed to reopen database: {}", e))?;
+    *db = Some(OpenDb {
+        pool,
+        key: open.key,
+    });
+    result
+}
+
+fn remove_plaintext_backups(path: &str) {
+    let path = Path::new(path);
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+    let prefix = format!("{}.v", name.to_string_lossy());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let file = entry.file_name().to_string_lossy().to_string();
+        if file.starts_with(&prefix) && file.ends_with(".bak") && !is_encrypted_file(entry.path()) {
+            if let Err(e) = fs::remove_file(entry.path()) {
+                log::warn!("[DB] Failed to remove plaintext backup {}: {}", file, e);
+            }
+        }
+    }
 }
 
 // Create the database file.
@@ -222,32 +498,11 @@ pub fn db_file_exists() -> bool {
     Path::new(&db_path).exists()
 }
 
-// Get the path where the database file should be located.
+// Get the path where the database file should be located (see `data_dir`).
 pub fn get_db_path() -> String {
-    let app_data_dir = if cfg!(debug_assertions) {
-        // During development, use the manifest directory
-        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
-    } else {
-        // In production, use the local app data directory
-        let local_app_data = if cfg!(target_os = "macos") {
-            let home = env::var("HOME").expect("Failed to get HOME directory");
-            PathBuf::from(home).join("Library/Application Support/com.kaleidoswap.dev")
-        } else if cfg!(target_os = "windows") {
-            let local_app_data =
-                env::var("LOCALAPPDATA").expect("Failed to get LOCALAPPDATA directory");
-            PathBuf::from(local_app_data).join("com.kaleidoswap.dev")
-        } else {
-            // Linux
-            let home = env::var("HOME").expect("Failed to get HOME directory");
-            PathBuf::from(home).join(".local/share/com.kaleidoswap.dev")
-        };
-
-        // Create the directory if it doesn't exist
-        fs::create_dir_all(&local_app_data).expect("Failed to create app data directory");
-        local_app_data
-    };
-
-    let db_path = app_data_dir.join("db/database.sqlite");
+    let db_path = data_dir::layout()
+        .expect("Failed to resolve the data directory")
+        .db_path;
 
     // Ensure the parent directory exists
     if let Some(parent) = db_path.parent() {
@@ -257,39 +512,7 @@ pub fn get_db_path() -> String {
     db_path.to_str().unwrap().to_string()
 }
 
-fn migrate_existing_orders(conn: &Connection) {
-    // Check if there are any orders without account_id
-    let count_result: Result<i64, _> = conn.query_row(
-        "SELECT COUNT(*) FROM ChannelOrders WHERE account_id IS NULL",
-        [],
-        |row| row.get(0),
-    );
-
-    if let Ok(count) = count_result {
-        if count > 0 {
-            // Get the first account id
-            let first_account_result: Result<i32, _> =
-                conn.query_row("SELECT id FROM Accounts ORDER BY id LIMIT 1", [], |row| {
-                    row.get(0)
-                });
-
-            if let Ok(first_account_id) = first_account_result {
-                // Update all orders without account_id to use the first account
-                let _ = conn.execute(
-                    "UPDATE ChannelOrders SET account_id = ?1 WHERE account_id IS NULL",
-                    [first_account_id],
-                );
-                println!(
-                    "Migrated {} existing channel orders to account {}",
-                    count, first_account_id
-                );
-            }
-        }
-    }
-}
-
-pub fn get_accounts() -> Result<Vec<Account>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn get_accounts(conn: &Connection) -> Result<Vec<Account>, rusqlite::Error> {
     let mut stmt = conn.prepare("SELECT id, name, network, datapath, rpc_connection_url, node_url, indexer_url, proxy_endpoint, default_lsp_url, maker_urls, default_maker_url, daemon_listening_port, ldk_peer_listening_port, bearer_token, encrypted_mnemonic, mnemonic_salt, mnemonic_nonce, language FROM Accounts")?;
     let accounts = stmt
         .query_map([], |row| {
@@ -322,6 +545,7 @@ pub fn get_accounts() -> Result<Vec<Account>, rusqlite::Error> {
 
 #[allow(clippy::too_many_arguments)]
 pub fn insert_account(
+    conn: &Connection,
     name: String,
     network: String,
     datapath: Option<String>,
@@ -337,8 +561,6 @@ pub fn insert_account(
     bearer_token: Option<String>,
     language: Option<String>,
 ) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
-
     // Check if an account with the same name already exists
     let mut stmt = conn.prepare("SELECT COUNT(*) FROM Accounts WHERE name = ?")?;
     let count: i64 = stmt.query_row([&name], |row| row.get(0))?;
@@ -359,6 +581,7 @@ pub fn insert_account(
 
 #[allow(clippy::too_many_arguments)]
 pub fn update_account(
+    conn: &Connection,
     name: String,
     network: String,
     datapath: Option<String>,
@@ -374,7 +597,6 @@ pub fn update_account(
     bearer_token: Option<String>,
     language: Option<String>,
 ) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
     conn.execute(
         "UPDATE Accounts SET
             network = ?1,
@@ -410,8 +632,10 @@ pub fn update_account(
     )
 }
 
-pub fn get_account_by_name(name: &str) -> Result<Option<Account>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn get_account_by_name(
+    conn: &Connection,
+    name: &str,
+) -> Result<Option<Account>, rusqlite::Error> {
     let mut stmt = conn.prepare("SELECT id, name, network, datapath, rpc_connection_url, node_url, indexer_url, proxy_endpoint, default_lsp_url, maker_urls, default_maker_url, daemon_listening_port, ldk_peer_listening_port, bearer_token, encrypted_mnemonic, mnemonic_salt, mnemonic_nonce, language FROM Accounts WHERE name = ?")?;
     let account = stmt
         .query_row([name], |row| {
@@ -441,18 +665,23 @@ pub fn get_account_by_name(name: &str) -> Result<Option<Account>, rusqlite::Erro
     Ok(account)
 }
 
-pub fn delete_account(name: String) -> Result<usize, rusqlite::Error> {
+pub fn delete_account(conn: &Connection, name: String) -> Result<usize, rusqlite::Error> {
     // First get the account to check its datapath
-    let account = get_account_by_name(&name)?;
+    let account = get_account_by_name(conn, &name)?;
 
-    let conn = Connection::open(get_db_path())?;
     let result = conn.execute("DELETE FROM Accounts WHERE name = ?1", [name])?;
 
     // If account exists and has a datapath, delete the directory
     if let Some(account) = account {
         if let Some(datapath) = account.datapath {
             if !datapath.is_empty() {
-                let full_path = PathBuf::from("../bin").join(datapath);
+                let full_path = match data_dir::layout() {
+                    Ok(layout) => layout.node_dir.join(datapath),
+                    Err(e) => {
+                        println!("Not deleting account folder: {}", e);
+                        return Ok(result);
+                    }
+                };
                 let full_path_str = full_path.to_string_lossy();
 
                 println!("Attempting to delete account folder at: {}", full_path_str);
@@ -470,79 +699,175 @@ pub fn delete_account(name: String) -> Result<usize, rusqlite::Error> {
     Ok(result)
 }
 
-pub fn check_account_exists(name: &str) -> Result<bool, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn check_account_exists(conn: &Connection, name: &str) -> Result<bool, rusqlite::Error> {
     let mut stmt = conn.prepare("SELECT COUNT(*) FROM Accounts WHERE name = ?")?;
     let count: i64 = stmt.query_row([name], |row| row.get(0))?;
     Ok(count > 0)
 }
 
+const CHANNEL_ORDER_COLUMNS: &str = "id, account_id, order_id, created_at, status, payload, lsp_url, access_token, client_balance_sat, lsp_balance_sat, asset_id, client_asset_amount, lsp_asset_amount, payment_state, fee_total_sat, order_total_sat, expires_at, channel_id, updated_at";
+
+fn row_to_channel_order(row: &rusqlite::Row) -> Result<ChannelOrder, rusqlite::Error> {
+    Ok(ChannelOrder {
+        id: row.get(0)?,
+        account_id: row.get(1)?,
+        order_id: row.get(2)?,
+        created_at: row.get(3)?,
+        status: row.get(4)?,
+        payload: row.get(5)?,
+        lsp_url: row.get(6)?,
+        access_token: row.get(7)?,
+        client_balance_sat: row.get(8)?,
+        lsp_balance_sat: row.get(9)?,
+        asset_id: row.get(10)?,
+        client_asset_amount: row.get(11)?,
+        lsp_asset_amount: row.get(12)?,
+        payment_state: row.get(13)?,
+        fee_total_sat: row.get(14)?,
+        order_total_sat: row.get(15)?,
+        expires_at: row.get(16)?,
+        channel_id: row.get(17)?,
+        updated_at: row.get(18)?,
+    })
+}
+
+/// Store an order, or replace the stored one with the same `order_id`. The
+/// typed fields are read from `payload`; `lsp_url` is kept when `None`.
+#[allow(clippy::too_many_arguments)]
 pub fn insert_channel_order(
+    conn: &Connection,
     account_id: i32,
     order_id: String,
     status: String,
     payload: String,
     created_at: String,
+    lsp_url: Option<String>,
+    now: i64,
 ) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
-    let updated = conn.execute(
-        "UPDATE ChannelOrders SET created_at = ?3, status = ?4, payload = ?5 WHERE account_id = ?1 AND order_id = ?2",
-        rusqlite::params![account_id, order_id, created_at, status, payload],
+    let mut updated = conn.execute(
+        "UPDATE ChannelOrders
+         SET created_at = ?3, status = ?4, payload = ?5, lsp_url = COALESCE(?6, lsp_url), updated_at = ?7
+         WHERE account_id = ?1 AND order_id = ?2",
+        rusqlite::params![account_id, order_id, created_at, status, payload, lsp_url, now],
     )?;
-
-    if updated > 0 {
-        return Ok(updated);
+    if updated == 0 {
+        updated = conn.execute(
+            "INSERT INTO ChannelOrders
+                (account_id, order_id, created_at, status, payload, lsp_url, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![account_id, order_id, created_at, status, payload, lsp_url, now],
+        )?;
     }
 
     conn.execute(
-        "INSERT INTO ChannelOrders (account_id, order_id, created_at, status, payload) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![account_id, order_id, created_at, status, payload],
+        "UPDATE ChannelOrders SET
+            access_token = COALESCE(
+                json_extract(payload, '$.access_token'),
+                json_extract(payload, '$.token'),
+                access_token
+            ),
+            client_balance_sat = json_extract(payload, '$.client_balance_sat'),
+            lsp_balance_sat = json_extract(payload, '$.lsp_balance_sat'),
+            asset_id = json_extract(payload, '$.asset_id'),
+            client_asset_amount = json_extract(payload, '$.client_asset_amount'),
+            lsp_asset_amount = json_extract(payload, '$.lsp_asset_amount')
+         WHERE account_id = ?1 AND order_id = ?2 AND json_valid(payload)",
+        rusqlite::params![account_id, order_id],
+    )?;
+    Ok(updated)
+}
+
+pub fn get_channel_orders(
+    conn: &Connection,
+    account_id: Option<i32>,
+) -> Result<Vec<ChannelOrder>, rusqlite::Error> {
+    let sql = format!(
+        "SELECT {} FROM ChannelOrders
+         WHERE ?1 IS NULL OR account_id = ?1
+         ORDER BY created_at DESC",
+        CHANNEL_ORDER_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let orders = stmt
+        .query_map([account_id], row_to_channel_order)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(orders)
+}
+
+/// Orders of an account the LSP may still change: not in a final state, and
+/// with the access token needed to ask for them.
+pub fn get_pending_channel_orders(
+    conn: &Connection,
+    account_id: i32,
+) -> Result<Vec<ChannelOrder>, rusqlite::Error> {
+    let sql = format!(
+        "SELECT {} FROM ChannelOrders
+         WHERE account_id = ?1 AND access_token IS NOT NULL
+           AND UPPER(status) NOT IN ('COMPLETED', 'FAILED', 'EXPIRED')
+         ORDER BY created_at ASC",
+        CHANNEL_ORDER_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let orders = stmt
+        .query_map([account_id], row_to_channel_order)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(orders)
+}
+
+pub fn get_channel_order(
+    conn: &Connection,
+    account_id: i32,
+    order_id: &str,
+) -> Result<Option<ChannelOrder>, rusqlite::Error> {
+    let sql = format!(
+        "SELECT {} FROM ChannelOrders WHERE account_id = ?1 AND order_id = ?2",
+        CHANNEL_ORDER_COLUMNS
+    );
+    conn.query_row(
+        &sql,
+        rusqlite::params![account_id, order_id],
+        row_to_channel_order,
     )
+    .optional()
 }
 
-pub fn get_channel_orders(account_id: Option<i32>) -> Result<Vec<ChannelOrder>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
-
-    match account_id {
-        Some(id) => {
-            let mut stmt = conn.prepare("SELECT id, account_id, order_id, created_at, status, payload FROM ChannelOrders WHERE account_id = ?1 ORDER BY created_at DESC")?;
-            let orders = stmt
-                .query_map([id], |row| {
-                    Ok(ChannelOrder {
-                        id: row.get(0)?,
-                        account_id: row.get(1)?,
-                        order_id: row.get(2)?,
-                        created_at: row.get(3)?,
-                        status: row.get(4)?,
-                        payload: row.get(5)?,
-                    })
-                })?
-                .map(|res| res.unwrap())
-                .collect();
-            Ok(orders)
-        }
-        None => {
-            let mut stmt = conn.prepare("SELECT id, account_id, order_id, created_at, status, payload FROM ChannelOrders ORDER BY created_at DESC")?;
-            let orders = stmt
-                .query_map([], |row| {
-                    Ok(ChannelOrder {
-                        id: row.get(0)?,
-                        account_id: row.get(1)?,
-                        order_id: row.get(2)?,
-                        created_at: row.get(3)?,
-                        status: row.get(4)?,
-                        payload: row.get(5)?,
-                    })
-                })?
-                .map(|res| res.unwrap())
-                .collect();
-            Ok(orders)
-        }
-    }
+/// Record a new state of an order. Returns the number of orders changed.
+pub fn update_channel_order_status(
+    conn: &Connection,
+    account_id: i32,
+    order_id: &str,
+    update: &ChannelOrderUpdate,
+    now: i64,
+) -> Result<usize, rusqlite::Error> {
+    conn.execute(
+        "UPDATE ChannelOrders SET
+            status = ?3,
+            payment_state = COALESCE(?4, payment_state),
+            fee_total_sat = COALESCE(?5, fee_total_sat),
+            order_total_sat = COALESCE(?6, order_total_sat),
+            expires_at = COALESCE(?7, expires_at),
+            channel_id = COALESCE(?8, channel_id),
+            updated_at = ?9
+         WHERE account_id = ?1 AND order_id = ?2",
+        rusqlite::params![
+            account_id,
+            order_id,
+            update.status,
+            update.payment_state,
+            update.fee_total_sat,
+            update.order_total_sat,
+            update.expires_at,
+            update.channel_id,
+            now
+        ],
+    )
 }
 
-pub fn delete_channel_order(account_id: i32, order_id: String) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn delete_channel_order(
+    conn: &Connection,
+    account_id: i32,
+    order_id: String,
+) -> Result<usize, rusqlite::Error> {
     let rows_affected = conn.execute(
         "DELETE FROM ChannelOrders WHERE account_id = ?1 AND order_id = ?2",
         rusqlite::params![account_id, order_id],
@@ -550,25 +875,28 @@ pub fn delete_channel_order(account_id: i32, order_id: String) -> Result<usize,
     Ok(rows_affected)
 }
 
+/// Store an account's mnemonic envelope (see `crypto`) and the envelope of
+/// its BIP39 passphrase, if it has one, dropping the salt and nonce a
+/// mnemonic stored before the envelope kept in their own columns.
 pub fn store_encrypted_mnemonic(
+    conn: &Connection,
     account_name: &str,
     encrypted_mnemonic: &str,
-    salt: &str,
-    nonce: &str,
+    encrypted_passphrase: Option<&str>,
 ) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
     conn.execute(
-        "UPDATE Accounts SET encrypted_mnemonic = ?1, mnemonic_salt = ?2, mnemonic_nonce = ?3 WHERE name = ?4",
-        rusqlite::params![encrypted_mnemonic, salt, nonce, account_name],
+        "UPDATE Accounts SET encrypted_mnemonic = ?1, mnemonic_salt = NULL, mnemonic_nonce = NULL,
+         encrypted_passphrase = ?2 WHERE name = ?3",
+        rusqlite::params![encrypted_mnemonic, encrypted_passphrase, account_name],
     )
 }
 
 pub fn upsert_dca_order(
+    conn: &Connection,
     account_id: i32,
     order_id: String,
     payload: String,
 ) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
     conn.execute(
         "INSERT INTO DcaOrders (account_id, order_id, payload) VALUES (?1, ?2, ?3)
          ON CONFLICT(account_id, order_id) DO UPDATE SET payload = excluded.payload",
@@ -576,8 +904,23 @@ pub fn upsert_dca_order(
     )
 }
 
-pub fn get_dca_orders(account_id: i32) -> Result<Vec<String>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+/// Set the low (high for sells) a trailing-entry order has tracked in its
+/// stored payload, leaving the rest of the payload as the frontend wrote it.
+pub fn set_dca_trailing_extreme(
+    conn: &Connection,
+    account_id: i32,
+    order_id: &str,
+    extreme_price: Option<f64>,
+) -> Result<usize, rusqlite::Error> {
+    conn.execute(
+        "UPDATE DcaOrders SET payload = json_set(payload, '$.strategy.extreme_price', ?3)
+         WHERE account_id = ?1 AND order_id = ?2
+         AND json_extract(payload, '$.strategy.kind') = 'trailing-entry'",
+        rusqlite::params![account_id, order_id, extreme_price],
+    )
+}
+
+pub fn get_dca_orders(conn: &Connection, account_id: i32) -> Result<Vec<String>, rusqlite::Error> {
     let mut stmt =
         conn.prepare("SELECT payload FROM DcaOrders WHERE account_id = ?1 ORDER BY id ASC")?;
     let payloads = stmt
@@ -587,29 +930,171 @@ pub fn get_dca_orders(account_id: i32) -> Result<Vec<String>, rusqlite::Error> {
     Ok(payloads)
 }
 
-pub fn delete_dca_order(account_id: i32, order_id: String) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn delete_dca_order(
+    conn: &Connection,
+    account_id: i32,
+    order_id: String,
+) -> Result<usize, rusqlite::Error> {
     conn.execute(
         "DELETE FROM DcaOrders WHERE account_id = ?1 AND order_id = ?2",
         rusqlite::params![account_id, order_id],
     )
 }
 
+const DCA_EXECUTION_COLUMNS: &str = "id, account_id, order_id, executed_at, quoted_price, amount_in, amount_out, fee, swap_id, status, error, spend_asset";
+
+fn row_to_dca_execution(row: &rusqlite::Row) -> Result<DcaExecution, rusqlite::Error> {
+    Ok(DcaExecution {
+        id: row.get(0)?,
+        account_id: row.get(1)?,
+        order_id: row.get(2)?,
+        executed_at: row.get(3)?,
+        quoted_price: row.get(4)?,
+        amount_in: row.get(5)?,
+        amount_out: row.get(6)?,
+        fee: row.get(7)?,
+        swap_id: row.get(8)?,
+        status: row.get(9)?,
+        error: row.get(10)?,
+        spend_asset: row.get(11)?,
+    })
+}
+
+/// Append an execution attempt to the DCA history. `id` and `account_id` on
+/// the passed record are ignored.
+pub fn insert_dca_execution(
+    conn: &Connection,
+    account_id: i32,
+    execution: &DcaExecution,
+) -> Result<i64, rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO DcaExecutions
+            (account_id, order_id, executed_at, quoted_price, amount_in, amount_out, fee, swap_id, status, error, spend_asset)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![
+            account_id,
+            execution.order_id,
+            execution.executed_at,
+            execution.quoted_price,
+            execution.amount_in,
+            execution.amount_out,
+            execution.fee,
+            execution.swap_id,
+            execution.status,
+            execution.error,
+            execution.spend_asset
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Execution history for an account, oldest first. Restricted to one order
+/// when `order_id` is given.
+pub fn get_dca_executions(
+    conn: &Connection,
+    account_id: i32,
+    order_id: Option<&str>,
+) -> Result<Vec<DcaExecution>, rusqlite::Error> {
+    let sql = format!(
+        "SELECT {} FROM DcaExecutions
+         WHERE account_id = ?1 AND (?2 IS NULL OR order_id = ?2)
+         ORDER BY executed_at ASC, id ASC",
+        DCA_EXECUTION_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![account_id, order_id],
+            row_to_dca_execution,
+        )?
+        .map(|r| r.unwrap())
+        .collect();
+    Ok(rows)
+}
+
+/// Successful DCA spending for an account, one row per execution.
+pub fn get_dca_spending(
+    conn: &Connection,
+    account_id: i32,
+) -> Result<Vec<DcaSpendRow>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT order_id, spend_asset, executed_at, amount_in FROM DcaExecutions
+         WHERE account_id = ?1 AND status = 'success'",
+    )?;
+    let rows = stmt
+        .query_map([account_id], |row| {
+            Ok(DcaSpendRow {
+                order_id: row.get(0)?,
+                spend_asset: row.get(1)?,
+                executed_at: row.get(2)?,
+                amount_in: row.get(3)?,
+            })
+        })?
+        .map(|r| r.unwrap())
+        .collect();
+    Ok(rows)
+}
+
+#[derive(Debug, Clone)]
+pub struct LimitOrderRow {
+    pub order_id: String,
+    pub order_type: String,
+    pub linked_order_id: Option<String>,
+    pub payload: String,
+}
+
+const UPSERT_LIMIT_ORDER: &str =
+    "INSERT INTO LimitOrders (account_id, order_id, order_type, linked_order_id, payload)
+     VALUES (?1, ?2, ?3, ?4, ?5)
+     ON CONFLICT(account_id, order_id) DO UPDATE SET
+        order_type = excluded.order_type,
+        linked_order_id = excluded.linked_order_id,
+        payload = excluded.payload";
+
 pub fn upsert_limit_order(
+    conn: &Connection,
     account_id: i32,
-    order_id: String,
-    payload: String,
+    row: &LimitOrderRow,
 ) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
     conn.execute(
-        "INSERT INTO LimitOrders (account_id, order_id, payload) VALUES (?1, ?2, ?3)
-         ON CONFLICT(account_id, order_id) DO UPDATE SET payload = excluded.payload",
-        rusqlite::params![account_id, order_id, payload],
+        UPSERT_LIMIT_ORDER,
+        rusqlite::params![
+            account_id,
+            row.order_id,
+            row.order_type,
+            row.linked_order_id,
+            row.payload
+        ],
     )
 }
 
-pub fn get_limit_orders(account_id: i32) -> Result<Vec<String>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+/// Write several orders in one transaction (a fill and the cancellation of
+/// its OCO sibling).
+pub fn save_limit_orders(
+    conn: &Connection,
+    account_id: i32,
+    rows: &[LimitOrderRow],
+) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    for row in rows {
+        tx.execute(
+            UPSERT_LIMIT_ORDER,
+            rusqlite::params![
+                account_id,
+                row.order_id,
+                row.order_type,
+                row.linked_order_id,
+                row.payload
+            ],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn get_limit_orders(
+    conn: &Connection,
+    account_id: i32,
+) -> Result<Vec<String>, rusqlite::Error> {
     let mut stmt =
         conn.prepare("SELECT payload FROM LimitOrders WHERE account_id = ?1 ORDER BY id ASC")?;
     let payloads = stmt
@@ -619,18 +1104,30 @@ pub fn get_limit_orders(account_id: i32) -> Result<Vec<String>, rusqlite::Error>
     Ok(payloads)
 }
 
-pub fn delete_limit_order(account_id: i32, order_id: String) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn delete_limit_order(
+    conn: &Connection,
+    account_id: i32,
+    order_id: String,
+) -> Result<usize, rusqlite::Error> {
     conn.execute(
         "DELETE FROM LimitOrders WHERE account_id = ?1 AND order_id = ?2",
         rusqlite::params![account_id, order_id],
     )
 }
 
+/// An account's encrypted mnemonic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptedMnemonic {
+    /// Hex-encoded envelope, or ciphertext of a mnemonic stored before it.
+    pub encrypted: String,
+    /// Hex salt and nonce of a mnemonic stored before the envelope.
+    pub legacy: Option<(String, String)>,
+}
+
 pub fn get_encrypted_mnemonic(
+    conn: &Connection,
     account_name: &str,
-) -> Result<Option<(String, String, String)>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+) -> Result<Option<EncryptedMnemonic>, rusqlite::Error> {
     let mut stmt = conn.prepare(
         "SELECT encrypted_mnemonic, mnemonic_salt, mnemonic_nonce FROM Accounts WHERE name = ?",
     )?;
@@ -641,31 +1138,345 @@ pub fn get_encrypted_mnemonic(
             let salt: Option<String> = row.get(1)?;
             let nonce: Option<String> = row.get(2)?;
 
-            match (encrypted, salt, nonce) {
-                (Some(e), Some(s), Some(n)) => Ok(Some((e, s, n))),
-                _ => Ok(None),
-            }
+            Ok(encrypted.map(|encrypted| EncryptedMnemonic {
+                encrypted,
+                legacy: salt.zip(nonce),
+            }))
         })
         .optional()?;
 
     Ok(result.flatten())
 }
 
+/// Secrets an account keeps encrypted under its password.
+#[derive(Debug, Clone, Default)]
+pub struct AccountSecrets {
+    pub mnemonic: Option<EncryptedMnemonic>,
+    /// Hex envelope of the mnemonic's BIP39 passphrase.
+    pub passphrase: Option<String>,
+}
+
+pub fn get_account_secrets(
+    conn: &Connection,
+    account_name: &str,
+) -> Result<AccountSecrets, rusqlite::Error> {
+    let passphrase = conn
+        .query_row(
+            "SELECT encrypted_passphrase FROM Accounts WHERE name = ?",
+            [account_name],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()?
+        .flatten();
+    Ok(AccountSecrets {
+        mnemonic: get_encrypted_mnemonic(conn, account_name)?,
+        passphrase,
+    })
+}
+
+/// Replace an account's secrets, read as `current`, with `sealed` (the same
+/// secrets sealed again, usually as envelopes) in one transaction. Fails
+/// without writing anything if any of them changed in between.
+pub fn replace_account_secrets(
+    conn: &Connection,
+    account_name: &str,
+    current: &AccountSecrets,
+    sealed: &AccountSecrets,
+) -> Result<(), rusqlite::Error> {
+    let changed = |secret: &str| {
+        rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            Some(format!("The {} changed while it was re-encrypted", secret)),
+        )
+    };
+    let tx = conn.unchecked_transaction()?;
+    if let (Some(current), Some(sealed)) = (&current.mnemonic, &sealed.mnemonic) {
+        let (salt, nonce) = sealed.legacy.clone().unzip();
+        let updated = tx.execute(
+            "UPDATE Accounts SET encrypted_mnemonic = ?1, mnemonic_salt = ?4, mnemonic_nonce = ?5
+             WHERE name = ?2 AND encrypted_mnemonic = ?3",
+            rusqlite::params![
+                sealed.encrypted,
+                account_name,
+                current.encrypted,
+                salt,
+                nonce
+            ],
+        )?;
+        if updated != 1 {
+            return Err(changed("mnemonic"));
+        }
+    }
+    if let (Some(current), Some(sealed)) = (&current.passphrase, &sealed.passphrase) {
+        let updated = tx.execute(
+            "UPDATE Accounts SET encrypted_passphrase = ?1
+             WHERE name = ?2 AND encrypted_passphrase = ?3",
+            rusqlite::params![sealed, account_name, current],
+        )?;
+        if updated != 1 {
+            return Err(changed("passphrase"));
+        }
+    }
+    tx.commit()
+}
+
+// ---------------------------------------------------------------------------
+// Trades (ledger of swaps executed through a maker)
+// ---------------------------------------------------------------------------
+
+/// Statuses a trade can have. Only `pending` trades change status.
+pub const TRADE_STATUSES: &[&str] = &["pending", "succeeded", "failed", "expired"];
+
+/// One swap executed through a maker, whatever started it.
+///
+/// Amounts are in the asset's display unit (BTC, not sats). `price` is the
+/// quote asset paid per base asset of `pair`. Timestamps are unix seconds.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Trade {
+    pub id: i64,
+    pub account_id: i32,
+    /// "manual" | "dca" | "limit"
+    pub source: String,
+    /// DCA or limit order the trade belongs to; None for manual swaps.
+    pub order_id: Option<String>,
+    /// "BASE/QUOTE"
+    pub pair: String,
+    /// "buy" | "sell", of the base asset
+    pub side: String,
+    pub from_asset: String,
+    pub from_amount: f64,
+    pub to_asset: String,
+    pub to_amount: f64,
+    pub price: f64,
+    pub fee: f64,
+    pub fee_asset: Option<String>,
+    pub maker_url: String,
+    /// Payment hash of the swap.
+    pub swap_id: Option<String>,
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Trade {
+    pub fn validate(&self) -> Result<(), String> {
+        if !["manual", "dca", "limit"].contains(&self.source.as_str()) {
+            return Err(format!("Invalid trade source '{}'", self.source));
+        }
+        if !["buy", "sell"].contains(&self.side.as_str()) {
+            return Err(format!("Invalid trade side '{}'", self.side));
+        }
+        if !TRADE_STATUSES.contains(&self.status.as_str()) {
+            return Err(format!("Invalid trade status '{}'", self.status));
+        }
+        if self.from_asset.is_empty() || self.to_asset.is_empty() || self.pair.is_empty() {
+            return Err("Trade assets and pair are required".to_string());
+        }
+        let amounts = [self.from_amount, self.to_amount, self.price, self.fee];
+        if amounts.iter().any(|a| !a.is_finite() || *a < 0.0) {
+            return Err("Trade amounts must be non-negative numbers".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Which trades to list; every field is optional.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TradeFilter {
+    pub source: Option<String>,
+    pub pair: Option<String>,
+    pub status: Option<String>,
+    /// Created at or after (unix seconds).
+    pub from: Option<i64>,
+    /// Created before (unix seconds).
+    pub to: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Succeeded trades of one asset over a period.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TradeAssetTotal {
+    pub asset: String,
+    pub sent: f64,
+    pub received: f64,
+    pub fees: f64,
+    pub trades: i64,
+}
+
+const TRADE_COLUMNS: &str = "id, account_id, source, order_id, pair, side, from_asset, from_amount, to_asset, to_amount, price, fee, fee_asset, maker_url, swap_id, status, error, created_at, updated_at";
+
+fn row_to_trade(row: &rusqlite::Row) -> Result<Trade, rusqlite::Error> {
+    Ok(Trade {
+        id: row.get(0)?,
+        account_id: row.get(1)?,
+        source: row.get(2)?,
+        order_id: row.get(3)?,
+        pair: row.get(4)?,
+        side: row.get(5)?,
+        from_asset: row.get(6)?,
+        from_amount: row.get(7)?,
+        to_asset: row.get(8)?,
+        to_amount: row.get(9)?,
+        price: row.get(10)?,
+        fee: row.get(11)?,
+        fee_asset: row.get(12)?,
+        maker_url: row.get(13)?,
+        swap_id: row.get(14)?,
+        status: row.get(15)?,
+        error: row.get(16)?,
+        created_at: row.get(17)?,
+        updated_at: row.get(18)?,
+    })
+}
+
+/// Add a trade to the ledger. `id` and `account_id` on the passed record are
+/// ignored and `updated_at` starts out as `created_at`. A swap can only be
+/// recorded once per account.
+pub fn insert_trade(
+    conn: &Connection,
+    account_id: i32,
+    trade: &Trade,
+) -> Result<i64, rusqlite::Error> {
+    conn.execute(
+        "INSERT INTO Trades
+            (account_id, source, order_id, pair, side, from_asset, from_amount, to_asset, to_amount,
+             price, fee, fee_asset, maker_url, swap_id, status, error, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?17)",
+        rusqlite::params![
+            account_id,
+            trade.source,
+            trade.order_id,
+            trade.pair,
+            trade.side,
+            trade.from_asset,
+            trade.from_amount,
+            trade.to_asset,
+            trade.to_amount,
+            trade.price,
+            trade.fee,
+            trade.fee_asset,
+            trade.maker_url,
+            trade.swap_id,
+            trade.status,
+            trade.error,
+            trade.created_at
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Move the pending trade of a swap to `status`. Returns the number of
+/// trades changed: 0 when there is none or it already settled.
+pub fn update_trade_status(
+    conn: &Connection,
+    account_id: i32,
+    swap_id: &str,
+    status: &str,
+    error: Option<&str>,
+    updated_at: i64,
+) -> Result<usize, rusqlite::Error> {
+    conn.execute(
+        "UPDATE Trades SET status = ?3, error = ?4, updated_at = ?5
+         WHERE account_id = ?1 AND swap_id = ?2 AND status = 'pending' AND ?3 != 'pending'",
+        rusqlite::params![account_id, swap_id, status, error, updated_at],
+    )
+}
+
+/// Trades of an account matching `filter`, newest first.
+pub fn get_trades(
+    conn: &Connection,
+    account_id: i32,
+    filter: &TradeFilter,
+) -> Result<Vec<Trade>, rusqlite::Error> {
+    let sql = format!(
+        "SELECT {} FROM Trades
+         WHERE account_id = ?1
+           AND (?2 IS NULL OR source = ?2)
+           AND (?3 IS NULL OR pair = ?3)
+           AND (?4 IS NULL OR status = ?4)
+           AND (?5 IS NULL OR created_at >= ?5)
+           AND (?6 IS NULL OR created_at < ?6)
+         ORDER BY created_at DESC, id DESC
+         LIMIT ?7 OFFSET ?8",
+        TRADE_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                account_id,
+                filter.source,
+                filter.pair,
+                filter.status,
+                filter.from,
+                filter.to,
+                filter.limit.unwrap_or(-1),
+                filter.offset.unwrap_or(0)
+            ],
+            row_to_trade,
+        )?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+/// Per-asset totals of the succeeded trades created in `[from, to)`. Fees
+/// count toward their own asset.
+pub fn get_trade_totals(
+    conn: &Connection,
+    account_id: i32,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Vec<TradeAssetTotal>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "WITH done AS (
+            SELECT * FROM Trades
+            WHERE account_id = ?1 AND status = 'succeeded'
+              AND (?2 IS NULL OR created_at >= ?2)
+              AND (?3 IS NULL OR created_at < ?3)
+         ), legs AS (
+            SELECT id, from_asset AS asset, from_amount AS sent, 0.0 AS received, 0.0 AS fees
+            FROM done
+            UNION ALL
+            SELECT id, to_asset, 0.0, to_amount, 0.0 FROM done
+            UNION ALL
+            SELECT id, fee_asset, 0.0, 0.0, fee FROM done
+            WHERE fee_asset IS NOT NULL AND fee > 0
+         )
+         SELECT asset, SUM(sent), SUM(received), SUM(fees), COUNT(DISTINCT id)
+         FROM legs GROUP BY asset ORDER BY asset",
+    )?;
+    let rows = stmt
+        .query_map(rusqlite::params![account_id, from, to], |row| {
+            Ok(TradeAssetTotal {
+                asset: row.get(0)?,
+                sent: row.get(1)?,
+                received: row.get(2)?,
+                fees: row.get(3)?,
+                trades: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
 // ---------------------------------------------------------------------------
 // App Settings (key-value store)
 // ---------------------------------------------------------------------------
 
-#[allow(dead_code)]
-pub fn get_app_setting(key: &str) -> Result<Option<String>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn get_app_setting(conn: &Connection, key: &str) -> Result<Option<String>, rusqlite::Error> {
     let mut stmt = conn.prepare("SELECT value FROM AppSettings WHERE key = ?")?;
     let result = stmt.query_row([key], |row| row.get(0)).optional()?;
     Ok(result)
 }
 
-#[allow(dead_code)]
-pub fn set_app_setting(key: &str, value: &str) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn set_app_setting(
+    conn: &Connection,
+    key: &str,
+    value: &str,
+) -> Result<usize, rusqlite::Error> {
     conn.execute(
         "INSERT INTO AppSettings (key, value) VALUES (?1, ?2)
          ON CONFLICT(key) DO UPDATE SET value = excluded.value",
@@ -699,6 +1510,7 @@ fn row_to_nwc_connection(row: &rusqlite::Row) -> Result<NwcConnection, rusqlite:
 
 #[allow(clippy::too_many_arguments)]
 pub fn insert_nwc_connection(
+    conn: &Connection,
     account_id: i32,
     name: &str,
     client_pubkey: &str,
@@ -709,7 +1521,6 @@ pub fn insert_nwc_connection(
     budget_renews_at: Option<i64>,
     created_at: i64,
 ) -> Result<i64, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
     conn.execute(
         "INSERT INTO NwcConnections
             (account_id, name, client_pubkey, client_secret, relays_json, methods_json, budget_msat, spent_msat, budget_renews_at, enabled, created_at)
@@ -729,8 +1540,10 @@ pub fn insert_nwc_connection(
     Ok(conn.last_insert_rowid())
 }
 
-pub fn get_nwc_connections(account_id: i32) -> Result<Vec<NwcConnection>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn get_nwc_connections(
+    conn: &Connection,
+    account_id: i32,
+) -> Result<Vec<NwcConnection>, rusqlite::Error> {
     let sql = format!(
         "SELECT {} FROM NwcConnections WHERE account_id = ?1 ORDER BY created_at DESC",
         NWC_COLUMNS
@@ -745,8 +1558,9 @@ pub fn get_nwc_connections(account_id: i32) -> Result<Vec<NwcConnection>, rusqli
 
 /// All enabled connections across every account, used to bootstrap the service.
 #[allow(dead_code)]
-pub fn get_all_enabled_nwc_connections() -> Result<Vec<NwcConnection>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn get_all_enabled_nwc_connections(
+    conn: &Connection,
+) -> Result<Vec<NwcConnection>, rusqlite::Error> {
     let sql = format!(
         "SELECT {} FROM NwcConnections WHERE enabled = 1",
         NWC_COLUMNS
@@ -760,9 +1574,9 @@ pub fn get_all_enabled_nwc_connections() -> Result<Vec<NwcConnection>, rusqlite:
 }
 
 pub fn get_enabled_nwc_connections_for_account(
+    conn: &Connection,
     account_id: i32,
 ) -> Result<Vec<NwcConnection>, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
     let sql = format!(
         "SELECT {} FROM NwcConnections WHERE account_id = ?1 AND enabled = 1",
         NWC_COLUMNS
@@ -775,16 +1589,22 @@ pub fn get_enabled_nwc_connections_for_account(
     Ok(rows)
 }
 
-pub fn set_nwc_connection_enabled(id: i32, enabled: bool) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn set_nwc_connection_enabled(
+    conn: &Connection,
+    id: i32,
+    enabled: bool,
+) -> Result<usize, rusqlite::Error> {
     conn.execute(
         "UPDATE NwcConnections SET enabled = ?1 WHERE id = ?2",
         rusqlite::params![enabled as i64, id],
     )
 }
 
-pub fn delete_nwc_connection(account_id: i32, id: i32) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn delete_nwc_connection(
+    conn: &Connection,
+    account_id: i32,
+    id: i32,
+) -> Result<usize, rusqlite::Error> {
     conn.execute(
         "DELETE FROM NwcConnections WHERE id = ?1 AND account_id = ?2",
         rusqlite::params![id, account_id],
@@ -793,11 +1613,11 @@ pub fn delete_nwc_connection(account_id: i32, id: i32) -> Result<usize, rusqlite
 
 /// Record spend against a connection's budget and bump `last_used_at`.
 pub fn add_nwc_spend(
+    conn: &Connection,
     client_pubkey: &str,
     amount_msat: i64,
     now: i64,
 ) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
     conn.execute(
         "UPDATE NwcConnections SET spent_msat = spent_msat + ?1, last_used_at = ?2 WHERE client_pubkey = ?3",
         rusqlite::params![amount_msat, now, client_pubkey],
@@ -805,8 +1625,11 @@ pub fn add_nwc_spend(
 }
 
 /// Touch `last_used_at` without recording spend (non-payment requests).
-pub fn touch_nwc_connection(client_pubkey: &str, now: i64) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn touch_nwc_connection(
+    conn: &Connection,
+    client_pubkey: &str,
+    now: i64,
+) -> Result<usize, rusqlite::Error> {
     conn.execute(
         "UPDATE NwcConnections SET last_used_at = ?1 WHERE client_pubkey = ?2",
         rusqlite::params![now, client_pubkey],
@@ -814,8 +1637,7 @@ pub fn touch_nwc_connection(client_pubkey: &str, now: i64) -> Result<usize, rusq
 }
 
 /// Reset spent budget windows that have elapsed.
-pub fn reset_expired_nwc_budgets(now: i64) -> Result<usize, rusqlite::Error> {
-    let conn = Connection::open(get_db_path())?;
+pub fn reset_expired_nwc_budgets(conn: &Connection, now: i64) -> Result<usize, rusqlite::Error> {
     conn.execute(
         "UPDATE NwcConnections SET spent_msat = 0, budget_renews_at = NULL
          WHERE budget_renews_at IS NOT NULL AND budget_renews_at <= ?1",
@@ -823,13 +1645,379 @@ pub fn reset_expired_nwc_budgets(now: i64) -> Result<usize, rusqlite::Error> {
     )
 }
 
+/// AppSettings key of an account's NWC service secret.
+pub fn nwc_service_secret_key(account_id: i64) -> String {
+    format!("nwc_service_secret_{account_id}")
+}
+
 /// The NWC wallet-service secret key (hex) for an account. This is a random
 /// Nostr identity independent of the wallet seed, generated once and reused so
 /// connection URIs stay valid across restarts. Stored in AppSettings.
-pub fn get_nwc_service_secret(account_id: i32) -> Result<Option<String>, rusqlite::Error> {
-    get_app_setting(&format!("nwc_service_secret_{account_id}"))
+pub fn get_nwc_service_secret(
+    conn: &Connection,
+    account_id: i32,
+) -> Result<Option<String>, rusqlite::Error> {
+    get_app_setting(conn, &nwc_service_secret_key(account_id.into()))
 }
 
-pub fn set_nwc_service_secret(account_id: i32, secret_hex: &str) -> Result<usize, rusqlite::Error> {
-    set_app_setting(&format!("nwc_service_secret_{account_id}"), secret_hex)
+pub fn set_nwc_service_secret(
+    conn: &Connection,
+    account_id: i32,
+    secret_hex: &str,
+) -> Result<usize, rusqlite::Error> {
+    set_app_setting(conn, &nwc_service_secret_key(account_id.into()), secret_hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db(name: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("kaleido-db-{}-{}.sqlite", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn insert_test_account(conn: &Connection, name: &str) -> i32 {
+        insert_account(
+            conn,
+            name.to_string(),
+            "Regtest".to_string(),
+            None,
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            String::new(),
+            "3001".to_string(),
+            "9735".to_string(),
+            None,
+            None,
+        )
+        .unwrap();
+        get_account_by_name(conn, name).unwrap().unwrap().id
+    }
+
+    #[test]
+    fn test_pool_settings() {
+        let path = temp_db("pool");
+        let pool = open_pool(&path, None).unwrap();
+        let conn = pool.get().unwrap();
+        let mode: String = conn
+            .pragma_query_value(None, "journal_mode", |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+        let foreign_keys: bool = conn
+            .pragma_query_value(None, "foreign_keys", |row| row.get(0))
+            .unwrap();
+        assert!(foreign_keys);
+        drop(conn);
+        drop(pool);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_encryption_roundtrip() {
+        let path = temp_db("encrypted");
+        let path = path.to_str().unwrap();
+        let pool = open_pool(path, None).unwrap();
+        let mut conn = pool.get().unwrap();
+        migrations::run(&mut conn).unwrap();
+        insert_test_account(&conn, "main");
+        drop(conn);
+        assert!(!is_encrypted_file(path));
+
+        rekey_file(pool, path, Some("hunter22")).unwrap();
+        assert!(is_encrypted_file(path));
+        assert_eq!(open_pool(path, None).unwrap_err(), "Database is encrypted");
+        assert_eq!(
+            open_pool(path, Some("wrong")).unwrap_err(),
+            "Wrong database password"
+        );
+
+        let pool = open_pool(path, Some("hunter22")).unwrap();
+        let conn = pool.get().unwrap();
+        assert!(check_account_exists(&conn, "main").unwrap());
+        // Pre-migration backups keep the key
+        let backup = backup_db(&conn, path, Some("hunter22")).unwrap();
+        assert!(is_encrypted_file(&backup));
+        drop(conn);
+
+        rekey_file(pool, path, Some("correct horse")).unwrap();
+        let pool = open_pool(path, Some("correct horse")).unwrap();
+        rekey_file(pool, path, None).unwrap();
+        assert!(!is_encrypted_file(path));
+        let pool = open_pool(path, None).unwrap();
+        assert!(check_account_exists(&pool.get().unwrap(), "main").unwrap());
+
+        drop(pool);
+        for file in [path.to_string(), backup] {
+            let _ = fs::remove_file(&file);
+            remove_wal_files(&file);
+        }
+    }
+
+    #[test]
+    fn test_rekey_reopen_failure() {
+        let path = temp_db("reopen");
+        let path = path.to_str().unwrap();
+        let pool = open_pool(path, None).unwrap();
+        migrations::run(&mut pool.get().unwrap()).unwrap();
+
+        // The re-key went through but neither key reopens the file
+        let failing = |_: &str, _: Option<&str>| Err("disk I/O error".to_string());
+        let (reopened, result) = rekey(pool, path, None, Some("hunter22"), failing);
+        assert_eq!(result, Ok(()));
+        assert_eq!(reopened.err().as_deref(), Some("disk I/O error"));
+        // Nothing is lost: the file is under the new key
+        assert!(is_encrypted_file(path));
+        let pool = open_pool(path, Some("hunter22")).unwrap();
+
+        // The other key is tried before giving up, and the first error kept
+        let tries = Mutex::new(Vec::new());
+        let first_fails = |path: &str, key: Option<&str>| {
+            let mut tries = tries.lock().unwrap();
+            tries.push(key.map(str::to_owned));
+            if tries.len() == 1 {
+                return Err("busy".to_string());
+            }
+            open_pool(path, key)
+        };
+        let (reopened, result) = rekey(
+            pool,
+            path,
+            Some("hunter22"),
+            Some("correct horse"),
+            first_fails,
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(reopened.err().as_deref(), Some("busy"));
+        assert_eq!(
+            *tries.lock().unwrap(),
+            [Some("correct horse".into()), Some("hunter22".into())]
+        );
+        drop(open_pool(path, Some("correct horse")).unwrap());
+
+        let _ = fs::remove_file(path);
+        remove_wal_files(path);
+    }
+
+    #[test]
+    fn test_dca_trailing_extreme() {
+        let path = temp_db("trailing");
+        let pool = open_pool(&path, None).unwrap();
+        let mut conn = pool.get().unwrap();
+        migrations::run(&mut conn).unwrap();
+        let account_id = insert_test_account(&conn, "main");
+        let trailing = r#"{"id":"t1","status":"active","strategy":{"kind":"trailing-entry","rebound_pct":5,"activation_price":null}}"#;
+        upsert_dca_order(&conn, account_id, "t1".into(), trailing.into()).unwrap();
+        upsert_dca_order(&conn, account_id, "s1".into(), r#"{"id":"s1"}"#.into()).unwrap();
+
+        assert_eq!(
+            set_dca_trailing_extreme(&conn, account_id, "t1", Some(45_000.5)).unwrap(),
+            1
+        );
+        // Only trailing-entry orders have one.
+        assert_eq!(
+            set_dca_trailing_extreme(&conn, account_id, "s1", Some(1.0)).unwrap(),
+            0
+        );
+        let payload = |id: &str| -> serde_json::Value {
+            get_dca_orders(&conn, account_id)
+                .unwrap()
+                .iter()
+                .map(|p| serde_json::from_str::<serde_json::Value>(p).unwrap())
+                .find(|p| p["id"] == id)
+                .unwrap()
+        };
+        let stored = payload("t1");
+        assert_eq!(stored["strategy"]["extreme_price"], 45_000.5);
+        assert_eq!(stored["strategy"]["rebound_pct"], 5);
+        assert_eq!(stored["status"], "active");
+        assert_eq!(payload("s1"), serde_json::json!({ "id": "s1" }));
+
+        set_dca_trailing_extreme(&conn, account_id, "t1", None).unwrap();
+        assert!(payload("t1")["strategy"]["extreme_price"].is_null());
+
+        drop(conn);
+        drop(pool);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_delete_account_cascades() {
+        let path = temp_db("cascade");
+        let pool = open_pool(&path, None).unwrap();
+        let mut conn = pool.get().unwrap();
+        migrations::run(&mut conn).unwrap();
+
+        let account_id = insert_test_account(&conn, "main");
+        let other_id = insert_test_account(&conn, "other");
+        upsert_dca_order(&conn, account_id, "d1".into(), "{}".into()).unwrap();
+        upsert_dca_order(&conn, other_id, "d2".into(), "{}".into()).unwrap();
+        insert_channel_order(
+            &conn,
+            account_id,
+            "c1".into(),
+            "paid".into(),
+            "{}".into(),
+            "t".into(),
+            None,
+            0,
+        )
+        .unwrap();
+
+        delete_account(&conn, "main".into()).unwrap();
+        assert!(get_dca_orders(&conn, account_id).unwrap().is_empty());
+        assert!(get_channel_orders(&conn, Some(account_id))
+            .unwrap()
+            .is_empty());
+        assert_eq!(get_dca_orders(&conn, other_id).unwrap().len(), 1);
+
+        drop(conn);
+        drop(pool);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_replace_account_secrets() {
+        let path = temp_db("secrets");
+        let pool = open_pool(&path, None).unwrap();
+        let mut conn = pool.get().unwrap();
+        migrations::run(&mut conn).unwrap();
+        insert_test_account(&conn, "main");
+        conn.execute(
+            "UPDATE Accounts SET encrypted_mnemonic = 'old', mnemonic_salt = 's', mnemonic_nonce = 'n',
+             encrypted_passphrase = 'old pass'",
+            [],
+        )
+        .unwrap();
+
+        let current = get_account_secrets(&conn, "main").unwrap();
+        let stored = current.mnemonic.clone().unwrap();
+        assert_eq!(stored.legacy, Some(("s".into(), "n".into())));
+        assert_eq!(current.passphrase.as_deref(), Some("old pass"));
+        let sealed = AccountSecrets {
+            mnemonic: Some(EncryptedMnemonic {
+                encrypted: "new".into(),
+                legacy: None,
+            }),
+            passphrase: Some("new pass".into()),
+        };
+        replace_account_secrets(&conn, "main", &current, &sealed).unwrap();
+        let replaced = get_account_secrets(&conn, "main").unwrap();
+        let mnemonic = replaced.mnemonic.clone().unwrap();
+        assert_eq!(mnemonic.encrypted, "new");
+        assert_eq!(mnemonic.legacy, None);
+        assert_eq!(replaced.passphrase.as_deref(), Some("new pass"));
+        // Secrets that changed in between aren't overwritten
+        assert!(replace_account_secrets(&conn, "main", &current, &sealed).is_err());
+        let only_passphrase = AccountSecrets {
+            passphrase: Some("other pass".into()),
+            ..Default::default()
+        };
+        assert!(replace_account_secrets(&conn, "main", &current, &only_passphrase).is_err());
+        assert_eq!(
+            get_account_secrets(&conn, "main").unwrap().passphrase,
+            replaced.passphrase
+        );
+        // Putting the previous secrets back keeps their legacy salt and nonce
+        replace_account_secrets(&conn, "main", &replaced, &current).unwrap();
+        let reverted = get_account_secrets(&conn, "main").unwrap();
+        assert_eq!(reverted.mnemonic.unwrap().legacy, stored.legacy);
+        assert_eq!(reverted.passphrase.as_deref(), Some("old pass"));
+
+        store_encrypted_mnemonic(&conn, "main", "stored", None).unwrap();
+        assert_eq!(get_account_secrets(&conn, "main").unwrap().passphrase, None);
+        assert!(get_account_secrets(&conn, "other")
+            .unwrap()
+            .mnemonic
+            .is_none());
+
+        drop(conn);
+        drop(pool);
+        let _ = fs::remove_file(&path);
+    }
+
+    fn test_trade(swap_id: &str, created_at: i64) -> Trade {
+        Trade {
+            id: 0,
+            account_id: 0,
+            source: "manual".into(),
+            order_id: None,
+            pair: "BTC/USDT".into(),
+            side: "buy".into(),
+            from_asset: "USDT".into(),
+            from_amount: 100.0,
+            to_asset: "BTC".into(),
+            to_amount: 0.001,
+            price: 100_000.0,
+            fee: 0.5,
+            fee_asset: Some("USDT".into()),
+            maker_url: "https://maker.example".into(),
+            swap_id: Some(swap_id.into()),
+            status: "pending".into(),
+            error: None,
+            created_at,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_trade_ledger() {
+        let path = temp_db("trades");
+        let pool = open_pool(&path, None).unwrap();
+        let mut conn = pool.get().unwrap();
+        migrations::run(&mut conn).unwrap();
+        let account_id = insert_test_account(&conn, "main");
+
+        insert_trade(&conn, account_id, &test_trade("h1", 100)).unwrap();
+        let mut sell = test_trade("h2", 200);
+        sell.side = "sell".into();
+        sell.from_asset = "BTC".into();
+        sell.from_amount = 0.0005;
+        sell.to_asset = "USDT".into();
+        sell.to_amount = 50.0;
+        insert_trade(&conn, account_id, &sell).unwrap();
+        assert!(insert_trade(&conn, account_id, &test_trade("h1", 300)).is_err());
+
+        assert_eq!(
+            update_trade_status(&conn, account_id, "h1", "succeeded", None, 150).unwrap(),
+            1
+        );
+        update_trade_status(&conn, account_id, "h2", "succeeded", None, 250).unwrap();
+        // Settled trades keep their status
+        assert_eq!(
+            update_trade_status(&conn, account_id, "h1", "failed", Some("late"), 400).unwrap(),
+            0
+        );
+
+        let trades = get_trades(&conn, account_id, &TradeFilter::default()).unwrap();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].swap_id.as_deref(), Some("h2"));
+        assert_eq!(trades[1].status, "succeeded");
+        assert_eq!(trades[1].updated_at, 150);
+        let filter = TradeFilter {
+            from: Some(150),
+            ..Default::default()
+        };
+        assert_eq!(get_trades(&conn, account_id, &filter).unwrap().len(), 1);
+
+        let totals = get_trade_totals(&conn, account_id, None, None).unwrap();
+        let usdt = totals.iter().find(|t| t.asset == "USDT").unwrap();
+        assert_eq!((usdt.sent, usdt.received, usdt.fees), (100.0, 50.0, 1.0));
+        assert_eq!(usdt.trades, 2);
+        let btc = totals.iter().find(|t| t.asset == "BTC").unwrap();
+        assert_eq!((btc.sent, btc.received), (0.0005, 0.001));
+        assert!(get_trade_totals(&conn, account_id, Some(300), None)
+            .unwrap()
+            .is_empty());
+
+        drop(conn);
+        drop(pool);
+        let _ = fs::remove_file(&path);
+    }
 }
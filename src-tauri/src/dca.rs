@@ -1,54 +1,325 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
-use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
 
-const POLL_INTERVAL_SECS: u64 = 30;
-const COINGECKO_URL: &str =
-    "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd";
+use crate::db::{self, DcaExecution};
+use crate::dca_limits::{check_end_date, check_spend, DcaStatusChange, SpendCheck, SpendContext};
+use crate::dca_pair::{AssetAmount, AssetPair, PriceSource, TradeSide};
+use crate::dca_schedule::DcaSchedule;
+use crate::tray;
+
+/// A price reading is shared by all orders on the same source for this long.
+const PRICE_CACHE_SECS: u64 = 10;
+/// The tray warns once price requests have been failing for this long.
+const ORACLE_WARNING_SECS: u64 = 10 * 60;
+/// A due run older than this when first noticed counts as late (the machine
+/// was asleep or the app was closed).
+const LATE_GRACE_SECS: u64 = 15 * 60;
+/// Upper bound on missed runs enumerated (and logged) per catch-up.
+const MAX_DUE_RUNS: usize = 500;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DcaOrderInfo {
     pub id: String,
-    pub order_type: String, // "scheduled" | "price-target"
-    pub status: String,     // "active" | "paused" | "completed" | "cancelled"
-    pub amount_usdt: f64,
+    // "scheduled" | "price-target" | "value-averaging" | "dip-ladder" | "trailing-entry"
+    pub order_type: String,
+    pub status: String, // "active" | "paused" | "completed" | "cancelled"
+    /// Market traded; defaults to BTC/USDT.
+    #[serde(default)]
+    pub pair: AssetPair,
+    #[serde(default)]
+    pub side: TradeSide,
+    /// Amount spent per execution, in the spent asset (quote for buys, base
+    /// for sells).
+    pub amount: AssetAmount,
     // Scheduled
     pub interval_secs: Option<u64>,
     pub last_executed_at: Option<u64>,
-    // Price-target
-    pub trigger_price_usd: Option<f64>,
+    /// Cron / calendar schedule; takes precedence over `interval_secs`.
+    #[serde(default)]
+    pub schedule: Option<DcaSchedule>,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
+    // Price-target; prices are in the pair's quote asset
+    pub trigger_price: Option<f64>,
+    /// Parameters for the value-averaging / dip-ladder / trailing-entry types.
+    #[serde(default)]
+    pub strategy: Option<DcaStrategy>,
+    /// Total the order may spend over its lifetime, in the spent asset.
+    #[serde(default)]
+    pub budget: Option<AssetAmount>,
+    /// Unix time after which the order completes.
+    #[serde(default)]
+    pub end_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum DcaStrategy {
+    /// Each scheduled period, buy whatever brings the order's holdings up to
+    /// a target value that grows by `increment` per period since creation.
+    /// Buy side only.
+    ValueAveraging {
+        /// Target growth per period, in the quote asset.
+        increment: AssetAmount,
+        /// Cap on a single buy, in the quote asset.
+        max_amount: AssetAmount,
+        /// Base asset bought by this order so far.
+        #[serde(default)]
+        acquired: AssetAmount,
+    },
+    /// Trade a fixed amount at each of several price levels: descending
+    /// levels for buys, ascending levels for sells.
+    DipLadder { steps: Vec<LadderStep> },
+    /// Buy once the price rebounds `rebound_pct` from its lowest point seen
+    /// (below `activation_price`, if set); sells mirror this from the high.
+    TrailingEntry {
+        rebound_pct: f64,
+        activation_price: Option<f64>,
+        /// Previously observed low (high for sells), so tracking survives a
+        /// restart.
+        #[serde(default)]
+        extreme_price: Option<f64>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LadderStep {
+    pub price: f64,
+    /// Amount spent at this level, in the spent asset.
+    pub amount: AssetAmount,
+    #[serde(default)]
+    pub filled: bool,
+}
+
+/// What to do with scheduled runs missed while the machine slept or the app
+/// was closed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "kebab-case")]
+pub enum CatchUpPolicy {
+    /// Drop missed runs; only a run that is due right now executes.
+    Skip,
+    /// Execute once in place of all missed runs.
+    #[default]
+    RunOnce,
+    /// Execute every missed run, up to `max_runs`.
+    RunAll { max_runs: u32 },
+}
+
+impl CatchUpPolicy {
+    fn label(&self) -> String {
+        match self {
+            CatchUpPolicy::Skip => "skip".to_string(),
+            CatchUpPolicy::RunOnce => "run once".to_string(),
+            CatchUpPolicy::RunAll { max_runs } => format!("run all, max {}", max_runs),
+        }
+    }
+}
+
+impl DcaOrderInfo {
+    /// Ticker of the asset each execution spends.
+    pub fn spend_ticker(&self) -> &str {
+        &self.side.spend_asset(&self.pair).ticker
+    }
+
+    /// Cron / calendar schedule if set, otherwise the legacy fixed interval.
+    pub(crate) fn effective_schedule(&self) -> Option<DcaSchedule> {
+        self.schedule.clone().or_else(|| {
+            self.interval_secs
+                .map(|interval_secs| DcaSchedule::Interval { interval_secs })
+        })
+    }
+}
+
+/// Scheduler-side bookkeeping for one order, kept across `set_orders` calls
+/// so catch-up decisions aren't re-made when the frontend re-syncs orders.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OrderRunState {
+    /// Latest scheduled run already accounted for (queued or missed).
+    anchor: Option<u64>,
+    /// Triggers still owed to the order and not yet executed.
+    pub(crate) pending: u32,
+    /// Lowest (buy) or highest (sell) price seen by a trailing-entry order.
+    trailing_extreme: Option<f64>,
+}
+
+/// A scheduled run that was not executed, with the reason.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MissedRun {
+    pub order_id: String,
+    pub scheduled_at: u64,
+    pub reason: String,
 }
 
 #[derive(Debug, Serialize, Clone)]
-struct DcaTriggerPayload {
-    order_id: String,
-    current_price: f64,
+pub(crate) struct DcaTriggerPayload {
+    pub(crate) order_id: String,
+    pub(crate) current_price: f64,
+    /// Amount to spend when the strategy sizes the trade itself (value
+    /// averaging, ladder steps); None means the order's `amount`.
+    pub(crate) amount: Option<AssetAmount>,
+    /// Dip-ladder step being filled.
+    pub(crate) ladder_step: Option<usize>,
+}
+
+/// Payload of `dca:trailing`: the low (high for sells) a trailing-entry
+/// order tracks moved, or was cleared after the order fired.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub(crate) struct DcaTrailingUpdate {
+    pub(crate) order_id: String,
+    pub(crate) extreme_price: Option<f64>,
+}
+
+impl DcaTrailingUpdate {
+    /// The update to store if the extreme tracked in `state` differs from
+    /// the one in the order.
+    fn changed(order: &DcaOrderInfo, state: &OrderRunState) -> Option<Self> {
+        match &order.strategy {
+            Some(DcaStrategy::TrailingEntry { extreme_price, .. })
+                if *extreme_price != state.trailing_extreme =>
+            {
+                Some(DcaTrailingUpdate {
+                    order_id: order.id.clone(),
+                    extreme_price: state.trailing_extreme,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl DcaTriggerPayload {
+    fn new(order: &DcaOrderInfo, current_price: f64) -> Self {
+        DcaTriggerPayload {
+            order_id: order.id.clone(),
+            current_price,
+            amount: None,
+            ladder_step: None,
+        }
+    }
+}
+
+/// Timing of the scheduler loop. Stored as the `dca` field of the app
+/// [`Settings`](crate::settings::Settings).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DcaSchedulerConfig {
+    /// How often price-watching orders are checked, and how soon a run that
+    /// is owed but has not executed is triggered again.
+    pub poll_interval_secs: u64,
+    /// Up to this many seconds are added at random to every order check, so
+    /// price requests don't all hit the sources at the same moment.
+    pub jitter_secs: u64,
+}
+
+impl Default for DcaSchedulerConfig {
+    fn default() -> Self {
+        DcaSchedulerConfig {
+            poll_interval_secs: 30,
+            jitter_secs: 5,
+        }
+    }
+}
+
+impl DcaSchedulerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(5..=3600).contains(&self.poll_interval_secs) {
+            return Err("Poll interval must be between 5 seconds and 1 hour".to_string());
+        }
+        if self.jitter_secs > self.poll_interval_secs {
+            return Err("Jitter cannot exceed the poll interval".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Scheduler counters since app start.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DcaMetrics {
+    /// Wake-ups that checked at least one order.
+    pub ticks: u64,
+    /// `dca:trigger` events emitted.
+    pub triggers: u64,
+    /// Failed price requests, over all sources.
+    pub oracle_failures: u64,
+    /// Unix time of the last successful price request.
+    pub last_price_at: Option<u64>,
+    /// Start of the current run of failed price requests.
+    pub oracle_failing_since: Option<u64>,
+    pub last_oracle_error: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct CoinGeckoResponse {
-    bitcoin: Option<CoinGeckoPrice>,
+impl DcaMetrics {
+    fn record_price(&mut self, now: u64) {
+        self.last_price_at = Some(now);
+        self.oracle_failing_since = None;
+    }
+
+    fn record_oracle_failure(&mut self, now: u64, error: String) {
+        self.oracle_failures += 1;
+        self.oracle_failing_since.get_or_insert(now);
+        self.last_oracle_error = Some(error);
+    }
+
+    /// Price requests have failed without a success for
+    /// [`ORACLE_WARNING_SECS`].
+    fn oracle_degraded(&self, now: u64) -> bool {
+        self.oracle_failing_since
+            .is_some_and(|since| now.saturating_sub(since) >= ORACLE_WARNING_SECS)
+    }
+}
+
+/// Returned by `dca_status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DcaStatus {
+    pub running: bool,
+    pub active_orders: usize,
+    pub oracle_degraded: bool,
+    #[serde(flatten)]
+    pub metrics: DcaMetrics,
 }
 
-#[derive(Debug, Deserialize)]
-struct CoinGeckoPrice {
-    usd: Option<f64>,
+/// The spawned scheduler loop and the token that stops it.
+struct RunningScheduler {
+    cancel: CancellationToken,
+    task: tauri::async_runtime::JoinHandle<()>,
 }
 
 pub struct DcaScheduler {
     orders: Arc<RwLock<Vec<DcaOrderInfo>>>,
+    run_states: Arc<Mutex<HashMap<String, OrderRunState>>>,
+    account_id: Arc<Mutex<Option<i32>>>,
+    /// Spendable Lightning balance per asset ticker, pushed by the frontend.
+    balances: Arc<Mutex<HashMap<String, AssetAmount>>>,
     app_handle: Arc<Mutex<Option<AppHandle>>>,
-    running: Arc<Mutex<bool>>,
+    config: Arc<RwLock<DcaSchedulerConfig>>,
+    metrics: Arc<Mutex<DcaMetrics>>,
+    /// Wakes the loop early when orders or settings change.
+    wake: Arc<Notify>,
+    /// Async mutex: held across the await in `start` / `stop` so they can't
+    /// interleave.
+    task: tokio::sync::Mutex<Option<RunningScheduler>>,
 }
 
 impl DcaScheduler {
     pub fn new() -> Self {
         DcaScheduler {
             orders: Arc::new(RwLock::new(Vec::new())),
-            running: Arc::new(Mutex::new(false)),
+            run_states: Arc::new(Mutex::new(HashMap::new())),
+            account_id: Arc::new(Mutex::new(None)),
+            balances: Arc::new(Mutex::new(HashMap::new())),
             app_handle: Arc::new(Mutex::new(None)),
+            config: Arc::new(RwLock::new(DcaSchedulerConfig::default())),
+            metrics: Arc::new(Mutex::new(DcaMetrics::default())),
+            wake: Arc::new(Notify::new()),
+            task: tokio::sync::Mutex::new(None),
         }
     }
 
@@ -56,183 +327,886 @@ impl DcaScheduler {
         *self.app_handle.lock().unwrap() = Some(handle);
     }
 
-    pub fn set_orders(&self, orders: Vec<DcaOrderInfo>) {
-        println!("[DCA] set_orders: {} orders", orders.len());
+    pub fn set_orders(&self, account_id: Option<i32>, orders: Vec<DcaOrderInfo>) {
+        log::debug!("[DCA] set_orders: {} orders", orders.len());
         for o in &orders {
-            println!(
-                "[DCA]   order id={} type={} status={} interval={:?} last_exec={:?} trigger_price={:?}",
-                o.id, o.order_type, o.status, o.interval_secs, o.last_executed_at, o.trigger_price_usd
+            log::debug!(
+                "[DCA]   order id={} type={} {:?} {} status={} interval={:?} last_exec={:?} trigger_price={:?}",
+                o.id,
+                o.order_type,
+                o.side,
+                o.pair.label(),
+                o.status,
+                o.interval_secs,
+                o.last_executed_at,
+                o.trigger_price
             );
         }
+        {
+            let mut account = self.account_id.lock().unwrap();
+            let mut states = self.run_states.lock().unwrap();
+            if *account != account_id {
+                states.clear();
+            } else {
+                states.retain(|id, _| orders.iter().any(|o| &o.id == id));
+            }
+            *account = account_id;
+        }
         *self.orders.write().unwrap() = orders;
+        self.wake.notify_one();
+    }
+
+    pub fn set_balances(&self, balances: HashMap<String, AssetAmount>) {
+        *self.balances.lock().unwrap() = balances;
     }
 
-    pub fn update_last_executed(&self, order_id: &str, timestamp: u64) {
+    pub fn update_last_executed(&self, order_id: &str, timestamp: u64, ladder_step: Option<usize>) {
+        let mut cleared = None;
         let mut orders = self.orders.write().unwrap();
         if let Some(order) = orders.iter_mut().find(|o| o.id == order_id) {
             order.last_executed_at = Some(timestamp);
-            println!(
+            if let (Some(DcaStrategy::DipLadder { steps }), Some(i)) =
+                (order.strategy.as_mut(), ladder_step)
+            {
+                if let Some(step) = steps.get_mut(i) {
+                    step.filled = true;
+                }
+            }
+            if let Some(DcaStrategy::TrailingEntry { extreme_price, .. }) = &order.strategy {
+                // The run fired: tracking starts over.
+                if extreme_price.is_some() {
+                    cleared = Some(DcaTrailingUpdate {
+                        order_id: order_id.to_string(),
+                        extreme_price: None,
+                    });
+                }
+            }
+            log::info!(
                 "[DCA] updated last_executed_at for order {} to {}",
-                order_id, timestamp
+                order_id,
+                timestamp
             );
         }
+        drop(orders);
+        if let Some(state) = self.run_states.lock().unwrap().get_mut(order_id) {
+            state.pending = state.pending.saturating_sub(1);
+            state.trailing_extreme = None;
+        }
+        if let Some(cleared) = cleared {
+            let handle = self.app_handle.lock().unwrap().clone();
+            self.save_trailing_extremes(&[cleared], handle.as_ref());
+        }
+        self.wake.notify_one();
     }
 
-    pub fn start(&self) {
+    /// Store the extremes trailing-entry orders now track: in the orders
+    /// here, in the stored payloads so tracking survives a restart, and in
+    /// the frontend's copy (`dca:trailing`) so it doesn't write the old one
+    /// back.
+    fn save_trailing_extremes(&self, updates: &[DcaTrailingUpdate], handle: Option<&AppHandle>) {
         {
-            let mut running = self.running.lock().unwrap();
-            if *running {
-                println!("[DCA] scheduler already running, skipping start");
-                return;
+            let mut orders = self.orders.write().unwrap();
+            for update in updates {
+                if let Some(DcaStrategy::TrailingEntry { extreme_price, .. }) = orders
+                    .iter_mut()
+                    .find(|o| o.id == update.order_id)
+                    .and_then(|o| o.strategy.as_mut())
+                {
+                    *extreme_price = update.extreme_price;
+                }
+            }
+        }
+        if let Some(account_id) = *self.account_id.lock().unwrap() {
+            let saved = db::with(|conn| {
+                updates.iter().try_for_each(|update| {
+                    db::set_dca_trailing_extreme(
+                        conn,
+                        account_id,
+                        &update.order_id,
+                        update.extreme_price,
+                    )
+                    .map(|_| ())
+                })
+            });
+            if let Err(e) = saved {
+                log::warn!("[DCA] failed to store trailing-entry extremes: {}", e);
             }
-            *running = true;
         }
+        if let Some(handle) = handle {
+            for update in updates {
+                if let Err(e) = handle.emit("dca:trailing", update) {
+                    log::warn!("[DCA] emit error: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Replace the scheduler settings and re-plan the timers.
+    pub fn set_config(&self, config: DcaSchedulerConfig) {
+        *self.config.write().unwrap() = config;
+        self.wake.notify_one();
+    }
 
-        println!(
-            "[DCA] scheduler starting (poll every {}s)",
-            POLL_INTERVAL_SECS
+    /// Start the scheduler task. Start and stop are serialized, and a stop
+    /// waits for the task to exit, so at most one loop ever runs.
+    pub async fn start(self: &Arc<Self>) {
+        let mut task = self.task.lock().await;
+        if task.is_some() {
+            log::info!("[DCA] scheduler already running, skipping start");
+            return;
+        }
+        let config = *self.config.read().unwrap();
+        log::info!(
+            "[DCA] scheduler starting (poll every {}s, jitter up to {}s)",
+            config.poll_interval_secs,
+            config.jitter_secs
         );
 
-        let orders = Arc::clone(&self.orders);
-        let app_handle = Arc::clone(&self.app_handle);
-        let running = Arc::clone(&self.running);
+        let cancel = CancellationToken::new();
+        let scheduler = Arc::clone(self);
+        let token = cancel.clone();
+        let handle = tauri::async_runtime::spawn(async move { scheduler.run(token).await });
+        *task = Some(RunningScheduler {
+            cancel,
+            task: handle,
+        });
+    }
 
-        thread::spawn(move || {
-            let http = reqwest::blocking::Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build()
-                .unwrap_or_else(|_| reqwest::blocking::Client::new());
+    /// Stop the scheduler task and wait for it to exit. Safe to call when not
+    /// running.
+    pub async fn stop(&self) {
+        let mut task = self.task.lock().await;
+        if let Some(running) = task.take() {
+            running.cancel.cancel();
+            if let Err(e) = running.task.await {
+                log::error!("[DCA] scheduler task failed: {:?}", e);
+            }
+        }
+    }
 
-            loop {
-                {
-                    let is_running = running.lock().unwrap();
-                    if !*is_running {
-                        println!("[DCA] scheduler stopping");
-                        break;
+    /// The scheduler loop: sleep until the earliest order timer (or until
+    /// orders or settings change), then check the orders that are due.
+    async fn run(self: Arc<Self>, cancel: CancellationToken) {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        let mut prices = PriceCache::default();
+        // Next check time per order id; orders without one are due now.
+        let mut timers: HashMap<String, u64> = HashMap::new();
+        let mut oracle_warning = false;
+
+        loop {
+            let now = unix_now();
+            let config = *self.config.read().unwrap();
+            let due: Vec<DcaOrderInfo> = {
+                let orders = self.orders.read().unwrap();
+                timers.retain(|id, _| orders.iter().any(|o| &o.id == id && o.status == "active"));
+                orders
+                    .iter()
+                    .filter(|o| o.status == "active")
+                    .filter(|o| timers.get(&o.id).is_none_or(|&at| at <= now))
+                    .cloned()
+                    .collect()
+            };
+
+            if !due.is_empty() {
+                tokio::select! {
+                    _ = cancel.cancelled() => break,
+                    _ = prices.refresh(&http, &due, now, &self.metrics) => {}
+                }
+                oracle_warning = self.update_oracle_warning(oracle_warning, now);
+                let scheduler = Arc::clone(&self);
+                let current = prices.current(now);
+                let checked = tauri::async_runtime::spawn_blocking(move || {
+                    scheduler.check_orders(&due, &current, now, &config)
+                })
+                .await;
+                match checked {
+                    Ok(next) => timers.extend(next),
+                    Err(e) => log::error!("[DCA] order check failed: {:?}", e),
+                }
+            }
+
+            let wake_at = timers
+                .values()
+                .min()
+                .copied()
+                .unwrap_or(now + config.poll_interval_secs);
+            let delay = Duration::from_secs(wake_at.saturating_sub(unix_now()));
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = self.wake.notified() => {
+                    // Orders or settings changed: re-plan time-based orders now;
+                    // price watchers keep their timers so a resync doesn't
+                    // trigger extra price requests.
+                    let orders = self.orders.read().unwrap();
+                    timers.retain(|id, _| orders.iter().any(|o| &o.id == id && watches_price(o)));
+                }
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+        if oracle_warning {
+            if let Some(handle) = self.app_handle.lock().unwrap().as_ref() {
+                tray::set_dca_warning(handle, None);
+            }
+        }
+        log::info!("[DCA] scheduler stopped");
+    }
+
+    /// Show or clear the tray warning as the price oracle starts or stops
+    /// failing. Returns whether the warning is now shown.
+    fn update_oracle_warning(&self, shown: bool, now: u64) -> bool {
+        let (degraded, error) = {
+            let metrics = self.metrics.lock().unwrap();
+            (
+                metrics.oracle_degraded(now),
+                metrics.last_oracle_error.clone(),
+            )
+        };
+        if degraded == shown {
+            return shown;
+        }
+        if degraded {
+            log::warn!(
+                "[DCA] price oracle failing for over {}s: {}",
+                ORACLE_WARNING_SECS,
+                error.unwrap_or_default()
+            );
+        } else {
+            log::info!("[DCA] price oracle recovered");
+        }
+        if let Some(handle) = self.app_handle.lock().unwrap().as_ref() {
+            let warning = degraded.then(|| "DCA: price feed unavailable".to_string());
+            tray::set_dca_warning(handle, warning);
+        }
+        degraded
+    }
+
+    /// Scheduler state and counters for `dca_status`.
+    pub async fn status(&self) -> DcaStatus {
+        let running = self.task.lock().await.is_some();
+        let metrics = self.metrics.lock().unwrap().clone();
+        DcaStatus {
+            running,
+            active_orders: self
+                .orders
+                .read()
+                .unwrap()
+                .iter()
+                .filter(|o| o.status == "active")
+                .count(),
+            oracle_degraded: metrics.oracle_degraded(unix_now()),
+            metrics,
+        }
+    }
+
+    /// Check the due orders once: end dates, triggers and spend limits,
+    /// then emit the resulting events. Returns when each order should be
+    /// checked next.
+    fn check_orders(
+        &self,
+        due: &[DcaOrderInfo],
+        prices: &HashMap<PriceSource, Option<f64>>,
+        now: u64,
+        config: &DcaSchedulerConfig,
+    ) -> Vec<(String, u64)> {
+        log::debug!(
+            "[DCA] checking {} due order(s) at t={} — prices={:?}",
+            due.len(),
+            now,
+            prices
+        );
+        let mut missed: Vec<MissedRun> = Vec::new();
+        let mut status_changes: Vec<(&str, DcaStatusChange)> = Vec::new();
+        let mut triggers: Vec<DcaTriggerPayload> = Vec::new();
+        let mut trailing: Vec<DcaTrailingUpdate> = Vec::new();
+        let mut next_checks = Vec::with_capacity(due.len());
+        {
+            let balances = self.balances.lock().unwrap().clone();
+            let mut spend = match *self.account_id.lock().unwrap() {
+                Some(id) => {
+                    let orders = self.orders.read().unwrap().clone();
+                    SpendContext::load(id, &orders, balances, now).unwrap_or_else(|e| {
+                        log::warn!("[DCA] failed to load spend history: {}", e);
+                        SpendContext::default()
+                    })
+                }
+                None => SpendContext::default(),
+            };
+            let mut states = self.run_states.lock().unwrap();
+            let mut rng = rand::thread_rng();
+            for o in due {
+                if let Some(change) = check_end_date(o, now) {
+                    status_changes.push(("completed", change));
+                    continue;
+                }
+                // Prices are optional: scheduled orders don't need them.
+                let price = prices
+                    .get(&o.pair.price_source)
+                    .copied()
+                    .flatten()
+                    .and_then(|p| o.pair.price_from_source(p))
+                    .unwrap_or(0.0);
+                let state = states.entry(o.id.clone()).or_default();
+                if let Some(trigger) = check_trigger(o, state, price, now, &mut missed) {
+                    match gate_trigger(o, trigger, state, &mut spend) {
+                        Ok(Some(trigger)) => triggers.push(trigger),
+                        Ok(None) => {}
+                        Err(change) => status_changes.push(change),
                     }
                 }
+                trailing.extend(DcaTrailingUpdate::changed(o, state));
+                let jitter = rng.gen_range(0..=config.jitter_secs);
+                next_checks.push((o.id.clone(), next_check_at(o, state, now, config) + jitter));
+            }
+        }
 
-                // Fetch price (optional - scheduled orders don't need it)
-                let current_price = fetch_btc_price(&http);
-                println!("[DCA] poll tick — price={:?}", current_price);
-
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-
-                let triggers: Vec<DcaTriggerPayload> = {
-                    let orders_guard = orders.read().unwrap();
-                    println!(
-                        "[DCA] checking {} active orders at t={}",
-                        orders_guard.len(),
-                        now
-                    );
-                    orders_guard
-                        .iter()
-                        .filter(|o| o.status == "active")
-                        .filter_map(|o| check_trigger(o, current_price.unwrap_or(0.0), now))
-                        .collect()
-                };
-
-                println!("[DCA] {} trigger(s) to emit", triggers.len());
-                if !triggers.is_empty() {
-                    let handle_guard = app_handle.lock().unwrap();
-                    match handle_guard.as_ref() {
-                        Some(handle) => {
-                            for trigger in triggers {
-                                println!(
-                                    "[DCA] emitting dca:trigger for order={} price={}",
-                                    trigger.order_id, trigger.current_price
-                                );
-                                if let Err(e) = handle.emit("dca:trigger", &trigger) {
-                                    println!("[DCA] emit error: {:?}", e);
-                                }
-                            }
-                        }
-                        None => {
-                            println!("[DCA] no app_handle set, cannot emit events");
+        let app_handle = self.app_handle.lock().unwrap().clone();
+        if !trailing.is_empty() {
+            self.save_trailing_extremes(&trailing, app_handle.as_ref());
+        }
+        if !status_changes.is_empty() {
+            apply_status_changes(&self.orders, &status_changes, app_handle.as_ref());
+        }
+
+        if !missed.is_empty() {
+            let account_id = *self.account_id.lock().unwrap();
+            record_missed_runs(account_id, &missed, app_handle.as_ref());
+        }
+
+        log::debug!("[DCA] {} trigger(s) to emit", triggers.len());
+        {
+            let mut metrics = self.metrics.lock().unwrap();
+            metrics.ticks += 1;
+            metrics.triggers += triggers.len() as u64;
+        }
+        if !triggers.is_empty() {
+            match app_handle.as_ref() {
+                Some(handle) => {
+                    for trigger in triggers {
+                        log::info!(
+                            "[DCA] emitting dca:trigger for order={} price={}",
+                            trigger.order_id,
+                            trigger.current_price
+                        );
+                        if let Err(e) = handle.emit("dca:trigger", &trigger) {
+                            log::warn!("[DCA] emit error: {:?}", e);
                         }
                     }
                 }
-
-                thread::sleep(Duration::from_secs(POLL_INTERVAL_SECS));
+                None => {
+                    log::warn!("[DCA] no app_handle set, cannot emit events");
+                }
             }
-        });
+        }
+        next_checks
     }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Orders checked on every poll because they react to the price rather than
+/// to a schedule.
+fn watches_price(order: &DcaOrderInfo) -> bool {
+    !matches!(order.order_type.as_str(), "scheduled" | "value-averaging")
+}
+
+/// When an order next needs checking, before jitter: its next scheduled run
+/// for time-based orders, otherwise one poll interval from now (price
+/// watchers, and owed runs waiting to execute or retry). Never later than
+/// the order's end date.
+fn next_check_at(
+    order: &DcaOrderInfo,
+    state: &OrderRunState,
+    now: u64,
+    config: &DcaSchedulerConfig,
+) -> u64 {
+    let poll = now + config.poll_interval_secs;
+    let next = if watches_price(order) || state.pending > 0 {
+        poll
+    } else {
+        order
+            .effective_schedule()
+            .zip(
+                order
+                    .last_executed_at
+                    .or(order.created_at)
+                    .max(state.anchor),
+            )
+            .and_then(|(schedule, anchor)| schedule.next_run_after(anchor).ok().flatten())
+            .unwrap_or(poll)
+    };
+    let next = match order.end_at {
+        Some(end_at) => next.min(end_at),
+        None => next,
+    };
+    next.max(now + 1)
+}
+
+/// Run the pre-trade spend check on a trigger. Ok(None) defers it to a later
+/// tick; Err takes the order out of the active set.
+fn gate_trigger(
+    order: &DcaOrderInfo,
+    trigger: DcaTriggerPayload,
+    state: &mut OrderRunState,
+    spend: &mut SpendContext,
+) -> Result<Option<DcaTriggerPayload>, (&'static str, DcaStatusChange)> {
+    match check_spend(order, trigger.amount.unwrap_or(order.amount), spend) {
+        SpendCheck::Proceed(amount) => {
+            let amount = if trigger.amount.is_none() && amount == order.amount {
+                None
+            } else {
+                Some(amount)
+            };
+            Ok(Some(DcaTriggerPayload { amount, ..trigger }))
+        }
+        SpendCheck::Defer(reason) => {
+            log::info!("[DCA] order={} deferred: {}", order.id, reason);
+            Ok(None)
+        }
+        SpendCheck::Pause(change) => {
+            // Owed runs are dropped; resuming starts a fresh schedule.
+            state.pending = 0;
+            Err(("paused", change))
+        }
+        SpendCheck::Complete(change) => Err(("completed", change)),
+    }
+}
 
-    pub fn stop(&self) {
-        *self.running.lock().unwrap() = false;
+/// Mark orders paused / completed and emit `dca:paused` / `dca:completed`.
+fn apply_status_changes(
+    orders: &RwLock<Vec<DcaOrderInfo>>,
+    changes: &[(&str, DcaStatusChange)],
+    handle: Option<&AppHandle>,
+) {
+    let mut orders = orders.write().unwrap();
+    for (status, change) in changes {
+        log::info!(
+            "[DCA] order={} {}: {}",
+            change.order_id,
+            status,
+            change.message
+        );
+        if let Some(order) = orders.iter_mut().find(|o| o.id == change.order_id) {
+            order.status = status.to_string();
+        }
+        if let Some(handle) = handle {
+            if let Err(e) = handle.emit(&format!("dca:{}", status), change) {
+                log::warn!("[DCA] emit error: {:?}", e);
+            }
+        }
     }
 }
 
-fn fetch_btc_price(http: &reqwest::blocking::Client) -> Option<f64> {
-    match http.get(COINGECKO_URL).send() {
-        Ok(resp) => match resp.json::<CoinGeckoResponse>() {
-            Ok(data) => match data.bitcoin.and_then(|b| b.usd) {
-                Some(price) => {
-                    println!("[DCA] BTC price fetched: ${}", price);
+/// Latest reading per price source, shared by all orders so each source is
+/// fetched at most once per [`PRICE_CACHE_SECS`]. Failed fetches are cached
+/// too, so a source that is down isn't retried by every order.
+#[derive(Debug, Default)]
+struct PriceCache(HashMap<PriceSource, (Option<f64>, u64)>);
+
+impl PriceCache {
+    /// Fetch the price sources `orders` need that have no fresh reading.
+    /// Plain scheduled orders trade at the maker's quote and need none.
+    async fn refresh(
+        &mut self,
+        http: &reqwest::Client,
+        orders: &[DcaOrderInfo],
+        now: u64,
+        metrics: &Mutex<DcaMetrics>,
+    ) {
+        for order in orders.iter().filter(|o| o.order_type != "scheduled") {
+            let source = &order.pair.price_source;
+            if self.is_fresh(source, now) {
+                continue;
+            }
+            let price = match source.fetch(http).await {
+                Ok(price) => {
+                    metrics.lock().unwrap().record_price(now);
                     Some(price)
                 }
-                None => {
-                    println!("[DCA] price response missing bitcoin/usd field (rate-limited?)");
+                Err(e) => {
+                    log::warn!("[DCA] {}", e);
+                    metrics.lock().unwrap().record_oracle_failure(now, e);
                     None
                 }
-            },
-            Err(e) => {
-                println!("[DCA] price JSON parse error: {:?}", e);
-                None
+            };
+            self.0.insert(source.clone(), (price, now));
+        }
+    }
+
+    fn is_fresh(&self, source: &PriceSource, now: u64) -> bool {
+        self.0
+            .get(source)
+            .is_some_and(|(_, at)| now.saturating_sub(*at) < PRICE_CACHE_SECS)
+    }
+
+    /// Fresh readings only, so no order acts on an old price.
+    fn current(&self, now: u64) -> HashMap<PriceSource, Option<f64>> {
+        self.0
+            .iter()
+            .filter(|(source, _)| self.is_fresh(source, now))
+            .map(|(source, (price, _))| (source.clone(), *price))
+            .collect()
+    }
+}
+
+/// Record missed runs in the execution history and notify the frontend.
+fn record_missed_runs(account_id: Option<i32>, missed: &[MissedRun], handle: Option<&AppHandle>) {
+    log::info!("[DCA] {} missed run(s)", missed.len());
+    for run in missed {
+        if let Some(account_id) = account_id {
+            let execution = DcaExecution {
+                id: 0,
+                account_id,
+                order_id: run.order_id.clone(),
+                executed_at: run.scheduled_at as i64,
+                quoted_price: 0.0,
+                amount_in: 0.0,
+                amount_out: 0,
+                fee: 0,
+                swap_id: None,
+                status: "missed".to_string(),
+                error: Some(run.reason.clone()),
+                spend_asset: None,
+            };
+            if let Err(e) = db::with(|conn| db::insert_dca_execution(conn, account_id, &execution))
+            {
+                log::warn!("[DCA] failed to record missed run: {}", e);
             }
-        },
-        Err(e) => {
-            println!("[DCA] price fetch HTTP error: {:?}", e);
-            None
         }
+        if let Some(handle) = handle {
+            if let Err(e) = handle.emit("dca:missed", run) {
+                log::warn!("[DCA] emit error: {:?}", e);
+            }
+        }
+    }
+}
+
+/// Scheduled runs due in `(anchor, now]`, oldest first. Bounded by
+/// [`MAX_DUE_RUNS`]; when more are due only the most recent ones are kept.
+fn due_runs(schedule: &DcaSchedule, anchor: u64, now: u64) -> Vec<u64> {
+    if let DcaSchedule::Interval { interval_secs } = schedule {
+        if *interval_secs == 0 || anchor.saturating_add(*interval_secs) > now {
+            return Vec::new();
+        }
+        let first = anchor + interval_secs;
+        let count = (now - first) / interval_secs + 1;
+        let skip = count.saturating_sub(MAX_DUE_RUNS as u64);
+        return (skip..count).map(|i| first + i * interval_secs).collect();
+    }
+
+    let mut runs = Vec::new();
+    let mut cursor = anchor;
+    let mut rescanned = false;
+    while let Ok(Some(next)) = schedule.next_run_after(cursor) {
+        if next > now {
+            break;
+        }
+        if runs.len() == MAX_DUE_RUNS {
+            if rescanned {
+                break;
+            }
+            // Too many to enumerate: keep only the most recent window.
+            rescanned = true;
+            runs.clear();
+            cursor = cursor.max(now.saturating_sub(7 * 24 * 3600));
+            continue;
+        }
+        runs.push(next);
+        cursor = next;
+    }
+    runs
+}
+
+/// Split due runs into the number to execute and the ones to mark missed.
+fn plan_catch_up(policy: &CatchUpPolicy, due: &[u64], now: u64) -> (u32, Vec<u64>) {
+    let Some(&latest) = due.last() else {
+        return (0, Vec::new());
+    };
+    let earlier = &due[..due.len() - 1];
+    match policy {
+        CatchUpPolicy::Skip => {
+            if now.saturating_sub(latest) <= LATE_GRACE_SECS {
+                (1, earlier.to_vec())
+            } else {
+                (0, due.to_vec())
+            }
+        }
+        CatchUpPolicy::RunOnce => (1, earlier.to_vec()),
+        CatchUpPolicy::RunAll { max_runs } => {
+            let runs = due.len().min((*max_runs).max(1) as usize);
+            (runs as u32, due[..due.len() - runs].to_vec())
+        }
+    }
+}
+
+/// Advance the schedule of a time-based order; true while a run is owed.
+fn check_schedule(
+    order: &DcaOrderInfo,
+    state: &mut OrderRunState,
+    now: u64,
+    missed: &mut Vec<MissedRun>,
+) -> bool {
+    if state.pending == 0 {
+        let Some(schedule) = order.effective_schedule() else {
+            return false;
+        };
+        if let Err(e) = schedule.validate() {
+            log::warn!("[DCA] order {} has an invalid schedule: {}", order.id, e);
+            return false;
+        }
+        let base = order.last_executed_at.or(order.created_at);
+        let due = match (base.max(state.anchor), &schedule) {
+            (Some(anchor), _) => due_runs(&schedule, anchor, now),
+            // Never executed and no creation time: interval orders are
+            // due immediately, calendar orders wait for their next slot.
+            (None, DcaSchedule::Interval { .. }) => vec![now],
+            (None, _) => {
+                state.anchor = Some(now);
+                Vec::new()
+            }
+        };
+        let (runs, missed_at) = plan_catch_up(&order.catch_up, &due, now);
+        log::debug!(
+            "[DCA] scheduled order={} last={:?} due={} run={} missed={} now={}",
+            order.id,
+            order.last_executed_at,
+            due.len(),
+            runs,
+            missed_at.len(),
+            now
+        );
+        if let Some(&latest) = due.last() {
+            state.anchor = Some(latest);
+        }
+        state.pending = runs;
+        let reason = format!(
+            "Missed while the app was closed or asleep (catch-up policy: {})",
+            order.catch_up.label()
+        );
+        missed.extend(missed_at.into_iter().map(|scheduled_at| MissedRun {
+            order_id: order.id.clone(),
+            scheduled_at,
+            reason: reason.clone(),
+        }));
+    }
+    state.pending > 0
+}
+
+/// Number of whole schedule periods between order creation and `now`.
+fn elapsed_periods(order: &DcaOrderInfo, now: u64) -> Option<u64> {
+    let start = order.created_at?;
+    match order.effective_schedule()? {
+        DcaSchedule::Interval { interval_secs } if interval_secs > 0 => {
+            Some(now.saturating_sub(start) / interval_secs)
+        }
+        DcaSchedule::Interval { .. } => None,
+        schedule => {
+            let mut periods = 0;
+            let mut cursor = start;
+            while let Ok(Some(next)) = schedule.next_run_after(cursor) {
+                if next > now || periods >= 100_000 {
+                    break;
+                }
+                periods += 1;
+                cursor = next;
+            }
+            Some(periods)
+        }
+    }
+}
+
+/// Quote asset needed to bring holdings up to `increment * periods`,
+/// capped at `max_amount` and rounded down to its precision. Zero when
+/// ahead of the path.
+fn value_averaging_amount(
+    increment: AssetAmount,
+    periods: u64,
+    acquired: AssetAmount,
+    price: f64,
+    max_amount: AssetAmount,
+) -> AssetAmount {
+    let target = increment.to_decimal() * periods as f64;
+    let current = acquired.to_decimal() * price;
+    let amount = (target - current).clamp(0.0, max_amount.to_decimal());
+    AssetAmount::from_decimal(amount, max_amount.precision)
+}
+
+/// The first unfilled ladder level the price has reached: the highest one
+/// for buys, the lowest one for sells.
+fn next_ladder_step(steps: &[LadderStep], price: f64, side: TradeSide) -> Option<usize> {
+    let reached = steps
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !s.filled && side.price_reached(price, s.price));
+    let step = match side {
+        TradeSide::Buy => reached.max_by(|(_, a), (_, b)| a.price.total_cmp(&b.price)),
+        TradeSide::Sell => reached.min_by(|(_, a), (_, b)| a.price.total_cmp(&b.price)),
+    };
+    step.map(|(i, _)| i)
+}
+
+/// Track the local extreme (low for buys, high for sells) and report whether
+/// the price has moved back `rebound_pct` from it. Tracking starts once the
+/// price reaches `activation`, if set.
+fn trailing_entry_triggered(
+    extreme: &mut Option<f64>,
+    rebound_pct: f64,
+    activation: Option<f64>,
+    price: f64,
+    side: TradeSide,
+) -> bool {
+    match *extreme {
+        None => {
+            if activation.is_none_or(|a| side.price_reached(price, a)) {
+                *extreme = Some(price);
+            }
+            false
+        }
+        Some(e) if side.better_price(price, e) => {
+            *extreme = Some(price);
+            false
+        }
+        Some(e) => match side {
+            TradeSide::Buy => price >= e * (1.0 + rebound_pct / 100.0),
+            TradeSide::Sell => price <= e * (1.0 - rebound_pct / 100.0),
+        },
     }
 }
 
-fn check_trigger(order: &DcaOrderInfo, current_price: f64, now: u64) -> Option<DcaTriggerPayload> {
+pub(crate) fn check_trigger(
+    order: &DcaOrderInfo,
+    state: &mut OrderRunState,
+    current_price: f64,
+    now: u64,
+    missed: &mut Vec<MissedRun>,
+) -> Option<DcaTriggerPayload> {
     match order.order_type.as_str() {
         "scheduled" => {
-            let interval = order.interval_secs?;
-            let last = order.last_executed_at.unwrap_or(0);
-            let next = last + interval;
-            println!(
-                "[DCA] scheduled order={} interval={}s last={} next={} now={} → {}",
+            if check_schedule(order, state, now, missed) {
+                Some(DcaTriggerPayload::new(order, current_price))
+            } else {
+                None
+            }
+        }
+        "value-averaging" => {
+            let Some(DcaStrategy::ValueAveraging {
+                increment,
+                max_amount,
+                acquired,
+            }) = &order.strategy
+            else {
+                return None;
+            };
+            if order.side != TradeSide::Buy {
+                log::warn!(
+                    "[DCA] value-averaging order={} is sell-side, which is not supported",
+                    order.id
+                );
+                return None;
+            }
+            // Without a price the period stays pending until the next tick.
+            if !check_schedule(order, state, now, missed) || current_price <= 0.0 {
+                return None;
+            }
+            let periods = elapsed_periods(order, now)?;
+            let amount =
+                value_averaging_amount(*increment, periods, *acquired, current_price, *max_amount);
+            log::debug!(
+                "[DCA] value-averaging order={} period={} holdings={} {} → buy {} {}",
                 order.id,
-                interval,
-                last,
-                next,
-                now,
-                if now >= next { "TRIGGER" } else { "wait" }
+                periods,
+                acquired,
+                order.pair.base.ticker,
+                amount,
+                order.pair.quote.ticker
             );
-            if now >= next {
-                Some(DcaTriggerPayload {
-                    current_price,
-                    order_id: order.id.clone(),
-                })
+            if amount.is_zero() {
+                // Ahead of the value path: nothing to buy this period.
+                state.pending = state.pending.saturating_sub(1);
+                return None;
+            }
+            Some(DcaTriggerPayload {
+                amount: Some(amount),
+                ..DcaTriggerPayload::new(order, current_price)
+            })
+        }
+        "dip-ladder" => {
+            let Some(DcaStrategy::DipLadder { steps }) = &order.strategy else {
+                return None;
+            };
+            if current_price <= 0.0 {
+                return None;
+            }
+            let step = next_ladder_step(steps, current_price, order.side)?;
+            log::debug!(
+                "[DCA] dip-ladder order={} step={} level={} current={} → TRIGGER",
+                order.id,
+                step,
+                steps[step].price,
+                current_price
+            );
+            Some(DcaTriggerPayload {
+                amount: Some(steps[step].amount),
+                ladder_step: Some(step),
+                ..DcaTriggerPayload::new(order, current_price)
+            })
+        }
+        "trailing-entry" => {
+            let Some(DcaStrategy::TrailingEntry {
+                rebound_pct,
+                activation_price,
+                extreme_price,
+            }) = &order.strategy
+            else {
+                return None;
+            };
+            if current_price <= 0.0 {
+                return None;
+            }
+            if state.trailing_extreme.is_none() {
+                state.trailing_extreme = *extreme_price;
+            }
+            let triggered = trailing_entry_triggered(
+                &mut state.trailing_extreme,
+                *rebound_pct,
+                *activation_price,
+                current_price,
+                order.side,
+            );
+            log::debug!(
+                "[DCA] trailing-entry order={} extreme={:?} current={} → {}",
+                order.id,
+                state.trailing_extreme,
+                current_price,
+                if triggered { "TRIGGER" } else { "wait" }
+            );
+            if triggered {
+                Some(DcaTriggerPayload::new(order, current_price))
             } else {
                 None
             }
         }
         "price-target" => {
-            let trigger_price = order.trigger_price_usd?;
-            println!(
-                "[DCA] price-target order={} trigger=${} current=${} → {}",
+            let trigger_price = order.trigger_price?;
+            let reached =
+                current_price > 0.0 && order.side.price_reached(current_price, trigger_price);
+            log::debug!(
+                "[DCA] price-target order={} {:?} trigger={} current={} → {}",
                 order.id,
+                order.side,
                 trigger_price,
                 current_price,
-                if current_price <= trigger_price && current_price > 0.0 {
-                    "TRIGGER"
-                } else {
-                    "wait"
-                }
+                if reached { "TRIGGER" } else { "wait" }
             );
-            if current_price > 0.0 && current_price <= trigger_price {
-                Some(DcaTriggerPayload {
-                    current_price,
-                    order_id: order.id.clone(),
-                })
+            if reached {
+                Some(DcaTriggerPayload::new(order, current_price))
             } else {
                 None
             }
@@ -240,3 +1214,817 @@ fn check_trigger(order: &DcaOrderInfo, current_price: f64, now: u64) -> Option<D
         _ => None,
     }
 }
+
+/// The market an order trades, as far as its execution history needs it.
+/// Orders from before pairs were configurable, and the history of deleted
+/// orders, count as BTC/USDT buys.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct OrderMarket {
+    #[serde(default)]
+    pub pair: AssetPair,
+    #[serde(default)]
+    pub side: TradeSide,
+}
+
+impl OrderMarket {
+    fn spend_ticker(&self) -> &str {
+        &self.side.spend_asset(&self.pair).ticker
+    }
+
+    /// `raw` of the received asset, in its display unit.
+    fn received(&self, raw: i64) -> f64 {
+        let precision = self.side.receive_asset(&self.pair).precision;
+        AssetAmount::new(raw.max(0) as u64, precision).to_decimal()
+    }
+
+    /// Whether `e` spent this market's asset. Rows recorded before the spent
+    /// asset was stored are assumed to.
+    fn spent_by(&self, e: &DcaExecution) -> bool {
+        e.spend_asset
+            .as_deref()
+            .is_none_or(|ticker| ticker == self.spend_ticker())
+    }
+}
+
+/// The market of each stored order payload (as saved by the frontend), by
+/// order id. Unreadable payloads are skipped.
+pub fn order_markets(payloads: &[String]) -> HashMap<String, OrderMarket> {
+    #[derive(Deserialize)]
+    struct StoredOrder {
+        id: String,
+        #[serde(flatten)]
+        market: OrderMarket,
+    }
+
+    payloads
+        .iter()
+        .filter_map(|p| match serde_json::from_str::<StoredOrder>(p) {
+            Ok(order) => Some((order.id, order.market)),
+            Err(e) => {
+                log::warn!("[DCA] skipping unreadable order payload: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Aggregate figures over an order's execution history.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DcaOrderStats {
+    pub order_id: String,
+    pub execution_count: u32,
+    pub success_count: u32,
+    pub failure_count: u32,
+    /// Scheduled runs that were skipped by the catch-up policy (not attempts).
+    pub missed_count: u32,
+    /// Failed attempts over all attempts, 0.0–1.0.
+    pub failure_rate: f64,
+    /// Ticker of the asset the order spends.
+    pub spend_asset: String,
+    /// Ticker of the asset it receives.
+    pub receive_asset: String,
+    /// Sum of `amount_in` over successful executions, in the spent asset.
+    pub total_invested: f64,
+    /// Sum of `amount_out` over successful executions, in the received asset.
+    pub total_received: f64,
+    /// Sum of fees over successful executions, in the received asset.
+    pub total_fees: f64,
+    /// Average fill price in quote asset per base asset, for buys and sells
+    /// alike. None until something was received.
+    pub average_price: Option<f64>,
+    pub first_executed_at: Option<i64>,
+    pub last_executed_at: Option<i64>,
+}
+
+pub fn compute_order_stats(
+    order_id: &str,
+    market: &OrderMarket,
+    executions: &[DcaExecution],
+) -> DcaOrderStats {
+    let mut stats = DcaOrderStats {
+        order_id: order_id.to_string(),
+        spend_asset: market.spend_ticker().to_string(),
+        receive_asset: market.side.receive_asset(&market.pair).ticker.clone(),
+        ..Default::default()
+    };
+
+    for e in executions.iter().filter(|e| e.order_id == order_id) {
+        if e.status == "missed" {
+            stats.missed_count += 1;
+            continue;
+        }
+        stats.execution_count += 1;
+        match e.status.as_str() {
+            "success" => {
+                stats.success_count += 1;
+                // An execution that spent another asset can't be added up
+                // with the others
+                if market.spent_by(e) {
+                    stats.total_invested += e.amount_in;
+                    stats.total_received += market.received(e.amount_out);
+                    stats.total_fees += market.received(e.fee);
+                }
+            }
+            _ => stats.failure_count += 1,
+        }
+        stats.first_executed_at = Some(
+            stats
+                .first_executed_at
+                .map_or(e.executed_at, |t| t.min(e.executed_at)),
+        );
+        stats.last_executed_at = Some(
+            stats
+                .last_executed_at
+                .map_or(e.executed_at, |t| t.max(e.executed_at)),
+        );
+    }
+
+    if stats.execution_count > 0 {
+        stats.failure_rate = stats.failure_count as f64 / stats.execution_count as f64;
+    }
+    if stats.total_invested > 0.0 && stats.total_received > 0.0 {
+        stats.average_price = Some(match market.side {
+            TradeSide::Buy => stats.total_invested / stats.total_received,
+            TradeSide::Sell => stats.total_received / stats.total_invested,
+        });
+    }
+    stats
+}
+
+/// Render execution history as CSV (header + one row per execution).
+/// `amount_in` is in `spend_asset`; `amount_out` and `fee` are in
+/// `receive_asset`, taken from the order's market in `markets`.
+pub fn executions_to_csv(
+    executions: &[DcaExecution],
+    markets: &HashMap<String, OrderMarket>,
+) -> String {
+    let mut out = String::from(
+        "order_id,executed_at,quoted_price,amount_in,spend_asset,amount_out,fee,receive_asset,swap_id,status,error\n",
+    );
+    let default_market = OrderMarket::default();
+    for e in executions {
+        let market = markets.get(&e.order_id).unwrap_or(&default_market);
+        let receive = market.side.receive_asset(&market.pair);
+        let received = |raw: i64| AssetAmount::new(raw.max(0) as u64, receive.precision);
+        let fields = [
+            csv_field(&e.order_id),
+            e.executed_at.to_string(),
+            e.quoted_price.to_string(),
+            e.amount_in.to_string(),
+            csv_field(e.spend_asset.as_deref().unwrap_or(market.spend_ticker())),
+            received(e.amount_out).to_string(),
+            received(e.fee).to_string(),
+            csv_field(&receive.ticker),
+            csv_field(e.swap_id.as_deref().unwrap_or("")),
+            csv_field(&e.status),
+            csv_field(e.error.as_deref().unwrap_or("")),
+        ];
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dca_pair::DcaAsset;
+
+    fn execution(order_id: &str, at: i64, status: &str, amount_in: f64, sats: i64) -> DcaExecution {
+        DcaExecution {
+            id: 0,
+            account_id: 1,
+            order_id: order_id.to_string(),
+            executed_at: at,
+            quoted_price: 0.0,
+            amount_in,
+            amount_out: sats,
+            fee: 10,
+            swap_id: None,
+            status: status.to_string(),
+            error: None,
+            spend_asset: None,
+        }
+    }
+
+    #[test]
+    fn test_order_stats() {
+        let executions = vec![
+            execution("a", 100, "success", 50.0, 100_000),
+            execution("a", 200, "failed", 50.0, 0),
+            execution("a", 300, "success", 50.0, 50_000),
+            execution("b", 400, "success", 10.0, 1_000),
+            execution("a", 500, "missed", 0.0, 0),
+        ];
+
+        let stats = compute_order_stats("a", &OrderMarket::default(), &executions);
+        assert_eq!(stats.execution_count, 3);
+        assert_eq!(stats.success_count, 2);
+        assert_eq!(stats.failure_count, 1);
+        assert_eq!(stats.missed_count, 1);
+        assert!((stats.failure_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(
+            (stats.spend_asset.as_str(), stats.receive_asset.as_str()),
+            ("USDT", "BTC")
+        );
+        assert_eq!(stats.total_invested, 100.0);
+        assert!((stats.total_received - 0.0015).abs() < 1e-12);
+        assert!((stats.total_fees - 0.000_000_2).abs() < 1e-12);
+        // 100 USDT for 0.0015 BTC
+        assert!((stats.average_price.unwrap() - 66_666.666_666).abs() < 0.01);
+        assert_eq!(stats.first_executed_at, Some(100));
+        assert_eq!(stats.last_executed_at, Some(300));
+    }
+
+    #[test]
+    fn test_order_stats_sell_and_other_pairs() {
+        // Selling 0.001 BTC twice for 60 and 40 USDT (6 decimals)
+        let sell = OrderMarket {
+            side: TradeSide::Sell,
+            ..Default::default()
+        };
+        let mut executions = vec![
+            execution("s", 100, "success", 0.001, 60_000_000),
+            execution("s", 200, "success", 0.001, 40_000_000),
+        ];
+        executions
+            .iter_mut()
+            .for_each(|e| e.spend_asset = Some("BTC".into()));
+        let stats = compute_order_stats("s", &sell, &executions);
+        assert_eq!(
+            (stats.spend_asset.as_str(), stats.receive_asset.as_str()),
+            ("BTC", "USDT")
+        );
+        assert!((stats.total_received - 100.0).abs() < 1e-9);
+        assert!((stats.total_fees - 0.000_02).abs() < 1e-12);
+        // Quote per base, as for buys
+        assert!((stats.average_price.unwrap() - 50_000.0).abs() < 1e-6);
+
+        // Buying XAUT (2 decimals) with USDT; a row that spent another asset
+        // is counted but left out of the totals
+        let xaut = OrderMarket {
+            pair: AssetPair {
+                base: DcaAsset {
+                    asset_id: Some("rgb:xaut".into()),
+                    ticker: "XAUT".into(),
+                    precision: 2,
+                },
+                ..Default::default()
+            },
+            side: TradeSide::Buy,
+        };
+        let mut executions = vec![
+            execution("x", 100, "success", 250.0, 10),
+            execution("x", 200, "success", 1.0, 10),
+        ];
+        executions[0].spend_asset = Some("USDT".into());
+        executions[1].spend_asset = Some("BTC".into());
+        let stats = compute_order_stats("x", &xaut, &executions);
+        assert_eq!(stats.success_count, 2);
+        assert_eq!(stats.total_invested, 250.0);
+        assert!((stats.total_received - 0.1).abs() < 1e-12);
+        assert!((stats.average_price.unwrap() - 2_500.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_order_markets() {
+        let payloads = vec![
+            r#"{"id":"legacy","amountUsdt":10}"#.to_string(),
+            serde_json::json!({
+                "id": "sell",
+                "side": "sell",
+                "pair": AssetPair::default(),
+            })
+            .to_string(),
+            "not json".to_string(),
+        ];
+        let markets = order_markets(&payloads);
+        assert_eq!(markets.len(), 2);
+        assert_eq!(markets["legacy"], OrderMarket::default());
+        assert_eq!(markets["sell"].side, TradeSide::Sell);
+    }
+
+    const HOUR: u64 = 3600;
+
+    fn scheduled(last: u64, catch_up: CatchUpPolicy) -> DcaOrderInfo {
+        DcaOrderInfo {
+            id: "o".to_string(),
+            order_type: "scheduled".to_string(),
+            status: "active".to_string(),
+            pair: AssetPair::default(),
+            side: TradeSide::Buy,
+            amount: usdt(10.0),
+            interval_secs: Some(HOUR),
+            last_executed_at: Some(last),
+            schedule: None,
+            created_at: None,
+            catch_up,
+            trigger_price: None,
+            strategy: None,
+            budget: None,
+            end_at: None,
+        }
+    }
+
+    fn usdt(value: f64) -> AssetAmount {
+        AssetAmount::from_decimal(value, 6)
+    }
+
+    fn strategy_order(order_type: &str, strategy: DcaStrategy) -> DcaOrderInfo {
+        DcaOrderInfo {
+            order_type: order_type.to_string(),
+            strategy: Some(strategy),
+            created_at: Some(0),
+            ..scheduled(0, CatchUpPolicy::RunOnce)
+        }
+    }
+
+    #[test]
+    fn test_value_averaging_amount() {
+        let sats = |raw| AssetAmount::new(raw, 8);
+        // Target after 3 periods of $100 is $300; holding 0.005 BTC at $40k = $200.
+        assert_eq!(
+            value_averaging_amount(usdt(100.0), 3, sats(500_000), 40_000.0, usdt(1_000.0)),
+            usdt(100.0)
+        );
+        // Capped per buy.
+        assert_eq!(
+            value_averaging_amount(usdt(100.0), 3, sats(0), 40_000.0, usdt(250.0)),
+            usdt(250.0)
+        );
+        // Ahead of the path after a rally: nothing to buy.
+        assert!(
+            value_averaging_amount(usdt(100.0), 3, sats(500_000), 80_000.0, usdt(1_000.0))
+                .is_zero()
+        );
+        // Rounded down to the quote asset's precision.
+        assert_eq!(
+            value_averaging_amount(usdt(10.0), 1, sats(1), 33_333.0, usdt(100.0)),
+            AssetAmount::new(9_999_666, 6)
+        );
+    }
+
+    #[test]
+    fn test_check_trigger_value_averaging() {
+        let strategy = DcaStrategy::ValueAveraging {
+            increment: usdt(100.0),
+            max_amount: usdt(500.0),
+            acquired: AssetAmount::new(250_000, 8),
+        };
+        let mut order = strategy_order("value-averaging", strategy);
+        order.last_executed_at = Some(HOUR);
+        let mut state = OrderRunState::default();
+        let mut missed = Vec::new();
+
+        // Not due yet.
+        assert!(check_trigger(&order, &mut state, 40_000.0, HOUR + 10, &mut missed).is_none());
+        // Period 2: target $200, holdings $100 → buy $100.
+        let trigger = check_trigger(&order, &mut state, 40_000.0, 2 * HOUR, &mut missed).unwrap();
+        assert_eq!(trigger.amount, Some(usdt(100.0)));
+
+        // Price doubled: holdings already on the path, period is consumed.
+        let mut state = OrderRunState::default();
+        assert!(check_trigger(&order, &mut state, 80_000.0, 2 * HOUR, &mut missed).is_none());
+        assert_eq!(state.pending, 0);
+        assert!(check_trigger(&order, &mut state, 80_000.0, 2 * HOUR + 60, &mut missed).is_none());
+
+        // Selling on a value path is not supported.
+        order.side = TradeSide::Sell;
+        let mut state = OrderRunState::default();
+        assert!(check_trigger(&order, &mut state, 40_000.0, 2 * HOUR, &mut missed).is_none());
+    }
+
+    #[test]
+    fn test_next_ladder_step() {
+        let steps = vec![
+            LadderStep {
+                price: 60_000.0,
+                amount: usdt(50.0),
+                filled: true,
+            },
+            LadderStep {
+                price: 55_000.0,
+                amount: usdt(100.0),
+                filled: false,
+            },
+            LadderStep {
+                price: 50_000.0,
+                amount: usdt(200.0),
+                filled: false,
+            },
+        ];
+        assert_eq!(next_ladder_step(&steps, 58_000.0, TradeSide::Buy), None);
+        assert_eq!(next_ladder_step(&steps, 55_000.0, TradeSide::Buy), Some(1));
+        // A crash through several levels fills the highest one first.
+        assert_eq!(next_ladder_step(&steps, 45_000.0, TradeSide::Buy), Some(1));
+        // Sell ladders fill ascending levels, lowest first.
+        assert_eq!(next_ladder_step(&steps, 52_000.0, TradeSide::Sell), Some(2));
+        assert_eq!(next_ladder_step(&steps, 70_000.0, TradeSide::Sell), Some(2));
+        assert_eq!(next_ladder_step(&steps, 49_000.0, TradeSide::Sell), None);
+    }
+
+    #[test]
+    fn test_check_trigger_dip_ladder() {
+        let strategy = DcaStrategy::DipLadder {
+            steps: vec![
+                LadderStep {
+                    price: 55_000.0,
+                    amount: usdt(100.0),
+                    filled: false,
+                },
+                LadderStep {
+                    price: 50_000.0,
+                    amount: usdt(200.0),
+                    filled: false,
+                },
+            ],
+        };
+        let scheduler = DcaScheduler::new();
+        scheduler.set_orders(Some(1), vec![strategy_order("dip-ladder", strategy)]);
+        let mut state = OrderRunState::default();
+        let mut missed = Vec::new();
+
+        let order = scheduler.orders.read().unwrap()[0].clone();
+        let trigger = check_trigger(&order, &mut state, 49_000.0, 0, &mut missed).unwrap();
+        assert_eq!(
+            (trigger.ladder_step, trigger.amount),
+            (Some(0), Some(usdt(100.0)))
+        );
+
+        scheduler.update_last_executed("o", 10, Some(0));
+        let order = scheduler.orders.read().unwrap()[0].clone();
+        let trigger = check_trigger(&order, &mut state, 49_000.0, 20, &mut missed).unwrap();
+        assert_eq!(
+            (trigger.ladder_step, trigger.amount),
+            (Some(1), Some(usdt(200.0)))
+        );
+
+        scheduler.update_last_executed("o", 30, Some(1));
+        let order = scheduler.orders.read().unwrap()[0].clone();
+        assert!(check_trigger(&order, &mut state, 40_000.0, 40, &mut missed).is_none());
+    }
+
+    #[test]
+    fn test_trailing_entry() {
+        let buy = |low: &mut Option<f64>, price| {
+            trailing_entry_triggered(low, 5.0, Some(60_000.0), price, TradeSide::Buy)
+        };
+        let mut low = None;
+        // Above activation: not tracking yet.
+        assert!(!buy(&mut low, 65_000.0));
+        assert_eq!(low, None);
+        assert!(!buy(&mut low, 59_000.0));
+        assert!(!buy(&mut low, 50_000.0));
+        assert_eq!(low, Some(50_000.0));
+        assert!(!buy(&mut low, 52_000.0));
+        assert!(buy(&mut low, 52_500.0));
+    }
+
+    #[test]
+    fn test_trailing_exit_sell_side() {
+        let sell = |high: &mut Option<f64>, price| {
+            trailing_entry_triggered(high, 5.0, Some(70_000.0), price, TradeSide::Sell)
+        };
+        let mut high = None;
+        assert!(!sell(&mut high, 65_000.0));
+        assert_eq!(high, None);
+        assert!(!sell(&mut high, 72_000.0));
+        assert!(!sell(&mut high, 80_000.0));
+        assert_eq!(high, Some(80_000.0));
+        assert!(!sell(&mut high, 76_500.0));
+        assert!(sell(&mut high, 76_000.0));
+    }
+
+    #[test]
+    fn test_check_trigger_trailing_entry_uses_stored_low() {
+        let strategy = DcaStrategy::TrailingEntry {
+            rebound_pct: 10.0,
+            activation_price: None,
+            extreme_price: Some(40_000.0),
+        };
+        let order = strategy_order("trailing-entry", strategy);
+        let mut state = OrderRunState::default();
+        let mut missed = Vec::new();
+        assert!(check_trigger(&order, &mut state, 43_000.0, 0, &mut missed).is_none());
+        assert!(check_trigger(&order, &mut state, 44_000.0, 0, &mut missed).is_some());
+    }
+
+    #[test]
+    fn test_trailing_extreme_survives_restart() {
+        let strategy = DcaStrategy::TrailingEntry {
+            rebound_pct: 5.0,
+            activation_price: None,
+            extreme_price: None,
+        };
+        let order = strategy_order("trailing-entry", strategy);
+        let config = DcaSchedulerConfig::default();
+        // Triggers so far.
+        let check = |scheduler: &DcaScheduler, price: f64| {
+            let prices = HashMap::from([(order.pair.price_source.clone(), Some(price))]);
+            let due = scheduler.orders.read().unwrap().clone();
+            scheduler.check_orders(&due, &prices, 0, &config);
+            scheduler.metrics.lock().unwrap().triggers
+        };
+        let extreme = |scheduler: &DcaScheduler| match &scheduler.orders.read().unwrap()[0].strategy
+        {
+            Some(DcaStrategy::TrailingEntry { extreme_price, .. }) => *extreme_price,
+            _ => unreachable!(),
+        };
+
+        let scheduler = DcaScheduler::new();
+        scheduler.set_orders(None, vec![order.clone()]);
+        check(&scheduler, 50_000.0);
+        check(&scheduler, 45_000.0);
+        assert_eq!(extreme(&scheduler), Some(45_000.0));
+
+        // After a restart the rebound is measured from the stored low, not
+        // from the first price seen again.
+        let stored = scheduler.orders.read().unwrap().clone();
+        let restarted = DcaScheduler::new();
+        restarted.set_orders(None, stored);
+        assert_eq!(check(&restarted, 46_000.0), 0);
+        assert_eq!(check(&restarted, 47_300.0), 1);
+
+        // Once the run fired, tracking starts over.
+        restarted.update_last_executed(&order.id, 1, None);
+        assert_eq!(extreme(&restarted), None);
+        let states = restarted.run_states.lock().unwrap();
+        assert_eq!(states[&order.id].trailing_extreme, None);
+    }
+
+    #[test]
+    fn test_check_trigger_price_target_sell() {
+        let order = DcaOrderInfo {
+            order_type: "price-target".to_string(),
+            side: TradeSide::Sell,
+            amount: AssetAmount::new(100_000, 8),
+            trigger_price: Some(70_000.0),
+            ..scheduled(0, CatchUpPolicy::RunOnce)
+        };
+        let mut state = OrderRunState::default();
+        let mut missed = Vec::new();
+        assert!(check_trigger(&order, &mut state, 65_000.0, 0, &mut missed).is_none());
+        assert!(check_trigger(&order, &mut state, 0.0, 0, &mut missed).is_none());
+        assert!(check_trigger(&order, &mut state, 71_000.0, 0, &mut missed).is_some());
+    }
+
+    #[test]
+    fn test_order_info_legacy_pair_defaults() {
+        let order: DcaOrderInfo = serde_json::from_str(
+            r#"{
+                "id": "o", "order_type": "scheduled", "status": "active",
+                "amount": {"raw": 25000000, "precision": 6},
+                "interval_secs": 3600, "last_executed_at": null,
+                "trigger_price": null
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(order.side, TradeSide::Buy);
+        assert_eq!(order.pair, AssetPair::default());
+        assert_eq!(order.amount.to_string(), "25.000000");
+    }
+
+    #[test]
+    fn test_gate_trigger() {
+        let order = scheduled(0, CatchUpPolicy::RunOnce);
+        let mut state = OrderRunState {
+            pending: 2,
+            ..Default::default()
+        };
+        let mut spend = SpendContext::default();
+
+        let trigger = gate_trigger(
+            &order,
+            DcaTriggerPayload::new(&order, 0.0),
+            &mut state,
+            &mut spend,
+        );
+        assert_eq!(trigger.unwrap().unwrap().amount, None);
+
+        spend.balances.insert("USDT".to_string(), usdt(5.0));
+        let result = gate_trigger(
+            &order,
+            DcaTriggerPayload::new(&order, 0.0),
+            &mut state,
+            &mut spend,
+        );
+        let (status, change) = result.unwrap_err();
+        assert_eq!(status, "paused");
+        assert_eq!(change.order_id, "o");
+        assert_eq!(state.pending, 0);
+    }
+
+    #[test]
+    fn test_next_check_at() {
+        let config = DcaSchedulerConfig::default();
+        let mut order = scheduled(10 * HOUR, CatchUpPolicy::RunOnce);
+        let mut state = OrderRunState::default();
+        let now = 10 * HOUR + 60;
+
+        // Time-based orders sleep until their next run...
+        assert_eq!(next_check_at(&order, &state, now, &config), 11 * HOUR);
+        // ...or their end date, whichever comes first.
+        order.end_at = Some(10 * HOUR + 120);
+        assert_eq!(next_check_at(&order, &state, now, &config), 10 * HOUR + 120);
+        order.end_at = None;
+        // An owed run is retried on the poll interval.
+        state.pending = 1;
+        assert_eq!(next_check_at(&order, &state, now, &config), now + 30);
+
+        let target = DcaOrderInfo {
+            order_type: "price-target".to_string(),
+            trigger_price: Some(50_000.0),
+            ..scheduled(0, CatchUpPolicy::RunOnce)
+        };
+        assert_eq!(
+            next_check_at(&target, &OrderRunState::default(), now, &config),
+            now + 30
+        );
+    }
+
+    #[test]
+    fn test_scheduler_config() {
+        let config: DcaSchedulerConfig = serde_json::from_str(r#"{"jitter_secs": 0}"#).unwrap();
+        assert_eq!(config.poll_interval_secs, 30);
+        assert!(config.validate().is_ok());
+        let too_fast = DcaSchedulerConfig {
+            poll_interval_secs: 1,
+            jitter_secs: 0,
+        };
+        assert!(too_fast.validate().is_err());
+        let too_jittery = DcaSchedulerConfig {
+            poll_interval_secs: 10,
+            jitter_secs: 20,
+        };
+        assert!(too_jittery.validate().is_err());
+    }
+
+    #[test]
+    fn test_price_cache_freshness() {
+        let source = AssetPair::default().price_source;
+        let mut cache = PriceCache::default();
+        cache.0.insert(source.clone(), (Some(60_000.0), 100));
+        assert!(cache.is_fresh(&source, 100 + PRICE_CACHE_SECS - 1));
+        assert_eq!(
+            cache.current(105).get(&source).copied().flatten(),
+            Some(60_000.0)
+        );
+        assert!(!cache.is_fresh(&source, 100 + PRICE_CACHE_SECS));
+        assert!(cache.current(100 + PRICE_CACHE_SECS).is_empty());
+    }
+
+    #[test]
+    fn test_oracle_degraded_after_sustained_failures() {
+        let mut metrics = DcaMetrics::default();
+        metrics.record_oracle_failure(100, "timeout".to_string());
+        metrics.record_oracle_failure(130, "timeout".to_string());
+        assert_eq!(metrics.oracle_failing_since, Some(100));
+        assert!(!metrics.oracle_degraded(100 + ORACLE_WARNING_SECS - 1));
+        assert!(metrics.oracle_degraded(100 + ORACLE_WARNING_SECS));
+
+        metrics.record_price(200);
+        assert!(!metrics.oracle_degraded(100 + ORACLE_WARNING_SECS));
+        assert_eq!(metrics.oracle_failures, 2);
+        assert_eq!(metrics.last_price_at, Some(200));
+    }
+
+    #[tokio::test]
+    async fn test_start_stop_single_instance() {
+        let scheduler = Arc::new(DcaScheduler::new());
+        scheduler.start().await;
+        scheduler.start().await;
+        assert!(scheduler.task.lock().await.is_some());
+        scheduler.stop().await;
+        assert!(scheduler.task.lock().await.is_none());
+        // Stopping twice and restarting right away is fine.
+        scheduler.stop().await;
+        scheduler.start().await;
+        scheduler.stop().await;
+    }
+
+    #[test]
+    fn test_due_runs_interval() {
+        let schedule = DcaSchedule::Interval {
+            interval_secs: HOUR,
+        };
+        assert!(due_runs(&schedule, 0, HOUR - 1).is_empty());
+        assert_eq!(
+            due_runs(&schedule, 0, 3 * HOUR + 5),
+            vec![HOUR, 2 * HOUR, 3 * HOUR]
+        );
+        // A very long gap keeps only the most recent runs.
+        let runs = due_runs(&schedule, 0, 10_000 * HOUR);
+        assert_eq!(runs.len(), MAX_DUE_RUNS);
+        assert_eq!(*runs.last().unwrap(), 10_000 * HOUR);
+    }
+
+    #[test]
+    fn test_plan_catch_up() {
+        let due = [HOUR, 2 * HOUR, 3 * HOUR];
+        let now = 3 * HOUR + 60;
+        assert_eq!(
+            plan_catch_up(&CatchUpPolicy::RunOnce, &due, now),
+            (1, vec![HOUR, 2 * HOUR])
+        );
+        // Skip still runs the latest slot when it is on time...
+        assert_eq!(
+            plan_catch_up(&CatchUpPolicy::Skip, &due, now),
+            (1, vec![HOUR, 2 * HOUR])
+        );
+        // ...but not when it is late.
+        assert_eq!(
+            plan_catch_up(&CatchUpPolicy::Skip, &due, 4 * HOUR - 1),
+            (0, due.to_vec())
+        );
+        assert_eq!(
+            plan_catch_up(&CatchUpPolicy::RunAll { max_runs: 2 }, &due, now),
+            (2, vec![HOUR])
+        );
+        assert_eq!(
+            plan_catch_up(&CatchUpPolicy::RunAll { max_runs: 10 }, &due, now),
+            (3, vec![])
+        );
+    }
+
+    #[test]
+    fn test_check_trigger_catch_up_after_sleep() {
+        // Hourly order, machine asleep for a week.
+        let mut order = scheduled(0, CatchUpPolicy::RunAll { max_runs: 3 });
+        let mut state = OrderRunState::default();
+        let mut missed = Vec::new();
+        let now = 7 * 24 * HOUR + 10;
+
+        assert!(check_trigger(&order, &mut state, 0.0, now, &mut missed).is_some());
+        assert_eq!(state.pending, 3);
+        assert_eq!(missed.len(), 7 * 24 - 3);
+        assert!(missed[0].reason.contains("run all"));
+
+        // Each execution consumes one pending run; no re-evaluation meanwhile.
+        missed.clear();
+        for _ in 0..3 {
+            assert!(check_trigger(&order, &mut state, 0.0, now, &mut missed).is_some());
+            state.pending -= 1;
+            order.last_executed_at = Some(now);
+        }
+        assert!(check_trigger(&order, &mut state, 0.0, now + 60, &mut missed).is_none());
+        assert!(missed.is_empty());
+        assert!(check_trigger(&order, &mut state, 0.0, now + HOUR, &mut missed).is_some());
+    }
+
+    #[test]
+    fn test_check_trigger_skip_logs_all_missed() {
+        let order = scheduled(0, CatchUpPolicy::Skip);
+        let mut state = OrderRunState::default();
+        let mut missed = Vec::new();
+        let now = 5 * HOUR + 30 * 60;
+
+        assert!(check_trigger(&order, &mut state, 0.0, now, &mut missed).is_none());
+        assert_eq!(missed.len(), 5);
+        // Re-syncing the same order does not log the runs again.
+        missed.clear();
+        assert!(check_trigger(&order, &mut state, 0.0, now + 60, &mut missed).is_none());
+        assert!(missed.is_empty());
+        assert!(check_trigger(&order, &mut state, 0.0, 6 * HOUR, &mut missed).is_some());
+    }
+
+    #[test]
+    fn test_order_stats_empty() {
+        let stats = compute_order_stats("a", &OrderMarket::default(), &[]);
+        assert_eq!(stats.execution_count, 0);
+        assert_eq!(stats.failure_rate, 0.0);
+        assert_eq!(stats.average_price, None);
+    }
+
+    #[test]
+    fn test_csv_escaping() {
+        let mut e = execution("a", 100, "failed", 5.0, 0);
+        e.error = Some("quote expired, \"retry\"".to_string());
+        let csv = executions_to_csv(&[e], &HashMap::new());
+        let row = csv.lines().nth(1).unwrap();
+        assert_eq!(
+            row,
+            "a,100,0,5,USDT,0.00000000,0.00000010,BTC,,failed,\"quote expired, \"\"retry\"\"\""
+        );
+
+        // Amounts in the order's own assets
+        let mut e = execution("s", 100, "success", 0.001, 60_000_000);
+        e.spend_asset = Some("BTC".into());
+        let sell = OrderMarket {
+            side: TradeSide::Sell,
+            ..Default::default()
+        };
+        let csv = executions_to_csv(&[e], &HashMap::from([("s".to_string(), sell)]));
+        assert!(csv.starts_with(
+            "order_id,executed_at,quoted_price,amount_in,spend_asset,amount_out,fee,receive_asset,"
+        ));
+        assert!(csv.contains("s,100,0,0.001,BTC,60.000000,0.000010,USDT,,success,"));
+    }
+}
@@ -0,0 +1,587 @@
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::db;
+use crate::dca::{check_trigger, DcaOrderInfo, DcaStrategy, OrderRunState};
+use crate::dca_limits::{check_end_date, check_spend, SpendCheck, SpendContext};
+use crate::dca_pair::{AssetAmount, AssetPair, TradeSide};
+
+/// Downloaded histories are reused for this long.
+const HISTORY_CACHE_SECS: u64 = 6 * 3600;
+/// Longest history that can be downloaded.
+const MAX_HISTORY_DAYS: u32 = 365;
+/// Upper bound on replayed instants (price points plus scheduled runs).
+const MAX_TICKS: usize = 200_000;
+/// Guards against an order that keeps triggering at the same instant.
+const MAX_FILLS_PER_TICK: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PricePoint {
+    /// Unix seconds.
+    pub timestamp: u64,
+    /// Price of one base asset in the pair's quote asset.
+    pub price: f64,
+}
+
+/// Where a backtest's prices come from. Imported prices are taken as the
+/// pair's price; downloaded ones go through the pair's price source.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum PriceHistory {
+    /// `timestamp,price` rows, with or without a header line. Timestamps are
+    /// unix seconds or milliseconds, RFC 3339, or `YYYY-MM-DD` (UTC).
+    Csv { data: String },
+    /// An array of `[timestamp, price]` pairs or `{"timestamp", "price"}`
+    /// objects, or a CoinGecko `market_chart` response.
+    Json { data: String },
+    /// The last `days` of the order's price source.
+    Download { days: u32 },
+}
+
+impl PriceHistory {
+    /// Load the series, sorted by time with duplicate timestamps dropped.
+    pub async fn load(&self, pair: &AssetPair) -> Result<Vec<PricePoint>, String> {
+        let mut points = match self {
+            PriceHistory::Csv { data } => parse_csv(data)?,
+            PriceHistory::Json { data } => parse_json(data)?,
+            PriceHistory::Download { days } => download_history(pair, *days).await?,
+        };
+        points.sort_by_key(|p| p.timestamp);
+        points.dedup_by_key(|p| p.timestamp);
+        if points.len() < 2 {
+            return Err("Price history needs at least two points".to_string());
+        }
+        Ok(points)
+    }
+}
+
+/// Unix seconds from a number that may be in milliseconds.
+fn normalize_timestamp(value: f64) -> u64 {
+    if value > 1e11 {
+        (value / 1000.0) as u64
+    } else {
+        value as u64
+    }
+}
+
+fn parse_timestamp(field: &str) -> Option<u64> {
+    if let Ok(value) = field.parse::<f64>() {
+        return (value.is_finite() && value >= 0.0).then(|| normalize_timestamp(value));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(field) {
+        return u64::try_from(dt.timestamp()).ok();
+    }
+    let date = NaiveDate::parse_from_str(field, "%Y-%m-%d").ok()?;
+    u64::try_from(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp()).ok()
+}
+
+fn parse_csv(data: &str) -> Result<Vec<PricePoint>, String> {
+    let mut points = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split([',', ';']).map(|f| f.trim().trim_matches('"'));
+        let timestamp = fields.next().and_then(parse_timestamp);
+        let price = fields.next().and_then(|f| f.parse::<f64>().ok());
+        match (timestamp, price) {
+            (Some(timestamp), Some(price)) if price.is_finite() && price > 0.0 => {
+                points.push(PricePoint { timestamp, price })
+            }
+            // A header line
+            _ if points.is_empty() && i == 0 => {}
+            _ => return Err(format!("Invalid price row on line {}: {}", i + 1, line)),
+        }
+    }
+    Ok(points)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonPoint {
+    Pair(f64, f64),
+    Object { timestamp: f64, price: f64 },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonHistory {
+    Points(Vec<JsonPoint>),
+    MarketChart { prices: Vec<JsonPoint> },
+}
+
+fn parse_json(data: &str) -> Result<Vec<PricePoint>, String> {
+    let history: JsonHistory =
+        serde_json::from_str(data).map_err(|e| format!("Invalid price history JSON: {}", e))?;
+    let (JsonHistory::Points(points) | JsonHistory::MarketChart { prices: points }) = history;
+    Ok(points
+        .into_iter()
+        .map(|p| match p {
+            JsonPoint::Pair(timestamp, price) | JsonPoint::Object { timestamp, price } => {
+                PricePoint {
+                    timestamp: normalize_timestamp(timestamp),
+                    price,
+                }
+            }
+        })
+        .filter(|p| p.price.is_finite() && p.price > 0.0)
+        .collect())
+}
+
+/// Raw price-source readings as cached in AppSettings.
+#[derive(Serialize, Deserialize)]
+struct CachedHistory {
+    fetched_at: u64,
+    points: Vec<(u64, f64)>,
+}
+
+async fn download_history(pair: &AssetPair, days: u32) -> Result<Vec<PricePoint>, String> {
+    if days == 0 || days > MAX_HISTORY_DAYS {
+        return Err(format!(
+            "History length must be between 1 and {} days",
+            MAX_HISTORY_DAYS
+        ));
+    }
+    let source = serde_json::to_string(&pair.price_source).map_err(|e| e.to_string())?;
+    let key = format!("dca_price_history:{}:{}", source, days);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let cached = db::with(|conn| db::get_app_setting(conn, &key))
+        .ok()
+        .flatten()
+        .and_then(|json| serde_json::from_str::<CachedHistory>(&json).ok())
+        .filter(|c| now.saturating_sub(c.fetched_at) < HISTORY_CACHE_SECS);
+    let raw = match cached {
+        Some(cached) => cached.points,
+        None => {
+            let http = reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new());
+            let points = pair.price_source.fetch_history(&http, days).await?;
+            let cache = CachedHistory {
+                fetched_at: now,
+                points: points.clone(),
+            };
+            if let Err(e) = serde_json::to_string(&cache)
+                .map_err(|e| e.to_string())
+                .and_then(|json| {
+                    db::with(|conn| db::set_app_setting(conn, &key, &json))
+                        .map_err(|e| e.to_string())
+                })
+            {
+                log::warn!("[DCA] failed to cache price history: {}", e);
+            }
+            points
+        }
+    };
+    Ok(raw
+        .into_iter()
+        .filter_map(|(timestamp, price)| {
+            let price = pair.price_from_source(price)?;
+            Some(PricePoint { timestamp, price })
+        })
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestExecution {
+    pub executed_at: u64,
+    pub price: f64,
+    /// In the spent asset.
+    pub spent: f64,
+    /// In the received asset.
+    pub received: f64,
+    pub ladder_step: Option<usize>,
+}
+
+/// The same total traded at once, at the first price of the series.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LumpSumResult {
+    pub price: f64,
+    pub received: f64,
+    pub return_pct: f64,
+    pub max_drawdown_pct: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BacktestReport {
+    pub executions: Vec<BacktestExecution>,
+    /// In the spent asset.
+    pub total_spent: f64,
+    /// In the received asset.
+    pub total_received: f64,
+    /// Quote asset paid (buys) or received (sells) per base asset; None
+    /// without fills.
+    pub average_price: Option<f64>,
+    pub start_price: f64,
+    pub final_price: f64,
+    /// Gain at the final price, in percent: for buys, the value of what was
+    /// bought against what was spent; for sells, the proceeds against what
+    /// the sold amount would be worth now.
+    pub return_pct: f64,
+    /// Deepest the position was under water during the replay, in percent of
+    /// its basis.
+    pub max_drawdown_pct: f64,
+    pub lump_sum: LumpSumResult,
+    /// Why the replay ended before the last price, if it did.
+    pub stopped_early: Option<String>,
+}
+
+/// What a trade of `spent` at `price` receives.
+fn fill_amount(side: TradeSide, spent: f64, price: f64) -> f64 {
+    match side {
+        TradeSide::Buy => spent / price,
+        TradeSide::Sell => spent * price,
+    }
+}
+
+/// Gain of a position at `price`, in percent of its basis (see
+/// [`BacktestReport::return_pct`]). None before anything was traded.
+fn position_return(side: TradeSide, spent: f64, received: f64, price: f64) -> Option<f64> {
+    let (value, basis) = match side {
+        TradeSide::Buy => (received * price, spent),
+        TradeSide::Sell => (received, spent * price),
+    };
+    (basis > 0.0).then(|| (value - basis) / basis * 100.0)
+}
+
+/// Instants to replay: every price point, plus every scheduled run of
+/// time-based orders (priced at the latest point before it).
+fn replay_ticks(order: &DcaOrderInfo, prices: &[PricePoint]) -> Result<Vec<u64>, String> {
+    let mut ticks: Vec<u64> = prices.iter().map(|p| p.timestamp).collect();
+    if matches!(order.order_type.as_str(), "scheduled" | "value-averaging") {
+        let schedule = order
+            .effective_schedule()
+            .ok_or_else(|| "Scheduled orders need an interval or a schedule".to_string())?;
+        schedule.validate()?;
+        let (start, end) = (prices[0].timestamp, prices[prices.len() - 1].timestamp);
+        let mut cursor = start;
+        while let Some(next) = schedule.next_run_after(cursor)? {
+            if next > end {
+                break;
+            }
+            if ticks.len() >= MAX_TICKS {
+                return Err(
+                    "Too many scheduled runs; shorten the history or the schedule".to_string(),
+                );
+            }
+            ticks.push(next);
+            cursor = next;
+        }
+        ticks.sort_unstable();
+        ticks.dedup();
+    }
+    Ok(ticks)
+}
+
+/// Apply a fill to the order the way the app does after an execution.
+/// Returns why the order is done, for one-shot orders.
+fn after_fill(
+    order: &mut DcaOrderInfo,
+    ladder_step: Option<usize>,
+    received: f64,
+    price: f64,
+    rearm_pct: Option<f64>,
+) -> Option<String> {
+    let base_precision = order.pair.base.precision;
+    match (order.order_type.as_str(), order.strategy.as_mut()) {
+        ("dip-ladder", Some(DcaStrategy::DipLadder { steps })) => {
+            if let Some(step) = ladder_step.and_then(|i| steps.get_mut(i)) {
+                step.filled = true;
+            }
+            None
+        }
+        ("value-averaging", Some(DcaStrategy::ValueAveraging { acquired, .. })) => {
+            let bought = AssetAmount::from_decimal(received, base_precision);
+            *acquired = AssetAmount::new(
+                acquired.at_precision(base_precision).raw + bought.raw,
+                base_precision,
+            );
+            None
+        }
+        ("trailing-entry", _) => Some("Trailing entry filled".to_string()),
+        ("price-target", _) => match rearm_pct {
+            Some(pct) => {
+                order.trigger_price = Some(match order.side {
+                    TradeSide::Buy => price * (1.0 - pct / 100.0),
+                    TradeSide::Sell => price * (1.0 + pct / 100.0),
+                });
+                None
+            }
+            None => Some("Price target filled".to_string()),
+        },
+        _ => None,
+    }
+}
+
+/// Replay `order` over `prices` with the scheduler's trigger logic and the
+/// order's budget and end date. Global daily caps and balances don't apply.
+/// Price-target orders re-arm `rearm_pct` away from each fill, as the app
+/// does for orders with a target percentage; without it they fill once.
+pub fn run_backtest(
+    order: DcaOrderInfo,
+    prices: &[PricePoint],
+    rearm_pct: Option<f64>,
+) -> Result<BacktestReport, String> {
+    let (Some(first), Some(last)) = (prices.first().copied(), prices.last().copied()) else {
+        return Err("Price history is empty".to_string());
+    };
+    if rearm_pct.is_some_and(|pct| !(pct > 0.0 && pct < 100.0)) {
+        return Err("Re-arm percentage must be between 0 and 100".to_string());
+    }
+
+    // Start from a fresh order as if it were created at the first price.
+    let mut order = DcaOrderInfo {
+        status: "active".to_string(),
+        last_executed_at: None,
+        created_at: Some(first.timestamp),
+        ..order
+    };
+    match order.strategy.as_mut() {
+        Some(DcaStrategy::DipLadder { steps }) => steps.iter_mut().for_each(|s| s.filled = false),
+        Some(DcaStrategy::ValueAveraging { acquired, .. }) => {
+            *acquired = AssetAmount::new(0, order.pair.base.precision)
+        }
+        Some(DcaStrategy::TrailingEntry { extreme_price, .. }) => *extreme_price = None,
+        None => {}
+    }
+
+    let side = order.side;
+    let ticks = replay_ticks(&order, prices)?;
+    let mut state = OrderRunState::default();
+    let mut spend = SpendContext::default();
+    let mut missed = Vec::new();
+    let mut executions = Vec::new();
+    let (mut total_spent, mut total_received) = (0.0, 0.0);
+    let mut worst_return: f64 = 0.0;
+    let mut stopped_early = None;
+    let mut cursor = 0;
+
+    'replay: for t in ticks {
+        while cursor + 1 < prices.len() && prices[cursor + 1].timestamp <= t {
+            cursor += 1;
+        }
+        let price = prices[cursor].price;
+        if let Some(change) = check_end_date(&order, t) {
+            stopped_early = Some(change.message);
+            break;
+        }
+        for _ in 0..MAX_FILLS_PER_TICK {
+            let Some(trigger) = check_trigger(&order, &mut state, price, t, &mut missed) else {
+                break;
+            };
+            let amount =
+                match check_spend(&order, trigger.amount.unwrap_or(order.amount), &mut spend) {
+                    SpendCheck::Proceed(amount) => amount,
+                    SpendCheck::Complete(change) => {
+                        stopped_early = Some(change.message);
+                        break 'replay;
+                    }
+                    SpendCheck::Defer(_) | SpendCheck::Pause(_) => break,
+                };
+            let spent = amount.to_decimal();
+            let received = fill_amount(side, spent, price);
+            total_spent += spent;
+            total_received += received;
+            executions.push(BacktestExecution {
+                executed_at: t,
+                price,
+                spent,
+                received,
+                ladder_step: trigger.ladder_step,
+            });
+            state.pending = state.pending.saturating_sub(1);
+            order.last_executed_at = Some(t);
+            if let Some(reason) =
+                after_fill(&mut order, trigger.ladder_step, received, price, rearm_pct)
+            {
+                stopped_early = Some(reason);
+                break 'replay;
+            }
+        }
+        if let Some(r) = position_return(side, total_spent, total_received, price) {
+            worst_return = worst_return.min(r);
+        }
+    }
+
+    let lump_received = fill_amount(side, total_spent, first.price);
+    let lump_worst = prices
+        .iter()
+        .filter_map(|p| position_return(side, total_spent, lump_received, p.price))
+        .fold(0.0, f64::min);
+    let average_price = (total_spent > 0.0 && total_received > 0.0).then(|| match side {
+        TradeSide::Buy => total_spent / total_received,
+        TradeSide::Sell => total_received / total_spent,
+    });
+
+    Ok(BacktestReport {
+        executions,
+        total_spent,
+        total_received,
+        average_price,
+        start_price: first.price,
+        final_price: last.price,
+        return_pct: position_return(side, total_spent, total_received, last.price).unwrap_or(0.0),
+        max_drawdown_pct: -worst_return,
+        lump_sum: LumpSumResult {
+            price: first.price,
+            received: lump_received,
+            return_pct: position_return(side, total_spent, lump_received, last.price)
+                .unwrap_or(0.0),
+            max_drawdown_pct: -lump_worst,
+        },
+        stopped_early,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dca::{CatchUpPolicy, LadderStep};
+
+    const DAY: u64 = 24 * 3600;
+
+    fn usdt(value: f64) -> AssetAmount {
+        AssetAmount::from_decimal(value, 6)
+    }
+
+    fn daily(prices: &[f64]) -> Vec<PricePoint> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| PricePoint {
+                timestamp: i as u64 * DAY,
+                price,
+            })
+            .collect()
+    }
+
+    fn order(order_type: &str) -> DcaOrderInfo {
+        DcaOrderInfo {
+            id: "bt".to_string(),
+            order_type: order_type.to_string(),
+            status: "active".to_string(),
+            pair: AssetPair::default(),
+            side: TradeSide::Buy,
+            amount: usdt(100.0),
+            interval_secs: Some(DAY),
+            last_executed_at: None,
+            schedule: None,
+            created_at: None,
+            catch_up: CatchUpPolicy::RunOnce,
+            trigger_price: None,
+            strategy: None,
+            budget: None,
+            end_at: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let points = parse_csv(
+            "date,price\n2024-01-01,42000.5\n1704153600000,43000\n\n\"2024-01-03T00:00:00Z\";44000\n",
+        )
+        .unwrap();
+        assert_eq!(
+            points.iter().map(|p| p.timestamp).collect::<Vec<_>>(),
+            vec![1_704_067_200, 1_704_153_600, 1_704_240_000]
+        );
+        assert_eq!(points[0].price, 42_000.5);
+        assert!(parse_csv("1704067200,42000\n1704153600,abc\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let pairs = parse_json("[[1704067200000, 42000], [1704153600000, 43000]]").unwrap();
+        let chart =
+            parse_json(r#"{"prices": [[1704067200000, 42000], [1704153600000, 43000]]}"#).unwrap();
+        let objects = parse_json(
+            r#"[{"timestamp": 1704067200, "price": 42000}, {"timestamp": 1704153600, "price": 43000}]"#,
+        )
+        .unwrap();
+        assert_eq!(pairs, chart);
+        assert_eq!(pairs, objects);
+        assert_eq!(pairs[1].timestamp, 1_704_153_600);
+    }
+
+    #[test]
+    fn test_scheduled_backtest_vs_lump_sum() {
+        // Daily buys of $100 while the price halves and recovers.
+        let prices = daily(&[100.0, 50.0, 50.0, 100.0]);
+        let report = run_backtest(order("scheduled"), &prices, None).unwrap();
+
+        assert_eq!(report.executions.len(), 3);
+        assert_eq!(report.total_spent, 300.0);
+        assert_eq!(report.total_received, 5.0);
+        assert_eq!(report.average_price, Some(60.0));
+        assert!((report.return_pct - 66.666_666).abs() < 1e-3);
+        // After the first buy at 50 the position is flat; never under water.
+        assert_eq!(report.max_drawdown_pct, 0.0);
+
+        // $300 at the first price: 3 units, back to even, 50% down midway.
+        assert_eq!(report.lump_sum.received, 3.0);
+        assert_eq!(report.lump_sum.return_pct, 0.0);
+        assert_eq!(report.lump_sum.max_drawdown_pct, 50.0);
+        assert_eq!(report.stopped_early, None);
+    }
+
+    #[test]
+    fn test_backtest_budget_and_drawdown() {
+        let mut o = order("scheduled");
+        o.budget = Some(usdt(150.0));
+        let prices = daily(&[100.0, 100.0, 80.0, 40.0, 60.0]);
+        let report = run_backtest(o, &prices, None).unwrap();
+
+        // $100 at 100, then the remaining $50 at 80.
+        assert_eq!(report.total_spent, 150.0);
+        assert_eq!(report.executions[1].spent, 50.0);
+        assert!(report.stopped_early.unwrap().contains("Budget"));
+        // 1.625 BTC worth $130 for $150; the replay stopped at the third
+        // run, before the crash to 40.
+        assert!((report.max_drawdown_pct - 13.333_333).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_backtest_dip_ladder_and_rearm() {
+        let mut ladder = order("dip-ladder");
+        ladder.strategy = Some(DcaStrategy::DipLadder {
+            steps: vec![
+                LadderStep {
+                    price: 90.0,
+                    amount: usdt(100.0),
+                    filled: true,
+                },
+                LadderStep {
+                    price: 80.0,
+                    amount: usdt(200.0),
+                    filled: false,
+                },
+            ],
+        });
+        let prices = daily(&[100.0, 75.0, 70.0, 95.0]);
+        let report = run_backtest(ladder, &prices, None).unwrap();
+        // Stored fills are ignored; both levels fill once on the drop to 75.
+        assert_eq!(
+            report
+                .executions
+                .iter()
+                .map(|e| (e.executed_at, e.ladder_step))
+                .collect::<Vec<_>>(),
+            vec![(DAY, Some(0)), (DAY, Some(1))]
+        );
+
+        let mut target = order("price-target");
+        target.trigger_price = Some(90.0);
+        let report = run_backtest(target.clone(), &prices, Some(10.0)).unwrap();
+        // Fills at 75, re-arms at 67.5 which is never reached.
+        assert_eq!(report.executions.len(), 1);
+        let report = run_backtest(target, &prices, None).unwrap();
+        assert_eq!(report.stopped_early.as_deref(), Some("Price target filled"));
+    }
+}
@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::db;
+use crate::dca::DcaOrderInfo;
+use crate::dca_pair::AssetAmount;
+
+/// AppSettings key holding the JSON-encoded [`DcaSpendLimits`].
+const SPEND_LIMITS_SETTING: &str = "dca_spend_limits";
+/// Window the global cap applies to (rolling, so it doesn't depend on the
+/// time zone).
+pub const DAILY_WINDOW_SECS: u64 = 24 * 3600;
+
+/// Limits that apply across all DCA orders.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DcaSpendLimits {
+    /// Most all orders together may spend in any 24 hours, per spent asset
+    /// ticker. Assets without an entry are uncapped.
+    #[serde(default)]
+    pub daily_caps: HashMap<String, AssetAmount>,
+}
+
+impl DcaSpendLimits {
+    pub fn load() -> Result<Self, String> {
+        match db::with(|conn| db::get_app_setting(conn, SPEND_LIMITS_SETTING))
+            .map_err(|e| e.to_string())?
+        {
+            Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        db::with(|conn| db::set_app_setting(conn, SPEND_LIMITS_SETTING, &json))
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// Why the scheduler took an order out of the active set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusReason {
+    /// The spendable balance no longer covers a trade (order paused).
+    InsufficientFunds,
+    /// The order's total budget has been spent (order completed).
+    BudgetExhausted,
+    /// The order's end date has passed (order completed).
+    Ended,
+}
+
+/// Payload of the `dca:paused` and `dca:completed` events.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DcaStatusChange {
+    pub order_id: String,
+    pub reason: StatusReason,
+    pub message: String,
+}
+
+/// Spending and balances a trade is checked against. Spent amounts are
+/// decimals of the spent asset, as stored in the execution history.
+#[derive(Debug, Clone, Default)]
+pub struct SpendContext {
+    pub limits: DcaSpendLimits,
+    /// Lifetime spend per order id.
+    pub order_spent: HashMap<String, f64>,
+    /// Spend in the last [`DAILY_WINDOW_SECS`] per asset ticker.
+    pub daily_spent: HashMap<String, f64>,
+    /// Spendable Lightning balance per asset ticker, as last reported by the
+    /// frontend. Assets without an entry are not checked.
+    pub balances: HashMap<String, AssetAmount>,
+}
+
+impl SpendContext {
+    /// Load lifetime and rolling-window spend from the execution history.
+    /// Executions recorded before the spent asset was stored are attributed
+    /// to the asset their order currently spends.
+    pub fn load(
+        account_id: i32,
+        orders: &[DcaOrderInfo],
+        balances: HashMap<String, AssetAmount>,
+        now: u64,
+    ) -> Result<Self, String> {
+        let mut ctx = SpendContext {
+            limits: DcaSpendLimits::load()?,
+            balances,
+            ..Default::default()
+        };
+        let since = now.saturating_sub(DAILY_WINDOW_SECS) as i64;
+        for row in
+            db::with(|conn| db::get_dca_spending(conn, account_id)).map_err(|e| e.to_string())?
+        {
+            *ctx.order_spent.entry(row.order_id.clone()).or_default() += row.amount_in;
+            if row.executed_at < since {
+                continue;
+            }
+            let ticker = row.spend_asset.or_else(|| {
+                orders
+                    .iter()
+                    .find(|o| o.id == row.order_id)
+                    .map(|o| o.spend_ticker().to_string())
+            });
+            if let Some(ticker) = ticker {
+                *ctx.daily_spent.entry(ticker).or_default() += row.amount_in;
+            }
+        }
+        Ok(ctx)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpendCheck {
+    /// Trade this amount; less than requested when the budget is nearly spent.
+    Proceed(AssetAmount),
+    /// Over the global daily cap: keep the run pending and retry later.
+    Defer(String),
+    Pause(DcaStatusChange),
+    Complete(DcaStatusChange),
+}
+
+/// Complete orders whose end date has passed.
+pub fn check_end_date(order: &DcaOrderInfo, now: u64) -> Option<DcaStatusChange> {
+    let end_at = order.end_at?;
+    (now >= end_at).then(|| DcaStatusChange {
+        order_id: order.id.clone(),
+        reason: StatusReason::Ended,
+        message: format!("End date reached ({})", end_at),
+    })
+}
+
+/// Pre-trade check of `amount` against the order budget, the global daily
+/// cap and the spendable balance. Approved trades are added to the context
+/// so later orders in the same tick see them.
+pub fn check_spend(
+    order: &DcaOrderInfo,
+    amount: AssetAmount,
+    ctx: &mut SpendContext,
+) -> SpendCheck {
+    let ticker = order.spend_ticker().to_string();
+    let precision = amount.precision;
+    let mut amount = amount;
+
+    if let Some(budget) = order.budget {
+        let spent = ctx.order_spent.get(&order.id).copied().unwrap_or(0.0);
+        let spent = AssetAmount::from_decimal(spent, precision);
+        let remaining = budget.at_precision(precision).raw.saturating_sub(spent.raw);
+        if remaining == 0 {
+            return SpendCheck::Complete(DcaStatusChange {
+                order_id: order.id.clone(),
+                reason: StatusReason::BudgetExhausted,
+                message: format!("Budget of {} {} spent", budget, ticker),
+            });
+        }
+        amount.raw = amount.raw.min(remaining);
+    }
+
+    if let Some(cap) = ctx.limits.daily_caps.get(&ticker) {
+        let spent = ctx.daily_spent.get(&ticker).copied().unwrap_or(0.0);
+        let spent = AssetAmount::from_decimal(spent, precision);
+        let remaining = cap.at_precision(precision).raw.saturating_sub(spent.raw);
+        if amount.raw > remaining {
+            return SpendCheck::Defer(format!(
+                "daily cap of {} {} reached ({} spent in the last 24h)",
+                cap, ticker, spent
+            ));
+        }
+    }
+
+    if let Some(balance) = ctx.balances.get(&ticker) {
+        if balance.at_precision(precision).raw < amount.raw {
+            return SpendCheck::Pause(DcaStatusChange {
+                order_id: order.id.clone(),
+                reason: StatusReason::InsufficientFunds,
+                message: format!("Insufficient {} balance ({} < {})", ticker, balance, amount),
+            });
+        }
+    }
+
+    *ctx.order_spent.entry(order.id.clone()).or_default() += amount.to_decimal();
+    *ctx.daily_spent.entry(ticker).or_default() += amount.to_decimal();
+    SpendCheck::Proceed(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dca::CatchUpPolicy;
+    use crate::dca_pair::{AssetPair, TradeSide};
+
+    fn usdt(value: f64) -> AssetAmount {
+        AssetAmount::from_decimal(value, 6)
+    }
+
+    fn order(id: &str) -> DcaOrderInfo {
+        DcaOrderInfo {
+            id: id.to_string(),
+            order_type: "scheduled".to_string(),
+            status: "active".to_string(),
+            pair: AssetPair::default(),
+            side: TradeSide::Buy,
+            amount: usdt(10.0),
+            interval_secs: Some(3600),
+            last_executed_at: None,
+            schedule: None,
+            created_at: None,
+            catch_up: CatchUpPolicy::RunOnce,
+            trigger_price: None,
+            strategy: None,
+            budget: None,
+            end_at: None,
+        }
+    }
+
+    #[test]
+    fn test_budget_caps_and_completes() {
+        let mut o = order("a");
+        o.budget = Some(usdt(25.0));
+        let mut ctx = SpendContext::default();
+        ctx.order_spent.insert("a".to_string(), 20.0);
+
+        // Last trade is reduced to what is left of the budget.
+        assert_eq!(
+            check_spend(&o, usdt(10.0), &mut ctx),
+            SpendCheck::Proceed(usdt(5.0))
+        );
+        match check_spend(&o, usdt(10.0), &mut ctx) {
+            SpendCheck::Complete(change) => {
+                assert_eq!(change.reason, StatusReason::BudgetExhausted)
+            }
+            other => panic!("expected completion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_daily_cap_is_shared_across_orders() {
+        let mut ctx = SpendContext::default();
+        ctx.limits.daily_caps.insert("USDT".to_string(), usdt(25.0));
+        ctx.daily_spent.insert("USDT".to_string(), 5.0);
+
+        assert!(matches!(
+            check_spend(&order("a"), usdt(10.0), &mut ctx),
+            SpendCheck::Proceed(_)
+        ));
+        assert!(matches!(
+            check_spend(&order("b"), usdt(10.0), &mut ctx),
+            SpendCheck::Proceed(_)
+        ));
+        assert!(matches!(
+            check_spend(&order("c"), usdt(10.0), &mut ctx),
+            SpendCheck::Defer(_)
+        ));
+        // Sell orders spend BTC, which has no cap.
+        let mut sell = order("d");
+        sell.side = TradeSide::Sell;
+        assert!(matches!(
+            check_spend(&sell, AssetAmount::new(10_000, 8), &mut ctx),
+            SpendCheck::Proceed(_)
+        ));
+    }
+
+    #[test]
+    fn test_insufficient_balance_pauses() {
+        let mut ctx = SpendContext::default();
+        ctx.balances.insert("USDT".to_string(), usdt(9.5));
+        match check_spend(&order("a"), usdt(10.0), &mut ctx) {
+            SpendCheck::Pause(change) => {
+                assert_eq!(change.reason, StatusReason::InsufficientFunds);
+                assert_eq!(
+                    serde_json::to_value(&change).unwrap()["reason"],
+                    "insufficient_funds"
+                );
+            }
+            other => panic!("expected pause, got {:?}", other),
+        }
+        // A rejected trade is not counted as spent.
+        assert!(ctx.daily_spent.is_empty());
+    }
+
+    #[test]
+    fn test_end_date() {
+        let mut o = order("a");
+        assert!(check_end_date(&o, 1_000).is_none());
+        o.end_at = Some(1_000);
+        assert!(check_end_date(&o, 999).is_none());
+        assert_eq!(
+            check_end_date(&o, 1_000).unwrap().reason,
+            StatusReason::Ended
+        );
+    }
+}
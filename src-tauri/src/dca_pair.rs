@@ -0,0 +1,366 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Asset id used by the maker for on-chain / Lightning bitcoin.
+pub const BTC_ASSET_ID: &str = "BTC";
+
+const COINGECKO_URL: &str = "https://api.coingecko.com/api/v3/simple/price";
+const BINANCE_URL: &str = "https://api.binance.com/api/v3/ticker/price";
+const COINGECKO_CHART_URL: &str = "https://api.coingecko.com/api/v3/coins";
+const BINANCE_KLINES_URL: &str = "https://api.binance.com/api/v3/klines";
+/// Binance returns at most this many candles per request.
+const BINANCE_MAX_KLINES: u32 = 1000;
+
+/// An amount in an asset's smallest unit, together with that asset's
+/// precision (number of decimal places), e.g. 12.5 USDT = 12_500_000 @ 6.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetAmount {
+    pub raw: u64,
+    pub precision: u8,
+}
+
+impl AssetAmount {
+    pub fn new(raw: u64, precision: u8) -> Self {
+        AssetAmount { raw, precision }
+    }
+
+    /// Convert a decimal value, rounding down to the asset's precision.
+    /// Negative and non-finite values become zero.
+    pub fn from_decimal(value: f64, precision: u8) -> Self {
+        let scaled = value * 10f64.powi(precision as i32);
+        let raw = if scaled.is_finite() && scaled > 0.0 {
+            // Absorb float noise such as 0.29 * 100 = 28.999999999999996.
+            (scaled + 1e-6).floor() as u64
+        } else {
+            0
+        };
+        AssetAmount { raw, precision }
+    }
+
+    pub fn to_decimal(self) -> f64 {
+        self.raw as f64 / 10f64.powi(self.precision as i32)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.raw == 0
+    }
+
+    /// The same amount at another precision, rounded down when reducing it.
+    pub fn at_precision(self, precision: u8) -> Self {
+        let raw = if precision >= self.precision {
+            self.raw
+                .saturating_mul(10u64.pow((precision - self.precision) as u32))
+        } else {
+            self.raw / 10u64.pow((self.precision - precision) as u32)
+        };
+        AssetAmount { raw, precision }
+    }
+}
+
+impl fmt::Display for AssetAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.precision == 0 {
+            return write!(f, "{}", self.raw);
+        }
+        let unit = 10u64.pow(self.precision as u32);
+        write!(
+            f,
+            "{}.{:0width$}",
+            self.raw / unit,
+            self.raw % unit,
+            width = self.precision as usize
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DcaAsset {
+    /// RGB asset id, or [`BTC_ASSET_ID`]. None means "the node's asset with
+    /// this ticker", which is how legacy USDT orders were stored.
+    pub asset_id: Option<String>,
+    pub ticker: String,
+    pub precision: u8,
+}
+
+impl DcaAsset {
+    pub fn btc() -> Self {
+        DcaAsset {
+            asset_id: Some(BTC_ASSET_ID.to_string()),
+            ticker: "BTC".to_string(),
+            precision: 8,
+        }
+    }
+
+    pub fn usdt() -> Self {
+        DcaAsset {
+            asset_id: None,
+            ticker: "USDT".to_string(),
+            precision: 6,
+        }
+    }
+}
+
+/// Where the scheduler reads a pair's price from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "kebab-case")]
+pub enum PriceSource {
+    /// CoinGecko simple price, e.g. `bitcoin` in `usd`.
+    CoinGecko {
+        coin_id: String,
+        vs_currency: String,
+    },
+    /// Binance spot ticker, e.g. `BTCUSDT`.
+    Binance { symbol: String },
+}
+
+impl PriceSource {
+    pub async fn fetch(&self, http: &reqwest::Client) -> Result<f64, String> {
+        let result = match self {
+            PriceSource::CoinGecko {
+                coin_id,
+                vs_currency,
+            } => {
+                let url = format!(
+                    "{}?ids={}&vs_currencies={}",
+                    COINGECKO_URL, coin_id, vs_currency
+                );
+                get_json::<HashMap<String, HashMap<String, f64>>>(http, url)
+                    .await
+                    .map(|data| {
+                        data.get(coin_id)
+                            .and_then(|prices| prices.get(vs_currency))
+                            .copied()
+                    })
+            }
+            PriceSource::Binance { symbol } => {
+                let url = format!("{}?symbol={}", BINANCE_URL, symbol);
+                get_json::<BinanceTicker>(http, url)
+                    .await
+                    .map(|ticker| ticker.price.parse::<f64>().ok())
+            }
+        };
+        match result {
+            Ok(Some(price)) if price > 0.0 => {
+                log::debug!("[DCA] price fetched from {:?}: {}", self, price);
+                Ok(price)
+            }
+            Ok(_) => Err(format!(
+                "price response from {:?} missing price (rate-limited?)",
+                self
+            )),
+            Err(e) => Err(format!("price fetch error from {:?}: {}", self, e)),
+        }
+    }
+
+    /// Price history over the last `days`, as `(unix seconds, price)` pairs
+    /// oldest first. CoinGecko picks the granularity (hourly up to 90 days,
+    /// daily beyond); Binance candles are hourly while they fit in one
+    /// request, daily otherwise.
+    pub async fn fetch_history(
+        &self,
+        http: &reqwest::Client,
+        days: u32,
+    ) -> Result<Vec<(u64, f64)>, String> {
+        let mut points = match self {
+            PriceSource::CoinGecko {
+                coin_id,
+                vs_currency,
+            } => {
+                let url = format!(
+                    "{}/{}/market_chart?vs_currency={}&days={}",
+                    COINGECKO_CHART_URL, coin_id, vs_currency, days
+                );
+                get_json::<MarketChart>(http, url)
+                    .await
+                    .map_err(|e| format!("Failed to download price history: {}", e))?
+                    .prices
+                    .into_iter()
+                    .map(|(ms, price)| ((ms / 1000.0) as u64, price))
+                    .collect::<Vec<_>>()
+            }
+            PriceSource::Binance { symbol } => {
+                let (interval, limit) = if days * 24 <= BINANCE_MAX_KLINES {
+                    ("1h", days * 24)
+                } else {
+                    ("1d", days.min(BINANCE_MAX_KLINES))
+                };
+                let url = format!(
+                    "{}?symbol={}&interval={}&limit={}",
+                    BINANCE_KLINES_URL, symbol, interval, limit
+                );
+                get_json::<Vec<Vec<serde_json::Value>>>(http, url)
+                    .await
+                    .map_err(|e| format!("Failed to download price history: {}", e))?
+                    .into_iter()
+                    .filter_map(|kline| {
+                        // [open time (ms), open, high, low, close, ...]
+                        let open_ms = kline.first()?.as_u64()?;
+                        let close = kline.get(4)?.as_str()?.parse::<f64>().ok()?;
+                        Some((open_ms / 1000, close))
+                    })
+                    .collect()
+            }
+        };
+        points.retain(|(_, price)| price.is_finite() && *price > 0.0);
+        points.sort_by_key(|(t, _)| *t);
+        if points.is_empty() {
+            return Err(format!("No price history returned by {:?}", self));
+        }
+        Ok(points)
+    }
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+    http: &reqwest::Client,
+    url: String,
+) -> reqwest::Result<T> {
+    http.get(url).send().await?.json::<T>().await
+}
+
+#[derive(Debug, Deserialize)]
+struct MarketChart {
+    /// `[unix milliseconds, price]` pairs.
+    prices: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceTicker {
+    price: String,
+}
+
+/// A market as quoted by the maker: `base` priced in `quote`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetPair {
+    pub base: DcaAsset,
+    pub quote: DcaAsset,
+    pub price_source: PriceSource,
+    /// The source quotes quote-in-base (e.g. BTC per USDT) and must be inverted.
+    #[serde(default)]
+    pub invert_price: bool,
+}
+
+impl Default for AssetPair {
+    /// BTC/USDT priced from CoinGecko, which all orders used before pairs
+    /// were configurable.
+    fn default() -> Self {
+        AssetPair {
+            base: DcaAsset::btc(),
+            quote: DcaAsset::usdt(),
+            price_source: PriceSource::CoinGecko {
+                coin_id: "bitcoin".to_string(),
+                vs_currency: "usd".to_string(),
+            },
+            invert_price: false,
+        }
+    }
+}
+
+impl AssetPair {
+    /// Price of one `base` in `quote`, from the raw price-source reading.
+    pub fn price_from_source(&self, source_price: f64) -> Option<f64> {
+        if source_price <= 0.0 || !source_price.is_finite() {
+            None
+        } else if self.invert_price {
+            Some(1.0 / source_price)
+        } else {
+            Some(source_price)
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("{}/{}", self.base.ticker, self.quote.ticker)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TradeSide {
+    /// Spend the quote asset to acquire the base asset.
+    #[default]
+    Buy,
+    /// Spend the base asset to acquire the quote asset.
+    Sell,
+}
+
+impl TradeSide {
+    /// The asset an execution spends; order amounts are in this asset.
+    pub fn spend_asset<'a>(&self, pair: &'a AssetPair) -> &'a DcaAsset {
+        match self {
+            TradeSide::Buy => &pair.quote,
+            TradeSide::Sell => &pair.base,
+        }
+    }
+
+    pub fn receive_asset<'a>(&self, pair: &'a AssetPair) -> &'a DcaAsset {
+        match self {
+            TradeSide::Buy => &pair.base,
+            TradeSide::Sell => &pair.quote,
+        }
+    }
+
+    /// Whether `price` is at or past `level` in the direction this side
+    /// waits for: at or below it for buys, at or above it for sells.
+    pub fn price_reached(&self, price: f64, level: f64) -> bool {
+        match self {
+            TradeSide::Buy => price <= level,
+            TradeSide::Sell => price >= level,
+        }
+    }
+
+    /// Whether `a` is a better price than `b` for this side.
+    pub fn better_price(&self, a: f64, b: f64) -> bool {
+        match self {
+            TradeSide::Buy => a < b,
+            TradeSide::Sell => a > b,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_amount_decimal_round_trip() {
+        let amount = AssetAmount::from_decimal(12.5, 6);
+        assert_eq!(amount, AssetAmount::new(12_500_000, 6));
+        assert_eq!(amount.to_decimal(), 12.5);
+        assert_eq!(amount.to_string(), "12.500000");
+        assert_eq!(AssetAmount::from_decimal(0.29, 2).raw, 29);
+        // Rounded down, never up.
+        assert_eq!(AssetAmount::from_decimal(0.123456789, 8).raw, 12_345_678);
+        assert_eq!(AssetAmount::from_decimal(-1.0, 6).raw, 0);
+        assert_eq!(AssetAmount::from_decimal(f64::NAN, 6).raw, 0);
+        assert_eq!(AssetAmount::new(42, 0).to_string(), "42");
+        assert_eq!(amount.at_precision(2), AssetAmount::new(1_250, 2));
+        assert_eq!(amount.at_precision(8).raw, 1_250_000_000);
+    }
+
+    #[test]
+    fn test_pair_serde_defaults() {
+        let pair: AssetPair = serde_json::from_str(
+            r#"{
+                "base": {"asset_id": "BTC", "ticker": "BTC", "precision": 8},
+                "quote": {"asset_id": "rgb:abc", "ticker": "XAUT", "precision": 6},
+                "price_source": {"source": "binance", "symbol": "PAXGBTC"},
+                "invert_price": true
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(pair.label(), "BTC/XAUT");
+        assert_eq!(pair.price_from_source(0.04), Some(25.0));
+        assert_eq!(pair.price_from_source(0.0), None);
+        assert_eq!(TradeSide::Sell.spend_asset(&pair).ticker, "BTC");
+        assert_eq!(TradeSide::Buy.spend_asset(&pair).ticker, "XAUT");
+    }
+
+    #[test]
+    fn test_side_price_direction() {
+        assert!(TradeSide::Buy.price_reached(99.0, 100.0));
+        assert!(!TradeSide::Buy.price_reached(101.0, 100.0));
+        assert!(TradeSide::Sell.price_reached(101.0, 100.0));
+        assert!(TradeSide::Sell.better_price(101.0, 100.0));
+        assert!(TradeSide::Buy.better_price(99.0, 100.0));
+    }
+}
@@ -0,0 +1,518 @@
+//! Calendar schedules for DCA orders.
+//!
+//! A [`DcaSchedule`] is either a fixed interval counted from the previous
+//! execution (the original behaviour), a standard 5-field cron expression, or
+//! a simple calendar rule (daily / weekly / monthly at a wall-clock time).
+//! Cron and calendar schedules are evaluated in an IANA time zone so that
+//! "every Monday 09:00 Europe/Rome" stays at 09:00 local time across DST.
+//!
+//! DST handling follows cron conventions:
+//!   - a wall-clock time skipped by a spring-forward gap runs at the first
+//!     valid instant after the gap (e.g. 02:30 → 03:00),
+//!   - a wall-clock time repeated by a fall-back overlap runs once, at its
+//!     first occurrence.
+
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How far ahead `next_run_after` searches before giving up (covers Feb 29).
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum DcaSchedule {
+    /// Fixed interval counted from the last execution.
+    Interval { interval_secs: u64 },
+    /// Standard 5-field cron expression (`min hour dom month dow`).
+    Cron {
+        expression: String,
+        timezone: String,
+    },
+    /// Calendar rule at a wall-clock time.
+    Calendar {
+        rule: CalendarRule,
+        hour: u32,
+        minute: u32,
+        timezone: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "every", rename_all = "kebab-case")]
+pub enum CalendarRule {
+    Day,
+    /// `weekday` is 0 = Sunday … 6 = Saturday, like cron.
+    Week {
+        weekday: u32,
+    },
+    /// Days past the end of a short month run on its last day.
+    Month {
+        day: u32,
+    },
+}
+
+/// One entry of a schedule preview.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledRun {
+    /// Unix seconds.
+    pub at: u64,
+    /// RFC 3339 timestamp in the schedule's time zone.
+    pub local: String,
+}
+
+/// A schedule compiled into per-field bitmasks, ready for evaluation.
+#[derive(Debug, Clone)]
+struct CompiledSchedule {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    dom_restricted: bool,
+    dow_restricted: bool,
+    /// Calendar `Month { day }` rule, clamped to the month length.
+    clamp_day: Option<u32>,
+    tz: Tz,
+}
+
+impl DcaSchedule {
+    /// Validate the schedule without computing anything.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            DcaSchedule::Interval { interval_secs } => {
+                if *interval_secs == 0 {
+                    return Err("Interval must be greater than zero".to_string());
+                }
+                Ok(())
+            }
+            _ => self.compile().map(|_| ()),
+        }
+    }
+
+    /// The first run strictly after `after` (unix seconds).
+    ///
+    /// For interval schedules this is simply `after + interval`, where `after`
+    /// is the last execution time.
+    pub fn next_run_after(&self, after: u64) -> Result<Option<u64>, String> {
+        if let DcaSchedule::Interval { interval_secs } = self {
+            return Ok(Some(after.saturating_add(*interval_secs)));
+        }
+        let compiled = self.compile()?;
+        Ok(compiled.next_after(after))
+    }
+
+    /// The next `count` runs strictly after `after`.
+    pub fn preview(&self, after: u64, count: usize) -> Result<Vec<u64>, String> {
+        let mut runs = Vec::with_capacity(count);
+        let mut cursor = after;
+        while runs.len() < count {
+            match self.next_run_after(cursor)? {
+                Some(next) => {
+                    runs.push(next);
+                    cursor = next;
+                }
+                None => break,
+            }
+        }
+        Ok(runs)
+    }
+
+    /// The next `count` runs after `after`, with local-time formatting.
+    pub fn preview_runs(&self, after: u64, count: usize) -> Result<Vec<ScheduledRun>, String> {
+        Ok(self
+            .preview(after, count)?
+            .into_iter()
+            .map(|at| ScheduledRun {
+                at,
+                local: self.format_local(at),
+            })
+            .collect())
+    }
+
+    /// Format a unix timestamp in the schedule's time zone (UTC for intervals).
+    pub fn format_local(&self, at: u64) -> String {
+        let tz = match self {
+            DcaSchedule::Interval { .. } => Tz::UTC,
+            DcaSchedule::Cron { timezone, .. } | DcaSchedule::Calendar { timezone, .. } => {
+                parse_timezone(timezone).unwrap_or(Tz::UTC)
+            }
+        };
+        match Utc.timestamp_opt(at as i64, 0) {
+            LocalResult::Single(dt) => dt.with_timezone(&tz).to_rfc3339(),
+            _ => at.to_string(),
+        }
+    }
+
+    fn compile(&self) -> Result<CompiledSchedule, String> {
+        match self {
+            DcaSchedule::Interval { .. } => Err("Interval schedules are not calendar based".into()),
+            DcaSchedule::Cron {
+                expression,
+                timezone,
+            } => parse_cron(expression, parse_timezone(timezone)?),
+            DcaSchedule::Calendar {
+                rule,
+                hour,
+                minute,
+                timezone,
+            } => {
+                if *hour > 23 || *minute > 59 {
+                    return Err(format!("Invalid time {:02}:{:02}", hour, minute));
+                }
+                let mut compiled = CompiledSchedule {
+                    minutes: 1 << minute,
+                    hours: 1 << hour,
+                    days_of_month: ALL_DAYS_OF_MONTH,
+                    months: ALL_MONTHS,
+                    days_of_week: ALL_DAYS_OF_WEEK,
+                    dom_restricted: false,
+                    dow_restricted: false,
+                    clamp_day: None,
+                    tz: parse_timezone(timezone)?,
+                };
+                match rule {
+                    CalendarRule::Day => {}
+                    CalendarRule::Week { weekday } => {
+                        if *weekday > 6 {
+                            return Err(format!("Invalid weekday {}", weekday));
+                        }
+                        compiled.days_of_week = 1 << weekday;
+                        compiled.dow_restricted = true;
+                    }
+                    CalendarRule::Month { day } => {
+                        if !(1..=31).contains(day) {
+                            return Err(format!("Invalid day of month {}", day));
+                        }
+                        compiled.clamp_day = Some(*day);
+                    }
+                }
+                Ok(compiled)
+            }
+        }
+    }
+}
+
+fn parse_timezone(name: &str) -> Result<Tz, String> {
+    Tz::from_str(name).map_err(|_| format!("Unknown time zone: {}", name))
+}
+
+const ALL_DAYS_OF_MONTH: u32 = 0xFFFF_FFFE; // bits 1..=31
+const ALL_MONTHS: u16 = 0x1FFE; // bits 1..=12
+const ALL_DAYS_OF_WEEK: u8 = 0x7F; // bits 0..=6
+
+impl CompiledSchedule {
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        if let Some(day) = self.clamp_day {
+            let last = last_day_of_month(date);
+            return date.day() == day.min(last);
+        }
+        let dom = self.days_of_month & (1 << date.day()) != 0;
+        let dow = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+        // Vixie cron: when both fields are restricted, either may match.
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            _ => dom && dow,
+        }
+    }
+
+    /// Wall-clock times on a matching day, in ascending order.
+    fn times(&self) -> impl Iterator<Item = NaiveTime> + '_ {
+        (0..24u32)
+            .filter(move |h| self.hours & (1 << h) != 0)
+            .flat_map(move |h| {
+                (0..60u32)
+                    .filter(move |m| self.minutes & (1 << m) != 0)
+                    .filter_map(move |m| NaiveTime::from_hms_opt(h, m, 0))
+            })
+    }
+
+    fn next_after(&self, after: u64) -> Option<u64> {
+        let after_utc = DateTime::<Utc>::from_timestamp(after as i64, 0)?;
+        // Start one day early so a wall-clock time whose UTC instant lands
+        // after `after` on the previous local date is not missed.
+        let start = after_utc.with_timezone(&self.tz).date_naive() - Duration::days(1);
+
+        for offset in 0..MAX_SEARCH_DAYS {
+            let date = start + Duration::days(offset);
+            if !self.day_matches(date) {
+                continue;
+            }
+            for time in self.times() {
+                let at = resolve_local(&self.tz, date.and_time(time))?;
+                if at > after as i64 {
+                    return Some(at as u64);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Map a wall-clock time to a UTC instant, applying the DST rules above.
+fn resolve_local(tz: &Tz, local: NaiveDateTime) -> Option<i64> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(dt) => Some(dt.timestamp()),
+        LocalResult::Ambiguous(first, _) => Some(first.timestamp()),
+        LocalResult::None => {
+            // Inside a spring-forward gap: walk forward to the first valid minute.
+            let mut probe = local;
+            for _ in 0..(24 * 60) {
+                probe += Duration::minutes(1);
+                match tz.from_local_datetime(&probe) {
+                    LocalResult::Single(dt) => return Some(dt.timestamp()),
+                    LocalResult::Ambiguous(first, _) => return Some(first.timestamp()),
+                    LocalResult::None => continue,
+                }
+            }
+            None
+        }
+    }
+}
+
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let (y, m) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(y, m, 1)
+        .and_then(|d| d.pred_opt())
+        .map(|d| d.day())
+        .unwrap_or(28)
+}
+
+// ---------------------------------------------------------------------------
+// Cron parsing
+// ---------------------------------------------------------------------------
+
+const MONTH_NAMES: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+fn parse_cron(expression: &str, tz: Tz) -> Result<CompiledSchedule, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(format!(
+            "Cron expression must have 5 fields (min hour dom month dow), got {}",
+            fields.len()
+        ));
+    }
+
+    let minutes = parse_field(fields[0], 0, 59, &[])?;
+    let hours = parse_field(fields[1], 0, 23, &[])?;
+    let days_of_month = parse_field(fields[2], 1, 31, &[])?;
+    let months = parse_field(fields[3], 1, 12, &MONTH_NAMES)?;
+    // Day-of-week accepts 0–7 where both 0 and 7 are Sunday.
+    let mut days_of_week = parse_field(fields[4], 0, 7, &DAY_NAMES)?;
+    if days_of_week & (1 << 7) != 0 {
+        days_of_week = (days_of_week | 1) & !(1 << 7);
+    }
+
+    Ok(CompiledSchedule {
+        minutes,
+        hours: hours as u32,
+        days_of_month: days_of_month as u32,
+        months: months as u16,
+        days_of_week: days_of_week as u8,
+        dom_restricted: !fields[2].starts_with('*'),
+        dow_restricted: !fields[4].starts_with('*'),
+        clamp_day: None,
+        tz,
+    })
+}
+
+/// Parse one cron field (`*`, `a`, `a-b`, `*/n`, `a-b/n`, comma lists) into a
+/// bitmask. `names` maps symbolic values onto `min..`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("Invalid step in cron field '{}'", field))?;
+                if step == 0 {
+                    return Err(format!("Step cannot be zero in cron field '{}'", field));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (parse_value(a, names, min)?, parse_value(b, names, min)?)
+        } else {
+            let v = parse_value(range, names, min)?;
+            // `a/n` means "from a to max every n".
+            if step > 1 {
+                (v, max)
+            } else {
+                (v, v)
+            }
+        };
+
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "Value out of range in cron field '{}' (allowed {}-{})",
+                field, min, max
+            ));
+        }
+        let mut v = start;
+        while v <= end {
+            mask |= 1 << v;
+            v += step;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, names: &[&str], min: u32) -> Result<u32, String> {
+    if let Ok(n) = value.parse::<u32>() {
+        return Ok(n);
+    }
+    let upper = value.to_ascii_uppercase();
+    names
+        .iter()
+        .position(|n| *n == upper)
+        .map(|i| i as u32 + min)
+        .ok_or_else(|| format!("Invalid cron value '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> u64 {
+        DateTime::parse_from_rfc3339(s).unwrap().timestamp() as u64
+    }
+
+    fn cron(expression: &str, timezone: &str) -> DcaSchedule {
+        DcaSchedule::Cron {
+            expression: expression.to_string(),
+            timezone: timezone.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_weekly_across_spring_forward() {
+        // Europe/Rome switches to CEST on 2026-03-29.
+        let schedule = cron("0 9 * * MON", "Europe/Rome");
+        let runs = schedule.preview(ts("2026-03-20T00:00:00Z"), 2).unwrap();
+        assert_eq!(
+            runs,
+            vec![ts("2026-03-23T08:00:00Z"), ts("2026-03-30T07:00:00Z")]
+        );
+    }
+
+    #[test]
+    fn test_time_in_gap_runs_after_gap() {
+        let schedule = cron("30 2 * * *", "Europe/Rome");
+        let next = schedule
+            .next_run_after(ts("2026-03-28T12:00:00Z"))
+            .unwrap()
+            .unwrap();
+        // 02:30 does not exist on 2026-03-29; the run moves to 03:00 CEST.
+        assert_eq!(next, ts("2026-03-29T01:00:00Z"));
+    }
+
+    #[test]
+    fn test_repeated_time_runs_once() {
+        // 02:30 occurs twice on 2026-10-25 in Europe/Rome.
+        let schedule = cron("30 2 * * *", "Europe/Rome");
+        let runs = schedule.preview(ts("2026-10-24T12:00:00Z"), 2).unwrap();
+        assert_eq!(
+            runs,
+            vec![ts("2026-10-25T00:30:00Z"), ts("2026-10-26T01:30:00Z")]
+        );
+    }
+
+    #[test]
+    fn test_monthly_calendar_clamps_to_month_end() {
+        let schedule = DcaSchedule::Calendar {
+            rule: CalendarRule::Month { day: 31 },
+            hour: 12,
+            minute: 0,
+            timezone: "UTC".to_string(),
+        };
+        let runs = schedule.preview(ts("2026-01-01T00:00:00Z"), 3).unwrap();
+        assert_eq!(
+            runs,
+            vec![
+                ts("2026-01-31T12:00:00Z"),
+                ts("2026-02-28T12:00:00Z"),
+                ts("2026-03-31T12:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_of_month_cron() {
+        let schedule = cron("0 0 1 * *", "UTC");
+        let next = schedule
+            .next_run_after(ts("2026-01-01T00:00:00Z"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(next, ts("2026-02-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_dom_or_dow_when_both_restricted() {
+        // 13th of the month OR any Friday.
+        let schedule = cron("0 0 13 * 5", "UTC");
+        let runs = schedule.preview(ts("2026-02-01T00:00:00Z"), 3).unwrap();
+        assert_eq!(
+            runs,
+            vec![
+                ts("2026-02-06T00:00:00Z"),
+                ts("2026-02-13T00:00:00Z"),
+                ts("2026-02-20T00:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_steps_and_sunday_as_seven() {
+        let schedule = cron("*/15 8-9 * * 7", "UTC");
+        // 2026-02-01 is a Sunday.
+        let runs = schedule.preview(ts("2026-02-01T08:20:00Z"), 3).unwrap();
+        assert_eq!(
+            runs,
+            vec![
+                ts("2026-02-01T08:30:00Z"),
+                ts("2026-02-01T08:45:00Z"),
+                ts("2026-02-01T09:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interval() {
+        let schedule = DcaSchedule::Interval { interval_secs: 60 };
+        assert_eq!(schedule.preview(100, 2).unwrap(), vec![160, 220]);
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        assert!(cron("0 9 * *", "UTC").validate().is_err());
+        assert!(cron("60 9 * * *", "UTC").validate().is_err());
+        assert!(cron("0 9 * * FOO", "UTC").validate().is_err());
+        assert!(cron("*/0 9 * * *", "UTC").validate().is_err());
+        assert!(cron("0 9 * * *", "Mars/Olympus").validate().is_err());
+        assert!(DcaSchedule::Interval { interval_secs: 0 }
+            .validate()
+            .is_err());
+        assert!(cron("0 9 * * mon-fri", "America/New_York")
+            .validate()
+            .is_ok());
+    }
+}
@@ -19,7 +19,7 @@ const MAX_LOGS_IN_MEMORY: usize = 1000;
 const MONITOR_INTERVAL_SECS: u64 = 3;
 
 // Network name normalization — mirrors kaleido-cli config.py
-fn normalize_network_name(network: &str) -> String {
+pub(crate) fn normalize_network_name(network: &str) -> String {
     let lowered = network.trim().to_lowercase();
     match lowered.as_str() {
         "mutinynet" | "signetcustom" | "customsignet" => "signetcustom".to_string(),
@@ -28,7 +28,7 @@ fn normalize_network_name(network: &str) -> String {
 }
 
 /// Default base directory for Docker environments (~/.kaleido)
-fn default_base_dir() -> PathBuf {
+pub(crate) fn default_base_dir() -> PathBuf {
     dirs::home_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join(".kaleido")
@@ -288,7 +288,7 @@ impl DockerNodeManager {
     /// Mirrors the frontend `formatAccountName` logic: lowercase, replace
     /// non-alphanumeric with hyphens, collapse consecutive hyphens, strip
     /// leading/trailing hyphens, then prefix with `kaleidoswap-`.
-    fn format_env_name(account_name: &str) -> String {
+    pub(crate) fn format_env_name(account_name: &str) -> String {
         let mut formatted = String::new();
         for ch in account_name.to_lowercase().chars() {
             if ch.is_ascii_alphanumeric() {
@@ -0,0 +1,266 @@
+//! Sharing node setups with kaleido-cli.
+//!
+//! kaleido-cli keeps each node environment in a directory of its config
+//! directory (`~/.kaleido` by default): a `docker-compose.yml` with one
+//! `rgb_node_<n>` service per node, and `volumes/dataldk<n>` with each node's
+//! data. Docker accounts of the app run from the same layout (see
+//! `docker_node`), in an environment named `kaleidoswap-<account>`.
+//!
+//! Importing copies an environment to the app's Docker directory under that
+//! name and adds the account for it. Exporting writes an account's
+//! environment, with its node data, to a kaleido-cli config directory; a
+//! native account gets a compose file generated for it.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data_dir;
+use crate::docker_node::{normalize_network_name, DockerNodeManager, DockerSpawnConfig};
+
+const ENV_PREFIX: &str = "kaleidoswap-";
+const COMPOSE_FILE: &str = "docker-compose.yml";
+/// Data of the first node of an environment.
+const FIRST_NODE_DATA: &str = "volumes/dataldk0";
+
+/// A kaleido-cli environment that can be imported as an account.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CliEnvironment {
+    pub env_name: String,
+    /// Account name the environment is imported as by default.
+    pub account_name: String,
+    /// Network name as the app knows it (`Regtest`, `SignetCustom`, ...).
+    pub network: Option<String>,
+    /// Ports of the first node; the account runs that one.
+    pub daemon_port: Option<u16>,
+    pub peer_port: Option<u16>,
+    pub node_count: u16,
+    /// Whether the first node was initialized.
+    pub has_data: bool,
+    pub account_exists: bool,
+}
+
+/// Account settings kaleido-cli doesn't keep; the frontend fills them from
+/// the network defaults.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CliImportAccount {
+    pub name: String,
+    pub network: String,
+    pub rpc_connection_url: String,
+    pub indexer_url: String,
+    pub proxy_endpoint: String,
+    pub default_lsp_url: String,
+    pub maker_urls: String,
+    pub default_maker_url: String,
+}
+
+/// The app's name of an rgb-lightning-node network.
+pub fn app_network(network: &str) -> Option<&'static str> {
+    match normalize_network_name(network).as_str() {
+        "regtest" => Some("Regtest"),
+        "testnet" => Some("Testnet"),
+        "signet" => Some("Signet"),
+        "signetcustom" => Some("SignetCustom"),
+        "mainnet" | "bitcoin" => Some("Mainnet"),
+        _ => None,
+    }
+}
+
+/// The rgb-lightning-node network of an app network. The regtest flavours
+/// only differ in the services the app talks to.
+pub fn cli_network(network: &str) -> String {
+    let lowered = network.to_lowercase();
+    if lowered.ends_with("regtest") {
+        "regtest".to_string()
+    } else {
+        normalize_network_name(&lowered)
+    }
+}
+
+/// The account an environment is imported as: the app's own environments
+/// map back to their account name.
+pub fn account_name(env_name: &str) -> String {
+    env_name
+        .strip_prefix(ENV_PREFIX)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(env_name)
+        .to_string()
+}
+
+fn has_entries(dir: &Path) -> bool {
+    fs::read_dir(dir).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+/// The environments in the kaleido-cli config directory `dir`.
+pub fn scan(dir: &Path) -> Result<Vec<CliEnvironment>, String> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()));
+    }
+    Ok(DockerNodeManager::list_environments(Some(dir))
+        .into_iter()
+        .map(|env| CliEnvironment {
+            account_name: account_name(&env.name),
+            network: app_network(&env.network).map(str::to_string),
+            daemon_port: env.daemon_ports.first().copied(),
+            peer_port: env.peer_ports.first().copied(),
+            node_count: env.node_count,
+            has_data: has_entries(&Path::new(&env.compose_dir).join(FIRST_NODE_DATA)),
+            account_exists: false,
+            env_name: env.name,
+        })
+        .collect())
+}
+
+/// Copy the environment `env_name` of `dir` to `base` as the environment of
+/// `account`. Returns its directory, and whether it was copied: an
+/// environment already in place is used as it is.
+pub fn import_environment(
+    dir: &Path,
+    env_name: &str,
+    account: &str,
+    base: &Path,
+) -> Result<(PathBuf, bool), String> {
+    let source = dir.join(env_name);
+    if !source.join(COMPOSE_FILE).is_file() {
+        return Err(format!("No {} in {}", COMPOSE_FILE, source.display()));
+    }
+    let target = base.join(DockerNodeManager::format_env_name(account));
+    let same = |a: &Path, b: &Path| match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    };
+    if same(&source, &target) {
+        return Ok((target, false));
+    }
+    if target.exists() {
+        return Err(format!(
+            "A Docker environment already exists at {}",
+            target.display()
+        ));
+    }
+    if let Err(e) = data_dir::copy_dir(&source, &target) {
+        let _ = fs::remove_dir_all(&target);
+        return Err(format!("Failed to copy {}: {}", source.display(), e));
+    }
+    Ok((target, true))
+}
+
+fn export_target(dir: &Path, env_name: &str) -> Result<PathBuf, String> {
+    let target = dir.join(env_name);
+    if target.exists() {
+        return Err(format!("{} already exists", target.display()));
+    }
+    Ok(target)
+}
+
+/// Copy the Docker environment at `env_dir`, node data included, into the
+/// kaleido-cli config directory `dir`.
+pub fn export_environment(env_dir: &Path, dir: &Path) -> Result<PathBuf, String> {
+    if !env_dir.join(COMPOSE_FILE).is_file() {
+        return Err(format!("No {} in {}", COMPOSE_FILE, env_dir.display()));
+    }
+    let env_name = env_dir
+        .file_name()
+        .ok_or("Invalid environment directory")?
+        .to_string_lossy()
+        .to_string();
+    let target = export_target(dir, &env_name)?;
+    if let Err(e) = data_dir::copy_dir(env_dir, &target) {
+        let _ = fs::remove_dir_all(&target);
+        return Err(format!("Failed to copy {}: {}", env_dir.display(), e));
+    }
+    Ok(target)
+}
+
+/// Write a one-node environment for `config` into the kaleido-cli config
+/// directory `dir`, with the native node data at `node_data`.
+pub fn export_native(
+    node_data: &Path,
+    config: &DockerSpawnConfig,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    if !has_entries(node_data) {
+        return Err(format!("No node data at {}", node_data.display()));
+    }
+    let target = export_target(dir, &config.name)?;
+    let result = DockerNodeManager::create_environment(config, Some(dir)).and_then(|_| {
+        data_dir::copy_dir(node_data, &target.join(FIRST_NODE_DATA))
+            .map_err(|e| format!("Failed to copy {}: {}", node_data.display(), e))
+    });
+    if let Err(e) = result {
+        let _ = fs::remove_dir_all(&target);
+        return Err(e);
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kaleido-cli-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(app_network("mutinynet"), Some("SignetCustom"));
+        assert_eq!(app_network("Regtest"), Some("Regtest"));
+        assert_eq!(app_network("liquid"), None);
+        assert_eq!(cli_network("SignetCustom"), "signetcustom");
+        assert_eq!(cli_network("LocalDockerRegtest"), "regtest");
+        assert_eq!(cli_network("Mainnet"), "mainnet");
+        assert_eq!(account_name("kaleidoswap-alice"), "alice");
+        assert_eq!(account_name("dev-nodes"), "dev-nodes");
+        assert_eq!(account_name("kaleidoswap-"), "kaleidoswap-");
+    }
+
+    #[test]
+    fn test_export_and_import() {
+        let root = temp_dir("roundtrip");
+        let (cli_dir, app_base, native) = (root.join("cli"), root.join("app"), root.join("node"));
+        fs::create_dir_all(&cli_dir).unwrap();
+        fs::create_dir_all(native.join(".ldk")).unwrap();
+        fs::write(native.join(".ldk/wallet"), "w").unwrap();
+
+        let config = DockerSpawnConfig {
+            name: "kaleidoswap-alice".into(),
+            count: 1,
+            network: cli_network("SignetCustom"),
+            base_daemon_port: 3005,
+            base_peer_port: 9740,
+            disable_authentication: true,
+        };
+        assert!(export_native(&root.join("missing"), &config, &cli_dir).is_err());
+        let exported = export_native(&native, &config, &cli_dir).unwrap();
+        assert!(export_native(&native, &config, &cli_dir).is_err());
+        assert!(exported.join("volumes/dataldk0/.ldk/wallet").is_file());
+
+        let envs = scan(&cli_dir).unwrap();
+        assert_eq!(envs.len(), 1);
+        let env = &envs[0];
+        assert_eq!(env.account_name, "alice");
+        assert_eq!(env.network.as_deref(), Some("SignetCustom"));
+        assert_eq!((env.daemon_port, env.peer_port), (Some(3005), Some(9740)));
+        assert!(env.has_data);
+
+        let (imported, copied) =
+            import_environment(&cli_dir, &env.env_name, "Bob's node", &app_base).unwrap();
+        assert!(copied);
+        assert_eq!(imported, app_base.join("kaleidoswap-bob-s-node"));
+        assert!(imported.join("volumes/dataldk0/.ldk/wallet").is_file());
+        assert!(import_environment(&cli_dir, &env.env_name, "Bob's node", &app_base).is_err());
+        // An environment already in place is used as it is
+        let (_, copied) = import_environment(&cli_dir, &env.env_name, "alice", &cli_dir).unwrap();
+        assert!(!copied);
+
+        let again = export_environment(&imported, &root.join("cli2")).unwrap();
+        assert!(again.join(COMPOSE_FILE).is_file());
+        assert!(export_environment(&imported, &root.join("cli2")).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,1412 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+use crate::db;
+use crate::dca_pair::TradeSide;
+use crate::maker::{self, MakerClient, SwapLeg, SwapStatus, TradingPair};
+
+/// How often active orders are checked against the market.
+const POLL_INTERVAL_SECS: u64 = 20;
+/// Pair prices are reused for this long, so orders on the same pair share one
+/// quote per check.
+const PRICE_CACHE_SECS: u64 = 30;
+/// How long the maker's pair list (precisions, layers, min amounts) is reused.
+const PAIRS_CACHE_SECS: u64 = 300;
+/// A failed execution isn't retried before the next regular check, even if an
+/// order update wakes the engine earlier. The wait doubles with every failure
+/// in a row, up to `MAX_RETRY_DELAY_MS`.
+const RETRY_DELAY_MS: u64 = POLL_INTERVAL_SECS * 1000;
+const MAX_RETRY_DELAY_MS: u64 = 30 * 60 * 1000;
+/// Failures in a row after which an order is paused.
+const MAX_FAILURES: u32 = 5;
+/// Status of an order whose swap was handed over but may not have gone
+/// through; it isn't tried again until the node says how the swap ended.
+const PENDING_CONFIRMATION: &str = "pending-confirmation";
+/// Most the quoted price may be worse than the limit, unless the order sets
+/// its own `maxSlippagePct`. Stop-losses have no cap unless they set one: a
+/// stop exists to get out, and a price that gapped through it would
+/// otherwise never be accepted.
+pub const DEFAULT_MAX_SLIPPAGE_PCT: f64 = 3.0;
+/// Amount (raw quote units) to probe the price with when the maker doesn't
+/// list a minimum.
+const DEFAULT_PROBE_AMOUNT: u64 = 1000;
+const DEFAULT_QUOTE_PRECISION: u32 = 6;
+const DEFAULT_BASE_PRECISION: u32 = 8;
+
+/// What an order's price is: a limit to buy/sell at or better, or a trigger
+/// that protects a position.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LimitOrderType {
+    #[default]
+    Limit,
+    /// Fires when the price moves against the position: a sell once it falls
+    /// to the stop, a buy once it rises to it.
+    StopLoss,
+    /// Fires when the price moves in the position's favour, like a limit.
+    TakeProfit,
+}
+
+impl LimitOrderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitOrderType::Limit => "limit",
+            LimitOrderType::StopLoss => "stop-loss",
+            LimitOrderType::TakeProfit => "take-profit",
+        }
+    }
+}
+
+/// The fields the engine needs from a stored order. Payloads are the camelCase
+/// JSON written by the frontend; everything else in them is preserved.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitOrder {
+    pub id: String,
+    #[serde(default)]
+    pub order_type: LimitOrderType,
+    pub side: TradeSide,
+    pub status: String,
+    /// The other leg of a one-cancels-other pair.
+    #[serde(default)]
+    pub linked_order_id: Option<String>,
+    pub pair_id: String,
+    pub base_asset_ticker: String,
+    pub base_asset_id: String,
+    pub quote_asset_ticker: String,
+    pub quote_asset_id: String,
+    /// Display price (quote asset per base asset); the trigger price of stop
+    /// orders.
+    pub limit_price: f64,
+    /// Amount of the spent asset, in its smallest units.
+    pub amount_raw: u64,
+    /// Fill across several swaps, each sized to the channels' current
+    /// capacity, instead of waiting until one swap can take everything.
+    #[serde(default)]
+    pub allow_partial: bool,
+    /// Spent by the fills so far, in the units of `amount_raw`.
+    #[serde(default)]
+    pub filled_raw: u64,
+    /// Received by the fills so far, in the received asset's smallest units.
+    #[serde(default)]
+    pub received_raw: u64,
+    /// Fill price averaged over the base asset traded.
+    #[serde(default)]
+    pub average_price: Option<f64>,
+    /// Unix milliseconds.
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub max_slippage_pct: Option<f64>,
+    /// Failed attempts in a row.
+    #[serde(default)]
+    pub failures: u32,
+    #[serde(default)]
+    pub pending_swap: Option<PendingSwap>,
+}
+
+impl LimitOrder {
+    /// (ticker, asset id) of the asset a fill spends.
+    fn spend_asset(&self) -> (&str, &str) {
+        match self.side {
+            TradeSide::Buy => (&self.quote_asset_ticker, &self.quote_asset_id),
+            TradeSide::Sell => (&self.base_asset_ticker, &self.base_asset_id),
+        }
+    }
+
+    fn receive_asset(&self) -> (&str, &str) {
+        match self.side {
+            TradeSide::Buy => (&self.base_asset_ticker, &self.base_asset_id),
+            TradeSide::Sell => (&self.quote_asset_ticker, &self.quote_asset_id),
+        }
+    }
+
+    pub fn remaining_raw(&self) -> u64 {
+        self.amount_raw.saturating_sub(self.filled_raw)
+    }
+
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.expires_at.is_some_and(|at| now_ms >= at)
+    }
+
+    /// Limit buys fire at or below the limit, limit sells at or above it;
+    /// stop-losses fire in the other direction.
+    pub fn is_crossed(&self, price: f64) -> bool {
+        let falls_to = price <= self.limit_price;
+        let rises_to = price >= self.limit_price;
+        match (self.order_type, self.side) {
+            (LimitOrderType::StopLoss, TradeSide::Buy) => rises_to,
+            (LimitOrderType::StopLoss, TradeSide::Sell) => falls_to,
+            (_, TradeSide::Buy) => falls_to,
+            (_, TradeSide::Sell) => rises_to,
+        }
+    }
+
+    /// How much worse than the limit (or stop) `quoted` is, in percent
+    /// (negative when it's better).
+    pub fn slippage_pct(&self, quoted: f64) -> f64 {
+        let diff = match self.side {
+            TradeSide::Buy => quoted - self.limit_price,
+            TradeSide::Sell => self.limit_price - quoted,
+        };
+        diff / self.limit_price * 100.0
+    }
+
+    fn max_slippage_pct(&self) -> Option<f64> {
+        match self.order_type {
+            LimitOrderType::StopLoss => self.max_slippage_pct,
+            _ => Some(self.max_slippage_pct.unwrap_or(DEFAULT_MAX_SLIPPAGE_PCT)),
+        }
+    }
+}
+
+/// A swap handed to the maker for an order, kept in the order's `pendingSwap`
+/// while whether it went through is unknown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingSwap {
+    /// The swap's payment hash.
+    pub swap_id: String,
+    pub from_amount: u64,
+    pub to_amount: u64,
+    pub execution_price: f64,
+    /// Precisions of the base and quote asset, for the ledger entry.
+    pub precisions: (u32, u32),
+    /// Unix milliseconds.
+    pub started_at: u64,
+}
+
+impl PendingSwap {
+    /// The fill of `order` this swap makes once it went through.
+    fn execution(&self, order: &LimitOrder) -> LimitExecution {
+        LimitExecution {
+            id: execution_id(),
+            timestamp: now_ms(),
+            from_amount: self.from_amount,
+            from_asset_ticker: order.spend_asset().0.to_string(),
+            to_amount: self.to_amount,
+            to_asset_ticker: order.receive_asset().0.to_string(),
+            execution_price: self.execution_price,
+            status: "success",
+        }
+    }
+}
+
+/// Why [`execute`] didn't hand a swap over.
+#[derive(Debug)]
+enum ExecuteError {
+    /// Nothing was traded; trying again is safe.
+    Failed(String),
+    /// Whitelisting or executing the swap failed, but it may still go
+    /// through.
+    Unconfirmed(String, PendingSwap),
+}
+
+impl From<String> for ExecuteError {
+    fn from(error: String) -> Self {
+        ExecuteError::Failed(error)
+    }
+}
+
+/// How long to wait before trying an order again after `failures` in a row.
+fn retry_delay_ms(failures: u32) -> u64 {
+    let doublings = failures.saturating_sub(1).min(16);
+    (RETRY_DELAY_MS << doublings).min(MAX_RETRY_DELAY_MS)
+}
+
+/// One entry of the order's `executions` list, in the frontend's shape.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitExecution {
+    pub id: String,
+    pub timestamp: u64,
+    pub from_amount: u64,
+    pub from_asset_ticker: String,
+    pub to_amount: u64,
+    pub to_asset_ticker: String,
+    pub execution_price: f64,
+    pub status: &'static str,
+}
+
+/// A stored order: the typed view plus the raw payload it is written back as.
+#[derive(Debug, Clone)]
+struct StoredOrder {
+    order: LimitOrder,
+    payload: Value,
+}
+
+impl StoredOrder {
+    fn parse(payload: &str) -> Result<Self, String> {
+        let payload: Value = serde_json::from_str(payload).map_err(|e| e.to_string())?;
+        let order = serde_json::from_value(payload.clone()).map_err(|e| e.to_string())?;
+        Ok(StoredOrder { order, payload })
+    }
+
+    fn row(&self) -> db::LimitOrderRow {
+        db::LimitOrderRow {
+            order_id: self.order.id.clone(),
+            order_type: self.order.order_type.as_str().to_string(),
+            linked_order_id: self.order.linked_order_id.clone(),
+            payload: self.payload.to_string(),
+        }
+    }
+
+    fn set_status(&mut self, status: &str) {
+        self.order.status = status.to_string();
+        self.payload["status"] = Value::from(status);
+    }
+
+    /// Note a failed attempt in `lastError` and `failures`. Returns how many
+    /// failed in a row.
+    fn record_failure(&mut self, error: &str) -> u32 {
+        self.order.failures += 1;
+        self.payload["failures"] = Value::from(self.order.failures);
+        self.payload["lastError"] = Value::from(error);
+        self.order.failures
+    }
+
+    /// Pause the order, with the reason shown to the user.
+    fn pause(&mut self, reason: &str) {
+        self.set_status("paused");
+        self.payload["pauseReason"] = Value::from(reason);
+    }
+
+    /// Hold the order until `swap` is known to have gone through or not.
+    fn set_pending_swap(&mut self, swap: PendingSwap) {
+        self.set_status(PENDING_CONFIRMATION);
+        self.payload["pendingSwap"] = serde_json::to_value(&swap).unwrap_or(Value::Null);
+        self.order.pending_swap = Some(swap);
+    }
+
+    /// Make the order active again once its pending swap has ended.
+    fn clear_pending_swap(&mut self) {
+        self.set_status("active");
+        if let Some(payload) = self.payload.as_object_mut() {
+            payload.remove("pendingSwap");
+        }
+        self.order.pending_swap = None;
+    }
+
+    /// Take the fills of `saved` if this copy is behind it.
+    fn keep_fills(&mut self, saved: &StoredOrder) {
+        if saved.order.filled_raw <= self.order.filled_raw {
+            return;
+        }
+        for key in ["filledRaw", "receivedRaw", "averagePrice", "executions"] {
+            self.payload[key] = saved.payload[key].clone();
+        }
+        self.order.filled_raw = saved.order.filled_raw;
+        self.order.received_raw = saved.order.received_raw;
+        self.order.average_price = saved.order.average_price;
+    }
+
+    /// Append a fill to the executions and the fill totals, clearing the
+    /// failures before it. Returns whether the order is now completely
+    /// filled.
+    fn record(&mut self, execution: &LimitExecution) -> bool {
+        let entry = serde_json::to_value(execution).unwrap_or(Value::Null);
+        match self
+            .payload
+            .get_mut("executions")
+            .and_then(Value::as_array_mut)
+        {
+            Some(list) => list.push(entry),
+            None => self.payload["executions"] = Value::Array(vec![entry]),
+        }
+        self.order.failures = 0;
+        if let Some(payload) = self.payload.as_object_mut() {
+            payload.remove("failures");
+            payload.remove("lastError");
+        }
+
+        let order = &mut self.order;
+        // The average is weighted by the base asset: received on buys, spent
+        // on sells.
+        let (base_before, base_now) = match order.side {
+            TradeSide::Buy => (order.received_raw, execution.to_amount),
+            TradeSide::Sell => (order.filled_raw, execution.from_amount),
+        };
+        let total = (base_before + base_now) as f64;
+        if total > 0.0 {
+            let before = order.average_price.unwrap_or(0.0) * base_before as f64;
+            order.average_price =
+                Some((before + execution.execution_price * base_now as f64) / total);
+        }
+        order.filled_raw += execution.from_amount;
+        order.received_raw += execution.to_amount;
+        self.payload["filledRaw"] = Value::from(order.filled_raw);
+        self.payload["receivedRaw"] = Value::from(order.received_raw);
+        if let Some(price) = order.average_price {
+            self.payload["averagePrice"] = Value::from(price);
+        }
+
+        let complete = self.order.remaining_raw() == 0;
+        if complete {
+            self.set_status("filled");
+            self.payload["filledAt"] = Value::from(execution.timestamp);
+        }
+        complete
+    }
+}
+
+/// The database row for a payload from the frontend; fails on an unknown
+/// order type.
+pub fn order_row(order_id: String, payload: String) -> Result<db::LimitOrderRow, String> {
+    let value: Value = serde_json::from_str(&payload).map_err(|e| e.to_string())?;
+    let order_type: LimitOrderType = match value.get("orderType") {
+        Some(t) => serde_json::from_value(t.clone()).map_err(|e| e.to_string())?,
+        None => LimitOrderType::default(),
+    };
+    let linked_order_id = value
+        .get("linkedOrderId")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    Ok(db::LimitOrderRow {
+        order_id,
+        order_type: order_type.as_str().to_string(),
+        linked_order_id,
+        payload,
+    })
+}
+
+/// Size of the next swap of a partially fillable order: as much of what's
+/// left as `capacity` and the maker's per-swap maximum allow, without leaving
+/// a remainder below the maker's minimum. `None` when no swap is possible.
+pub fn chunk_size(remaining: u64, capacity: u64, min_amount: u64, max_amount: u64) -> Option<u64> {
+    let mut chunk = remaining.min(capacity);
+    if max_amount > 0 {
+        chunk = chunk.min(max_amount);
+    }
+    if chunk < remaining && remaining - chunk < min_amount {
+        chunk = remaining.saturating_sub(min_amount);
+    }
+    (chunk > 0 && (chunk >= min_amount || chunk == remaining)).then_some(chunk)
+}
+
+/// Payload of the `limit:filled`, `limit:partial`, `limit:expired`,
+/// `limit:failed`, `limit:unconfirmed` and `limit:cancelled` events.
+/// `order` is the updated payload, as now stored.
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitOrderEvent {
+    pub order_id: String,
+    pub order: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Where the engine trades: the account's node and default maker.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    pub account_id: i32,
+    pub node_url: String,
+    pub maker_url: String,
+    pub bearer_token: Option<String>,
+}
+
+struct RunningEngine {
+    account_id: i32,
+    cancel: CancellationToken,
+    task: tauri::async_runtime::JoinHandle<()>,
+}
+
+/// Watches the maker's prices for the current account's active limit orders
+/// and executes the swap when an order's limit is crossed. Managed in Tauri
+/// state behind an `Arc`, like `DcaScheduler`.
+pub struct LimitOrderEngine {
+    app_handle: Mutex<Option<AppHandle>>,
+    /// Orders to execute on the next pass regardless of the price.
+    manual: Mutex<Vec<String>>,
+    wake: Notify,
+    task: tokio::sync::Mutex<Option<RunningEngine>>,
+}
+
+impl LimitOrderEngine {
+    pub fn new() -> Self {
+        LimitOrderEngine {
+            app_handle: Mutex::new(None),
+            manual: Mutex::new(Vec::new()),
+            wake: Notify::new(),
+            task: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn set_app_handle(&self, handle: AppHandle) {
+        *self.app_handle.lock().unwrap() = Some(handle);
+    }
+
+    /// Re-read the orders from the database (after the frontend changed them).
+    pub fn reload(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Execute an active order now, without waiting for its limit.
+    pub fn execute_now(&self, order_id: String) {
+        let mut manual = self.manual.lock().unwrap();
+        if !manual.contains(&order_id) {
+            manual.push(order_id);
+        }
+        drop(manual);
+        self.wake.notify_one();
+    }
+
+    /// Start the engine for an account. A no-op if it already runs for it;
+    /// an engine for another account is stopped first, finishing a swap in
+    /// flight.
+    pub async fn start(self: &Arc<Self>, config: EngineConfig) {
+        let mut task = self.task.lock().await;
+        if let Some(running) = task.take() {
+            if running.account_id == config.account_id {
+                log::info!("[Limit] engine already running, skipping start");
+                *task = Some(running);
+                return;
+            }
+            log::info!(
+                "[Limit] account changed ({} -> {}), restarting engine",
+                running.account_id,
+                config.account_id
+            );
+            running.cancel.cancel();
+            if let Err(e) = running.task.await {
+                log::error!("[Limit] engine task failed: {:?}", e);
+            }
+            self.manual.lock().unwrap().clear();
+        }
+        log::info!(
+            "[Limit] engine starting for account {} (maker {})",
+            config.account_id,
+            config.maker_url
+        );
+        let cancel = CancellationToken::new();
+        let engine = Arc::clone(self);
+        let token = cancel.clone();
+        let account_id = config.account_id;
+        let handle = tauri::async_runtime::spawn(async move { engine.run(config, token).await });
+        *task = Some(RunningEngine {
+            account_id,
+            cancel,
+            task: handle,
+        });
+    }
+
+    /// Stop the engine and wait for it to exit. A swap already in flight is
+    /// finished and recorded first. Safe to call when not running.
+    pub async fn stop(&self) {
+        let mut task = self.task.lock().await;
+        if let Some(running) = task.take() {
+            running.cancel.cancel();
+            if let Err(e) = running.task.await {
+                log::error!("[Limit] engine task failed: {:?}", e);
+            }
+        }
+        self.manual.lock().unwrap().clear();
+    }
+
+    async fn run(self: Arc<Self>, config: EngineConfig, cancel: CancellationToken) {
+        let client = MakerClient::new(
+            &config.maker_url,
+            &config.node_url,
+            config.bearer_token.clone(),
+        );
+        let mut market = Market::default();
+        let mut state = RunState::default();
+
+        'engine: loop {
+            let orders = match load_orders(config.account_id).await {
+                Ok(orders) => orders,
+                Err(e) => {
+                    log::error!("[Limit] failed to load orders: {}", e);
+                    Vec::new()
+                }
+            };
+            let manual: Vec<String> = std::mem::take(&mut *self.manual.lock().unwrap());
+
+            for stored in &orders {
+                let mut stored = stored.clone();
+                if let Some(saved) = state.partial.get(&stored.order.id) {
+                    stored.keep_fills(saved);
+                }
+                if let Some(saved) = state.unconfirmed.get(&stored.order.id) {
+                    if stored.order.pending_swap.is_none() {
+                        stored = saved.clone();
+                    }
+                }
+                let order = stored.order.clone();
+
+                if let Some(swap) = &order.pending_swap {
+                    let status = tokio::select! {
+                        _ = cancel.cancelled() => break 'engine,
+                        status = client.swap_status(&swap.swap_id) => status,
+                    };
+                    match status {
+                        Ok(Some(SwapStatus::Succeeded)) => {
+                            log::info!(
+                                "[Limit] swap {} of order {} went through",
+                                swap.swap_id,
+                                order.id
+                            );
+                            state.unconfirmed.remove(&order.id);
+                            stored.clear_pending_swap();
+                            self.record_fill(&config, &orders, &mut state, stored, swap)
+                                .await;
+                        }
+                        Ok(None | Some(SwapStatus::Failed | SwapStatus::Expired)) => {
+                            state.unconfirmed.remove(&order.id);
+                            stored.clear_pending_swap();
+                            let error = format!("Swap {} did not go through", swap.swap_id);
+                            self.record_failure(config.account_id, &mut state, stored, error)
+                                .await;
+                        }
+                        Ok(Some(status)) => {
+                            log::debug!(
+                                "[Limit] swap {} of order {} is {:?}",
+                                swap.swap_id,
+                                order.id,
+                                status
+                            );
+                        }
+                        Err(e) => {
+                            log::warn!("[Limit] failed to check swap {}: {}", swap.swap_id, e)
+                        }
+                    }
+                    continue;
+                }
+
+                if order.status != "active" || state.finished.contains(&order.id) {
+                    if manual.contains(&order.id) {
+                        log::warn!("[Limit] order {} is not active, not executing", order.id);
+                    }
+                    continue;
+                }
+                let now = now_ms();
+                if order.is_expired(now) {
+                    log::info!("[Limit] order {} expired", order.id);
+                    stored.set_status("expired");
+                    self.save(config.account_id, &[&stored]).await;
+                    self.emit("expired", &stored, None);
+                    continue;
+                }
+
+                let forced = manual.contains(&order.id);
+                if !forced {
+                    if state.retry_at.get(&order.id).is_some_and(|&at| now < at) {
+                        continue;
+                    }
+                    let price = tokio::select! {
+                        _ = cancel.cancelled() => break 'engine,
+                        price = market.price(&client, &order) => price,
+                    };
+                    match price {
+                        Some(price) if order.is_crossed(price) => log::info!(
+                            "[Limit] {:?} trigger order={} current={} limit={}",
+                            order.side,
+                            order.id,
+                            price,
+                            order.limit_price
+                        ),
+                        _ => continue,
+                    }
+                }
+
+                // Not cancellable: once the swap is under way it has to be
+                // recorded, or the order would stay active and fill again.
+                match execute(&client, &mut market, &order).await {
+                    Ok(swap) => {
+                        state.retry_at.remove(&order.id);
+                        self.record_fill(&config, &orders, &mut state, stored, &swap)
+                            .await;
+                    }
+                    Err(ExecuteError::Failed(e)) => {
+                        log::warn!("[Limit] order {} execution failed: {}", order.id, e);
+                        self.record_failure(config.account_id, &mut state, stored, e)
+                            .await;
+                    }
+                    Err(ExecuteError::Unconfirmed(e, swap)) => {
+                        log::warn!(
+                            "[Limit] order {} swap {} may have gone through, waiting for it: {}",
+                            order.id,
+                            swap.swap_id,
+                            e
+                        );
+                        stored.set_pending_swap(swap);
+                        state.unconfirmed.insert(order.id.clone(), stored.clone());
+                        self.save(config.account_id, &[&stored]).await;
+                        self.emit("unconfirmed", &stored, Some(e));
+                    }
+                }
+                if cancel.is_cancelled() {
+                    break 'engine;
+                }
+            }
+
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = self.wake.notified() => {}
+                _ = tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+            }
+        }
+        log::info!("[Limit] engine stopped");
+    }
+
+    /// Record the fill `swap` made, with its ledger entry. One-cancels-other:
+    /// the sibling is cancelled in the same transaction as the (first) fill.
+    async fn record_fill(
+        &self,
+        config: &EngineConfig,
+        orders: &[StoredOrder],
+        state: &mut RunState,
+        mut stored: StoredOrder,
+        swap: &PendingSwap,
+    ) {
+        let order = stored.order.clone();
+        let execution = swap.execution(&order);
+        let trade = ledger_trade(
+            &order,
+            &execution,
+            swap.precisions,
+            &config.maker_url,
+            &swap.swap_id,
+        );
+        record_trade(config.account_id, trade).await;
+        let complete = stored.record(&execution);
+        log::info!(
+            "[Limit] order {} {} {} at {} ({} left)",
+            order.id,
+            if complete {
+                "filled"
+            } else {
+                "partially filled"
+            },
+            execution.from_amount,
+            execution.execution_price,
+            stored.order.remaining_raw()
+        );
+        if complete {
+            state.finished.insert(order.id.clone());
+        } else {
+            state.partial.insert(order.id.clone(), stored.clone());
+        }
+        let sibling = order
+            .linked_order_id
+            .as_ref()
+            .and_then(|id| orders.iter().find(|o| &o.order.id == id))
+            .filter(|o| matches!(o.order.status.as_str(), "active" | "paused"))
+            .map(|o| {
+                let mut sibling = o.clone();
+                sibling.set_status("cancelled");
+                sibling.payload["cancelledBy"] = Value::from(order.id.clone());
+                sibling
+            });
+        match &sibling {
+            Some(sibling) => {
+                log::info!(
+                    "[Limit] cancelling OCO sibling {} of {}",
+                    sibling.order.id,
+                    order.id
+                );
+                state.finished.insert(sibling.order.id.clone());
+                self.save(config.account_id, &[&stored, sibling]).await;
+            }
+            None => self.save(config.account_id, &[&stored]).await,
+        }
+        self.emit(if complete { "filled" } else { "partial" }, &stored, None);
+        if let Some(sibling) = &sibling {
+            self.emit("cancelled", sibling, None);
+        }
+    }
+
+    /// Record a failed attempt and back off. Errors that won't go away by
+    /// retrying, and too many failures in a row, pause the order.
+    async fn record_failure(
+        &self,
+        account_id: i32,
+        state: &mut RunState,
+        mut stored: StoredOrder,
+        error: String,
+    ) {
+        let failures = stored.record_failure(&error);
+        state
+            .retry_at
+            .insert(stored.order.id.clone(), now_ms() + retry_delay_ms(failures));
+        if is_insufficient_balance(&error) {
+            stored.pause(&error);
+        } else if failures >= MAX_FAILURES {
+            log::warn!(
+                "[Limit] pausing order {} after {} failures in a row",
+                stored.order.id,
+                failures
+            );
+            stored.pause(&format!(
+                "Paused after {} failed attempts in a row: {}",
+                failures, error
+            ));
+        }
+        self.save(account_id, &[&stored]).await;
+        self.emit("failed", &stored, Some(error));
+    }
+
+    /// Write orders back, all or nothing.
+    async fn save(&self, account_id: i32, stored: &[&StoredOrder]) {
+        let rows: Vec<db::LimitOrderRow> = stored.iter().map(|s| s.row()).collect();
+        let ids: Vec<String> = rows.iter().map(|r| r.order_id.clone()).collect();
+        let saved = tauri::async_runtime::spawn_blocking(move || {
+            db::with(|conn| db::save_limit_orders(conn, account_id, &rows))
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        if let Err(e) = saved {
+            log::error!("[Limit] failed to save orders {:?}: {}", ids, e);
+        }
+    }
+
+    fn emit(&self, event: &str, stored: &StoredOrder, message: Option<String>) {
+        if let Some(handle) = self.app_handle.lock().unwrap().as_ref() {
+            let payload = LimitOrderEvent {
+                order_id: stored.order.id.clone(),
+                order: stored.payload.clone(),
+                message,
+            };
+            if let Err(e) = handle.emit(&format!("limit:{}", event), payload) {
+                log::error!("[Limit] failed to emit limit:{}: {}", event, e);
+            }
+        }
+    }
+}
+
+/// What the engine remembers between passes.
+#[derive(Default)]
+struct RunState {
+    /// Orders filled this session. Guards against executing twice when a
+    /// stale copy of the order is written back before the frontend has seen
+    /// the fill.
+    finished: HashSet<String>,
+    /// Earliest time (ms) a failed order may be tried again.
+    retry_at: HashMap<String, u64>,
+    /// Last saved state of partially filled orders, for the same reason as
+    /// `finished`.
+    partial: HashMap<String, StoredOrder>,
+    /// Last saved state of orders waiting for their swap, likewise.
+    unconfirmed: HashMap<String, StoredOrder>,
+}
+
+/// Maker state shared by the orders checked in one pass.
+#[derive(Default)]
+struct Market {
+    pairs: Vec<TradingPair>,
+    pairs_at: Option<u64>,
+    prices: HashMap<String, (f64, u64)>,
+    pubkey: Option<String>,
+}
+
+impl Market {
+    async fn pairs(&mut self, client: &MakerClient) -> Result<&[TradingPair], String> {
+        let now = now_ms() / 1000;
+        if self.pairs_at.is_none_or(|at| now >= at + PAIRS_CACHE_SECS) {
+            self.pairs = client.pairs().await?;
+            self.pairs_at = Some(now);
+        }
+        Ok(&self.pairs)
+    }
+
+    /// Current price of the order's pair, from a minimum-size quote of the
+    /// quote asset into the base asset.
+    async fn price(&mut self, client: &MakerClient, order: &LimitOrder) -> Option<f64> {
+        let now = now_ms() / 1000;
+        if let Some(&(price, at)) = self.prices.get(&order.pair_id) {
+            if now < at + PRICE_CACHE_SECS {
+                return Some(price);
+            }
+        }
+
+        let pairs = match self.pairs(client).await {
+            Ok(pairs) => pairs,
+            Err(e) => {
+                log::warn!("[Limit] failed to fetch pairs: {}", e);
+                return None;
+            }
+        };
+        let pair = pairs.iter().find(|p| p.id == order.pair_id);
+        let probe = pair
+            .and_then(|p| p.quote.endpoints.first())
+            .map(|e| e.min_amount)
+            .filter(|&a| a > 0)
+            .unwrap_or(DEFAULT_PROBE_AMOUNT);
+        let precision = quote_precision(pair);
+        let from = SwapLeg {
+            asset_id: order.quote_asset_id.clone(),
+            layer: Some(maker::asset_layer(
+                &order.quote_asset_ticker,
+                &order.quote_asset_id,
+                pairs,
+            )),
+            amount: Some(probe),
+        };
+        let to = SwapLeg {
+            asset_id: order.base_asset_id.clone(),
+            layer: Some(maker::asset_layer(
+                &order.base_asset_ticker,
+                &order.base_asset_id,
+                pairs,
+            )),
+            amount: None,
+        };
+
+        match client.quote(from, to).await {
+            Ok(quote) => {
+                let price = display_price(quote.price?, precision)?;
+                log::debug!("[Limit] {} price {}", order.pair_id, price);
+                self.prices.insert(order.pair_id.clone(), (price, now));
+                Some(price)
+            }
+            Err(e) => {
+                log::warn!("[Limit] price quote for {} failed: {}", order.pair_id, e);
+                None
+            }
+        }
+    }
+
+    async fn pubkey(&mut self, client: &MakerClient) -> Result<String, String> {
+        if self.pubkey.is_none() {
+            self.pubkey = Some(client.node_pubkey().await?);
+        }
+        Ok(self.pubkey.clone().unwrap_or_default())
+    }
+}
+
+/// Quote the order (or, for partial orders, the next chunk of it), check
+/// slippage against the limit, then init, validate, whitelist and execute
+/// the swap. Returns the swap handed over.
+async fn execute(
+    client: &MakerClient,
+    market: &mut Market,
+    order: &LimitOrder,
+) -> Result<PendingSwap, ExecuteError> {
+    let pairs = market.pairs(client).await?;
+    let pair = pairs.iter().find(|p| p.id == order.pair_id);
+    let precision = quote_precision(pair);
+    let base_precision = pair
+        .map(|p| p.base.precision)
+        .unwrap_or(DEFAULT_BASE_PRECISION);
+    let (from_ticker, from_id) = order.spend_asset();
+    let (to_ticker, to_id) = order.receive_asset();
+    let from_layer = maker::asset_layer(from_ticker, from_id, pairs);
+    let to_layer = maker::asset_layer(to_ticker, to_id, pairs);
+    let (min_amount, max_amount) = pair
+        .and_then(|p| {
+            [&p.base, &p.quote]
+                .into_iter()
+                .find(|a| a.ticker == from_ticker)
+        })
+        .and_then(|a| a.endpoints.first())
+        .map(|e| (e.min_amount, e.max_amount))
+        .unwrap_or((0, 0));
+    let legs = |amount: u64| {
+        (
+            SwapLeg {
+                asset_id: from_id.to_string(),
+                layer: Some(from_layer.clone()),
+                amount: Some(amount),
+            },
+            SwapLeg {
+                asset_id: to_id.to_string(),
+                layer: Some(to_layer.clone()),
+                amount: None,
+            },
+        )
+    };
+
+    let remaining = order.remaining_raw();
+    let mut amount = remaining;
+    if order.allow_partial {
+        let outbound = client.capacity(from_id).await?.outbound;
+        amount = chunk_size(remaining, outbound, min_amount, max_amount).ok_or_else(|| {
+            format!(
+                "No {} outbound capacity for a partial fill ({} available)",
+                from_ticker, outbound
+            )
+        })?;
+    }
+    let (from, to) = legs(amount);
+    let mut quote = client.quote(from, to).await?;
+    if order.allow_partial {
+        // Shrink the chunk to what the receiving channels can take.
+        let inbound = client.capacity(to_id).await?.inbound;
+        let quoted = quote.to_asset.amount.unwrap_or(0);
+        if quoted > inbound {
+            let fits = (amount as u128 * inbound as u128 / quoted as u128) as u64;
+            amount = chunk_size(remaining, fits, min_amount, max_amount).ok_or_else(|| {
+                format!(
+                    "No {} inbound capacity for a partial fill ({} available)",
+                    to_ticker, inbound
+                )
+            })?;
+            let (from, to) = legs(amount);
+            quote = client.quote(from, to).await?;
+        }
+    }
+
+    let to_amount = quote
+        .to_asset
+        .amount
+        .ok_or_else(|| "Quote has no receive amount".to_string())?;
+    let from_amount = quote.from_asset.amount.unwrap_or(amount);
+    let price = quoted_price(
+        order,
+        quote.price,
+        (from_amount, to_amount),
+        (base_precision, precision),
+    )
+    .ok_or_else(|| "Quote has no price".to_string())?;
+    let slippage = order.slippage_pct(price);
+    log::info!(
+        "[Limit] order {} slippage {:.3}% (limit={} quoted={})",
+        order.id,
+        slippage,
+        order.limit_price,
+        price
+    );
+    if let Some(max) = order.max_slippage_pct().filter(|&max| slippage > max) {
+        return Err(ExecuteError::Failed(format!(
+            "Slippage too high: {:.2}% (max {}%)",
+            slippage, max
+        )));
+    }
+
+    let swap = client.init_swap(&quote, amount).await?;
+    maker::validate_swapstring(
+        &swap.swapstring,
+        (amount, &quote.from_asset.asset_id),
+        (to_amount, &quote.to_asset.asset_id),
+        &swap.payment_hash,
+    )?;
+    let pubkey = market.pubkey(client).await?;
+    let pending = PendingSwap {
+        swap_id: swap.payment_hash.clone(),
+        from_amount: amount,
+        to_amount,
+        execution_price: price,
+        precisions: (base_precision, precision),
+        started_at: now_ms(),
+    };
+    // From here on the taker side may have accepted the swap even when the
+    // call reports an error.
+    if let Err(e) = client.whitelist_trade(&swap.swapstring).await {
+        return Err(ExecuteError::Unconfirmed(e, pending));
+    }
+    if let Err(e) = client.execute_swap(&swap, &pubkey).await {
+        return Err(ExecuteError::Unconfirmed(e, pending));
+    }
+    Ok(pending)
+}
+
+/// The ledger entry of a fill. The swap is still pending on the node; its
+/// outcome is recorded when the frontend sees it settle.
+fn ledger_trade(
+    order: &LimitOrder,
+    execution: &LimitExecution,
+    (base_precision, quote_precision): (u32, u32),
+    maker_url: &str,
+    swap_id: &str,
+) -> db::Trade {
+    let (from_precision, to_precision, side) = match order.side {
+        TradeSide::Buy => (quote_precision, base_precision, "buy"),
+        TradeSide::Sell => (base_precision, quote_precision, "sell"),
+    };
+    let now = (execution.timestamp / 1000) as i64;
+    db::Trade {
+        id: 0,
+        account_id: 0,
+        source: "limit".to_string(),
+        order_id: Some(order.id.clone()),
+        pair: format!("{}/{}", order.base_asset_ticker, order.quote_asset_ticker),
+        side: side.to_string(),
+        from_asset: execution.from_asset_ticker.clone(),
+        from_amount: display_amount(execution.from_amount, from_precision),
+        to_asset: execution.to_asset_ticker.clone(),
+        to_amount: display_amount(execution.to_amount, to_precision),
+        price: execution.execution_price,
+        fee: 0.0,
+        fee_asset: None,
+        maker_url: maker_url.to_string(),
+        swap_id: Some(swap_id.to_string()),
+        status: "pending".to_string(),
+        error: None,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+async fn record_trade(account_id: i32, trade: db::Trade) {
+    let swap_id = trade.swap_id.clone();
+    let inserted = tauri::async_runtime::spawn_blocking(move || {
+        db::with(|conn| db::insert_trade(conn, account_id, &trade)).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+    if let Err(e) = inserted {
+        log::error!("[Limit] failed to record trade {:?}: {}", swap_id, e);
+    }
+}
+
+async fn load_orders(account_id: i32) -> Result<Vec<StoredOrder>, String> {
+    let payloads = tauri::async_runtime::spawn_blocking(move || {
+        db::with(|conn| db::get_limit_orders(conn, account_id))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+    Ok(payloads
+        .iter()
+        .filter_map(|p| match StoredOrder::parse(p) {
+            Ok(stored) => Some(stored),
+            Err(e) => {
+                log::warn!("[Limit] skipping unreadable order: {}", e);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Errors that won't go away by retrying: the order is paused instead.
+fn is_insufficient_balance(error: &str) -> bool {
+    let error = error.to_lowercase();
+    ["insufficient", "not enough balance", "exceeds available"]
+        .iter()
+        .any(|needle| error.contains(needle))
+}
+
+fn quote_precision(pair: Option<&TradingPair>) -> u32 {
+    pair.map(|p| p.quote.precision)
+        .unwrap_or(DEFAULT_QUOTE_PRECISION)
+}
+
+fn display_price(raw: f64, precision: u32) -> Option<f64> {
+    (raw > 0.0).then(|| raw / 10f64.powi(precision as i32))
+}
+
+fn display_amount(raw: u64, precision: u32) -> f64 {
+    raw as f64 / 10f64.powi(precision as i32)
+}
+
+/// Display price of a quote: the maker's `price`, or else the rate of its
+/// raw amounts, quote asset per base asset for either side.
+fn quoted_price(
+    order: &LimitOrder,
+    price: Option<f64>,
+    (from_amount, to_amount): (u64, u64),
+    (base_precision, quote_precision): (u32, u32),
+) -> Option<f64> {
+    if let Some(price) = price.and_then(|p| display_price(p, quote_precision)) {
+        return Some(price);
+    }
+    let (base, quote) = match order.side {
+        TradeSide::Buy => (to_amount, from_amount),
+        TradeSide::Sell => (from_amount, to_amount),
+    };
+    let base = display_amount(base, base_precision);
+    (base > 0.0 && quote > 0).then(|| display_amount(quote, quote_precision) / base)
+}
+
+fn execution_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(from_amount: u64, to_amount: u64, price: f64, now_ms: u64) -> LimitExecution {
+        LimitExecution {
+            id: execution_id(),
+            timestamp: now_ms,
+            from_amount,
+            from_asset_ticker: String::new(),
+            to_amount,
+            to_asset_ticker: String::new(),
+            execution_price: price,
+            status: "success",
+        }
+    }
+
+    fn payload(side: &str) -> String {
+        serde_json::json!({
+            "id": "o1",
+            "side": side,
+            "status": "active",
+            "createdAt": 1_700_000_000_000u64,
+            "pairId": "btc-usdt",
+            "baseAssetTicker": "BTC",
+            "baseAssetId": "BTC",
+            "quoteAssetTicker": "USDT",
+            "quoteAssetId": "rgb:usdt",
+            "limitPrice": 50_000.0,
+            "amount": 0.001,
+            "amountRaw": 100_000,
+            "expiresAt": 1_700_000_600_000u64,
+            "executions": []
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_trigger_and_slippage() {
+        let buy = StoredOrder::parse(&payload("buy")).unwrap().order;
+        assert!(buy.is_crossed(49_000.0));
+        assert!(buy.is_crossed(50_000.0));
+        assert!(!buy.is_crossed(50_001.0));
+        assert_eq!(buy.spend_asset(), ("USDT", "rgb:usdt"));
+        assert!((buy.slippage_pct(51_000.0) - 2.0).abs() < 1e-9);
+        assert!(buy.slippage_pct(49_000.0) < 0.0);
+
+        let sell = StoredOrder::parse(&payload("sell")).unwrap().order;
+        assert!(sell.is_crossed(50_500.0));
+        assert!(!sell.is_crossed(49_999.0));
+        assert_eq!(sell.spend_asset(), ("BTC", "BTC"));
+        assert!((sell.slippage_pct(48_500.0) - 3.0).abs() < 1e-9);
+        assert_eq!(sell.max_slippage_pct(), Some(DEFAULT_MAX_SLIPPAGE_PCT));
+    }
+
+    #[test]
+    fn test_stop_loss_and_take_profit_triggers() {
+        let mut value: Value = serde_json::from_str(&payload("sell")).unwrap();
+        value["orderType"] = Value::from("stop-loss");
+        value["linkedOrderId"] = Value::from("o2");
+        let stop = StoredOrder::parse(&value.to_string()).unwrap().order;
+        assert_eq!(stop.order_type, LimitOrderType::StopLoss);
+        assert!(stop.is_crossed(49_000.0));
+        assert!(!stop.is_crossed(51_000.0));
+        // Selling below the stop is slippage, as for a limit sell, but a
+        // stop-loss only caps it when asked to.
+        assert!((stop.slippage_pct(49_000.0) - 2.0).abs() < 1e-9);
+        assert_eq!(stop.max_slippage_pct(), None);
+
+        value["orderType"] = Value::from("take-profit");
+        let take = StoredOrder::parse(&value.to_string()).unwrap().order;
+        assert!(take.is_crossed(51_000.0));
+        assert!(!take.is_crossed(49_000.0));
+
+        let mut buy_stop = StoredOrder::parse(&payload("buy")).unwrap().order;
+        buy_stop.order_type = LimitOrderType::StopLoss;
+        assert!(buy_stop.is_crossed(51_000.0));
+        assert!(!buy_stop.is_crossed(49_000.0));
+    }
+
+    #[test]
+    fn test_stop_loss_gap_through() {
+        let mut value: Value = serde_json::from_str(&payload("sell")).unwrap();
+        value["orderType"] = Value::from("stop-loss");
+        let stop = StoredOrder::parse(&value.to_string()).unwrap().order;
+        // The market gaps from above the stop to 10% below it: the stop
+        // still fires and the quote isn't refused for slippage.
+        assert!(!stop.is_crossed(50_500.0));
+        assert!(stop.is_crossed(45_000.0));
+        let slippage = stop.slippage_pct(45_000.0);
+        assert!((slippage - 10.0).abs() < 1e-9);
+        assert!(stop
+            .max_slippage_pct()
+            .filter(|&max| slippage > max)
+            .is_none());
+
+        // Unless the order sets its own cap.
+        value["maxSlippagePct"] = Value::from(5.0);
+        let capped = StoredOrder::parse(&value.to_string()).unwrap().order;
+        assert_eq!(capped.max_slippage_pct(), Some(5.0));
+
+        // Take-profits keep the default cap.
+        value["orderType"] = Value::from("take-profit");
+        value.as_object_mut().unwrap().remove("maxSlippagePct");
+        let take = StoredOrder::parse(&value.to_string()).unwrap().order;
+        assert_eq!(take.max_slippage_pct(), Some(DEFAULT_MAX_SLIPPAGE_PCT));
+    }
+
+    #[test]
+    fn test_order_row() {
+        let row = order_row("o1".into(), payload("buy")).unwrap();
+        assert_eq!(row.order_type, "limit");
+        assert_eq!(row.linked_order_id, None);
+
+        let mut value: Value = serde_json::from_str(&payload("sell")).unwrap();
+        value["orderType"] = Value::from("take-profit");
+        value["linkedOrderId"] = Value::from("o2");
+        let row = order_row("o1".into(), value.to_string()).unwrap();
+        assert_eq!(row.order_type, "take-profit");
+        assert_eq!(row.linked_order_id.as_deref(), Some("o2"));
+
+        value["orderType"] = Value::from("trailing");
+        assert!(order_row("o1".into(), value.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let order = StoredOrder::parse(&payload("buy")).unwrap().order;
+        assert!(!order.is_expired(1_700_000_599_999));
+        assert!(order.is_expired(1_700_000_600_000));
+    }
+
+    #[test]
+    fn test_record_execution_preserves_payload() {
+        let mut stored = StoredOrder::parse(&payload("buy")).unwrap();
+        assert_eq!(stored.record_failure("Slippage too high"), 1);
+        assert_eq!(stored.record_failure("Slippage too high"), 2);
+        assert_eq!(stored.payload["status"], "active");
+        assert_eq!(stored.payload["failures"], 2);
+        assert_eq!(stored.payload["lastError"], "Slippage too high");
+        assert!(stored.payload["executions"].as_array().unwrap().is_empty());
+
+        assert!(stored.record(&fill(100_000, 2_000, 50.0, 2)));
+        assert_eq!(stored.payload["status"], "filled");
+        assert_eq!(stored.payload["filledAt"], 2);
+        assert_eq!(stored.payload["createdAt"], 1_700_000_000_000u64);
+        assert_eq!(stored.order.failures, 0);
+        assert!(stored.payload.get("failures").is_none());
+        assert!(stored.payload.get("lastError").is_none());
+        assert_eq!(stored.payload["executions"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_retry_backoff_and_pause() {
+        assert_eq!(retry_delay_ms(1), RETRY_DELAY_MS);
+        assert_eq!(retry_delay_ms(2), 2 * RETRY_DELAY_MS);
+        assert_eq!(retry_delay_ms(4), 8 * RETRY_DELAY_MS);
+        assert_eq!(retry_delay_ms(40), MAX_RETRY_DELAY_MS);
+
+        let mut stored = StoredOrder::parse(&payload("buy")).unwrap();
+        stored.pause("Paused after 5 failed attempts in a row: timeout");
+        assert_eq!(stored.order.status, "paused");
+        assert_eq!(
+            stored.payload["pauseReason"],
+            "Paused after 5 failed attempts in a row: timeout"
+        );
+    }
+
+    #[test]
+    fn test_pending_swap() {
+        let mut stored = StoredOrder::parse(&payload("sell")).unwrap();
+        let swap = PendingSwap {
+            swap_id: "hash".into(),
+            from_amount: 100_000,
+            to_amount: 50_000_000,
+            execution_price: 50_000.0,
+            precisions: (8, 6),
+            started_at: 1,
+        };
+        stored.set_pending_swap(swap);
+        assert_eq!(stored.payload["status"], PENDING_CONFIRMATION);
+        assert_eq!(stored.payload["pendingSwap"]["swapId"], "hash");
+
+        // It survives the trip through the database.
+        let mut stored = StoredOrder::parse(&stored.payload.to_string()).unwrap();
+        let swap = stored.order.pending_swap.clone().unwrap();
+        assert_eq!(swap.precisions, (8, 6));
+        let execution = swap.execution(&stored.order);
+        assert_eq!(
+            (execution.from_asset_ticker.as_str(), execution.to_amount),
+            ("BTC", 50_000_000)
+        );
+
+        stored.clear_pending_swap();
+        assert_eq!(stored.payload["status"], "active");
+        assert!(stored.payload.get("pendingSwap").is_none());
+        assert!(stored.record(&execution));
+        assert_eq!(stored.payload["status"], "filled");
+    }
+
+    #[test]
+    fn test_partial_fills() {
+        let mut stored = StoredOrder::parse(&payload("sell")).unwrap();
+        assert!(!stored.record(&fill(40_000, 20_000, 50_000.0, 2)));
+        assert_eq!(stored.payload["status"], "active");
+        assert_eq!(stored.order.remaining_raw(), 60_000);
+
+        // A stale copy written back by the frontend keeps the engine's fills.
+        let mut stale = StoredOrder::parse(&payload("sell")).unwrap();
+        stale.keep_fills(&stored);
+        assert_eq!(stale.order.remaining_raw(), 60_000);
+        assert_eq!(stale.payload["executions"].as_array().unwrap().len(), 1);
+
+        assert!(stored.record(&fill(60_000, 31_200, 52_000.0, 3)));
+        assert_eq!(stored.payload["status"], "filled");
+        assert_eq!(stored.payload["filledRaw"], 100_000);
+        assert_eq!(stored.payload["receivedRaw"], 51_200);
+        let average = stored.order.average_price.unwrap();
+        assert!((average - 51_200.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ledger_trade() {
+        let order = StoredOrder::parse(&payload("sell")).unwrap().order;
+        let mut execution = fill(100_000, 50_000_000, 50_000.0, 1_700_000_001_500);
+        execution.from_asset_ticker = "BTC".into();
+        execution.to_asset_ticker = "USDT".into();
+        let trade = ledger_trade(&order, &execution, (8, 6), "https://maker", "hash");
+        assert_eq!(trade.validate(), Ok(()));
+        assert_eq!(
+            (trade.source.as_str(), trade.side.as_str()),
+            ("limit", "sell")
+        );
+        assert_eq!(trade.pair, "BTC/USDT");
+        assert_eq!(trade.order_id.as_deref(), Some("o1"));
+        assert!((trade.from_amount - 0.001).abs() < 1e-12);
+        assert!((trade.to_amount - 50.0).abs() < 1e-9);
+        assert_eq!(trade.swap_id.as_deref(), Some("hash"));
+        assert_eq!(trade.status, "pending");
+        assert_eq!(trade.created_at, 1_700_000_001);
+    }
+
+    #[test]
+    fn test_quoted_price() {
+        let buy = StoredOrder::parse(&payload("buy")).unwrap().order;
+        let sell = StoredOrder::parse(&payload("sell")).unwrap().order;
+        // The maker's price wins
+        assert_eq!(
+            quoted_price(&buy, Some(51_000_000_000.0), (1, 1), (8, 6)),
+            Some(51_000.0)
+        );
+        // Otherwise the amounts: 50 USDT for 0.001 BTC, either way round
+        let price = quoted_price(&buy, None, (50_000_000, 100_000), (8, 6)).unwrap();
+        assert!((price - 50_000.0).abs() < 1e-6);
+        let price = quoted_price(&sell, None, (100_000, 49_000_000), (8, 6)).unwrap();
+        assert!((price - 49_000.0).abs() < 1e-6);
+        assert_eq!(quoted_price(&sell, Some(0.0), (100_000, 0), (8, 6)), None);
+        assert_eq!(quoted_price(&buy, None, (50_000_000, 0), (8, 6)), None);
+    }
+
+    #[test]
+    fn test_chunk_size() {
+        assert_eq!(chunk_size(1_000, 5_000, 100, 0), Some(1_000));
+        assert_eq!(chunk_size(1_000, 400, 100, 0), Some(400));
+        assert_eq!(chunk_size(1_000, 5_000, 100, 300), Some(300));
+        // Never leave a remainder the maker would refuse.
+        assert_eq!(chunk_size(1_000, 950, 100, 0), Some(900));
+        assert_eq!(chunk_size(1_000, 50, 100, 0), None);
+        assert_eq!(chunk_size(60, 5_000, 100, 0), Some(60));
+        assert_eq!(chunk_size(1_000, 0, 0, 0), None);
+    }
+
+    #[test]
+    fn test_insufficient_balance_pauses() {
+        assert!(is_insufficient_balance(
+            "API Error (400): Insufficient outbound"
+        ));
+        assert!(!is_insufficient_balance(
+            "Slippage too high: 4.00% (max 3%)"
+        ));
+        assert_eq!(display_price(5_000_000_000.0, 5), Some(50_000.0));
+        assert_eq!(display_price(0.0, 6), None);
+    }
+}
@@ -4,26 +4,48 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
 use tauri::{AppHandle, Emitter, Listener, Manager, State, Window};
 
+mod backup;
+mod channel_orders;
 mod crypto;
+mod data_dir;
 mod db;
 mod dca;
+mod dca_backtest;
+mod dca_limits;
+mod dca_pair;
+mod dca_schedule;
 mod docker_node;
+mod kaleido_cli;
+mod limit_orders;
+mod maker;
+mod migrations;
 mod mind;
 mod mind_runtime;
+mod mnemonic;
 mod node_backend;
 mod nwc;
 mod rgb_node;
+mod settings;
 mod tray;
+mod vault;
 
+use channel_orders::ChannelOrderPoller;
 use dca::{DcaOrderInfo, DcaScheduler};
 use docker_node::{DockerEnvironment, DockerNodeManager, DockerSpawnConfig};
+use limit_orders::LimitOrderEngine;
 use mind::MindProcess;
 use nwc::NwcManager;
 use rgb_node::{NodeProcess, NodeState};
+use vault::SessionVault;
 
 #[derive(Default)]
 pub(crate) struct CurrentAccount(pub(crate) RwLock<Option<db::Account>>);
 
+/// The secrets an account had before its last `change_password`, by account
+/// name, for `revert_password_change` to put back.
+#[derive(Default)]
+pub(crate) struct PreviousSecrets(Mutex<Option<(String, db::AccountSecrets)>>);
+
 // ── KaleidoMind sidecar commands ─────────────────────────────────────────
 // Thin pipe to apps/provider. The frontend builds protocol.ts Command objects
 // and sends them via `mind_send`; sidecar events arrive on the `mind-event`
@@ -78,16 +100,39 @@ pub struct NodeLogsResponse {
 fn main() {
     dotenvy::dotenv().ok();
     let _ = rustls::crypto::ring::default_provider().install_default();
+    let data_layout =
+        data_dir::init(std::env::args()).expect("Failed to resolve the data directory");
 
     let node_process = Arc::new(Mutex::new(NodeProcess::new()));
     let docker_manager = Arc::new(Mutex::new(DockerNodeManager::new()));
     let dca_scheduler = Arc::new(DcaScheduler::new());
+    let limit_engine = Arc::new(LimitOrderEngine::new());
+    let channel_order_poller = Arc::new(ChannelOrderPoller::new());
     let nwc_manager = Arc::new(NwcManager::new());
     let mind_process = Arc::new(MindProcess::new());
+    let vault = Arc::new(SessionVault::new());
 
     tauri::Builder::default()
         .plugin(tauri_plugin_updater::Builder::new().build())
-        .plugin(tauri_plugin_log::Builder::new().build())
+        .plugin({
+            use tauri_plugin_log::{Target, TargetKind};
+            // Rotated files are kept and pruned by age (log_retention_days)
+            let builder = tauri_plugin_log::Builder::new()
+                .rotation_strategy(tauri_plugin_log::RotationStrategy::KeepAll);
+            // A data directory of its own also holds the app logs
+            let builder = if data_layout.is_custom() {
+                builder.clear_targets().targets([
+                    Target::new(TargetKind::Stdout),
+                    Target::new(TargetKind::Folder {
+                        path: data_layout.log_dir.clone(),
+                        file_name: None,
+                    }),
+                ])
+            } else {
+                builder
+            };
+            builder.build()
+        })
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_fs::init())
@@ -97,9 +142,13 @@ fn main() {
         .manage(Arc::clone(&node_process))
         .manage(Arc::clone(&docker_manager))
         .manage(Arc::clone(&dca_scheduler))
+        .manage(Arc::clone(&limit_engine))
+        .manage(Arc::clone(&channel_order_poller))
         .manage(Arc::clone(&nwc_manager))
         .manage(Arc::clone(&mind_process))
+        .manage(Arc::clone(&vault))
         .manage(CurrentAccount::default())
+        .manage(PreviousSecrets::default())
         .on_window_event(|window, event| {
             if window.label() == "main" {
                 if let tauri::WindowEvent::CloseRequested { api, .. } = event {
@@ -112,19 +161,38 @@ fn main() {
             let node_process = Arc::clone(&node_process);
             let docker_manager = Arc::clone(&docker_manager);
             let dca_scheduler = Arc::clone(&dca_scheduler);
+            let limit_engine = Arc::clone(&limit_engine);
+            let channel_order_poller = Arc::clone(&channel_order_poller);
             let nwc_manager = Arc::clone(&nwc_manager);
+            let vault = Arc::clone(&vault);
             move |app| {
                 if let Some(main_window) = app.get_webview_window("main") {
                     node_process.lock().unwrap().set_window(main_window.clone());
                     docker_manager.lock().unwrap().set_window(main_window);
                 }
                 dca_scheduler.set_app_handle(app.handle().clone());
+                limit_engine.set_app_handle(app.handle().clone());
+                channel_order_poller.set_app_handle(app.handle().clone());
                 nwc_manager.set_app_handle(app.handle().clone());
+                vault.set_app_handle(app.handle().clone());
+                vault.start();
                 // NWC service is started lazily via nwc_start_service
                 // when the frontend detects the node is unlocked.
                 // DCA scheduler is started lazily via dca_start_scheduler
-                // when the frontend detects the node is unlocked.
-                db::init();
+                // when the frontend detects the node is unlocked, and so is
+                // the limit-order engine (limit_start_engine) and the
+                // channel-order poller (channel_orders_start_poller).
+                // An encrypted database stays closed until the frontend
+                // unlocks it (db_unlock).
+                if db::is_encrypted() {
+                    log::info!("[DB] Database is encrypted, waiting for unlock");
+                } else {
+                    db::init(None).map_err(|e| {
+                        log::error!("[DB] {}", e);
+                        e
+                    })?;
+                    prune_logs(app.handle());
+                }
 
                 // The agent runtime (provider + MCP + Node) isn't bundled in the
                 // installer — it's downloaded on demand into app data the first
@@ -190,9 +258,37 @@ fn main() {
             insert_channel_order,
             get_channel_orders,
             delete_channel_order,
+            update_channel_order_status,
+            channel_orders_start_poller,
+            channel_orders_stop_poller,
             // Mnemonic encryption commands
             store_encrypted_mnemonic,
-            get_decrypted_mnemonic,
+            validate_mnemonic,
+            generate_mnemonic,
+            change_password,
+            revert_password_change,
+            // Session vault
+            vault_status,
+            vault_unlock,
+            vault_lock,
+            vault_touch,
+            vault_unlock_node,
+            vault_sign_message,
+            vault_reveal_mnemonic,
+            // Database encryption
+            db_status,
+            db_unlock,
+            db_enable_encryption,
+            db_change_key,
+            // App settings
+            get_settings,
+            update_settings,
+            // App backup
+            backup_export,
+            backup_import,
+            // Data directory
+            get_data_directory,
+            move_data_directory,
             // New command
             is_local_node_supported,
             get_local_node_capabilities,
@@ -205,6 +301,16 @@ fn main() {
             dca_get_orders,
             dca_upsert_order,
             dca_delete_order,
+            dca_record_execution,
+            dca_get_executions,
+            dca_get_order_stats,
+            dca_export_executions_csv,
+            dca_preview_schedule,
+            dca_set_balances,
+            dca_get_spend_limits,
+            dca_set_spend_limits,
+            dca_backtest,
+            dca_status,
             // NWC commands
             nwc_get_status,
             nwc_service_npub,
@@ -218,6 +324,14 @@ fn main() {
             limit_get_orders,
             limit_upsert_order,
             limit_delete_order,
+            limit_start_engine,
+            limit_stop_engine,
+            limit_execute_order,
+            // Trade ledger
+            trades_record,
+            trades_update_status,
+            trades_list,
+            trades_totals,
             // Docker node commands
             check_docker_environment,
             is_docker_available,
@@ -225,6 +339,10 @@ fn main() {
             create_docker_environment,
             start_docker_node,
             stop_docker_node,
+            // kaleido-cli import/export
+            cli_scan_environments,
+            cli_import_environment,
+            cli_export_account,
             // KaleidoMind sidecar commands
             mind_start,
             mind_send,
@@ -387,7 +505,7 @@ fn stop_node(node_process: tauri::State<'_, Arc<Mutex<NodeProcess>>>) -> Result<
 
 #[tauri::command]
 fn get_accounts() -> Result<Vec<db::Account>, String> {
-    match db::get_accounts() {
+    match db::with(db::get_accounts) {
         Ok(configs) => Ok(configs),
         Err(e) => Err(e.to_string()),
     }
@@ -411,22 +529,25 @@ fn insert_account(
     bearer_token: Option<String>,
     language: Option<String>,
 ) -> Result<usize, String> {
-    match db::insert_account(
-        name,
-        network,
-        datapath,
-        rpc_connection_url,
-        node_url,
-        indexer_url,
-        proxy_endpoint,
-        default_lsp_url,
-        maker_urls,
-        default_maker_url,
-        daemon_listening_port,
-        ldk_peer_listening_port,
-        bearer_token,
-        language,
-    ) {
+    match db::with(|conn| {
+        db::insert_account(
+            conn,
+            name,
+            network,
+            datapath,
+            rpc_connection_url,
+            node_url,
+            indexer_url,
+            proxy_endpoint,
+            default_lsp_url,
+            maker_urls,
+            default_maker_url,
+            daemon_listening_port,
+            ldk_peer_listening_port,
+            bearer_token,
+            language,
+        )
+    }) {
         Ok(num_rows) => Ok(num_rows),
         Err(e) => Err(e.to_string()),
     }
@@ -450,22 +571,25 @@ fn update_account(
     bearer_token: Option<String>,
     language: Option<String>,
 ) -> Result<usize, String> {
-    match db::update_account(
-        name,
-        network,
-        datapath,
-        rpc_connection_url,
-        node_url,
-        indexer_url,
-        proxy_endpoint,
-        default_lsp_url,
-        maker_urls,
-        default_maker_url,
-        daemon_listening_port,
-        ldk_peer_listening_port,
-        bearer_token,
-        language,
-    ) {
+    match db::with(|conn| {
+        db::update_account(
+            conn,
+            name,
+            network,
+            datapath,
+            rpc_connection_url,
+            node_url,
+            indexer_url,
+            proxy_endpoint,
+            default_lsp_url,
+            maker_urls,
+            default_maker_url,
+            daemon_listening_port,
+            ldk_peer_listening_port,
+            bearer_token,
+            language,
+        )
+    }) {
         Ok(num_rows) => Ok(num_rows),
         Err(e) => Err(e.to_string()),
     }
@@ -485,7 +609,7 @@ fn delete_account(
         node_process.stop();
     }
 
-    match db::delete_account(name.clone()) {
+    match db::with(|conn| db::delete_account(conn, name.clone())) {
         Ok(num_rows) => {
             println!("Successfully deleted account: {}", name);
             Ok(num_rows)
@@ -499,7 +623,7 @@ fn delete_account(
 
 #[tauri::command]
 fn check_account_exists(name: String) -> Result<bool, String> {
-    match db::check_account_exists(&name) {
+    match db::with(|conn| db::check_account_exists(conn, &name)) {
         Ok(exists) => Ok(exists),
         Err(e) => Err(e.to_string()),
     }
@@ -508,14 +632,21 @@ fn check_account_exists(name: String) -> Result<bool, String> {
 #[tauri::command]
 fn set_current_account(
     state: tauri::State<CurrentAccount>,
+    vault: tauri::State<Arc<SessionVault>>,
     account_name: String,
 ) -> Result<db::Account, String> {
-    let accounts = db::get_accounts().map_err(|e| e.to_string())?;
+    let accounts = db::with(db::get_accounts).map_err(|e| e.to_string())?;
     let account = accounts
         .into_iter()
         .find(|a| a.name == account_name)
         .ok_or_else(|| "Account not found".to_string())?;
 
+    if vault
+        .account_name()
+        .is_some_and(|name| name != account.name)
+    {
+        vault.lock(vault::LockReason::AccountChanged);
+    }
     *state.0.write().unwrap() = Some(account.clone());
     Ok(account)
 }
@@ -527,7 +658,7 @@ fn get_current_account(state: tauri::State<CurrentAccount>) -> Option<db::Accoun
 
 #[tauri::command]
 fn get_account_by_name(name: String) -> Result<Option<db::Account>, String> {
-    match db::get_account_by_name(&name) {
+    match db::with(|conn| db::get_account_by_name(conn, &name)) {
         Ok(account) => Ok(account),
         Err(e) => Err(e.to_string()),
     }
@@ -774,6 +905,7 @@ fn kill_process_on_port(_port: u16) -> bool {
 
 #[tauri::command]
 fn insert_channel_order(
+    poller: tauri::State<'_, Arc<ChannelOrderPoller>>,
     state: tauri::State<CurrentAccount>,
     #[allow(non_snake_case)] orderId: String,
     status: String,
@@ -786,8 +918,91 @@ fn insert_channel_order(
         "No account is currently selected. Please select an account first.".to_string()
     })?;
 
-    db::insert_channel_order(account.id, orderId, status, payload, createdAt)
-        .map_err(|e| e.to_string())
+    // Orders are placed on the account's default LSP
+    let lsp_url = Some(account.default_maker_url.clone()).filter(|u| !u.is_empty());
+    let inserted = db::with(|conn| {
+        db::insert_channel_order(
+            conn,
+            account.id,
+            orderId,
+            status,
+            payload,
+            createdAt,
+            lsp_url,
+            channel_orders::unix_now(),
+        )
+    })
+    .map_err(|e| e.to_string())?;
+    poller.refresh();
+    Ok(inserted)
+}
+
+/// Store a new status of a channel order of the current account, as seen by
+/// the frontend, and emit the same events as the poller.
+#[tauri::command]
+fn update_channel_order_status(
+    poller: tauri::State<'_, Arc<ChannelOrderPoller>>,
+    state: tauri::State<CurrentAccount>,
+    order_id: String,
+    update: db::ChannelOrderUpdate,
+) -> Result<db::ChannelOrder, String> {
+    let current_account = state.0.read().unwrap();
+    let account = current_account
+        .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    if update.status.trim().is_empty() {
+        return Err("Order status is required".to_string());
+    }
+    let (before, after) = db::with(|conn| {
+        let before = db::get_channel_order(conn, account.id, &order_id)?;
+        db::update_channel_order_status(
+            conn,
+            account.id,
+            &order_id,
+            &update,
+            channel_orders::unix_now(),
+        )?;
+        Ok((before, db::get_channel_order(conn, account.id, &order_id)?))
+    })
+    .map_err(|e| e.to_string())?;
+    let (before, after) = before
+        .zip(after)
+        .ok_or_else(|| format!("Channel order {} not found", order_id))?;
+    poller.emit(channel_orders::UPDATED_EVENT, &after);
+    if let Some(event) = channel_orders::lifecycle_event(&before, &update) {
+        poller.emit(event, &after);
+    }
+    Ok(after)
+}
+
+/// Start refreshing the current account's pending channel orders from its
+/// LSP (called when the node becomes unlocked).
+#[tauri::command]
+async fn channel_orders_start_poller(
+    poller: tauri::State<'_, Arc<ChannelOrderPoller>>,
+    state: tauri::State<'_, CurrentAccount>,
+) -> Result<(), String> {
+    let config = {
+        let current = state.0.read().unwrap();
+        let account = current
+            .as_ref()
+            .ok_or_else(|| "No account selected".to_string())?;
+        channel_orders::PollerConfig {
+            account_id: account.id,
+            lsp_url: account.default_maker_url.clone(),
+        }
+    };
+    poller.start(config).await;
+    Ok(())
+}
+
+/// Stop the channel-order poller (called when the node is locked/stopped).
+#[tauri::command]
+async fn channel_orders_stop_poller(
+    poller: tauri::State<'_, Arc<ChannelOrderPoller>>,
+) -> Result<(), String> {
+    poller.stop().await;
+    Ok(())
 }
 
 #[tauri::command]
@@ -798,7 +1013,7 @@ fn get_channel_orders(
     let current_account = state.0.read().unwrap();
     let account_id = current_account.as_ref().map(|account| account.id);
 
-    db::get_channel_orders(account_id).map_err(|e| e.to_string())
+    db::with(|conn| db::get_channel_orders(conn, account_id)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -812,7 +1027,7 @@ fn delete_channel_order(
         "No account is currently selected. Please select an account first.".to_string()
     })?;
 
-    db::delete_channel_order(account.id, orderId).map_err(|e| e.to_string())
+    db::with(|conn| db::delete_channel_order(conn, account.id, orderId)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -880,22 +1095,73 @@ async fn get_markdown_content(app: tauri::AppHandle, file_path: String) -> Resul
 /// Store encrypted mnemonic for an account
 ///
 /// This command encrypts the mnemonic using the user's password and stores it securely
-/// in the database. The encryption uses AES-256-GCM with Argon2id key derivation.
+/// in the database. The encryption uses AES-256-GCM with Argon2id key derivation, with
+/// parameters calibrated to this machine (see `crypto`). The mnemonic must be a valid
+/// BIP39 phrase (see `mnemonic`); an optional BIP39 passphrase is stored with it.
 #[tauri::command]
-fn store_encrypted_mnemonic(
+async fn store_encrypted_mnemonic(
     account_name: String,
     mnemonic: String,
     password: String,
+    passphrase: Option<String>,
 ) -> Result<(), String> {
-    // Encrypt the mnemonic
-    let (encrypted, salt, nonce) = crypto::encrypt_mnemonic(&mnemonic, &password)
-        .map_err(|e| format!("Failed to encrypt mnemonic: {}", e))?;
-
-    // Store in database
-    db::store_encrypted_mnemonic(&account_name, &encrypted, &salt, &nonce)
+    tauri::async_runtime::spawn_blocking(move || {
+        let account = db::with(|conn| db::get_account_by_name(conn, &account_name))
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Account not found".to_string())?;
+        let mnemonic =
+            mnemonic::validate(&mnemonic, mnemonic::language(account.language.as_deref()))?;
+
+        // Encrypt the mnemonic
+        let encrypted = crypto::encrypt_mnemonic(&mnemonic, &password)
+            .map_err(|e| format!("Failed to encrypt mnemonic: {}", e))?;
+        let encrypted_passphrase = passphrase
+            .filter(|p| !p.is_empty())
+            .map(|p| crypto::encrypt_passphrase(&p, &password))
+            .transpose()
+            .map_err(|e| format!("Failed to encrypt passphrase: {}", e))?;
+
+        // Store in database
+        db::with(|conn| {
+            db::store_encrypted_mnemonic(
+                conn,
+                &account_name,
+                &encrypted,
+                encrypted_passphrase.as_deref(),
+            )
+        })
         .map_err(|e| format!("Failed to store encrypted mnemonic: {}", e))?;
 
-    Ok(())
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The BIP39 wordlist of the current account's language.
+fn mnemonic_language(state: &CurrentAccount) -> bip39::Language {
+    let account = state.0.read().unwrap();
+    mnemonic::language(account.as_ref().and_then(|a| a.language.as_deref()))
+}
+
+/// Check a mnemonic against the BIP39 wordlist of the current account's language
+/// and its checksum. Errors name the first wrong word.
+#[tauri::command]
+fn validate_mnemonic(state: tauri::State<CurrentAccount>, mnemonic: String) -> Result<(), String> {
+    mnemonic::validate(&mnemonic, mnemonic_language(&state)).map(|_| ())
+}
+
+/// Generate a BIP39 mnemonic from OS entropy, in the current account's language.
+#[tauri::command]
+fn generate_mnemonic(
+    state: tauri::State<CurrentAccount>,
+    word_count: Option<usize>,
+) -> Result<String, String> {
+    mnemonic::generate(
+        mnemonic_language(&state),
+        word_count.unwrap_or(mnemonic::DEFAULT_WORD_COUNT),
+    )
+    .map(|phrase| phrase.to_string())
 }
 
 #[tauri::command]
@@ -904,7 +1170,7 @@ fn dca_get_orders(state: tauri::State<CurrentAccount>) -> Result<Vec<String>, St
     let account = current_account
         .as_ref()
         .ok_or_else(|| "No account selected".to_string())?;
-    db::get_dca_orders(account.id).map_err(|e| e.to_string())
+    db::with(|conn| db::get_dca_orders(conn, account.id)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -917,7 +1183,8 @@ fn dca_upsert_order(
     let account = current_account
         .as_ref()
         .ok_or_else(|| "No account selected".to_string())?;
-    db::upsert_dca_order(account.id, order_id, payload).map_err(|e| e.to_string())
+    db::with(|conn| db::upsert_dca_order(conn, account.id, order_id, payload))
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -929,7 +1196,81 @@ fn dca_delete_order(
     let account = current_account
         .as_ref()
         .ok_or_else(|| "No account selected".to_string())?;
-    db::delete_dca_order(account.id, order_id).map_err(|e| e.to_string())
+    db::with(|conn| db::delete_dca_order(conn, account.id, order_id)).map_err(|e| e.to_string())
+}
+
+/// Record a DCA execution attempt (success or failure) in the history table.
+#[tauri::command]
+fn dca_record_execution(
+    state: tauri::State<CurrentAccount>,
+    execution: db::DcaExecution,
+) -> Result<i64, String> {
+    let current_account = state.0.read().unwrap();
+    let account = current_account
+        .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    db::with(|conn| db::insert_dca_execution(conn, account.id, &execution))
+        .map_err(|e| e.to_string())
+}
+
+/// Execution history for the current account, optionally for a single order.
+#[tauri::command]
+fn dca_get_executions(
+    state: tauri::State<CurrentAccount>,
+    order_id: Option<String>,
+) -> Result<Vec<db::DcaExecution>, String> {
+    let current_account = state.0.read().unwrap();
+    let account = current_account
+        .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    db::with(|conn| db::get_dca_executions(conn, account.id, order_id.as_deref()))
+        .map_err(|e| e.to_string())
+}
+
+/// Average price, totals, execution count and failure rate for an order.
+#[tauri::command]
+fn dca_get_order_stats(
+    state: tauri::State<CurrentAccount>,
+    order_id: String,
+) -> Result<dca::DcaOrderStats, String> {
+    let current_account = state.0.read().unwrap();
+    let account = current_account
+        .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    let (executions, orders) = db::with(|conn| {
+        Ok((
+            db::get_dca_executions(conn, account.id, Some(&order_id))?,
+            db::get_dca_orders(conn, account.id)?,
+        ))
+    })
+    .map_err(|e| e.to_string())?;
+    let market = dca::order_markets(&orders)
+        .remove(&order_id)
+        .unwrap_or_default();
+    Ok(dca::compute_order_stats(&order_id, &market, &executions))
+}
+
+/// Write the execution history (all orders, or one) to a CSV file.
+#[tauri::command]
+fn dca_export_executions_csv(
+    state: tauri::State<CurrentAccount>,
+    file_path: String,
+    order_id: Option<String>,
+) -> Result<(), String> {
+    let current_account = state.0.read().unwrap();
+    let account = current_account
+        .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    let (executions, orders) = db::with(|conn| {
+        Ok((
+            db::get_dca_executions(conn, account.id, order_id.as_deref())?,
+            db::get_dca_orders(conn, account.id)?,
+        ))
+    })
+    .map_err(|e| e.to_string())?;
+    let markets = dca::order_markets(&orders);
+    std::fs::write(&file_path, dca::executions_to_csv(&executions, &markets))
+        .map_err(|e| format!("Failed to write {}: {}", file_path, e))
 }
 
 // ---------------------------------------------------------------------------
@@ -969,11 +1310,14 @@ async fn nwc_start_service(
         )
     };
 
+    let relays = settings::Settings::load()
+        .map(|s| s.nwc_relays)
+        .unwrap_or_default();
     nwc.start(nwc::StartConfig {
         account_id,
         network,
         node_url,
-        relays: Vec::new(),
+        relays,
     })
     .await
 }
@@ -1016,13 +1360,13 @@ fn nwc_list_connections(
         .as_ref()
         .map(|a| a.id)
         .ok_or_else(|| "No account is currently selected.".to_string())?;
-    db::get_nwc_connections(account_id).map_err(|e| e.to_string())
+    db::with(|conn| db::get_nwc_connections(conn, account_id)).map_err(|e| e.to_string())
 }
 
 /// Enable or disable a connection without deleting it.
 #[tauri::command]
 fn nwc_set_connection_enabled(id: i32, enabled: bool) -> Result<usize, String> {
-    db::set_nwc_connection_enabled(id, enabled).map_err(|e| e.to_string())
+    db::with(|conn| db::set_nwc_connection_enabled(conn, id, enabled)).map_err(|e| e.to_string())
 }
 
 /// Revoke (delete) a connection.
@@ -1035,35 +1379,128 @@ fn nwc_revoke_connection(state: tauri::State<CurrentAccount>, id: i32) -> Result
         .as_ref()
         .map(|a| a.id)
         .ok_or_else(|| "No account is currently selected.".to_string())?;
-    db::delete_nwc_connection(account_id, id).map_err(|e| e.to_string())
+    db::with(|conn| db::delete_nwc_connection(conn, account_id, id)).map_err(|e| e.to_string())
 }
 
 /// Start the DCA scheduler (called when node becomes unlocked).
 #[tauri::command]
-fn dca_start_scheduler(scheduler: tauri::State<'_, Arc<DcaScheduler>>) {
-    scheduler.start();
+async fn dca_start_scheduler(scheduler: tauri::State<'_, Arc<DcaScheduler>>) -> Result<(), String> {
+    let settings = settings::Settings::load().unwrap_or_else(|e| {
+        log::warn!("[DCA] failed to load settings: {}", e);
+        settings::Settings::default()
+    });
+    scheduler.set_config(settings.dca);
+    scheduler.start().await;
+    Ok(())
 }
 
 /// Stop the DCA scheduler (called when node is locked/stopped).
 #[tauri::command]
-fn dca_stop_scheduler(scheduler: tauri::State<'_, Arc<DcaScheduler>>) {
-    scheduler.stop();
+async fn dca_stop_scheduler(scheduler: tauri::State<'_, Arc<DcaScheduler>>) -> Result<(), String> {
+    scheduler.stop().await;
+    Ok(())
+}
+
+/// Scheduler state and counters (ticks, triggers, price oracle health).
+#[tauri::command]
+async fn dca_status(
+    scheduler: tauri::State<'_, Arc<DcaScheduler>>,
+) -> Result<dca::DcaStatus, String> {
+    Ok(scheduler.status().await)
 }
 
 /// Update the DCA scheduler with the current list of active orders from the frontend.
 #[tauri::command]
-fn dca_set_orders(scheduler: tauri::State<'_, Arc<DcaScheduler>>, orders: Vec<DcaOrderInfo>) {
-    scheduler.set_orders(orders);
+fn dca_set_orders(
+    scheduler: tauri::State<'_, Arc<DcaScheduler>>,
+    state: tauri::State<CurrentAccount>,
+    orders: Vec<DcaOrderInfo>,
+) {
+    let account_id = state.0.read().unwrap().as_ref().map(|a| a.id);
+    scheduler.set_orders(account_id, orders);
+}
+
+/// Report spendable Lightning balances (per asset ticker) for the DCA
+/// pre-trade check.
+#[tauri::command]
+fn dca_set_balances(
+    scheduler: tauri::State<'_, Arc<DcaScheduler>>,
+    balances: HashMap<String, dca_pair::AssetAmount>,
+) {
+    scheduler.set_balances(balances);
+}
+
+#[tauri::command]
+fn dca_get_spend_limits() -> Result<dca_limits::DcaSpendLimits, String> {
+    dca_limits::DcaSpendLimits::load()
+}
+
+#[tauri::command]
+fn dca_set_spend_limits(limits: dca_limits::DcaSpendLimits) -> Result<(), String> {
+    limits.save()
 }
 
-/// Called by the frontend after a DCA execution to update last_executed_at timestamp.
+/// Replay an order definition over historical prices with the scheduler's
+/// trigger logic and compare it against a lump-sum purchase.
+#[tauri::command]
+async fn dca_backtest(
+    order: DcaOrderInfo,
+    history: dca_backtest::PriceHistory,
+    rearm_pct: Option<f64>,
+) -> Result<dca_backtest::BacktestReport, String> {
+    let prices = history.load(&order.pair).await?;
+    tauri::async_runtime::spawn_blocking(move || {
+        dca_backtest::run_backtest(order, &prices, rearm_pct)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// List the next `count` run times of a schedule, starting after `from`
+/// (unix seconds, defaults to now).
+#[tauri::command]
+fn dca_preview_schedule(
+    schedule: dca_schedule::DcaSchedule,
+    count: usize,
+    from: Option<u64>,
+) -> Result<Vec<dca_schedule::ScheduledRun>, String> {
+    schedule.validate()?;
+    let from = from.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    });
+    schedule.preview_runs(from, count.min(100))
+}
+
+/// Called by the frontend after a DCA execution to update last_executed_at
+/// timestamp, and to add the swap to the trade ledger when one was made.
 #[tauri::command]
 fn dca_order_executed(
     scheduler: tauri::State<'_, Arc<DcaScheduler>>,
+    state: tauri::State<CurrentAccount>,
     order_id: String,
     timestamp: u64,
-) {
-    scheduler.update_last_executed(&order_id, timestamp);
+    ladder_step: Option<usize>,
+    trade: Option<db::Trade>,
+) -> Result<(), String> {
+    scheduler.update_last_executed(&order_id, timestamp, ladder_step);
+    if let Some(trade) = trade {
+        let current_account = state.0.read().unwrap();
+        let account = current_account
+            .as_ref()
+            .ok_or_else(|| "No account selected".to_string())?;
+        record_trade(
+            account,
+            db::Trade {
+                source: "dca".to_string(),
+                order_id: Some(order_id),
+                ..trade
+            },
+        )?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
@@ -1072,11 +1509,12 @@ fn limit_get_orders(state: tauri::State<CurrentAccount>) -> Result<Vec<String>,
     let account = current_account
         .as_ref()
         .ok_or_else(|| "No account selected".to_string())?;
-    db::get_limit_orders(account.id).map_err(|e| e.to_string())
+    db::with(|conn| db::get_limit_orders(conn, account.id)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 fn limit_upsert_order(
+    engine: tauri::State<'_, Arc<LimitOrderEngine>>,
     state: tauri::State<CurrentAccount>,
     order_id: String,
     payload: String,
@@ -1085,11 +1523,16 @@ fn limit_upsert_order(
     let account = current_account
         .as_ref()
         .ok_or_else(|| "No account selected".to_string())?;
-    db::upsert_limit_order(account.id, order_id, payload).map_err(|e| e.to_string())
+    let row = limit_orders::order_row(order_id, payload)?;
+    let updated = db::with(|conn| db::upsert_limit_order(conn, account.id, &row))
+        .map_err(|e| e.to_string())?;
+    engine.reload();
+    Ok(updated)
 }
 
 #[tauri::command]
 fn limit_delete_order(
+    engine: tauri::State<'_, Arc<LimitOrderEngine>>,
     state: tauri::State<CurrentAccount>,
     order_id: String,
 ) -> Result<usize, String> {
@@ -1097,38 +1540,649 @@ fn limit_delete_order(
     let account = current_account
         .as_ref()
         .ok_or_else(|| "No account selected".to_string())?;
-    db::delete_limit_order(account.id, order_id).map_err(|e| e.to_string())
+    let deleted = db::with(|conn| db::delete_limit_order(conn, account.id, order_id))
+        .map_err(|e| e.to_string())?;
+    engine.reload();
+    Ok(deleted)
 }
 
-/// Retrieve and decrypt mnemonic for an account
-///
-/// This command retrieves the encrypted mnemonic from the database and decrypts it
-/// using the provided password. Returns an error if the password is incorrect.
+/// Start the limit-order engine for the current account (called when the
+/// node becomes unlocked). Trades on the account's default maker.
+#[tauri::command]
+async fn limit_start_engine(
+    engine: tauri::State<'_, Arc<LimitOrderEngine>>,
+    state: tauri::State<'_, CurrentAccount>,
+) -> Result<(), String> {
+    let config = {
+        let current = state.0.read().unwrap();
+        let account = current
+            .as_ref()
+            .ok_or_else(|| "No account selected".to_string())?;
+        if account.default_maker_url.is_empty() {
+            return Err("No maker configured for this account".to_string());
+        }
+        limit_orders::EngineConfig {
+            account_id: account.id,
+            node_url: account.node_url.clone(),
+            maker_url: account.default_maker_url.clone(),
+            bearer_token: account.bearer_token.clone(),
+        }
+    };
+    engine.start(config).await;
+    Ok(())
+}
+
+/// Stop the limit-order engine (called when the node is locked/stopped).
 #[tauri::command]
-fn get_decrypted_mnemonic(
+async fn limit_stop_engine(engine: tauri::State<'_, Arc<LimitOrderEngine>>) -> Result<(), String> {
+    engine.stop().await;
+    Ok(())
+}
+
+/// Execute an active limit order now, at the current price (slippage is
+/// still checked against the limit).
+#[tauri::command]
+fn limit_execute_order(engine: tauri::State<'_, Arc<LimitOrderEngine>>, order_id: String) {
+    engine.execute_now(order_id);
+}
+
+/// Add a trade to the ledger of `account`. Trades without a maker URL were
+/// made on the account's default maker.
+fn record_trade(account: &db::Account, mut trade: db::Trade) -> Result<i64, String> {
+    trade.validate()?;
+    if trade.maker_url.is_empty() {
+        trade.maker_url = account.default_maker_url.clone();
+    }
+    db::with(|conn| db::insert_trade(conn, account.id, &trade)).map_err(|e| e.to_string())
+}
+
+/// Record a swap made from the trade page.
+#[tauri::command]
+fn trades_record(state: tauri::State<CurrentAccount>, trade: db::Trade) -> Result<i64, String> {
+    let current_account = state.0.read().unwrap();
+    let account = current_account
+        .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    record_trade(account, trade)
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TradeStatusUpdate {
+    swap_id: String,
+    status: String,
+    error: Option<String>,
+}
+
+/// Settle pending trades from the node's swap statuses. Returns how many
+/// trades changed.
+#[tauri::command]
+fn trades_update_status(
     state: tauri::State<CurrentAccount>,
-    password: String,
-) -> Result<String, String> {
-    // Get current account
+    updates: Vec<TradeStatusUpdate>,
+) -> Result<usize, String> {
     let current_account = state.0.read().unwrap();
     let account = current_account
         .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    if let Some(update) = updates
+        .iter()
+        .find(|u| !db::TRADE_STATUSES.contains(&u.status.as_str()))
+    {
+        return Err(format!("Invalid trade status '{}'", update.status));
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    db::with(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let mut changed = 0;
+        for update in &updates {
+            changed += db::update_trade_status(
+                &tx,
+                account.id,
+                &update.swap_id,
+                &update.status,
+                update.error.as_deref(),
+                now,
+            )?;
+        }
+        tx.commit()?;
+        Ok(changed)
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Trades of the current account, newest first.
+#[tauri::command]
+fn trades_list(
+    state: tauri::State<CurrentAccount>,
+    filter: Option<db::TradeFilter>,
+) -> Result<Vec<db::Trade>, String> {
+    let current_account = state.0.read().unwrap();
+    let account = current_account
+        .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    db::with(|conn| db::get_trades(conn, account.id, &filter.unwrap_or_default()))
+        .map_err(|e| e.to_string())
+}
+
+/// Per-asset totals of the succeeded trades created between `from` and `to`
+/// (unix seconds), for portfolio and tax reports.
+#[tauri::command]
+fn trades_totals(
+    state: tauri::State<CurrentAccount>,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> Result<Vec<db::TradeAssetTotal>, String> {
+    let current_account = state.0.read().unwrap();
+    let account = current_account
+        .as_ref()
+        .ok_or_else(|| "No account selected".to_string())?;
+    db::with(|conn| db::get_trade_totals(conn, account.id, from, to)).map_err(|e| e.to_string())
+}
+
+/// Change the password protecting the current account's secrets
+///
+/// The old password is checked by decrypting every secret, then all of them are
+/// encrypted again under the new one and stored in a single transaction. Nothing
+/// decrypted is sent back. Returns how many secrets were re-encrypted. The
+/// secrets as they were are kept until the next change, for
+/// `revert_password_change`.
+#[tauri::command]
+async fn change_password(
+    state: tauri::State<'_, CurrentAccount>,
+    vault: tauri::State<'_, Arc<SessionVault>>,
+    previous: tauri::State<'_, PreviousSecrets>,
+    old_password: String,
+    new_password: String,
+) -> Result<usize, String> {
+    if new_password.len() < 8 {
+        return Err("Password must be at least 8 characters".to_string());
+    }
+    let account_name = current_account_name(&state)?;
+    let vault = Arc::clone(&vault);
+    let name = account_name.clone();
+    let (count, secrets) = tauri::async_runtime::spawn_blocking(move || {
+        reseal_account_secrets(&vault, &name, &old_password, &new_password)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    *previous.0.lock().unwrap() = Some((account_name, secrets));
+    Ok(count)
+}
+
+/// Undo the last `change_password` of the current account when the node did
+/// not follow it: its secrets go back to exactly what they were, sealed under
+/// the previous password. `changed_password` must open them as they are now.
+#[tauri::command]
+async fn revert_password_change(
+    state: tauri::State<'_, CurrentAccount>,
+    vault: tauri::State<'_, Arc<SessionVault>>,
+    previous: tauri::State<'_, PreviousSecrets>,
+    changed_password: String,
+) -> Result<(), String> {
+    let account_name = current_account_name(&state)?;
+    let secrets = match previous.0.lock().unwrap().take() {
+        Some((name, secrets)) if name == account_name => secrets,
+        other => {
+            *previous.0.lock().unwrap() = other;
+            return Err("No password change to revert".to_string());
+        }
+    };
+    let vault = Arc::clone(&vault);
+    let name = account_name.clone();
+    let restore = secrets.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let current = db::with(|conn| db::get_account_secrets(conn, &name))
+            .map_err(|e| format!("Failed to retrieve secrets: {}", e))?;
+        if !opens_secrets(&current, &changed_password) {
+            return Err("Password is incorrect".to_string());
+        }
+        db::with(|conn| db::replace_account_secrets(conn, &name, &current, &restore))
+            .map_err(|e| format!("Failed to store secrets: {}", e))?;
+        // The session's keys were for the changed secrets
+        if vault.account_name().as_deref() == Some(name.as_str()) {
+            vault.lock(vault::LockReason::Manual);
+        }
+        log::info!("[Crypto] Restored the previous secrets of {}", name);
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if result.is_err() {
+        *previous.0.lock().unwrap() = Some((account_name, secrets));
+    }
+    result
+}
+
+// Whether `password` opens the account's secrets; true when it has none.
+fn opens_secrets(secrets: &db::AccountSecrets, password: &str) -> bool {
+    let envelope = match (&secrets.mnemonic, &secrets.passphrase) {
+        (Some(stored), _) => crypto::mnemonic_envelope(
+            &stored.encrypted,
+            stored
+                .legacy
+                .as_ref()
+                .map(|(salt, nonce)| (salt.as_str(), nonce.as_str())),
+        ),
+        (None, Some(passphrase)) => crypto::mnemonic_envelope(passphrase, None),
+        (None, None) => return true,
+    };
+    envelope.and_then(|e| e.open(password)).is_ok()
+}
+
+// Re-encrypt the account's secrets from `old_password` to `new_password` in
+// one transaction, and move an unlocked session over to the new password.
+// Returns how many were re-encrypted, and the secrets as they were before.
+fn reseal_account_secrets(
+    vault: &SessionVault,
+    account_name: &str,
+    old_password: &str,
+    new_password: &str,
+) -> Result<(usize, db::AccountSecrets), String> {
+    let current = db::with(|conn| db::get_account_secrets(conn, account_name))
+        .map_err(|e| format!("Failed to retrieve secrets: {}", e))?;
+
+    let mut sealed = db::AccountSecrets::default();
+    if let Some(stored) = &current.mnemonic {
+        let legacy = stored
+            .legacy
+            .as_ref()
+            .map(|(salt, nonce)| (salt.as_str(), nonce.as_str()));
+        let encrypted =
+            crypto::reencrypt_mnemonic(&stored.encrypted, legacy, old_password, new_password)
+                .map_err(|e| match e {
+                    crypto::CryptoError::DecryptionFailed(_) => {
+                        "Current password is incorrect".to_string()
+                    }
+                    e => e.to_string(),
+                })?;
+        sealed.mnemonic = Some(db::EncryptedMnemonic {
+            encrypted,
+            legacy: None,
+        });
+    }
+    if let Some(encrypted) = &current.passphrase {
+        sealed.passphrase = Some(
+            crypto::reencrypt_passphrase(encrypted, old_password, new_password).map_err(
+                |e| match e {
+                    crypto::CryptoError::DecryptionFailed(_) => {
+                        "Current password is incorrect".to_string()
+                    }
+                    e => e.to_string(),
+                },
+            )?,
+        );
+    }
+    let count = sealed.mnemonic.iter().count() + sealed.passphrase.iter().count();
+
+    db::with(|conn| db::replace_account_secrets(conn, account_name, &current, &sealed))
+        .map_err(|e| format!("Failed to store secrets: {}", e))?;
+    log::info!(
+        "[Crypto] Re-encrypted {} secret(s) of {} under a new password",
+        count,
+        account_name
+    );
+    // An unlocked session moves to the new password
+    if vault.account_name().as_deref() == Some(account_name) {
+        if let Err(e) = vault.unlock(account_name, &sealed, new_password) {
+            log::warn!("[Vault] {}", e);
+            vault.lock(vault::LockReason::Manual);
+        }
+    }
+    Ok((count, current))
+}
+
+fn vault_idle_timeout(settings: &settings::Settings) -> std::time::Duration {
+    std::time::Duration::from_secs(u64::from(settings.vault_idle_timeout_mins) * 60)
+}
+
+fn current_account_name(state: &CurrentAccount) -> Result<String, String> {
+    state
+        .0
+        .read()
+        .unwrap()
+        .as_ref()
+        .map(|account| account.name.clone())
+        .ok_or_else(|| "No account is currently selected.".to_string())
+}
+
+#[tauri::command]
+fn vault_status(vault: tauri::State<Arc<SessionVault>>) -> vault::VaultStatus {
+    vault.status()
+}
+
+/// Unlock the session vault for the current account
+///
+/// The password is checked against the account's stored secrets; accounts
+/// without any don't unlock. The vault keeps the keys it needs to sign until
+/// it locks; nothing decrypted is sent back.
+#[tauri::command]
+async fn vault_unlock(
+    state: tauri::State<'_, CurrentAccount>,
+    vault: tauri::State<'_, Arc<SessionVault>>,
+    password: String,
+) -> Result<vault::VaultStatus, String> {
+    let account_name = current_account_name(&state)?;
+    match settings::Settings::load() {
+        Ok(settings) => vault.set_idle_timeout(vault_idle_timeout(&settings)),
+        Err(e) => log::warn!("[Settings] {}", e),
+    }
+
+    let vault = Arc::clone(&vault);
+    tauri::async_runtime::spawn_blocking(move || {
+        let current = db::with(|conn| db::get_account_secrets(conn, &account_name))
+            .map_err(|e| format!("Failed to retrieve secrets: {}", e))?;
+        let sealed = vault.unlock(&account_name, &current, &password)?;
+
+        if sealed.mnemonic.is_some() || sealed.passphrase.is_some() {
+            let stored = db::with(|conn| {
+                db::replace_account_secrets(conn, &account_name, &current, &sealed)
+            });
+            match stored {
+                Ok(_) => log::info!("[Crypto] Re-encrypted secrets of {}", account_name),
+                Err(e) => log::warn!(
+                    "[Crypto] Failed to re-encrypt secrets of {}: {}",
+                    account_name,
+                    e
+                ),
+            }
+        }
+        Ok(vault.status())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn vault_lock(vault: tauri::State<Arc<SessionVault>>) -> bool {
+    vault.lock(vault::LockReason::Manual)
+}
+
+/// Record user activity, postponing the idle lock.
+#[tauri::command]
+fn vault_touch(vault: tauri::State<Arc<SessionVault>>) {
+    vault.touch();
+}
+
+/// Unlock the current account's node with `password`. The vault doesn't
+/// keep the password, so the unlock screen passes it again. Errors have the
+/// shape of the node API's fetch errors.
+#[tauri::command]
+async fn vault_unlock_node(
+    state: tauri::State<'_, CurrentAccount>,
+    password: String,
+) -> Result<(), vault::NodeError> {
+    let account = state
+        .0
+        .read()
+        .unwrap()
+        .clone()
         .ok_or_else(|| "No account is currently selected.".to_string())?;
+    vault::unlock_node(&account, &password).await
+}
+
+/// The current account's recovery phrase, for the viewer
+///
+/// The vault must be unlocked and the password entered again; it is checked
+/// against the stored mnemonic.
+#[tauri::command]
+async fn vault_reveal_mnemonic(
+    state: tauri::State<'_, CurrentAccount>,
+    vault: tauri::State<'_, Arc<SessionVault>>,
+    password: String,
+) -> Result<String, String> {
+    let account_name = current_account_name(&state)?;
+    let vault = Arc::clone(&vault);
+    tauri::async_runtime::spawn_blocking(move || {
+        vault
+            .reveal_mnemonic(&account_name, &password)
+            .map(|mnemonic| mnemonic.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Sign `message` with the current account's Nostr key.
+#[tauri::command]
+async fn vault_sign_message(
+    state: tauri::State<'_, CurrentAccount>,
+    vault: tauri::State<'_, Arc<SessionVault>>,
+    message: String,
+) -> Result<vault::SignedMessage, String> {
+    let account_name = current_account_name(&state)?;
+    let vault = Arc::clone(&vault);
+    tauri::async_runtime::spawn_blocking(move || vault.sign_message(&account_name, &message))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbStatus {
+    pub encrypted: bool,
+    pub unlocked: bool,
+    /// Why a password change left the database closed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Whether the app database is encrypted and, if so, whether it has been
+/// unlocked yet. Nothing else can read the database before that.
+#[tauri::command]
+fn db_status() -> DbStatus {
+    DbStatus {
+        encrypted: db::is_encrypted(),
+        unlocked: db::is_open(),
+        error: db::reopen_error(),
+    }
+}
+
+/// Open an encrypted database with its password and run pending migrations.
+#[tauri::command]
+async fn db_unlock(app: AppHandle, password: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || db::init(Some(&password)))
+        .await
+        .map_err(|e| e.to_string())??;
+    prune_logs(&app);
+    Ok(())
+}
+
+/// Encrypt the plaintext database with `password`.
+#[tauri::command]
+async fn db_enable_encryption(password: String) -> Result<(), String> {
+    if password.len() < 8 {
+        return Err("Password must be at least 8 characters".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || db::change_key(None, Some(&password)))
+        .await
+        .map_err(|e| e.to_string())?
+}
 
-    // Retrieve encrypted mnemonic from database
-    let (encrypted, salt, nonce) = db::get_encrypted_mnemonic(&account.name)
-        .map_err(|e| format!("Failed to retrieve encrypted mnemonic: {}", e))?
-        .ok_or_else(|| "No mnemonic stored for this account.".to_string())?;
+/// Re-encrypt the database under a new password, or store it in plaintext
+/// again when `new_password` is not given.
+#[tauri::command]
+async fn db_change_key(
+    current_password: String,
+    new_password: Option<String>,
+) -> Result<(), String> {
+    if new_password.as_ref().is_some_and(|p| p.len() < 8) {
+        return Err("Password must be at least 8 characters".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        db::change_key(Some(&current_password), new_password.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn get_settings() -> Result<settings::Settings, String> {
+    settings::Settings::load()
+}
 
-    // Decrypt the mnemonic
-    let mnemonic = crypto::decrypt_mnemonic(&encrypted, &password, &salt, &nonce).map_err(|e| {
-        format!(
-            "Failed to decrypt mnemonic: {}. This usually means the password is incorrect.",
+/// Change some settings; `patch` holds only the fields to change. Running
+/// services pick up the result (NWC relays apply on the next service start)
+/// and `settings:changed` is emitted with it.
+#[tauri::command]
+fn update_settings(
+    app: AppHandle,
+    scheduler: tauri::State<'_, Arc<DcaScheduler>>,
+    vault: tauri::State<'_, Arc<SessionVault>>,
+    patch: serde_json::Value,
+) -> Result<settings::Settings, String> {
+    let settings =
+        db::with(|conn| Ok(settings::update(conn, &patch))).map_err(|e| e.to_string())??;
+    scheduler.set_config(settings.dca);
+    vault.set_idle_timeout(vault_idle_timeout(&settings));
+    prune_logs(&app);
+    if let Err(e) = app.emit(settings::CHANGED_EVENT, &settings) {
+        log::warn!(
+            "[Settings] Failed to emit {}: {}",
+            settings::CHANGED_EVENT,
             e
-        )
-    })?;
+        );
+    }
+    Ok(settings)
+}
+
+/// Delete log files older than the configured retention.
+fn prune_logs(app: &AppHandle) {
+    let retention_days = match settings::Settings::load() {
+        Ok(settings) => settings.log_retention_days,
+        Err(e) => {
+            log::warn!("[Settings] {}", e);
+            return;
+        }
+    };
+    let dir = match data_dir::layout() {
+        Ok(layout) if layout.is_custom() => Ok(layout.log_dir),
+        _ => app.path().app_log_dir(),
+    };
+    let dir = match dir {
+        Ok(dir) => dir,
+        Err(e) => {
+            log::warn!("[Settings] No log directory: {}", e);
+            return;
+        }
+    };
+    match settings::prune_logs(&dir, retention_days) {
+        Ok(0) => {}
+        Ok(n) => log::info!(
+            "[Settings] Removed {} log file(s) older than {} days",
+            n,
+            retention_days
+        ),
+        Err(e) => log::warn!("[Settings] Failed to prune logs: {}", e),
+    }
+}
+
+/// Write an encrypted backup of all app data to `path`, optionally with the
+/// node data directories (the node must be stopped for a consistent copy).
+#[tauri::command]
+async fn backup_export(
+    node_process: tauri::State<'_, Arc<Mutex<NodeProcess>>>,
+    path: String,
+    password: String,
+    include_node_data: bool,
+) -> Result<backup::BackupManifest, String> {
+    if include_node_data && node_process.lock().unwrap().is_running() {
+        return Err("Stop the node before including its data in a backup".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let node_root = match include_node_data {
+            true => Some(rgb_node::node_data_root()?),
+            false => None,
+        };
+        db::with(|conn| {
+            Ok(backup::export(
+                conn,
+                std::path::Path::new(&path),
+                &password,
+                node_root.as_deref(),
+            ))
+        })
+        .map_err(|e| e.to_string())?
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Restore a backup made by [`backup_export`]. With `dry_run` nothing is
+/// written and the report previews what the import would do.
+#[tauri::command]
+async fn backup_import(
+    state: tauri::State<'_, CurrentAccount>,
+    node_process: tauri::State<'_, Arc<Mutex<NodeProcess>>>,
+    path: String,
+    password: String,
+    mode: backup::ImportMode,
+    dry_run: bool,
+) -> Result<backup::ImportReport, String> {
+    let replace = mode == backup::ImportMode::Replace;
+    if replace && !dry_run && node_process.lock().unwrap().is_running() {
+        return Err("Stop the node before replacing all app data".to_string());
+    }
+    let report = tauri::async_runtime::spawn_blocking(move || {
+        let archive = backup::read(std::path::Path::new(&path), &password)?;
+        let node_root = rgb_node::node_data_root()?;
+        db::with(|conn| {
+            Ok(backup::import(
+                conn,
+                &archive,
+                mode,
+                Some(&node_root),
+                dry_run,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // The selected account may no longer exist
+    if replace && report.applied {
+        *state.0.write().unwrap() = None;
+    }
+    Ok(report)
+}
 
-    Ok(mnemonic)
+/// Where the app keeps its database, node data and logs, and what chose it.
+#[tauri::command]
+fn get_data_directory() -> Result<data_dir::DataLayout, String> {
+    data_dir::layout()
+}
+
+/// Move the database, node data and logs to `path` and keep them there on
+/// later launches. The database is closed while its files are copied; the
+/// originals are deleted once it is open again at the new place. App logs
+/// follow on the next launch.
+#[tauri::command]
+async fn move_data_directory(
+    node_process: tauri::State<'_, Arc<Mutex<NodeProcess>>>,
+    path: String,
+) -> Result<data_dir::DataLayout, String> {
+    if node_process.lock().unwrap().is_running() {
+        return Err("Stop the node before moving the data directory".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let old = data_dir::layout()?;
+        let moved = db::with_closed(|| {
+            let moved = data_dir::copy_data(&old, std::path::Path::new(&path))?;
+            data_dir::switch_to(moved.layout.clone())?;
+            Ok(moved)
+        })?;
+        data_dir::remove_moved_data(&old, &moved);
+        log::info!(
+            "[DataDir] Moved app data from {} to {}",
+            old.root.display(),
+            moved.layout.root.display()
+        );
+        Ok(moved.layout)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 // ---------------------------------------------------------------------------
@@ -1182,3 +2236,152 @@ fn stop_docker_node(
     let dm = docker_manager.lock().unwrap();
     dm.stop()
 }
+
+// ---------------------------------------------------------------------------
+// kaleido-cli import/export commands
+// ---------------------------------------------------------------------------
+
+#[tauri::command]
+fn cli_scan_environments(dir: Option<String>) -> Result<Vec<kaleido_cli::CliEnvironment>, String> {
+    let dir = dir
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(docker_node::default_base_dir);
+    let mut envs = kaleido_cli::scan(&dir)?;
+    for env in &mut envs {
+        env.account_exists = db::with(|conn| db::check_account_exists(conn, &env.account_name))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(envs)
+}
+
+#[tauri::command]
+async fn cli_import_environment(
+    dir: Option<String>,
+    env_name: String,
+    account: kaleido_cli::CliImportAccount,
+) -> Result<db::Account, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let dir = dir
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(docker_node::default_base_dir);
+        let env = kaleido_cli::scan(&dir)?
+            .into_iter()
+            .find(|env| env.env_name == env_name)
+            .ok_or_else(|| format!("No environment {} in {}", env_name, dir.display()))?;
+        let daemon_port = env
+            .daemon_port
+            .ok_or_else(|| format!("Environment {} has no node", env_name))?;
+        if db::with(|conn| db::check_account_exists(conn, &account.name))
+            .map_err(|e| e.to_string())?
+        {
+            return Err(format!("Account {} already exists", account.name));
+        }
+
+        let (env_dir, copied) = kaleido_cli::import_environment(
+            &dir,
+            &env_name,
+            &account.name,
+            &docker_node::default_base_dir(),
+        )?;
+        // Docker accounts have an empty datapath
+        let inserted = db::with(|conn| {
+            db::insert_account(
+                conn,
+                account.name.clone(),
+                account.network.clone(),
+                Some(String::new()),
+                account.rpc_connection_url.clone(),
+                format!("http://127.0.0.1:{}", daemon_port),
+                account.indexer_url.clone(),
+                account.proxy_endpoint.clone(),
+                account.default_lsp_url.clone(),
+                account.maker_urls.clone(),
+                account.default_maker_url.clone(),
+                daemon_port.to_string(),
+                env.peer_port.unwrap_or(9735).to_string(),
+                None,
+                Some("en".to_string()),
+            )?;
+            db::get_account_by_name(conn, &account.name)
+        });
+        match inserted {
+            Ok(Some(created)) => {
+                log::info!(
+                    "[kaleido-cli] Imported {} as account {}",
+                    env_name,
+                    account.name
+                );
+                Ok(created)
+            }
+            result => {
+                if copied {
+                    let _ = std::fs::remove_dir_all(&env_dir);
+                }
+                Err(result
+                    .err()
+                    .map(|e| e.to_string())
+                    .unwrap_or_else(|| format!("Failed to import {}", env_name)))
+            }
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn cli_export_account(
+    node_process: tauri::State<'_, Arc<Mutex<NodeProcess>>>,
+    docker_manager: tauri::State<'_, Arc<Mutex<DockerNodeManager>>>,
+    account_name: String,
+    dir: String,
+) -> Result<String, String> {
+    let env_name = DockerNodeManager::format_env_name(&account_name);
+    let docker_running = {
+        let dm = docker_manager.lock().unwrap();
+        dm.is_running() && dm.get_current_environment().as_deref() == Some(env_name.as_str())
+    };
+    if docker_running
+        || node_process
+            .lock()
+            .unwrap()
+            .is_running_for_account(&account_name)
+    {
+        return Err("Stop the node before exporting the account".to_string());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let account = db::with(|conn| db::get_account_by_name(conn, &account_name))
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Account {} not found", account_name))?;
+        let dir = std::path::PathBuf::from(dir);
+        let exported = match account.datapath.as_deref().filter(|p| !p.is_empty()) {
+            None => kaleido_cli::export_environment(
+                &docker_node::default_base_dir().join(&env_name),
+                &dir,
+            )?,
+            Some(datapath) => {
+                let config = DockerSpawnConfig {
+                    name: env_name,
+                    count: 1,
+                    network: kaleido_cli::cli_network(&account.network),
+                    base_daemon_port: account.daemon_listening_port.parse().unwrap_or(3001),
+                    base_peer_port: account.ldk_peer_listening_port.parse().unwrap_or(9735),
+                    disable_authentication: account.bearer_token.is_none(),
+                };
+                kaleido_cli::export_native(
+                    &rgb_node::node_data_root()?.join(datapath),
+                    &config,
+                    &dir,
+                )?
+            }
+        };
+        log::info!(
+            "[kaleido-cli] Exported account {} to {}",
+            account_name,
+            exported.display()
+        );
+        Ok(exported.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
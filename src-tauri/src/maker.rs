@@ -0,0 +1,365 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Requests to the maker and the node time out after this long, so a stalled
+/// quote can't hold up the engine.
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+
+/// Layer of an asset when the maker doesn't list one.
+const DEFAULT_RGB_LAYER: &str = "RGB_LN";
+const BTC_LAYER: &str = "BTC_LN";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TradingLimits {
+    pub layer: String,
+    #[serde(default)]
+    pub min_amount: u64,
+    /// Largest single swap the maker accepts; 0 when unlimited.
+    #[serde(default)]
+    pub max_amount: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TradableAsset {
+    pub ticker: String,
+    pub precision: u32,
+    #[serde(default)]
+    pub endpoints: Vec<TradingLimits>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TradingPair {
+    pub id: String,
+    pub base: TradableAsset,
+    pub quote: TradableAsset,
+}
+
+#[derive(Debug, Deserialize)]
+struct PairsResponse {
+    pairs: Vec<TradingPair>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapLeg {
+    pub asset_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Quote {
+    pub rfq_id: String,
+    pub from_asset: SwapLeg,
+    pub to_asset: SwapLeg,
+    /// Price in raw units of the quote asset; divide by
+    /// `10^quote.precision` for the display price.
+    #[serde(default)]
+    pub price: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InitSwap {
+    pub swapstring: String,
+    pub payment_hash: String,
+}
+
+#[derive(Deserialize)]
+struct NodeInfo {
+    pubkey: String,
+}
+
+#[derive(Deserialize)]
+struct ChannelsResponse {
+    #[serde(default)]
+    channels: Vec<Channel>,
+}
+
+#[derive(Deserialize)]
+struct Channel {
+    #[serde(default)]
+    ready: bool,
+    #[serde(default)]
+    next_outbound_htlc_limit_msat: u64,
+    #[serde(default)]
+    inbound_balance_msat: u64,
+}
+
+#[derive(Deserialize)]
+struct AssetBalance {
+    #[serde(default)]
+    offchain_outbound: u64,
+    #[serde(default)]
+    offchain_inbound: u64,
+}
+
+/// How much of an asset the node's channels can send and receive right now,
+/// in the units swaps are quoted in (msat for BTC).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Capacity {
+    pub outbound: u64,
+    pub inbound: u64,
+}
+
+fn btc_capacity(channels: &[Channel]) -> Capacity {
+    let ready = channels.iter().filter(|c| c.ready);
+    Capacity {
+        // A single swap is one HTLC, so the largest one any channel can send.
+        outbound: ready
+            .clone()
+            .map(|c| c.next_outbound_htlc_limit_msat)
+            .max()
+            .unwrap_or(0),
+        inbound: ready.map(|c| c.inbound_balance_msat).sum(),
+    }
+}
+
+/// Layer to trade `asset_id` on: BTC always settles on Lightning, RGB assets
+/// on the first layer the maker lists for them.
+pub fn asset_layer(ticker: &str, asset_id: &str, pairs: &[TradingPair]) -> String {
+    if ticker.eq_ignore_ascii_case("BTC") || asset_id.eq_ignore_ascii_case("BTC") {
+        return BTC_LAYER.to_string();
+    }
+    pairs
+        .iter()
+        .flat_map(|p| [&p.base, &p.quote])
+        .find(|a| a.ticker == ticker)
+        .and_then(|a| a.endpoints.first())
+        .map(|e| e.layer.clone())
+        .unwrap_or_else(|| DEFAULT_RGB_LAYER.to_string())
+}
+
+/// Check that the swapstring returned by the maker is for exactly the trade
+/// we asked for. Format: `fromAmount/fromAsset/toAmount/toAsset/expiry/paymentHash`.
+pub fn validate_swapstring(
+    swapstring: &str,
+    from: (u64, &str),
+    to: (u64, &str),
+    payment_hash: &str,
+) -> Result<(), String> {
+    let parts: Vec<&str> = swapstring.split('/').collect();
+    if parts.len() != 6 {
+        return Err(format!("Invalid swap string format: {}", swapstring));
+    }
+    let same_asset = |a: &str, b: &str| {
+        // BTC is case-insensitive; RGB contract ids are not.
+        a == b || (a.eq_ignore_ascii_case("btc") && b.eq_ignore_ascii_case("btc"))
+    };
+    let matches = parts[0].parse::<u64>().ok() == Some(from.0)
+        && same_asset(parts[1], from.1)
+        && parts[2].parse::<u64>().ok() == Some(to.0)
+        && same_asset(parts[3], to.1)
+        && parts[5] == payment_hash;
+    if !matches {
+        return Err(format!(
+            "Swap string validation failed: expected {}/{}/{}/{}/{}, got {}",
+            from.0, from.1, to.0, to.1, payment_hash, swapstring
+        ));
+    }
+    Ok(())
+}
+
+/// Where a swap is, as the node reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum SwapStatus {
+    Waiting,
+    Pending,
+    Succeeded,
+    Expired,
+    Failed,
+}
+
+#[derive(Deserialize)]
+struct SwapResponse {
+    swap: SwapDetails,
+}
+
+#[derive(Deserialize)]
+struct SwapDetails {
+    status: SwapStatus,
+}
+
+/// Minimal client for the maker's market/swap API and the node endpoints a
+/// taker swap needs.
+#[derive(Clone)]
+pub struct MakerClient {
+    http: reqwest::Client,
+    maker_url: String,
+    node_url: String,
+    bearer_token: Option<String>,
+}
+
+impl MakerClient {
+    pub fn new(maker_url: &str, node_url: &str, bearer_token: Option<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_else(|_| reqwest::Client::new());
+        MakerClient {
+            http,
+            maker_url: maker_url.trim_end_matches('/').to_string(),
+            node_url: node_url.trim_end_matches('/').to_string(),
+            bearer_token: bearer_token.filter(|t| !t.is_empty()),
+        }
+    }
+
+    pub async fn pairs(&self) -> Result<Vec<TradingPair>, String> {
+        let url = format!("{}/api/v1/market/pairs", self.maker_url);
+        let resp: PairsResponse = send(self.http.get(&url), &url).await?;
+        Ok(resp.pairs)
+    }
+
+    /// Ask the maker for a quote; `from.amount` must be set.
+    pub async fn quote(&self, from: SwapLeg, to: SwapLeg) -> Result<Quote, String> {
+        let url = format!("{}/api/v1/market/quote", self.maker_url);
+        let body = serde_json::json!({ "from_asset": from, "to_asset": to });
+        send(self.http.post(&url).json(&body), &url).await
+    }
+
+    pub async fn init_swap(&self, quote: &Quote, from_amount: u64) -> Result<InitSwap, String> {
+        let url = format!("{}/api/v1/swaps/init", self.maker_url);
+        let body = serde_json::json!({
+            "from_amount": from_amount,
+            "from_asset": quote.from_asset.asset_id,
+            "rfq_id": quote.rfq_id,
+            "to_amount": quote.to_asset.amount.unwrap_or(0),
+            "to_asset": quote.to_asset.asset_id,
+        });
+        send(self.http.post(&url).json(&body), &url).await
+    }
+
+    pub async fn execute_swap(&self, swap: &InitSwap, taker_pubkey: &str) -> Result<(), String> {
+        let url = format!("{}/api/v1/swaps/execute", self.maker_url);
+        let body = serde_json::json!({
+            "payment_hash": swap.payment_hash,
+            "swapstring": swap.swapstring,
+            "taker_pubkey": taker_pubkey,
+        });
+        send::<serde_json::Value>(self.http.post(&url).json(&body), &url).await?;
+        Ok(())
+    }
+
+    /// Tell the node to accept the incoming leg of the swap.
+    pub async fn whitelist_trade(&self, swapstring: &str) -> Result<(), String> {
+        let url = format!("{}/taker", self.node_url);
+        let body = serde_json::json!({ "swapstring": swapstring });
+        send::<serde_json::Value>(self.node_auth(self.http.post(&url).json(&body)), &url).await?;
+        Ok(())
+    }
+
+    /// Status of the swap the node took as taker; `None` if it doesn't know
+    /// it (the swap was never whitelisted).
+    pub async fn swap_status(&self, payment_hash: &str) -> Result<Option<SwapStatus>, String> {
+        let url = format!("{}/getswap", self.node_url);
+        let body = serde_json::json!({ "payment_hash": payment_hash, "taker": true });
+        match send::<SwapResponse>(self.node_auth(self.http.post(&url).json(&body)), &url).await {
+            Ok(resp) => Ok(Some(resp.swap.status)),
+            Err(e) if e.to_lowercase().contains("not found") => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn node_pubkey(&self) -> Result<String, String> {
+        let url = format!("{}/nodeinfo", self.node_url);
+        let info: NodeInfo = send(self.node_auth(self.http.get(&url)), &url).await?;
+        Ok(info.pubkey)
+    }
+
+    /// Lightning capacity for `asset_id` ("BTC" or an RGB asset id).
+    pub async fn capacity(&self, asset_id: &str) -> Result<Capacity, String> {
+        if asset_id.eq_ignore_ascii_case("BTC") {
+            let url = format!("{}/listchannels", self.node_url);
+            let resp: ChannelsResponse = send(self.node_auth(self.http.get(&url)), &url).await?;
+            return Ok(btc_capacity(&resp.channels));
+        }
+        let url = format!("{}/assetbalance", self.node_url);
+        let body = serde_json::json!({ "asset_id": asset_id });
+        let balance: AssetBalance =
+            send(self.node_auth(self.http.post(&url).json(&body)), &url).await?;
+        Ok(Capacity {
+            outbound: balance.offchain_outbound,
+            inbound: balance.offchain_inbound,
+        })
+    }
+
+    fn node_auth(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.bearer_token {
+            Some(token) => req.bearer_auth(token),
+            None => req,
+        }
+    }
+}
+
+async fn send<T: DeserializeOwned>(req: reqwest::RequestBuilder, url: &str) -> Result<T, String> {
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("{} returned {}: {}", url, status, text));
+    }
+    // Some endpoints answer with an empty body.
+    let text = resp.text().await.map_err(|e| e.to_string())?;
+    let text = if text.trim().is_empty() {
+        "null"
+    } else {
+        &text
+    };
+    serde_json::from_str(text).map_err(|e| format!("Invalid response from {}: {}", url, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_swapstring() {
+        let s = "1000/BTC/25000/rgb:abc/1700000000/hash";
+        assert!(validate_swapstring(s, (1000, "btc"), (25000, "rgb:abc"), "hash").is_ok());
+        assert!(validate_swapstring(s, (1001, "BTC"), (25000, "rgb:abc"), "hash").is_err());
+        assert!(validate_swapstring(s, (1000, "BTC"), (25000, "rgb:ABC"), "hash").is_err());
+        assert!(validate_swapstring(s, (1000, "BTC"), (25000, "rgb:abc"), "other").is_err());
+        assert!(validate_swapstring("1000/BTC", (1000, "BTC"), (1, "x"), "hash").is_err());
+    }
+
+    #[test]
+    fn test_btc_capacity() {
+        let resp: ChannelsResponse = serde_json::from_value(serde_json::json!({
+            "channels": [
+                { "ready": true, "next_outbound_htlc_limit_msat": 5_000_000, "inbound_balance_msat": 1_000 },
+                { "ready": true, "next_outbound_htlc_limit_msat": 8_000_000, "inbound_balance_msat": 2_000 },
+                { "ready": false, "next_outbound_htlc_limit_msat": 9_000_000, "inbound_balance_msat": 4_000 }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            btc_capacity(&resp.channels),
+            Capacity {
+                outbound: 8_000_000,
+                inbound: 3_000
+            }
+        );
+    }
+
+    #[test]
+    fn test_asset_layer() {
+        let pairs: Vec<TradingPair> = serde_json::from_value(serde_json::json!([{
+            "id": "btc-usdt",
+            "base": { "ticker": "BTC", "precision": 8, "endpoints": [{ "layer": "BTC_LN" }] },
+            "quote": {
+                "ticker": "USDT",
+                "precision": 6,
+                "protocol_ids": { "RGB": "rgb:usdt" },
+                "endpoints": [{ "layer": "RGB_SPARK", "min_amount": 1000, "max_amount": 10000000 }]
+            }
+        }]))
+        .unwrap();
+        assert_eq!(asset_layer("BTC", "BTC", &pairs), "BTC_LN");
+        assert_eq!(asset_layer("USDT", "rgb:usdt", &pairs), "RGB_SPARK");
+        assert_eq!(asset_layer("XAUT", "rgb:xaut", &pairs), "RGB_LN");
+    }
+}
@@ -0,0 +1,755 @@
+//! Versioned schema migrations.
+//!
+//! Every change to the schema is a [`Migration`] appended to [`MIGRATIONS`].
+//! Applied migrations are recorded in `schema_version` together with a
+//! checksum of their statements, so an edited or unknown migration is
+//! reported instead of silently skipped. Each migration runs in its own
+//! transaction: it is either applied and recorded, or not at all.
+//!
+//! Never edit or reorder a migration that has shipped; add a new one.
+
+use rusqlite::{Connection, Transaction};
+use sha2::{Digest, Sha256};
+
+/// One statement of a migration.
+pub enum Step {
+    Sql(&'static str),
+    /// `ALTER TABLE .. ADD COLUMN`, skipped when the column is already there.
+    /// Databases created before versioning ran these unconditionally, so they
+    /// may have any of them already.
+    AddColumn {
+        table: &'static str,
+        column: &'static str,
+        definition: &'static str,
+    },
+}
+
+impl Step {
+    fn sql(&self) -> String {
+        match self {
+            Step::Sql(sql) => sql.to_string(),
+            Step::AddColumn {
+                table,
+                column,
+                definition,
+            } => format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+        }
+    }
+
+    fn apply(&self, tx: &Transaction) -> rusqlite::Result<()> {
+        if let Step::AddColumn { table, column, .. } = self {
+            if has_column(tx, table, column)? {
+                return Ok(());
+            }
+        }
+        tx.execute_batch(&self.sql())
+    }
+}
+
+pub struct Migration {
+    pub id: u32,
+    pub name: &'static str,
+    pub steps: &'static [Step],
+}
+
+impl Migration {
+    /// Hex SHA-256 of the migration's statements.
+    pub fn checksum(&self) -> String {
+        let mut hasher = Sha256::new();
+        for step in self.steps {
+            hasher.update(step.sql().as_bytes());
+            hasher.update(b";\n");
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// The schema, in order. Ids are consecutive starting at 1.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        id: 1,
+        name: "initial_schema",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS accounts (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    name TEXT NOT NULL,
+                    network TEXT NOT NULL,
+                    datapath TEXT,
+                    rpc_connection_url TEXT NOT NULL,
+                    node_url TEXT NOT NULL,
+                    indexer_url TEXT NOT NULL,
+                    proxy_endpoint TEXT NOT NULL,
+                    default_lsp_url TEXT NOT NULL,
+                    maker_urls TEXT NOT NULL,
+                    default_maker_url TEXT NOT NULL,
+                    daemon_listening_port TEXT NOT NULL,
+                    ldk_peer_listening_port TEXT NOT NULL,
+                    bearer_token TEXT,
+                    terms_accepted INTEGER DEFAULT 0
+                )",
+            ),
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS 'ChannelOrders' (
+                    'id' INTEGER PRIMARY KEY AUTOINCREMENT,
+                    'order_id' TEXT NOT NULL,
+                    'created_at' TEXT NOT NULL,
+                    'status' TEXT NOT NULL,
+                    'payload' TEXT NOT NULL
+                )",
+            ),
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS 'AppSettings' (
+                    'key' TEXT PRIMARY KEY NOT NULL,
+                    'value' TEXT NOT NULL
+                )",
+            ),
+        ],
+    },
+    Migration {
+        id: 2,
+        name: "account_mnemonic_and_language",
+        steps: &[
+            Step::AddColumn {
+                table: "Accounts",
+                column: "encrypted_mnemonic",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "Accounts",
+                column: "mnemonic_salt",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "Accounts",
+                column: "mnemonic_nonce",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "Accounts",
+                column: "language",
+                definition: "TEXT DEFAULT 'en'",
+            },
+        ],
+    },
+    Migration {
+        id: 3,
+        name: "channel_order_account",
+        steps: &[
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "account_id",
+                definition: "INTEGER REFERENCES Accounts(id) ON DELETE CASCADE",
+            },
+            // Orders from before accounts existed belong to the first one
+            Step::Sql(
+                "UPDATE ChannelOrders
+                 SET account_id = (SELECT id FROM Accounts ORDER BY id LIMIT 1)
+                 WHERE account_id IS NULL",
+            ),
+        ],
+    },
+    Migration {
+        id: 4,
+        name: "dca_orders",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS 'DcaOrders' (
+                    'id' INTEGER PRIMARY KEY AUTOINCREMENT,
+                    'account_id' INTEGER NOT NULL,
+                    'order_id' TEXT NOT NULL,
+                    'payload' TEXT NOT NULL,
+                    UNIQUE(account_id, order_id),
+                    FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
+                )",
+            ),
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS 'DcaExecutions' (
+                    'id' INTEGER PRIMARY KEY AUTOINCREMENT,
+                    'account_id' INTEGER NOT NULL,
+                    'order_id' TEXT NOT NULL,
+                    'executed_at' INTEGER NOT NULL,
+                    'quoted_price' REAL NOT NULL DEFAULT 0,
+                    'amount_in' REAL NOT NULL DEFAULT 0,
+                    'amount_out' INTEGER NOT NULL DEFAULT 0,
+                    'fee' INTEGER NOT NULL DEFAULT 0,
+                    'swap_id' TEXT,
+                    'status' TEXT NOT NULL,
+                    'error' TEXT,
+                    FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
+                )",
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_dca_executions_order
+                 ON DcaExecutions(account_id, order_id, executed_at)",
+            ),
+        ],
+    },
+    Migration {
+        id: 5,
+        name: "dca_execution_spend_asset",
+        steps: &[Step::AddColumn {
+            table: "DcaExecutions",
+            column: "spend_asset",
+            definition: "TEXT",
+        }],
+    },
+    Migration {
+        id: 6,
+        name: "limit_orders",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS 'LimitOrders' (
+                'id' INTEGER PRIMARY KEY AUTOINCREMENT,
+                'account_id' INTEGER NOT NULL,
+                'order_id' TEXT NOT NULL,
+                'payload' TEXT NOT NULL,
+                UNIQUE(account_id, order_id),
+                FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
+            )",
+        )],
+    },
+    Migration {
+        id: 7,
+        name: "limit_order_types",
+        steps: &[
+            Step::AddColumn {
+                table: "LimitOrders",
+                column: "order_type",
+                definition: "TEXT NOT NULL DEFAULT 'limit'",
+            },
+            Step::AddColumn {
+                table: "LimitOrders",
+                column: "linked_order_id",
+                definition: "TEXT",
+            },
+        ],
+    },
+    Migration {
+        id: 8,
+        name: "nwc_connections",
+        steps: &[Step::Sql(
+            "CREATE TABLE IF NOT EXISTS 'NwcConnections' (
+                'id' INTEGER PRIMARY KEY AUTOINCREMENT,
+                'account_id' INTEGER NOT NULL,
+                'name' TEXT NOT NULL,
+                'client_pubkey' TEXT NOT NULL,
+                'client_secret' TEXT NOT NULL,
+                'relays_json' TEXT NOT NULL,
+                'methods_json' TEXT NOT NULL,
+                'budget_msat' INTEGER,
+                'spent_msat' INTEGER NOT NULL DEFAULT 0,
+                'budget_renews_at' INTEGER,
+                'enabled' INTEGER NOT NULL DEFAULT 1,
+                'created_at' INTEGER NOT NULL,
+                'last_used_at' INTEGER,
+                UNIQUE(account_id, client_pubkey),
+                FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
+            )",
+        )],
+    },
+    Migration {
+        id: 9,
+        name: "typed_settings",
+        steps: &[
+            // The DCA scheduler timing becomes the `dca` field of the
+            // settings document
+            Step::Sql(
+                "INSERT OR IGNORE INTO AppSettings (key, value)
+                 SELECT 'settings', json_object('dca', json(value))
+                 FROM AppSettings
+                 WHERE key = 'dca_scheduler_config' AND json_valid(value)",
+            ),
+            Step::Sql("DELETE FROM AppSettings WHERE key = 'dca_scheduler_config'"),
+        ],
+    },
+    Migration {
+        id: 10,
+        name: "trades",
+        steps: &[
+            Step::Sql(
+                "CREATE TABLE IF NOT EXISTS 'Trades' (
+                    'id' INTEGER PRIMARY KEY AUTOINCREMENT,
+                    'account_id' INTEGER NOT NULL,
+                    'source' TEXT NOT NULL,
+                    'order_id' TEXT,
+                    'pair' TEXT NOT NULL,
+                    'side' TEXT NOT NULL,
+                    'from_asset' TEXT NOT NULL,
+                    'from_amount' REAL NOT NULL,
+                    'to_asset' TEXT NOT NULL,
+                    'to_amount' REAL NOT NULL,
+                    'price' REAL NOT NULL DEFAULT 0,
+                    'fee' REAL NOT NULL DEFAULT 0,
+                    'fee_asset' TEXT,
+                    'maker_url' TEXT NOT NULL,
+                    'swap_id' TEXT,
+                    'status' TEXT NOT NULL,
+                    'error' TEXT,
+                    'created_at' INTEGER NOT NULL,
+                    'updated_at' INTEGER NOT NULL,
+                    UNIQUE(account_id, swap_id),
+                    FOREIGN KEY(account_id) REFERENCES Accounts(id) ON DELETE CASCADE
+                )",
+            ),
+            Step::Sql(
+                "CREATE INDEX IF NOT EXISTS idx_trades_created
+                 ON Trades(account_id, created_at)",
+            ),
+        ],
+    },
+    Migration {
+        id: 11,
+        name: "channel_order_lifecycle",
+        steps: &[
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "lsp_url",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "access_token",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "client_balance_sat",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "lsp_balance_sat",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "asset_id",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "client_asset_amount",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "lsp_asset_amount",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "payment_state",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "fee_total_sat",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "order_total_sat",
+                definition: "INTEGER",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "expires_at",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "channel_id",
+                definition: "TEXT",
+            },
+            Step::AddColumn {
+                table: "ChannelOrders",
+                column: "updated_at",
+                definition: "INTEGER",
+            },
+            // Orders stored so far only have the request payload
+            Step::Sql(
+                "UPDATE ChannelOrders SET
+                    access_token = COALESCE(
+                        json_extract(payload, '$.access_token'),
+                        json_extract(payload, '$.token')
+                    ),
+                    client_balance_sat = json_extract(payload, '$.client_balance_sat'),
+                    lsp_balance_sat = json_extract(payload, '$.lsp_balance_sat'),
+                    asset_id = json_extract(payload, '$.asset_id'),
+                    client_asset_amount = json_extract(payload, '$.client_asset_amount'),
+                    lsp_asset_amount = json_extract(payload, '$.lsp_asset_amount')
+                 WHERE json_valid(payload)",
+            ),
+        ],
+    },
+    Migration {
+        id: 12,
+        name: "mnemonic_passphrase",
+        steps: &[Step::AddColumn {
+            table: "Accounts",
+            column: "encrypted_passphrase",
+            definition: "TEXT",
+        }],
+    },
+];
+
+const CREATE_SCHEMA_VERSION: &str = "CREATE TABLE IF NOT EXISTS schema_version (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    applied_at INTEGER NOT NULL
+)";
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+/// Applied migrations as `(id, name, checksum)`, oldest first.
+fn applied(conn: &Connection) -> Result<Vec<(u32, String, String)>, String> {
+    conn.execute_batch(CREATE_SCHEMA_VERSION)
+        .map_err(|e| format!("Failed to create schema_version: {}", e))?;
+    let mut stmt = conn
+        .prepare("SELECT id, name, checksum FROM schema_version ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(rows)
+}
+
+/// Migrations of `migrations` not yet applied to `conn`. Fails when the
+/// recorded history doesn't match: an applied migration this build doesn't
+/// know (a newer build ran), or one whose statements have changed.
+fn pending<'a>(
+    conn: &Connection,
+    migrations: &'a [Migration],
+) -> Result<Vec<&'a Migration>, String> {
+    let applied = applied(conn)?;
+    for (id, name, checksum) in &applied {
+        let migration = migrations.iter().find(|m| m.id == *id).ok_or_else(|| {
+            format!(
+                "Database has migration {} ({}) which this version doesn't know; it was opened by a newer version",
+                id, name
+            )
+        })?;
+        if migration.checksum() != *checksum {
+            return Err(format!(
+                "Checksum mismatch for applied migration {} ({})",
+                id, migration.name
+            ));
+        }
+    }
+    Ok(migrations
+        .iter()
+        .filter(|m| !applied.iter().any(|(id, _, _)| *id == m.id))
+        .collect())
+}
+
+/// Number of [`MIGRATIONS`] not yet applied to `conn`.
+pub fn pending_count(conn: &Connection) -> Result<usize, String> {
+    pending(conn, MIGRATIONS).map(|p| p.len())
+}
+
+/// Apply every pending migration in order. Returns how many were applied.
+pub fn run(conn: &mut Connection) -> Result<usize, String> {
+    apply(conn, MIGRATIONS)
+}
+
+fn apply(conn: &mut Connection, migrations: &[Migration]) -> Result<usize, String> {
+    let pending = pending(conn, migrations)?;
+    for migration in &pending {
+        let failed = |e: rusqlite::Error| {
+            format!(
+                "Migration {} ({}) failed: {}",
+                migration.id, migration.name, e
+            )
+        };
+        let tx = conn.transaction().map_err(failed)?;
+        for step in migration.steps {
+            step.apply(&tx).map_err(failed)?;
+        }
+        tx.execute(
+            "INSERT INTO schema_version (id, name, checksum, applied_at)
+             VALUES (?1, ?2, ?3, strftime('%s', 'now'))",
+            rusqlite::params![migration.id, migration.name, migration.checksum()],
+        )
+        .map_err(failed)?;
+        tx.commit().map_err(failed)?;
+        log::info!(
+            "[DB] Applied migration {} ({})",
+            migration.id,
+            migration.name
+        );
+    }
+    Ok(pending.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn tables(conn: &Connection) -> BTreeSet<String> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            )
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn columns(conn: &Connection, table: &str) -> BTreeSet<String> {
+        let mut stmt = conn
+            .prepare("SELECT name FROM pragma_table_info(?1)")
+            .unwrap();
+        stmt.query_map([table], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    fn schema(conn: &Connection) -> Vec<(String, BTreeSet<String>)> {
+        tables(conn)
+            .into_iter()
+            .map(|t| {
+                let cols = columns(conn, &t);
+                (t.to_lowercase(), cols)
+            })
+            .collect()
+    }
+
+    fn fresh() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_ids_are_consecutive() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.id as usize, i + 1, "{}", migration.name);
+        }
+    }
+
+    #[test]
+    fn test_from_empty_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(run(&mut conn).unwrap(), MIGRATIONS.len());
+        assert_eq!(pending_count(&conn).unwrap(), 0);
+        // Running again is a no-op
+        assert_eq!(run(&mut conn).unwrap(), 0);
+
+        assert!(columns(&conn, "Accounts").contains("language"));
+        assert!(columns(&conn, "Accounts").contains("encrypted_passphrase"));
+        assert!(columns(&conn, "ChannelOrders").contains("account_id"));
+        assert!(columns(&conn, "DcaExecutions").contains("spend_asset"));
+        assert!(columns(&conn, "LimitOrders").contains("linked_order_id"));
+        assert!(tables(&conn).contains("NwcConnections"));
+    }
+
+    /// The first schema: accounts and channel orders without per-account
+    /// ownership.
+    const SNAPSHOT_EARLY: &str = "
+        CREATE TABLE accounts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL,
+            network TEXT NOT NULL, datapath TEXT, rpc_connection_url TEXT NOT NULL,
+            node_url TEXT NOT NULL, indexer_url TEXT NOT NULL,
+            proxy_endpoint TEXT NOT NULL, default_lsp_url TEXT NOT NULL,
+            maker_urls TEXT NOT NULL, default_maker_url TEXT NOT NULL,
+            daemon_listening_port TEXT NOT NULL, ldk_peer_listening_port TEXT NOT NULL,
+            bearer_token TEXT, terms_accepted INTEGER DEFAULT 0);
+        CREATE TABLE 'ChannelOrders' ('id' INTEGER PRIMARY KEY AUTOINCREMENT,
+            'order_id' TEXT NOT NULL, 'created_at' TEXT NOT NULL,
+            'status' TEXT NOT NULL, 'payload' TEXT NOT NULL);
+        INSERT INTO accounts (name, network, rpc_connection_url, node_url,
+            indexer_url, proxy_endpoint, default_lsp_url, maker_urls,
+            default_maker_url, daemon_listening_port, ldk_peer_listening_port)
+            VALUES ('main', 'Regtest', '', '', '', '', '', '', '', '3001', '9735');
+        INSERT INTO ChannelOrders (order_id, created_at, status, payload)
+            VALUES ('o1', '2024-01-01', 'paid', '{}');
+    ";
+
+    /// The last unversioned schema, with every column the old unconditional
+    /// `ALTER TABLE`s added.
+    const SNAPSHOT_UNVERSIONED: &str = "
+        CREATE TABLE accounts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL,
+            network TEXT NOT NULL, datapath TEXT, rpc_connection_url TEXT NOT NULL,
+            node_url TEXT NOT NULL, indexer_url TEXT NOT NULL,
+            proxy_endpoint TEXT NOT NULL, default_lsp_url TEXT NOT NULL,
+            maker_urls TEXT NOT NULL, default_maker_url TEXT NOT NULL,
+            daemon_listening_port TEXT NOT NULL, ldk_peer_listening_port TEXT NOT NULL,
+            bearer_token TEXT, terms_accepted INTEGER DEFAULT 0,
+            encrypted_mnemonic TEXT, mnemonic_salt TEXT, mnemonic_nonce TEXT,
+            language TEXT DEFAULT 'en');
+        CREATE TABLE 'ChannelOrders' ('id' INTEGER PRIMARY KEY AUTOINCREMENT,
+            'account_id' INTEGER NOT NULL, 'order_id' TEXT NOT NULL,
+            'created_at' TEXT NOT NULL, 'status' TEXT NOT NULL,
+            'payload' TEXT NOT NULL);
+        CREATE TABLE 'DcaOrders' ('id' INTEGER PRIMARY KEY AUTOINCREMENT,
+            'account_id' INTEGER NOT NULL, 'order_id' TEXT NOT NULL,
+            'payload' TEXT NOT NULL, UNIQUE(account_id, order_id));
+        CREATE TABLE 'DcaExecutions' ('id' INTEGER PRIMARY KEY AUTOINCREMENT,
+            'account_id' INTEGER NOT NULL, 'order_id' TEXT NOT NULL,
+            'executed_at' INTEGER NOT NULL, 'quoted_price' REAL NOT NULL DEFAULT 0,
+            'amount_in' REAL NOT NULL DEFAULT 0, 'amount_out' INTEGER NOT NULL DEFAULT 0,
+            'fee' INTEGER NOT NULL DEFAULT 0, 'swap_id' TEXT, 'status' TEXT NOT NULL,
+            'error' TEXT, spend_asset TEXT);
+        CREATE TABLE 'LimitOrders' ('id' INTEGER PRIMARY KEY AUTOINCREMENT,
+            'account_id' INTEGER NOT NULL, 'order_id' TEXT NOT NULL,
+            'payload' TEXT NOT NULL, order_type TEXT NOT NULL DEFAULT 'limit',
+            linked_order_id TEXT, UNIQUE(account_id, order_id));
+        CREATE TABLE 'AppSettings' ('key' TEXT PRIMARY KEY NOT NULL, 'value' TEXT NOT NULL);
+        INSERT INTO AppSettings (key, value) VALUES ('node_backend', 'docker');
+    ";
+
+    #[test]
+    fn test_from_early_snapshot() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SNAPSHOT_EARLY).unwrap();
+        assert_eq!(run(&mut conn).unwrap(), MIGRATIONS.len());
+        assert_eq!(schema(&conn), schema(&fresh()));
+
+        let owner: i64 = conn
+            .query_row(
+                "SELECT account_id FROM ChannelOrders WHERE order_id = 'o1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(owner, 1);
+    }
+
+    #[test]
+    fn test_from_unversioned_snapshot() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SNAPSHOT_UNVERSIONED).unwrap();
+        assert_eq!(run(&mut conn).unwrap(), MIGRATIONS.len());
+        assert_eq!(schema(&conn), schema(&fresh()));
+
+        let backend: String = conn
+            .query_row(
+                "SELECT value FROM AppSettings WHERE key = 'node_backend'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(backend, "docker");
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        const BROKEN: &[Migration] = &[
+            Migration {
+                id: 1,
+                name: "ok",
+                steps: &[Step::Sql("CREATE TABLE a (id INTEGER)")],
+            },
+            Migration {
+                id: 2,
+                name: "broken",
+                steps: &[
+                    Step::Sql("CREATE TABLE b (id INTEGER)"),
+                    Step::Sql("INSERT INTO missing VALUES (1)"),
+                ],
+            },
+        ];
+        let mut conn = Connection::open_in_memory().unwrap();
+        let err = apply(&mut conn, BROKEN).unwrap_err();
+        assert!(err.starts_with("Migration 2 (broken) failed"), "{}", err);
+        assert!(tables(&conn).contains("a"));
+        assert!(!tables(&conn).contains("b"));
+        assert_eq!(pending(&conn, BROKEN).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_history_mismatch() {
+        let mut conn = fresh();
+        conn.execute(
+            "UPDATE schema_version SET checksum = 'edited' WHERE id = 3",
+            [],
+        )
+        .unwrap();
+        let err = run(&mut conn).unwrap_err();
+        assert!(err.contains("Checksum mismatch"), "{}", err);
+
+        let mut conn = fresh();
+        conn.execute(
+            "INSERT INTO schema_version VALUES (999, 'future', '', 0)",
+            [],
+        )
+        .unwrap();
+        let err = run(&mut conn).unwrap_err();
+        assert!(err.contains("newer version"), "{}", err);
+    }
+
+    #[test]
+    fn test_scheduler_config_moves_into_settings() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        apply(&mut conn, &MIGRATIONS[..8]).unwrap();
+        conn.execute(
+            "INSERT INTO AppSettings VALUES ('dca_scheduler_config', '{\"poll_interval_secs\":60}')",
+            [],
+        )
+        .unwrap();
+        run(&mut conn).unwrap();
+        let settings: String = conn
+            .query_row(
+                "SELECT value FROM AppSettings WHERE key = 'settings'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(settings, r#"{"dca":{"poll_interval_secs":60}}"#);
+        let legacy: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM AppSettings WHERE key = 'dca_scheduler_config'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(legacy, 0);
+    }
+
+    #[test]
+    fn test_channel_order_fields_from_payload() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        apply(&mut conn, &MIGRATIONS[..10]).unwrap();
+        conn.execute_batch(
+            "INSERT INTO ChannelOrders (order_id, created_at, status, payload)
+             VALUES ('o1', 't', 'paid',
+                     '{\"token\":\"tok\",\"lsp_balance_sat\":900000,\"client_balance_sat\":0}');
+             INSERT INTO ChannelOrders (order_id, created_at, status, payload)
+             VALUES ('o2', 't', 'paid', 'not json');",
+        )
+        .unwrap();
+        run(&mut conn).unwrap();
+        let (token, lsp_balance): (Option<String>, Option<i64>) = conn
+            .query_row(
+                "SELECT access_token, lsp_balance_sat FROM ChannelOrders WHERE order_id = 'o1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(token.as_deref(), Some("tok"));
+        assert_eq!(lsp_balance, Some(900_000));
+        let token: Option<String> = conn
+            .query_row(
+                "SELECT access_token FROM ChannelOrders WHERE order_id = 'o2'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(token, None);
+    }
+}
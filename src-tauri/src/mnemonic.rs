@@ -0,0 +1,136 @@
+//! BIP39 mnemonics: validation and generation.
+//!
+//! A mnemonic is checked against the wordlist of the account's language (its
+//! `language` column, an app language code) and its checksum before it is
+//! stored, so a mistyped phrase is never kept as the wallet's backup. A
+//! phrase that is valid in another wordlist is accepted too: the node writes
+//! English ones whatever the app's language.
+
+use bip39::{Language, Mnemonic};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+/// Word counts BIP39 allows.
+pub const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+pub const DEFAULT_WORD_COUNT: usize = 24;
+
+/// The BIP39 wordlist for an app language code (`en`, `zh-TW`, ...).
+/// English when the language has none.
+pub fn language(code: Option<&str>) -> Language {
+    let code = code.unwrap_or_default().to_lowercase();
+    let (lang, region) = code.split_once(['-', '_']).unwrap_or((&code, ""));
+    match (lang, region) {
+        ("zh", "tw" | "hk" | "hant") => Language::TraditionalChinese,
+        ("zh", _) => Language::SimplifiedChinese,
+        ("cs", _) => Language::Czech,
+        ("es", _) => Language::Spanish,
+        ("fr", _) => Language::French,
+        ("it", _) => Language::Italian,
+        ("ja", _) => Language::Japanese,
+        ("ko", _) => Language::Korean,
+        ("pt", _) => Language::Portuguese,
+        _ => Language::English,
+    }
+}
+
+fn bad_word_count(count: usize) -> String {
+    format!("A mnemonic has 12, 15, 18, 21 or 24 words, not {}", count)
+}
+
+fn describe(error: bip39::Error, phrase: &str, language: Language) -> String {
+    match error {
+        bip39::Error::BadWordCount(count) => bad_word_count(count),
+        bip39::Error::UnknownWord(i) => format!(
+            "Word {} ({}) is not in the {} wordlist",
+            i + 1,
+            phrase.split_whitespace().nth(i).unwrap_or_default(),
+            language
+        ),
+        bip39::Error::InvalidChecksum => {
+            "Invalid mnemonic checksum; check the words and their order".to_string()
+        }
+        e => e.to_string(),
+    }
+}
+
+/// Validate `phrase` in `language`, or in the wordlist it's written in.
+/// Returns it normalized: NFKD, single spaces.
+pub fn validate(phrase: &str, language: Language) -> Result<Zeroizing<String>, String> {
+    match Mnemonic::parse_in(language, phrase) {
+        Ok(mnemonic) => Ok(Zeroizing::new(mnemonic.to_string())),
+        Err(error) => Mnemonic::parse(phrase)
+            .map(|mnemonic| Zeroizing::new(mnemonic.to_string()))
+            .map_err(|_| describe(error, phrase, language)),
+    }
+}
+
+/// A new mnemonic of `word_count` words from OS entropy.
+pub fn generate(language: Language, word_count: usize) -> Result<Zeroizing<String>, String> {
+    if !WORD_COUNTS.contains(&word_count) {
+        return Err(bad_word_count(word_count));
+    }
+    // 32 bits of entropy per 3 words
+    let mut entropy = Zeroizing::new([0u8; 32]);
+    let entropy = &mut entropy[..word_count / 3 * 4];
+    OsRng.fill_bytes(entropy);
+    let mnemonic = Mnemonic::from_entropy_in(language, entropy).map_err(|e| e.to_string())?;
+    Ok(Zeroizing::new(mnemonic.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_language() {
+        assert_eq!(language(None), Language::English);
+        assert_eq!(language(Some("de")), Language::English);
+        assert_eq!(language(Some("ja")), Language::Japanese);
+        assert_eq!(language(Some("zh")), Language::SimplifiedChinese);
+        assert_eq!(language(Some("zh-TW")), Language::TraditionalChinese);
+        assert_eq!(language(Some("pt_BR")), Language::Portuguese);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            validate(&format!("  {}\n", MNEMONIC), Language::English)
+                .unwrap()
+                .as_str(),
+            MNEMONIC
+        );
+        // English phrases are accepted for any account language
+        assert!(validate(MNEMONIC, Language::Japanese).is_ok());
+
+        let typo = MNEMONIC.replace("about", "abuot");
+        let err = validate(&typo, Language::English).unwrap_err();
+        assert!(err.contains("Word 12 (abuot)"), "{}", err);
+        let swapped = MNEMONIC.replace("abandon about", "about abandon");
+        assert!(validate(&swapped, Language::English)
+            .unwrap_err()
+            .contains("checksum"));
+        assert!(validate("abandon abandon", Language::English)
+            .unwrap_err()
+            .contains("not 2"));
+    }
+
+    #[test]
+    fn test_generate() {
+        for language in [Language::English, Language::Japanese, Language::Spanish] {
+            for count in WORD_COUNTS {
+                let phrase = generate(language, count).unwrap();
+                assert_eq!(phrase.split_whitespace().count(), count);
+                let parsed = Mnemonic::parse_in(language, phrase.as_str()).unwrap();
+                assert_eq!(parsed.language(), language);
+            }
+        }
+        assert_ne!(
+            generate(Language::English, 12).unwrap(),
+            generate(Language::English, 12).unwrap()
+        );
+        assert!(generate(Language::English, 13).is_err());
+    }
+}
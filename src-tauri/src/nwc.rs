@@ -149,14 +149,20 @@ impl NwcManager {
         // Load (or generate + persist) the service keypair. It's a random Nostr
         // identity independent of the wallet seed, so this works for every
         // account type — including remote-node accounts with no stored mnemonic.
-        let keys = match db::get_nwc_service_secret(cfg.account_id) {
+        let keys = match db::with(|conn| db::get_nwc_service_secret(conn, cfg.account_id)) {
             Ok(Some(hex)) => {
                 Keys::parse(&hex).map_err(|e| format!("Stored NWC service key is invalid: {e}"))?
             }
             _ => {
                 let generated = Keys::generate();
-                db::set_nwc_service_secret(cfg.account_id, &generated.secret_key().to_secret_hex())
-                    .map_err(|e| format!("Failed to persist NWC service key: {e}"))?;
+                db::with(|conn| {
+                    db::set_nwc_service_secret(
+                        conn,
+                        cfg.account_id,
+                        &generated.secret_key().to_secret_hex(),
+                    )
+                })
+                .map_err(|e| format!("Failed to persist NWC service key: {e}"))?;
                 generated
             }
         };
@@ -293,17 +299,20 @@ impl NwcManager {
         let methods_json = serde_json::to_string(methods).unwrap_or_else(|_| "[]".to_string());
         let relays_json = serde_json::to_string(&relays).unwrap_or_else(|_| "[]".to_string());
 
-        db::insert_nwc_connection(
-            account_id,
-            name,
-            &client_pubkey.to_hex(),
-            &client_secret.to_secret_hex(),
-            &relays_json,
-            &methods_json,
-            budget_msat,
-            None,
-            now_secs(),
-        )
+        db::with(|conn| {
+            db::insert_nwc_connection(
+                conn,
+                account_id,
+                name,
+                &client_pubkey.to_hex(),
+                &client_secret.to_secret_hex(),
+                &relays_json,
+                &methods_json,
+                budget_msat,
+                None,
+                now_secs(),
+            )
+        })
         .map_err(|e| format!("Failed to store NWC connection: {e}"))?;
 
         Ok(uri.to_string())
@@ -332,16 +341,17 @@ async fn handle_request(ctx: ServiceCtx, event: Event) {
     let client_hex = client_pubkey.to_hex();
 
     // Reset any spend budgets whose window has elapsed before authorizing.
-    let _ = db::reset_expired_nwc_budgets(now_secs());
+    let _ = db::with(|conn| db::reset_expired_nwc_budgets(conn, now_secs()));
 
     // Authorize: the author must be a known, enabled connection for this account.
-    let connections = match db::get_enabled_nwc_connections_for_account(ctx.account_id) {
-        Ok(c) => c,
-        Err(e) => {
-            log::error!("[NWC] db error loading connections: {e}");
-            return;
-        }
-    };
+    let connections =
+        match db::with(|conn| db::get_enabled_nwc_connections_for_account(conn, ctx.account_id)) {
+            Ok(c) => c,
+            Err(e) => {
+                log::error!("[NWC] db error loading connections: {e}");
+                return;
+            }
+        };
     let connection = match connections
         .into_iter()
         .find(|c| c.client_pubkey == client_hex)
@@ -394,7 +404,7 @@ async fn handle_request(ctx: ServiceCtx, event: Event) {
     }
 
     let now = now_secs();
-    let _ = db::touch_nwc_connection(&client_hex, now);
+    let _ = db::with(|conn| db::touch_nwc_connection(conn, &client_hex, now));
 
     if method_str == "get_info" {
         // Answer get_info with raw JSON so we can advertise this connection's
@@ -907,7 +917,9 @@ async fn rln_pay_invoice(
 
     // Record spend against the budget (best-effort; fees not exposed by RLN).
     if let Some(amt) = amount_msat {
-        let _ = db::add_nwc_spend(&connection.client_pubkey, amt as i64, now_secs());
+        let _ = db::with(|conn| {
+            db::add_nwc_spend(conn, &connection.client_pubkey, amt as i64, now_secs())
+        });
     }
 
     Ok(nip47::PayInvoiceResponse {
@@ -967,7 +979,9 @@ async fn rln_pay_keysend(
         return Err(err(nip47::ErrorCode::PaymentFailed, "Keysend failed"));
     }
 
-    let _ = db::add_nwc_spend(&connection.client_pubkey, p.amount as i64, now_secs());
+    let _ = db::with(|conn| {
+        db::add_nwc_spend(conn, &connection.client_pubkey, p.amount as i64, now_secs())
+    });
 
     Ok(nip47::PayKeysendResponse {
         preimage: resp.payment_preimage,
@@ -1,6 +1,5 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::env;
 use std::fs::File;
 use std::io;
 use std::io::{BufRead, BufReader, Write};
@@ -16,6 +15,8 @@ use std::time::{Duration, Instant};
 use tauri::Manager;
 use tauri::{AppHandle, Emitter, WebviewWindow};
 
+use crate::data_dir;
+
 const SHUTDOWN_TIMEOUT_SECS: u64 = 5;
 const STARTUP_TIMEOUT_SECS: u64 = 30;
 const MAX_LOGS_IN_MEMORY: usize = 1000;
@@ -48,6 +49,12 @@ pub struct NodeProcess {
     daemon_port: Arc<Mutex<Option<u16>>>,
 }
 
+/// Directory holding each account's node data (the account `datapath` is
+/// relative to it).
+pub fn node_data_root() -> Result<PathBuf, String> {
+    Ok(data_dir::layout()?.node_dir)
+}
+
 impl NodeProcess {
     pub fn new() -> Self {
         let (tx, rx) = channel();
@@ -118,7 +125,9 @@ impl NodeProcess {
             if guard.as_ref().is_some() {
                 if let Ok(account_guard) = self.current_account.lock() {
                     if let Some(account) = account_guard.as_ref() {
-                        if let Ok(Some(account_info)) = crate::db::get_account_by_name(account) {
+                        if let Ok(Some(account_info)) =
+                            crate::db::with(|conn| crate::db::get_account_by_name(conn, account))
+                        {
                             ports.insert(account_info.daemon_listening_port, account.clone());
                             ports.insert(account_info.ldk_peer_listening_port, account.clone());
                         }
@@ -408,25 +417,7 @@ impl NodeProcess {
         self.set_state(NodeState::Starting);
 
         // 2) Build the final data path for the node
-        let app_data_dir = if cfg!(debug_assertions) {
-            println!("Debug mode: Using local bin directory");
-            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../bin")
-        } else if cfg!(target_os = "macos") {
-            println!("MacOS: Using Application Support directory");
-            let home =
-                env::var("HOME").map_err(|e| format!("Failed to get HOME directory: {}", e))?;
-            PathBuf::from(home).join("Library/Application Support/com.kaleidoswap.dev/data")
-        } else if cfg!(target_os = "windows") {
-            println!("Windows: Using LOCALAPPDATA directory");
-            let local_app_data = env::var("LOCALAPPDATA")
-                .map_err(|e| format!("Failed to get LOCALAPPDATA: {}", e))?;
-            PathBuf::from(local_app_data).join("com.kaleidoswap.dev/data")
-        } else {
-            println!("Linux: Using .local/share directory");
-            let home =
-                env::var("HOME").map_err(|e| format!("Failed to get HOME directory: {}", e))?;
-            PathBuf::from(home).join(".local/share/com.kaleidoswap.dev/data")
-        };
+        let app_data_dir = node_data_root()?;
 
         println!("App data directory: {:?}", app_data_dir);
 
@@ -911,26 +902,7 @@ impl NodeProcess {
 
     /// Returns the path to the log file
     fn get_log_file_path(&self) -> Result<PathBuf, String> {
-        let log_dir = if cfg!(debug_assertions) {
-            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("logs")
-        } else if cfg!(target_os = "macos") {
-            // macOS: ~/Library/Logs/com.kaleidoswap.dev/
-            let home =
-                env::var("HOME").map_err(|e| format!("Failed to get HOME directory: {}", e))?;
-            PathBuf::from(home).join("Library/Logs/com.kaleidoswap.dev")
-        } else if cfg!(target_os = "windows") {
-            // Windows: %APPDATA%\com.kaleidoswap.dev\logs
-            let app_data = env::var("APPDATA")
-                .map_err(|e| format!("Failed to get APPDATA directory: {}", e))?;
-            PathBuf::from(app_data)
-                .join("com.kaleidoswap.dev")
-                .join("logs")
-        } else {
-            // Linux: ~/.local/share/com.kaleidoswap.dev/logs
-            let home =
-                env::var("HOME").map_err(|e| format!("Failed to get HOME directory: {}", e))?;
-            PathBuf::from(home).join(".local/share/com.kaleidoswap.dev/logs")
-        };
+        let log_dir = data_dir::layout()?.log_dir;
 
         Ok(log_dir.join("rgb-lightning-node.log"))
     }
@@ -1136,26 +1108,7 @@ impl NodeProcess {
         }
 
         // Set up logging directory
-        let log_dir = if cfg!(debug_assertions) {
-            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("logs")
-        } else if cfg!(target_os = "macos") {
-            // macOS: ~/Library/Logs/com.kaleidoswap.dev/
-            let home =
-                env::var("HOME").map_err(|e| format!("Failed to get HOME directory: {}", e))?;
-            PathBuf::from(home).join("Library/Logs/com.kaleidoswap.dev")
-        } else if cfg!(target_os = "windows") {
-            // Windows: %APPDATA%\com.kaleidoswap.dev\logs
-            let app_data = env::var("APPDATA")
-                .map_err(|e| format!("Failed to get APPDATA directory: {}", e))?;
-            PathBuf::from(app_data)
-                .join("com.kaleidoswap.dev")
-                .join("logs")
-        } else {
-            // Linux: ~/.local/share/com.kaleidoswap.dev/logs
-            let home =
-                env::var("HOME").map_err(|e| format!("Failed to get HOME directory: {}", e))?;
-            PathBuf::from(home).join(".local/share/com.kaleidoswap.dev/logs")
-        };
+        let log_dir = data_dir::layout()?.log_dir;
 
         // Ensure log directory exists
         std::fs::create_dir_all(&log_dir)
@@ -0,0 +1,253 @@
+//! Typed app settings.
+//!
+//! [`Settings`] is kept as one JSON document in the [`SETTINGS_KEY`] row of
+//! `AppSettings`. Fields missing from the stored document take their
+//! defaults, so adding a setting needs no migration. Changes go through
+//! [`update`], which merges, validates and writes the document inside one
+//! immediate transaction, so concurrent updates of different fields don't
+//! overwrite each other.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::db;
+use crate::dca::DcaSchedulerConfig;
+use crate::node_backend::NodeBackendType;
+
+/// AppSettings key holding the JSON-encoded [`Settings`].
+pub const SETTINGS_KEY: &str = "settings";
+/// Emitted with the full [`Settings`] after every change.
+pub const CHANGED_EVENT: &str = "settings:changed";
+const MAX_RELAYS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// DCA scheduler timing.
+    pub dca: DcaSchedulerConfig,
+    /// Relays the NWC service listens on. Empty uses `nwc::DEFAULT_RELAYS`.
+    pub nwc_relays: Vec<String>,
+    /// How new local nodes are run.
+    pub node_backend: NodeBackendType,
+    /// Days log files are kept after their last write.
+    pub log_retention_days: u32,
+    /// Minutes without use after which the session vault locks.
+    pub vault_idle_timeout_mins: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            dca: DcaSchedulerConfig::default(),
+            nwc_relays: Vec::new(),
+            node_backend: NodeBackendType::default(),
+            log_retention_days: 14,
+            vault_idle_timeout_mins: 15,
+        }
+    }
+}
+
+impl Settings {
+    /// Stored settings, or the defaults when none are stored.
+    pub fn load() -> Result<Self, String> {
+        db::with(|conn| Ok(read(conn))).map_err(|e| e.to_string())?
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.dca.validate()?;
+        if self.nwc_relays.len() > MAX_RELAYS {
+            return Err(format!("At most {} NWC relays can be set", MAX_RELAYS));
+        }
+        for (i, relay) in self.nwc_relays.iter().enumerate() {
+            let host = relay
+                .strip_prefix("wss://")
+                .or_else(|| relay.strip_prefix("ws://"));
+            if host.is_none_or(|h| h.is_empty() || h.contains(char::is_whitespace)) {
+                return Err(format!("Invalid relay URL: {}", relay));
+            }
+            if self.nwc_relays[..i].contains(relay) {
+                return Err(format!("Duplicate relay: {}", relay));
+            }
+        }
+        if !(1..=365).contains(&self.log_retention_days) {
+            return Err("Log retention must be between 1 and 365 days".to_string());
+        }
+        if !(1..=1440).contains(&self.vault_idle_timeout_mins) {
+            return Err("Vault idle timeout must be between 1 and 1440 minutes".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub fn read(conn: &Connection) -> Result<Settings, String> {
+    match db::get_app_setting(conn, SETTINGS_KEY).map_err(|e| e.to_string())? {
+        Some(json) => {
+            serde_json::from_str(&json).map_err(|e| format!("Invalid stored settings: {}", e))
+        }
+        None => Ok(Settings::default()),
+    }
+}
+
+/// Recursively merge `patch` into `target`: objects merge key by key, any
+/// other value replaces.
+fn merge(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+/// Apply `patch`, a partial settings object, to the stored settings and
+/// return the result. Nothing is written when it doesn't validate.
+pub fn update(conn: &Connection, patch: &Value) -> Result<Settings, String> {
+    let Value::Object(fields) = patch else {
+        return Err("Settings patch must be an object".to_string());
+    };
+    let known = serde_json::to_value(Settings::default()).map_err(|e| e.to_string())?;
+    if let Some(key) = fields.keys().find(|k| known.get(k.as_str()).is_none()) {
+        return Err(format!("Unknown setting '{}'", key));
+    }
+
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let current = read(&tx).unwrap_or_else(|e| {
+        log::warn!("[Settings] {}; starting from defaults", e);
+        Settings::default()
+    });
+    let mut merged = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    merge(&mut merged, patch);
+    let settings: Settings =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid settings: {}", e))?;
+    settings.validate()?;
+
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    db::set_app_setting(&tx, SETTINGS_KEY, &json).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
+/// Delete `.log` files in `dir` last written more than `retention_days` ago.
+/// Returns how many were removed.
+pub fn prune_logs(dir: &Path, retention_days: u32) -> io::Result<usize> {
+    let cutoff = SystemTime::now() - Duration::from_secs(u64::from(retention_days) * 24 * 3600);
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut removed = 0;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "log")
+            && entry.metadata()?.modified()? < cutoff
+        {
+            fs::remove_file(&path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations;
+    use serde_json::json;
+
+    fn test_db(name: &str) -> (db::DbPool, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!(
+            "kaleido-settings-{}-{}.sqlite",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let pool = db::open_pool(&path, None).unwrap();
+        migrations::run(&mut pool.get().unwrap()).unwrap();
+        (pool, path)
+    }
+
+    #[test]
+    fn test_update_merges_and_validates() {
+        let (pool, path) = test_db("update");
+        let conn = pool.get().unwrap();
+        assert_eq!(read(&conn).unwrap(), Settings::default());
+
+        let settings = update(&conn, &json!({ "dca": { "jitter_secs": 0 } })).unwrap();
+        assert_eq!(settings.dca.jitter_secs, 0);
+        assert_eq!(settings.dca.poll_interval_secs, 30);
+
+        let settings = update(
+            &conn,
+            &json!({ "nwc_relays": ["wss://relay.example"], "node_backend": "docker" }),
+        )
+        .unwrap();
+        assert_eq!(settings.dca.jitter_secs, 0);
+        assert_eq!(settings.node_backend, NodeBackendType::Docker);
+        assert_eq!(read(&conn).unwrap(), settings);
+
+        for bad in [
+            json!({ "nwc_relays": ["https://relay.example"] }),
+            json!({ "nwc_relays": ["wss://a", "wss://a"] }),
+            json!({ "log_retention_days": 0 }),
+            json!({ "vault_idle_timeout_mins": 0 }),
+            json!({ "dca": { "poll_interval_secs": 1 } }),
+            json!({ "node_backend": "vm" }),
+            json!({ "log_retention": 3 }),
+            json!(["not", "an", "object"]),
+        ] {
+            assert!(update(&conn, &bad).is_err(), "{} should be rejected", bad);
+        }
+        assert_eq!(read(&conn).unwrap(), settings);
+
+        drop((conn, pool));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_stored_settings_take_defaults() {
+        let (pool, path) = test_db("defaults");
+        let conn = pool.get().unwrap();
+        db::set_app_setting(&conn, SETTINGS_KEY, r#"{"log_retention_days": 3}"#).unwrap();
+        let settings = read(&conn).unwrap();
+        assert_eq!(settings.log_retention_days, 3);
+        assert_eq!(settings.dca, DcaSchedulerConfig::default());
+
+        drop((conn, pool));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_prune_logs() {
+        let dir = std::env::temp_dir().join(format!("kaleido-logs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let old = dir.join("kaleidoswap_2020-01-01.log");
+        fs::write(&old, "old").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(30 * 24 * 3600))
+            .unwrap();
+        fs::write(dir.join("kaleidoswap.log"), "current").unwrap();
+        fs::write(dir.join("notes.txt"), "kept").unwrap();
+
+        assert_eq!(prune_logs(&dir, 14).unwrap(), 1);
+        assert!(!old.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        assert_eq!(prune_logs(&dir.join("missing"), 14).unwrap(), 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -15,7 +15,16 @@ pub const TRAY_ID: &str = "main-tray";
 const MENU_OPEN_ID: &str = "open";
 const MENU_NODE_STATUS_ID: &str = "node-status";
 const MENU_START_STOP_ID: &str = "start-stop-node";
+const MENU_DCA_WARNING_ID: &str = "dca-warning";
 const MENU_QUIT_ID: &str = "quit";
+const TOOLTIP: &str = "KaleidoSwap";
+
+/// Warning from the DCA scheduler, shown in the menu and tooltip until it
+/// clears.
+static DCA_WARNING: Mutex<Option<String>> = Mutex::new(None);
+/// Node state the menu was last built with, to rebuild it for a warning
+/// without locking the node process.
+static MENU_NODE_STATE: Mutex<Option<NodeState>> = Mutex::new(None);
 
 fn node_status_label(state: &NodeState) -> String {
     match state {
@@ -53,6 +62,20 @@ fn build_tray_menu(app: &AppHandle, node_state: &NodeState) -> tauri::Result<Men
         true,
         None::<&str>,
     )?;
+    let dca_warning = DCA_WARNING
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|warning| {
+            MenuItem::with_id(
+                app,
+                MENU_DCA_WARNING_ID,
+                format!("\u{26a0} {}", warning),
+                false,
+                None::<&str>,
+            )
+        })
+        .transpose()?;
     let sep2 = PredefinedMenuItem::separator(app)?;
     let quit = MenuItem::with_id(app, MENU_QUIT_ID, "Quit KaleidoSwap", true, None::<&str>)?;
 
@@ -60,6 +83,9 @@ fn build_tray_menu(app: &AppHandle, node_state: &NodeState) -> tauri::Result<Men
     menu.append(&sep1)?;
     menu.append(&node_status)?;
     menu.append(&start_stop)?;
+    if let Some(dca_warning) = &dca_warning {
+        menu.append(dca_warning)?;
+    }
     menu.append(&sep2)?;
     menu.append(&quit)?;
 
@@ -78,6 +104,7 @@ pub fn show_main_window(app: &AppHandle) {
 }
 
 pub fn update_tray_menu(app: &AppHandle, node_state: NodeState) {
+    *MENU_NODE_STATE.lock().unwrap() = Some(node_state.clone());
     if let Some(tray) = app.tray_by_id(TRAY_ID) {
         if let Ok(new_menu) = build_tray_menu(app, &node_state) {
             let _ = tray.set_menu(Some(new_menu));
@@ -85,11 +112,36 @@ pub fn update_tray_menu(app: &AppHandle, node_state: NodeState) {
     }
 }
 
+/// Show (`Some`) or clear (`None`) the DCA warning in the tray.
+pub fn set_dca_warning(app: &AppHandle, warning: Option<String>) {
+    {
+        let mut current = DCA_WARNING.lock().unwrap();
+        if *current == warning {
+            return;
+        }
+        *current = warning.clone();
+    }
+    let node_state = MENU_NODE_STATE
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or(NodeState::Stopped);
+    update_tray_menu(app, node_state);
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let tooltip = match &warning {
+            Some(warning) => format!("{} \u{2014} {}", TOOLTIP, warning),
+            None => TOOLTIP.to_string(),
+        };
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+}
+
 pub fn setup_tray(app: &AppHandle, node_process: Arc<Mutex<NodeProcess>>) -> tauri::Result<()> {
     let icon = app.default_window_icon().cloned().unwrap();
 
     let node_state = node_process.lock().unwrap().get_state();
     let menu = build_tray_menu(app, &node_state)?;
+    *MENU_NODE_STATE.lock().unwrap() = Some(node_state);
 
     let np_for_menu = Arc::clone(&node_process);
 
@@ -97,7 +149,7 @@ pub fn setup_tray(app: &AppHandle, node_process: Arc<Mutex<NodeProcess>>) -> tau
         .icon(icon)
         .icon_as_template(true)
         .menu(&menu)
-        .tooltip("KaleidoSwap")
+        .tooltip(TOOLTIP)
         .on_menu_event(move |app, event| {
             handle_menu_event(app, event.id().as_ref(), Arc::clone(&np_for_menu));
         })
@@ -0,0 +1,631 @@
+//! Session vault.
+//!
+//! Unlocking the vault with the current account's password keeps, for the
+//! session, the keys of the account's mnemonic and passphrase envelopes, in
+//! memory that is zeroized when the vault locks. The password itself isn't
+//! kept. The webview asks for operations (sign a message) instead of handling
+//! the seed: the mnemonic is only decrypted inside them, and for the
+//! recovery-phrase viewer once the password is entered again. Accounts that
+//! store no secrets (restored without their mnemonic) have nothing to unlock.
+//!
+//! The vault locks after `vault_idle_timeout_mins` (see `settings`) without
+//! use, and when the machine wakes from sleep, noticed as a watchdog tick
+//! that comes much later than due. [`UNLOCKED_EVENT`] and [`LOCKED_EVENT`]
+//! announce the transitions.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use nostr::hashes::{sha256, Hash};
+use nostr::nips::nip06::FromMnemonic;
+use nostr::secp256k1::Message;
+use nostr::Keys;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio_util::sync::CancellationToken;
+use zeroize::Zeroizing;
+
+use crate::crypto::{self, Envelope, KdfParams};
+use crate::db;
+
+/// Emitted with [`UnlockedEvent`] when the vault unlocks.
+pub const UNLOCKED_EVENT: &str = "vault:unlocked";
+/// Emitted with [`LockedEvent`] when the vault locks.
+pub const LOCKED_EVENT: &str = "vault:locked";
+/// [`SessionVault::unlock`]'s error for an account without stored secrets.
+pub const NO_SECRETS: &str = "No secrets are stored for this account";
+/// How often the watchdog checks for idleness and sleep.
+const TICK: Duration = Duration::from_secs(15);
+/// A tick this much later than due means the machine was asleep.
+const SLEEP_GAP: Duration = Duration::from_secs(60);
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+const NODE_UNLOCK_TIMEOUT_SECS: u64 = 120;
+/// Sent as the node's alias, as the unlock screen does.
+const ANNOUNCE_ALIAS: &str = "kaleidoswap-desktop";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockReason {
+    Manual,
+    Idle,
+    Sleep,
+    AccountChanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnlockedEvent {
+    pub account_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LockedEvent {
+    pub account_name: String,
+    pub reason: LockReason,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStatus {
+    pub unlocked: bool,
+    pub account_name: Option<String>,
+    /// Seconds until the vault locks if it isn't used.
+    pub locks_in_secs: Option<u64>,
+}
+
+/// A message signed with the account's Nostr key (NIP-06, from its
+/// mnemonic): a BIP-340 signature of the message's SHA-256.
+#[derive(Debug, Clone, Serialize)]
+pub struct SignedMessage {
+    pub pubkey: String,
+    pub signature: String,
+}
+
+/// A failed node request, shaped like the frontend's fetch errors (`status`,
+/// `data.error`) so the unlock flow handles it the same way.
+#[derive(Debug, Serialize)]
+pub struct NodeError {
+    pub status: NodeErrorStatus,
+    pub data: NodeErrorData,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum NodeErrorStatus {
+    Http(u16),
+    Other(&'static str),
+}
+
+#[derive(Debug, Serialize)]
+pub struct NodeErrorData {
+    pub error: String,
+}
+
+impl NodeError {
+    fn new(status: NodeErrorStatus, error: impl Into<String>) -> Self {
+        NodeError {
+            status,
+            data: NodeErrorData {
+                error: error.into(),
+            },
+        }
+    }
+}
+
+impl From<String> for NodeError {
+    fn from(error: String) -> Self {
+        NodeError::new(NodeErrorStatus::Other("CUSTOM_ERROR"), error)
+    }
+}
+
+/// A stored secret and the key that opens it.
+type Sealed = (Envelope, Zeroizing<[u8; 32]>);
+
+struct Session {
+    account_name: String,
+    /// The stored mnemonic. Accounts restored without one only have a
+    /// passphrase, if anything.
+    mnemonic: Option<Sealed>,
+    /// The mnemonic's BIP39 passphrase, if it has one.
+    passphrase: Option<Sealed>,
+    last_used: Instant,
+}
+
+impl Session {
+    fn locks_in(&self, timeout: Duration, now: Instant) -> Duration {
+        timeout.saturating_sub(now.duration_since(self.last_used))
+    }
+}
+
+/// Whether the watchdog tick that came `gap` after the previous one (by the
+/// wall clock) was held up by the machine sleeping.
+fn woke_from_sleep(gap: Duration) -> bool {
+    gap > TICK + SLEEP_GAP
+}
+
+/// `user:password@host:port`, as accounts store their bitcoind RPC.
+fn parse_rpc_url(url: &str) -> Option<(&str, &str, &str, u16)> {
+    let (credentials, host_port) = url.split_once('@')?;
+    let (username, password) = credentials.split_once(':')?;
+    let (host, port) = host_port.rsplit_once(':')?;
+    Some((username, password, host, port.parse().ok()?))
+}
+
+/// `node_url` as the frontend's client reaches it: `localhost` resolves to
+/// IPv4, as the node listens there.
+fn normalize_node_url(node_url: &str) -> String {
+    node_url
+        .replacen("://localhost", "://127.0.0.1", 1)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+pub struct SessionVault {
+    session: Mutex<Option<Session>>,
+    idle_timeout: Mutex<Duration>,
+    app_handle: Mutex<Option<AppHandle>>,
+    watchdog: Mutex<Option<CancellationToken>>,
+}
+
+impl SessionVault {
+    pub fn new() -> Self {
+        SessionVault {
+            session: Mutex::new(None),
+            idle_timeout: Mutex::new(DEFAULT_IDLE_TIMEOUT),
+            app_handle: Mutex::new(None),
+            watchdog: Mutex::new(None),
+        }
+    }
+
+    pub fn set_app_handle(&self, handle: AppHandle) {
+        *self.app_handle.lock().unwrap() = Some(handle);
+    }
+
+    pub fn set_idle_timeout(&self, timeout: Duration) {
+        *self.idle_timeout.lock().unwrap() = timeout;
+    }
+
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(app) = self.app_handle.lock().unwrap().as_ref() {
+            if let Err(e) = app.emit(event, payload) {
+                log::warn!("[Vault] Failed to emit {}: {}", event, e);
+            }
+        }
+    }
+
+    /// Unlock for `account_name` with its password, checked against its
+    /// stored secrets; fails with [`NO_SECRETS`] if it has none. Secrets
+    /// stored with older encryption settings are sealed again: the new
+    /// envelopes are returned for the caller to store, and the vault keeps
+    /// their keys.
+    pub fn unlock(
+        &self,
+        account_name: &str,
+        secrets: &db::AccountSecrets,
+        password: &str,
+    ) -> Result<db::AccountSecrets, String> {
+        if password.is_empty() {
+            return Err("Password cannot be empty".to_string());
+        }
+        if secrets.mnemonic.is_none() && secrets.passphrase.is_none() {
+            return Err(NO_SECRETS.to_string());
+        }
+        let mut resealed = db::AccountSecrets::default();
+        let mnemonic = match &secrets.mnemonic {
+            Some(stored) => {
+                let legacy = stored
+                    .legacy
+                    .as_ref()
+                    .map(|(salt, nonce)| (salt.as_str(), nonce.as_str()));
+                let mut envelope = crypto::mnemonic_envelope(&stored.encrypted, legacy)
+                    .map_err(|e| e.to_string())?;
+                let mut key = envelope.key(password).map_err(|e| e.to_string())?;
+                let plaintext = envelope
+                    .open_with(&key)
+                    .map_err(|_| "Invalid password".to_string())?;
+                let mnemonic = crypto::mnemonic_text(plaintext).map_err(|e| e.to_string())?;
+                if envelope.is_outdated() {
+                    envelope = Envelope::seal(mnemonic.as_bytes(), password, KdfParams::current())
+                        .map_err(|e| e.to_string())?;
+                    key = envelope.key(password).map_err(|e| e.to_string())?;
+                    resealed.mnemonic = Some(db::EncryptedMnemonic {
+                        encrypted: hex::encode(envelope.to_bytes()),
+                        legacy: None,
+                    });
+                }
+                Some((envelope, key))
+            }
+            None => None,
+        };
+        let passphrase = match &secrets.passphrase {
+            Some(encrypted) => {
+                let mut envelope =
+                    crypto::mnemonic_envelope(encrypted, None).map_err(|e| e.to_string())?;
+                let mut key = envelope.key(password).map_err(|e| e.to_string())?;
+                let plaintext = envelope
+                    .open_with(&key)
+                    .map_err(|_| "Invalid password".to_string())?;
+                let passphrase = crypto::mnemonic_text(plaintext).map_err(|e| e.to_string())?;
+                if envelope.is_outdated() {
+                    envelope =
+                        Envelope::seal(passphrase.as_bytes(), password, KdfParams::current())
+                            .map_err(|e| e.to_string())?;
+                    key = envelope.key(password).map_err(|e| e.to_string())?;
+                    resealed.passphrase = Some(hex::encode(envelope.to_bytes()));
+                }
+                Some((envelope, key))
+            }
+            None => None,
+        };
+
+        let previous = self.session.lock().unwrap().replace(Session {
+            account_name: account_name.to_string(),
+            mnemonic,
+            passphrase,
+            last_used: Instant::now(),
+        });
+        if let Some(previous) = previous.filter(|s| s.account_name != account_name) {
+            self.emit(
+                LOCKED_EVENT,
+                LockedEvent {
+                    account_name: previous.account_name,
+                    reason: LockReason::AccountChanged,
+                },
+            );
+        }
+        log::info!("[Vault] Unlocked for {}", account_name);
+        self.emit(
+            UNLOCKED_EVENT,
+            UnlockedEvent {
+                account_name: account_name.to_string(),
+            },
+        );
+        Ok(resealed)
+    }
+
+    /// Lock, dropping (and zeroizing) the session. Returns whether it was
+    /// unlocked.
+    pub fn lock(&self, reason: LockReason) -> bool {
+        let Some(session) = self.session.lock().unwrap().take() else {
+            return false;
+        };
+        log::info!("[Vault] Locked for {} ({:?})", session.account_name, reason);
+        self.emit(
+            LOCKED_EVENT,
+            LockedEvent {
+                account_name: session.account_name.clone(),
+                reason,
+            },
+        );
+        true
+    }
+
+    /// Record activity, postponing the idle lock.
+    pub fn touch(&self) {
+        if let Some(session) = self.session.lock().unwrap().as_mut() {
+            session.last_used = Instant::now();
+        }
+    }
+
+    pub fn account_name(&self) -> Option<String> {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.account_name.clone())
+    }
+
+    pub fn status(&self) -> VaultStatus {
+        let timeout = *self.idle_timeout.lock().unwrap();
+        let session = self.session.lock().unwrap();
+        VaultStatus {
+            unlocked: session.is_some(),
+            account_name: session.as_ref().map(|s| s.account_name.clone()),
+            locks_in_secs: session
+                .as_ref()
+                .map(|s| s.locks_in(timeout, Instant::now()).as_secs()),
+        }
+    }
+
+    /// Run `f` on the session of `account_name`, counting as activity.
+    fn with_session<T>(
+        &self,
+        account_name: &str,
+        f: impl FnOnce(&Session) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut session = self.session.lock().unwrap();
+        let session = session
+            .as_mut()
+            .filter(|s| s.account_name == account_name)
+            .ok_or_else(|| "The vault is locked".to_string())?;
+        session.last_used = Instant::now();
+        f(session)
+    }
+
+    /// Sign `message` with the Nostr key of the account's mnemonic and
+    /// passphrase.
+    pub fn sign_message(&self, account_name: &str, message: &str) -> Result<SignedMessage, String> {
+        let keys = self.with_session(account_name, |session| {
+            let (envelope, key) = session
+                .mnemonic
+                .as_ref()
+                .ok_or_else(|| "No mnemonic stored for this account".to_string())?;
+            let plaintext = envelope.open_with(key).map_err(|e| e.to_string())?;
+            let mnemonic = crypto::mnemonic_text(plaintext).map_err(|e| e.to_string())?;
+            let passphrase = match &session.passphrase {
+                Some((envelope, key)) => Some(
+                    envelope
+                        .open_with(key)
+                        .map_err(|e| e.to_string())
+                        .and_then(|p| crypto::mnemonic_text(p).map_err(|e| e.to_string()))?,
+                ),
+                None => None,
+            };
+            Keys::from_mnemonic(mnemonic.as_str(), passphrase.as_ref().map(|p| p.as_str()))
+                .map_err(|e| e.to_string())
+        })?;
+        let digest = sha256::Hash::hash(message.as_bytes());
+        let signature = keys.sign_schnorr(&Message::from_digest(digest.to_byte_array()));
+        Ok(SignedMessage {
+            pubkey: keys.public_key().to_hex(),
+            signature: signature.to_string(),
+        })
+    }
+
+    /// The account's mnemonic, for the recovery-phrase viewer. Needs the
+    /// vault unlocked for `account_name` and the password again: it is
+    /// checked against the stored mnemonic, not the session.
+    pub fn reveal_mnemonic(
+        &self,
+        account_name: &str,
+        password: &str,
+    ) -> Result<Zeroizing<String>, String> {
+        // The key is derived outside the session lock
+        let envelope = self.with_session(account_name, |session| {
+            session
+                .mnemonic
+                .as_ref()
+                .map(|(envelope, _)| envelope.clone())
+                .ok_or_else(|| "No mnemonic stored for this account".to_string())
+        })?;
+        let plaintext = envelope
+            .open(password)
+            .map_err(|_| "Invalid password".to_string())?;
+        let mnemonic = crypto::mnemonic_text(plaintext).map_err(|e| e.to_string())?;
+        log::info!("[Vault] Revealed the mnemonic of {}", account_name);
+        Ok(mnemonic)
+    }
+
+    /// Lock if the machine slept through the last tick or the session sat
+    /// idle for the timeout.
+    fn check(&self, wall_gap: Duration, now: Instant) {
+        if woke_from_sleep(wall_gap) {
+            self.lock(LockReason::Sleep);
+            return;
+        }
+        let timeout = *self.idle_timeout.lock().unwrap();
+        let idle = self
+            .session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|s| s.locks_in(timeout, now).is_zero());
+        if idle {
+            self.lock(LockReason::Idle);
+        }
+    }
+
+    /// Start the auto-lock watchdog. A no-op if it's running.
+    pub fn start(self: &Arc<Self>) {
+        let mut watchdog = self.watchdog.lock().unwrap();
+        if watchdog.is_some() {
+            return;
+        }
+        let cancel = CancellationToken::new();
+        let token = cancel.clone();
+        let vault = Arc::clone(self);
+        tauri::async_runtime::spawn(async move {
+            let mut last_tick = SystemTime::now();
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    _ = tokio::time::sleep(TICK) => {}
+                }
+                let now = SystemTime::now();
+                let gap = now.duration_since(last_tick).unwrap_or_default();
+                last_tick = now;
+                vault.check(gap, Instant::now());
+            }
+        });
+        *watchdog = Some(cancel);
+    }
+}
+
+impl Default for SessionVault {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Unlock `account`'s node with `password`, which the node checks.
+pub async fn unlock_node(account: &db::Account, password: &str) -> Result<(), NodeError> {
+    let (username, rpc_password, host, port) = parse_rpc_url(&account.rpc_connection_url)
+        .ok_or_else(|| "Invalid RPC connection URL".to_string())?;
+    let body = Zeroizing::new(
+        serde_json::json!({
+            "announce_addresses": [],
+            "announce_alias": ANNOUNCE_ALIAS,
+            "bitcoind_rpc_host": host,
+            "bitcoind_rpc_password": rpc_password,
+            "bitcoind_rpc_port": port,
+            "bitcoind_rpc_username": username,
+            "indexer_url": account.indexer_url,
+            "password": password,
+            "proxy_endpoint": account.proxy_endpoint,
+        })
+        .to_string(),
+    );
+
+    let url = format!("{}/unlock", normalize_node_url(&account.node_url));
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(NODE_UNLOCK_TIMEOUT_SECS))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut req = client
+        .post(&url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string());
+    if let Some(token) = account.bearer_token.as_deref().filter(|t| !t.is_empty()) {
+        req = req.bearer_auth(token);
+    }
+    let resp = req.send().await.map_err(|e| {
+        let status = if e.is_timeout() {
+            "TIMEOUT_ERROR"
+        } else {
+            "FETCH_ERROR"
+        };
+        NodeError::new(NodeErrorStatus::Other(status), e.to_string())
+    })?;
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let text = resp.text().await.unwrap_or_default();
+    let error = serde_json::from_str::<serde_json::Value>(&text)
+        .ok()
+        .and_then(|v| v.get("error")?.as_str().map(str::to_string))
+        .unwrap_or(text);
+    Err(NodeError::new(
+        NodeErrorStatus::Http(status.as_u16()),
+        error,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn secrets(password: &str, passphrase: Option<&str>) -> db::AccountSecrets {
+        db::AccountSecrets {
+            mnemonic: Some(db::EncryptedMnemonic {
+                encrypted: crypto::encrypt_mnemonic(MNEMONIC, password).unwrap(),
+                legacy: None,
+            }),
+            passphrase: passphrase.map(|p| crypto::encrypt_passphrase(p, password).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_unlock_sign_and_lock() {
+        let vault = SessionVault::new();
+        let stored = secrets("password", None);
+        assert!(vault.unlock("main", &stored, "wrong").is_err());
+        assert!(!vault.status().unlocked);
+        assert!(vault.sign_message("main", "hello").is_err());
+
+        let resealed = vault.unlock("main", &stored, "password").unwrap();
+        assert!(resealed.mnemonic.is_none() && resealed.passphrase.is_none());
+        assert_eq!(vault.account_name().as_deref(), Some("main"));
+        let signed = vault.sign_message("main", "hello").unwrap();
+        // NIP-06 key of the test mnemonic
+        assert_eq!(
+            signed.pubkey,
+            Keys::from_mnemonic(MNEMONIC, None)
+                .unwrap()
+                .public_key()
+                .to_hex()
+        );
+        assert_eq!(signed.signature.len(), 128);
+        assert!(vault.sign_message("other", "hello").is_err());
+
+        assert!(vault.lock(LockReason::Manual));
+        assert!(!vault.lock(LockReason::Manual));
+        assert!(vault.sign_message("main", "hello").is_err());
+
+        // The passphrase changes the key
+        vault
+            .unlock("main", &secrets("password", Some("extra")), "password")
+            .unwrap();
+        assert_eq!(
+            vault.sign_message("main", "hello").unwrap().pubkey,
+            Keys::from_mnemonic(MNEMONIC, Some("extra"))
+                .unwrap()
+                .public_key()
+                .to_hex()
+        );
+
+        // Accounts without stored secrets don't unlock, whatever the password,
+        // and those without a stored mnemonic can't sign
+        let none = db::AccountSecrets::default();
+        assert_eq!(
+            vault.unlock("restored", &none, "anything").unwrap_err(),
+            NO_SECRETS
+        );
+        assert_eq!(vault.account_name().as_deref(), Some("main"));
+        let passphrase_only = db::AccountSecrets {
+            mnemonic: None,
+            ..secrets("password", Some("extra"))
+        };
+        assert!(vault.unlock("restored", &passphrase_only, "wrong").is_err());
+        vault
+            .unlock("restored", &passphrase_only, "password")
+            .unwrap();
+        assert!(vault.sign_message("restored", "hello").is_err());
+    }
+
+    #[test]
+    fn test_reveal_mnemonic() {
+        let vault = SessionVault::new();
+        let stored = secrets("password", None);
+        assert!(vault.reveal_mnemonic("main", "password").is_err());
+
+        vault.unlock("main", &stored, "password").unwrap();
+        assert_eq!(
+            vault.reveal_mnemonic("main", "password").unwrap().as_str(),
+            MNEMONIC
+        );
+        // The password is asked for again, and checked
+        assert_eq!(
+            vault.reveal_mnemonic("main", "wrong").unwrap_err(),
+            "Invalid password"
+        );
+        assert!(vault.reveal_mnemonic("other", "password").is_err());
+
+        vault.lock(LockReason::Manual);
+        assert!(vault.reveal_mnemonic("main", "password").is_err());
+    }
+
+    #[test]
+    fn test_auto_lock() {
+        assert!(!woke_from_sleep(TICK));
+        assert!(woke_from_sleep(TICK + SLEEP_GAP * 2));
+
+        let vault = SessionVault::new();
+        vault.set_idle_timeout(Duration::from_secs(60));
+        let stored = secrets("password", None);
+        vault.unlock("main", &stored, "password").unwrap();
+        let now = Instant::now();
+        vault.check(TICK, now);
+        assert!(vault.status().unlocked);
+        vault.check(TICK, now + Duration::from_secs(61));
+        assert!(!vault.status().unlocked);
+
+        vault.unlock("main", &stored, "password").unwrap();
+        vault.check(Duration::from_secs(3600), Instant::now());
+        assert!(!vault.status().unlocked);
+    }
+
+    #[test]
+    fn test_node_urls() {
+        assert_eq!(
+            parse_rpc_url("user:password@regtest-bitcoind.rgbtools.org:80"),
+            Some(("user", "password", "regtest-bitcoind.rgbtools.org", 80))
+        );
+        assert_eq!(parse_rpc_url("localhost:18443"), None);
+        assert_eq!(parse_rpc_url("user:pw@host:port"), None);
+        assert_eq!(
+            normalize_node_url("http://localhost:3001/"),
+            "http://127.0.0.1:3001"
+        );
+    }
+}
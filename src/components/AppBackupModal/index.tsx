@@ -0,0 +1,497 @@
+import { invoke } from '@tauri-apps/api/core'
+import { open, save } from '@tauri-apps/plugin-dialog'
+import {
+  Archive,
+  CheckCircle2,
+  Eye,
+  EyeOff,
+  FolderOpen,
+  Loader2,
+  Upload,
+  X,
+} from 'lucide-react'
+import { useState } from 'react'
+import { createPortal } from 'react-dom'
+import { useTranslation } from 'react-i18next'
+
+import {
+  getModalPortalTarget,
+  getModalPositionClass,
+} from '../../helpers/modalPortal'
+
+interface AppBackupModalProps {
+  showModal: boolean
+  onClose: () => void
+}
+
+type Tab = 'export' | 'import'
+type ImportMode = 'merge' | 'replace'
+
+interface BackupManifest {
+  format: number
+  createdAt: number
+  appVersion: string
+  schemaVersion: number
+  accounts: string[]
+  counts: Record<string, number>
+  nodeDirs: string[]
+}
+
+interface ImportReport {
+  manifest: BackupManifest
+  mode: ImportMode
+  applied: boolean
+  conflicts: string[]
+  imported: Record<string, number>
+  nodeDirs: string[]
+  skippedNodeDirs: string[]
+}
+
+const BACKUP_EXTENSION = 'ksbak'
+const BACKUP_FILTERS = [
+  { extensions: [BACKUP_EXTENSION], name: 'Kaleidoswap Backup' },
+]
+
+// Exports all app data (accounts, encrypted mnemonics, orders, NWC
+// connections, settings and optionally node data) to one encrypted file, and
+// restores such a file by merging it in or replacing everything.
+export const AppBackupModal: React.FC<AppBackupModalProps> = ({
+  showModal,
+  onClose,
+}) => {
+  const { t } = useTranslation()
+
+  const [tab, setTab] = useState<Tab>('export')
+  const [password, setPassword] = useState('')
+  const [confirmPassword, setConfirmPassword] = useState('')
+  const [showPasswords, setShowPasswords] = useState(false)
+  const [includeNodeData, setIncludeNodeData] = useState(false)
+  const [importPath, setImportPath] = useState<string | null>(null)
+  const [mode, setMode] = useState<ImportMode>('merge')
+  const [preview, setPreview] = useState<ImportReport | null>(null)
+  const [doneMessage, setDoneMessage] = useState<string | null>(null)
+  const [isLoading, setIsLoading] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+
+  if (!showModal) return null
+
+  const reset = () => {
+    setPassword('')
+    setConfirmPassword('')
+    setImportPath(null)
+    setPreview(null)
+    setDoneMessage(null)
+    setError(null)
+    setIsLoading(false)
+  }
+
+  const handleClose = () => {
+    const reload = preview?.mode === 'replace' && doneMessage !== null
+    reset()
+    onClose()
+    // Everything the app had loaded belongs to the old data
+    if (reload) window.location.reload()
+  }
+
+  const switchTab = (next: Tab) => {
+    reset()
+    setTab(next)
+  }
+
+  const handleExport = async (e: React.FormEvent) => {
+    e.preventDefault()
+    if (password !== confirmPassword) {
+      setError(t('appBackup.errorMismatch', 'Passwords do not match.'))
+      return
+    }
+    if (password.length < 8) {
+      setError(
+        t('appBackup.errorTooShort', 'Password must be at least 8 characters.')
+      )
+      return
+    }
+    const path = await save({
+      defaultPath: `kaleidoswap-backup-${new Date().toISOString().split('T')[0]}.${BACKUP_EXTENSION}`,
+      filters: BACKUP_FILTERS,
+    })
+    if (!path) return
+
+    setError(null)
+    setIsLoading(true)
+    try {
+      const manifest = await invoke<BackupManifest>('backup_export', {
+        includeNodeData,
+        password,
+        path,
+      })
+      setDoneMessage(
+        t('appBackup.exported', {
+          count: manifest.accounts.length,
+          defaultValue: 'Backed up {{count}} account(s) to {{path}}.',
+          path,
+        })
+      )
+    } catch (err) {
+      setError(String(err))
+    } finally {
+      setIsLoading(false)
+    }
+  }
+
+  const handleSelectFile = async () => {
+    const selected = await open({
+      directory: false,
+      filters: BACKUP_FILTERS,
+      multiple: false,
+    })
+    if (selected && typeof selected === 'string') {
+      setImportPath(selected)
+      setPreview(null)
+    }
+  }
+
+  const runImport = async (dryRun: boolean) => {
+    setError(null)
+    setIsLoading(true)
+    try {
+      const report = await invoke<ImportReport>('backup_import', {
+        dryRun,
+        mode,
+        password,
+        path: importPath,
+      })
+      setPreview(report)
+      if (report.applied) {
+        setDoneMessage(
+          t('appBackup.imported', {
+            count: report.imported.Accounts ?? 0,
+            defaultValue: 'Restored {{count}} account(s).',
+          })
+        )
+      }
+    } catch (err) {
+      setError(String(err))
+    } finally {
+      setIsLoading(false)
+    }
+  }
+
+  const pos = getModalPositionClass()
+
+  const passwordInput = (
+    label: string,
+    value: string,
+    onChange: (v: string) => void,
+    autoComplete: string
+  ) => (
+    <div className="space-y-1.5">
+      <label className="text-xs font-medium text-content-secondary uppercase tracking-wider">
+        {label}
+      </label>
+      <div className="relative">
+        <input
+          autoComplete={autoComplete}
+          className="w-full rounded-lg border border-border-default/50 bg-surface-overlay/40 px-3 py-2.5 pr-10 text-sm text-white placeholder:text-content-tertiary focus:border-primary/50 focus:outline-none focus:ring-1 focus:ring-primary/30 transition-colors"
+          disabled={isLoading}
+          onChange={(e) => onChange(e.target.value)}
+          placeholder="••••••••"
+          type={showPasswords ? 'text' : 'password'}
+          value={value}
+        />
+        <button
+          className="absolute right-3 top-1/2 -translate-y-1/2 text-content-tertiary hover:text-content-secondary transition-colors"
+          onClick={() => setShowPasswords(!showPasswords)}
+          tabIndex={-1}
+          type="button"
+        >
+          {showPasswords ? (
+            <EyeOff className="w-4 h-4" />
+          ) : (
+            <Eye className="w-4 h-4" />
+          )}
+        </button>
+      </div>
+    </div>
+  )
+
+  const primaryButtonClass =
+    'w-full flex items-center justify-center gap-2 px-4 py-2.5 bg-primary hover:bg-primary-emphasis text-primary-foreground rounded-md font-semibold transition-colors text-sm disabled:opacity-50 disabled:cursor-not-allowed'
+  const dangerButtonClass =
+    'w-full flex items-center justify-center gap-2 px-4 py-2.5 bg-status-danger hover:bg-status-danger/80 text-white rounded-md font-semibold transition-colors text-sm disabled:opacity-50 disabled:cursor-not-allowed'
+
+  const renderExport = () => (
+    <form className="space-y-4" onSubmit={handleExport}>
+      <p className="text-sm text-content-secondary leading-relaxed">
+        {t(
+          'appBackup.exportDescription',
+          'Save accounts, encrypted recovery phrases, channel, DCA and limit orders, NWC connections and settings to one encrypted file. Use it to move to a new machine.'
+        )}
+      </p>
+      {passwordInput(
+        t('appBackup.password', 'Backup password'),
+        password,
+        setPassword,
+        'new-password'
+      )}
+      {passwordInput(
+        t('appBackup.confirmPassword', 'Confirm backup password'),
+        confirmPassword,
+        setConfirmPassword,
+        'new-password'
+      )}
+      <label className="flex items-start gap-3 text-sm text-white cursor-pointer">
+        <input
+          checked={includeNodeData}
+          className="mt-0.5 accent-primary"
+          disabled={isLoading}
+          onChange={(e) => setIncludeNodeData(e.target.checked)}
+          type="checkbox"
+        />
+        <span>
+          {t('appBackup.includeNodeData', 'Include node data')}
+          <span className="block text-xs text-content-secondary">
+            {t(
+              'appBackup.includeNodeDataHint',
+              'Adds each local node data directory. Stop the node first; the file can get large.'
+            )}
+          </span>
+        </span>
+      </label>
+      {error && (
+        <p className="text-sm text-status-danger leading-relaxed">{error}</p>
+      )}
+      <button
+        className={primaryButtonClass}
+        disabled={isLoading || !password || !confirmPassword}
+        type="submit"
+      >
+        {isLoading ? (
+          <Loader2 className="w-4 h-4 animate-spin" />
+        ) : (
+          <Archive className="w-4 h-4" />
+        )}
+        {t('appBackup.export', 'Export backup')}
+      </button>
+    </form>
+  )
+
+  const renderPreview = (report: ImportReport) => (
+    <div className="space-y-2 rounded-xl border border-border-default/50 bg-surface-overlay/30 p-4 text-sm">
+      <div className="flex justify-between gap-4">
+        <span className="text-content-secondary">
+          {t('appBackup.createdAt', 'Created')}
+        </span>
+        <span className="text-white">
+          {new Date(report.manifest.createdAt * 1000).toLocaleString()} · v
+          {report.manifest.appVersion}
+        </span>
+      </div>
+      <div className="flex justify-between gap-4">
+        <span className="text-content-secondary">
+          {t('appBackup.accounts', 'Accounts')}
+        </span>
+        <span className="text-right text-white">
+          {report.manifest.accounts.join(', ') || '—'}
+        </span>
+      </div>
+      <div className="flex justify-between gap-4">
+        <span className="text-content-secondary">
+          {t('appBackup.rowsToImport', 'Records to import')}
+        </span>
+        <span className="text-white">
+          {Object.values(report.imported).reduce((a, b) => a + b, 0)}
+        </span>
+      </div>
+      {report.nodeDirs.length > 0 && (
+        <div className="flex justify-between gap-4">
+          <span className="text-content-secondary">
+            {t('appBackup.nodeData', 'Node data')}
+          </span>
+          <span className="text-right text-white">
+            {report.nodeDirs.join(', ')}
+          </span>
+        </div>
+      )}
+      {report.mode === 'merge' && report.conflicts.length > 0 && (
+        <p className="text-xs text-status-warning">
+          {t('appBackup.conflicts', {
+            accounts: report.conflicts.join(', '),
+            defaultValue:
+              'Already here, will be skipped: {{accounts}}. Their orders and connections are not imported.',
+          })}
+        </p>
+      )}
+      {report.skippedNodeDirs.length > 0 && (
+        <p className="text-xs text-status-warning">
+          {t('appBackup.skippedNodeDirs', {
+            dirs: report.skippedNodeDirs.join(', '),
+            defaultValue:
+              'Node data already exists and is kept as is: {{dirs}}.',
+          })}
+        </p>
+      )}
+      {report.mode === 'replace' && (
+        <p className="text-xs text-status-danger">
+          {t(
+            'appBackup.replaceWarning',
+            'All current accounts, orders, connections and settings will be deleted and replaced by the backup.'
+          )}
+        </p>
+      )}
+    </div>
+  )
+
+  const renderImport = () => (
+    <div className="space-y-4">
+      <p className="text-sm text-content-secondary leading-relaxed">
+        {t(
+          'appBackup.importDescription',
+          'Restore an app backup. Merge adds accounts that are not here yet; Replace restores the backup exactly and removes everything else.'
+        )}
+      </p>
+      <button
+        className="w-full flex items-center gap-3 rounded-lg border border-border-default/50 bg-surface-overlay/40 px-3 py-2.5 text-left text-sm text-white hover:bg-surface-elevated transition-colors"
+        disabled={isLoading}
+        onClick={handleSelectFile}
+        type="button"
+      >
+        <FolderOpen className="w-4 h-4 text-primary flex-shrink-0" />
+        <span className="truncate">
+          {importPath ?? t('appBackup.selectFile', 'Select backup file')}
+        </span>
+      </button>
+      {passwordInput(
+        t('appBackup.password', 'Backup password'),
+        password,
+        (value) => {
+          setPassword(value)
+          setPreview(null)
+        },
+        'current-password'
+      )}
+      <div className="grid grid-cols-2 gap-2">
+        {(['merge', 'replace'] as ImportMode[]).map((m) => (
+          <button
+            className={`rounded-lg border px-3 py-2 text-sm font-medium transition-colors ${
+              mode === m
+                ? 'border-primary/50 bg-primary/10 text-primary'
+                : 'border-border-default/50 bg-surface-overlay/30 text-content-secondary hover:text-white'
+            }`}
+            disabled={isLoading}
+            key={m}
+            onClick={() => {
+              setMode(m)
+              setPreview(null)
+            }}
+            type="button"
+          >
+            {m === 'merge'
+              ? t('appBackup.merge', 'Merge')
+              : t('appBackup.replace', 'Replace')}
+          </button>
+        ))}
+      </div>
+      {preview && renderPreview(preview)}
+      {error && (
+        <p className="text-sm text-status-danger leading-relaxed">{error}</p>
+      )}
+      <button
+        className={
+          preview?.mode === 'replace' ? dangerButtonClass : primaryButtonClass
+        }
+        disabled={isLoading || !importPath || !password}
+        onClick={() => runImport(preview === null)}
+        type="button"
+      >
+        {isLoading ? (
+          <Loader2 className="w-4 h-4 animate-spin" />
+        ) : (
+          <Upload className="w-4 h-4" />
+        )}
+        {preview
+          ? t('appBackup.confirmImport', 'Import')
+          : t('appBackup.preview', 'Check backup')}
+      </button>
+    </div>
+  )
+
+  const renderBody = () => {
+    if (doneMessage) {
+      return (
+        <div className="flex flex-col items-center gap-4 py-6 text-center">
+          <div className="w-14 h-14 rounded-2xl bg-status-success/10 border border-status-success/20 flex items-center justify-center">
+            <CheckCircle2 className="w-7 h-7 text-status-success" />
+          </div>
+          <p className="text-sm text-content-secondary break-all">
+            {doneMessage}
+          </p>
+          <button
+            className="inline-flex h-10 items-center justify-center gap-2 rounded-md bg-primary hover:bg-primary-emphasis px-6 text-sm font-semibold text-primary-foreground transition-colors"
+            onClick={handleClose}
+            type="button"
+          >
+            {t('appBackup.close', 'Close')}
+          </button>
+        </div>
+      )
+    }
+
+    return (
+      <>
+        <div className="grid grid-cols-2 gap-1 rounded-lg bg-surface-overlay/40 p-1 mb-5">
+          {(['export', 'import'] as Tab[]).map((value) => (
+            <button
+              className={`rounded-md px-3 py-1.5 text-sm font-medium transition-colors ${
+                tab === value
+                  ? 'bg-surface-elevated text-white'
+                  : 'text-content-secondary hover:text-white'
+              }`}
+              disabled={isLoading}
+              key={value}
+              onClick={() => switchTab(value)}
+              type="button"
+            >
+              {value === 'export'
+                ? t('appBackup.exportTab', 'Export')
+                : t('appBackup.importTab', 'Import')}
+            </button>
+          ))}
+        </div>
+        {tab === 'export' ? renderExport() : renderImport()}
+      </>
+    )
+  }
+
+  const dismissable = !isLoading
+
+  return createPortal(
+    <div
+      className={`${pos} inset-0 bg-surface-base/80 backdrop-blur-sm flex items-center justify-center z-50 p-4`}
+      onMouseDown={(e) =>
+        dismissable && e.target === e.currentTarget && handleClose()
+      }
+    >
+      <div className="w-full max-w-lg bg-surface-base rounded-3xl border border-border-subtle/50 shadow-2xl shadow-black/20 overflow-hidden relative">
+        <div className="max-h-[85vh] overflow-y-auto px-8 py-8">
+          <div className="flex items-center gap-3 pb-4 border-b border-divider/10 mb-6">
+            <Archive className="w-6 h-6 text-primary" />
+            <h3 className="text-xl font-bold text-white flex-1">
+              {t('appBackup.title', 'App Backup')}
+            </h3>
+            {dismissable && (
+              <button
+                className="p-1.5 rounded-md text-content-secondary hover:text-white hover:bg-surface-overlay/50 transition-colors"
+                onClick={handleClose}
+                type="button"
+              >
+                <X className="w-4 h-4" />
+              </button>
+            )}
+          </div>
+          {renderBody()}
+        </div>
+      </div>
+    </div>,
+    getModalPortalTarget()
+  )
+}
@@ -687,6 +687,14 @@ export const BuyChannelModal: React.FC<BuyChannelModalProps> = ({
         setAccessToken(orderAccessToken)
         setOrderPayload(payload)
         setOrder(channelResponse.data as Lsps1CreateOrderResponse)
+        // Stored before payment so the backend can track it to the end
+        persistChannelOrder({
+          order: channelResponse.data,
+          orderId,
+          orderPayload: payload,
+        }).catch((dbError) =>
+          console.error('Error saving order to database:', dbError)
+        )
         setStep(2)
       } catch (error) {
         toast.error(
@@ -20,10 +20,10 @@ import {
   getModalPositionClass,
 } from '../../helpers/modalPortal'
 import { nodeApi } from '../../slices/nodeApi/nodeApi.slice'
+import { logger } from '../../utils/logger'
 
 interface ChangePasswordModalProps {
   showModal: boolean
-  accountName: string
   onClose: () => void
 }
 
@@ -41,7 +41,6 @@ const messageOf = (err: unknown, fallback: string): string => {
 
 export const ChangePasswordModal: React.FC<ChangePasswordModalProps> = ({
   showModal,
-  accountName,
   onClose,
 }) => {
   const { t } = useTranslation()
@@ -115,39 +114,60 @@ export const ChangePasswordModal: React.FC<ChangePasswordModalProps> = ({
 
     setIsLoading(true)
 
-    // 1. Verify the current password locally (and grab the mnemonic so we can
-    //    keep the local encrypted copy — used by the recovery-phrase viewer —
-    //    in sync). Restored wallets may have no local mnemonic; that's fine,
-    //    the node still validates the current password during /changepassword.
-    let mnemonic: string | null = null
+    // 1. Re-encrypt the local secrets (the mnemonic copy used by the
+    //    recovery-phrase viewer) under the new password. The backend checks
+    //    the current password by decrypting them and never returns them.
+    //    Restored wallets may have no local mnemonic; that's fine, the node
+    //    still validates the current password during /changepassword.
     try {
-      mnemonic = await invoke<string>('get_decrypted_mnemonic', {
-        password: currentPassword,
+      await invoke<number>('change_password', {
+        newPassword,
+        oldPassword: currentPassword,
       })
     } catch (err) {
-      if (!String(err).includes('No mnemonic stored')) {
-        setError(
-          t(
-            'changePassword.errorWrongPassword',
-            'Current password is incorrect.'
-          )
-        )
-        setIsLoading(false)
-        return
-      }
-      // No local mnemonic — proceed without a local copy to re-encrypt.
+      setError(
+        String(err).includes('incorrect')
+          ? t(
+              'changePassword.errorWrongPassword',
+              'Current password is incorrect.'
+            )
+          : String(err)
+      )
+      setIsLoading(false)
+      return
     }
 
+    // Put the local secrets back as they were, under the current password,
+    // when the node keeps it. Returns a warning for the user if that failed:
+    // the recovery phrase then opens with the new password only.
+    const revertLocal = () =>
+      invoke('revert_password_change', { changedPassword: newPassword })
+        .then(() => null)
+        .catch((err) => {
+          logger.error('Failed to restore local secrets password', err)
+          return t('changePassword.errorRestore', {
+            defaultValue:
+              'Your recovery phrase could not be restored to the current password ({{msg}}); it now opens with the new password.',
+            msg: String(err),
+          })
+        })
+
     // 2. Lock the node — required before /changepassword. If this fails the
     //    node is still unlocked and the session is unharmed.
     setProgress(t('changePassword.progressLocking', 'Locking wallet…'))
     const lockRes = await lock()
     if ('error' in lockRes) {
+      const warning = await revertLocal()
       setError(
-        t('changePassword.errorLock', {
-          defaultValue: 'Could not lock the wallet: {{msg}}',
-          msg: messageOf(lockRes.error, 'unknown error'),
-        })
+        [
+          t('changePassword.errorLock', {
+            defaultValue: 'Could not lock the wallet: {{msg}}',
+            msg: messageOf(lockRes.error, 'unknown error'),
+          }),
+          warning,
+        ]
+          .filter(Boolean)
+          .join(' ')
       )
       setProgress(null)
       setIsLoading(false)
@@ -164,28 +184,18 @@ export const ChangePasswordModal: React.FC<ChangePasswordModalProps> = ({
     if ('error' in cpRes) {
       // Node is now locked but the password is UNCHANGED — the user must
       // unlock with their current password.
-      setError(messageOf(cpRes.error, t('changePassword.errorGeneric')))
+      const warning = await revertLocal()
+      setError(
+        [messageOf(cpRes.error, t('changePassword.errorGeneric')), warning]
+          .filter(Boolean)
+          .join(' ')
+      )
       setPhase('locked-unchanged')
       setProgress(null)
       setIsLoading(false)
       return
     }
 
-    // 4. Best-effort: re-encrypt the local mnemonic copy with the new password
-    //    so the recovery-phrase viewer keeps working. The node password has
-    //    already changed, so a failure here is non-fatal.
-    if (mnemonic) {
-      try {
-        await invoke('store_encrypted_mnemonic', {
-          accountName,
-          mnemonic,
-          password: newPassword,
-        })
-      } catch (err) {
-        console.error('Failed to re-encrypt local mnemonic copy:', err)
-      }
-    }
-
     setPhase('changed')
     setProgress(null)
     setIsLoading(false)
@@ -0,0 +1,107 @@
+import { invoke } from '@tauri-apps/api/core'
+import { open } from '@tauri-apps/plugin-dialog'
+import { FolderOpen, HardDrive } from 'lucide-react'
+import { useEffect, useState } from 'react'
+import { useTranslation } from 'react-i18next'
+import { toast } from 'react-toastify'
+
+type DataRootSource = 'cli_flag' | 'env' | 'portable' | 'moved' | 'default'
+
+/** Where the backend keeps its data (`data_dir::DataLayout`). */
+interface DataLayout {
+  source: DataRootSource
+  root: string
+  db_path: string
+  node_dir: string
+  log_dir: string
+}
+
+// The data root (database, node data and logs) and a way to move it. Roots
+// set by --data-dir, KALEIDO_DATA_DIR or portable mode can't be moved here.
+export const DataDirectorySettings = () => {
+  const { t } = useTranslation()
+  const [layout, setLayout] = useState<DataLayout | null>(null)
+  const [isMoving, setIsMoving] = useState(false)
+
+  useEffect(() => {
+    invoke<DataLayout>('get_data_directory')
+      .then(setLayout)
+      .catch((err) => toast.error(String(err)))
+  }, [])
+
+  if (!layout) return null
+
+  const movable = layout.source === 'moved' || layout.source === 'default'
+
+  const handleMove = async () => {
+    const selected = await open({ directory: true, multiple: false })
+    if (typeof selected !== 'string') return
+    if (
+      !window.confirm(
+        t('dataDirectory.confirmMove', {
+          defaultValue:
+            'Move all app data to {{path}}? The node must be stopped.',
+          path: selected,
+        })
+      )
+    ) {
+      return
+    }
+    setIsMoving(true)
+    try {
+      setLayout(
+        await invoke<DataLayout>('move_data_directory', { path: selected })
+      )
+      toast.success(t('dataDirectory.moved', 'App data moved'))
+    } catch (err) {
+      toast.error(String(err))
+    } finally {
+      setIsMoving(false)
+    }
+  }
+
+  const row = (label: string, value: string) => (
+    <div className="space-y-0.5">
+      <p className="text-xs text-content-tertiary">{label}</p>
+      <p className="text-sm text-white font-mono break-all">{value}</p>
+    </div>
+  )
+
+  return (
+    <section className="overflow-hidden rounded-2xl border border-border-subtle bg-surface-overlay">
+      <div className="flex items-center gap-3 px-5 py-4 border-b border-divider/10">
+        <HardDrive className="w-5 h-5 text-primary flex-shrink-0" />
+        <h2 className="text-base font-bold text-white">
+          {t('dataDirectory.title', 'Data directory')}
+        </h2>
+      </div>
+
+      <div className="p-5 space-y-4">
+        {row(t('dataDirectory.database', 'Database'), layout.db_path)}
+        {row(t('dataDirectory.nodeData', 'Node data'), layout.node_dir)}
+        {row(t('dataDirectory.logs', 'Logs'), layout.log_dir)}
+
+        {movable ? (
+          <button
+            className="w-full inline-flex items-center justify-center gap-2 h-10 rounded-lg border border-border-default/50 text-sm font-semibold text-white hover:bg-surface-overlay/50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+            disabled={isMoving}
+            onClick={handleMove}
+            type="button"
+          >
+            <FolderOpen className="w-4 h-4" />
+            {isMoving
+              ? t('dataDirectory.moving', 'Moving…')
+              : t('dataDirectory.move', 'Move…')}
+          </button>
+        ) : (
+          <p className="text-xs text-content-tertiary">
+            {t(
+              'dataDirectory.pinned',
+              'Set by --data-dir, KALEIDO_DATA_DIR or portable mode; change it there.'
+            )}
+          </p>
+        )}
+      </div>
+    </section>
+  )
+}
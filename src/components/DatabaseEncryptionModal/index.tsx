@@ -0,0 +1,310 @@
+import { invoke } from '@tauri-apps/api/core'
+import {
+  CheckCircle2,
+  Database,
+  Eye,
+  EyeOff,
+  Loader2,
+  LockOpen,
+  X,
+} from 'lucide-react'
+import { useEffect, useState } from 'react'
+import { createPortal } from 'react-dom'
+import { useTranslation } from 'react-i18next'
+
+import {
+  getModalPortalTarget,
+  getModalPositionClass,
+} from '../../helpers/modalPortal'
+import { DbStatus } from '../DatabaseGate'
+
+interface DatabaseEncryptionModalProps {
+  showModal: boolean
+  onClose: () => void
+}
+
+type Phase = 'form' | 'done'
+
+// Encrypts the app database (accounts, node URLs and tokens, NWC secrets,
+// order history) with a password, changes that password, or removes it.
+// Separate from the wallet password, which only protects the node.
+export const DatabaseEncryptionModal: React.FC<
+  DatabaseEncryptionModalProps
+> = ({ showModal, onClose }) => {
+  const { t } = useTranslation()
+
+  const [encrypted, setEncrypted] = useState<boolean | null>(null)
+  const [currentPassword, setCurrentPassword] = useState('')
+  const [newPassword, setNewPassword] = useState('')
+  const [confirmPassword, setConfirmPassword] = useState('')
+  const [showPasswords, setShowPasswords] = useState(false)
+  const [isLoading, setIsLoading] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  const [phase, setPhase] = useState<Phase>('form')
+  const [doneMessage, setDoneMessage] = useState('')
+
+  useEffect(() => {
+    if (!showModal) return
+    invoke<DbStatus>('db_status')
+      .then((status) => setEncrypted(status.encrypted))
+      .catch((err) => setError(String(err)))
+  }, [showModal])
+
+  if (!showModal) return null
+
+  const reset = () => {
+    setCurrentPassword('')
+    setNewPassword('')
+    setConfirmPassword('')
+    setError(null)
+    setPhase('form')
+    setIsLoading(false)
+  }
+
+  const handleClose = () => {
+    reset()
+    onClose()
+  }
+
+  const run = async (command: string, args: object, message: string) => {
+    setError(null)
+    setIsLoading(true)
+    try {
+      await invoke(command, args)
+      setDoneMessage(message)
+      setPhase('done')
+    } catch (err) {
+      setError(String(err))
+      // A failed re-key can leave the database closed: hand over to the
+      // gate, which asks for the password again
+      const status = await invoke<DbStatus>('db_status').catch(() => null)
+      if (status && !status.unlocked) window.location.reload()
+    } finally {
+      setIsLoading(false)
+    }
+  }
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault()
+    if (newPassword !== confirmPassword) {
+      setError(
+        t('database.encryption.errorMismatch', 'Passwords do not match.')
+      )
+      return
+    }
+    if (newPassword.length < 8) {
+      setError(
+        t(
+          'database.encryption.errorTooShort',
+          'Password must be at least 8 characters.'
+        )
+      )
+      return
+    }
+    if (encrypted) {
+      await run(
+        'db_change_key',
+        { currentPassword, newPassword },
+        t(
+          'database.encryption.changed',
+          'The database password has been changed.'
+        )
+      )
+    } else {
+      await run(
+        'db_enable_encryption',
+        { password: newPassword },
+        t(
+          'database.encryption.enabled',
+          'Your app data is now encrypted. You will be asked for this password every time the app starts.'
+        )
+      )
+    }
+  }
+
+  const handleDisable = () =>
+    run(
+      'db_change_key',
+      { currentPassword, newPassword: null },
+      t(
+        'database.encryption.disabled',
+        'Encryption has been removed. Your app data is stored in plaintext.'
+      )
+    )
+
+  const pos = getModalPositionClass()
+
+  const passwordInput = (
+    label: string,
+    value: string,
+    onChange: (v: string) => void,
+    autoComplete: string
+  ) => (
+    <div className="space-y-1.5">
+      <label className="text-xs font-medium text-content-secondary uppercase tracking-wider">
+        {label}
+      </label>
+      <div className="relative">
+        <input
+          autoComplete={autoComplete}
+          className="w-full rounded-lg border border-border-default/50 bg-surface-overlay/40 px-3 py-2.5 pr-10 text-sm text-white placeholder:text-content-tertiary focus:border-primary/50 focus:outline-none focus:ring-1 focus:ring-primary/30 transition-colors"
+          disabled={isLoading}
+          onChange={(e) => onChange(e.target.value)}
+          placeholder="••••••••"
+          type={showPasswords ? 'text' : 'password'}
+          value={value}
+        />
+        <button
+          className="absolute right-3 top-1/2 -translate-y-1/2 text-content-tertiary hover:text-content-secondary transition-colors"
+          onClick={() => setShowPasswords(!showPasswords)}
+          tabIndex={-1}
+          type="button"
+        >
+          {showPasswords ? (
+            <EyeOff className="w-4 h-4" />
+          ) : (
+            <Eye className="w-4 h-4" />
+          )}
+        </button>
+      </div>
+    </div>
+  )
+
+  const renderBody = () => {
+    if (phase === 'done') {
+      return (
+        <div className="flex flex-col items-center gap-4 py-6 text-center">
+          <div className="w-14 h-14 rounded-2xl bg-status-success/10 border border-status-success/20 flex items-center justify-center">
+            <CheckCircle2 className="w-7 h-7 text-status-success" />
+          </div>
+          <p className="text-sm text-content-secondary">{doneMessage}</p>
+          <button
+            className="inline-flex h-10 items-center justify-center gap-2 rounded-md bg-primary hover:bg-primary-emphasis px-6 text-sm font-semibold text-primary-foreground transition-colors"
+            onClick={handleClose}
+            type="button"
+          >
+            {t('database.encryption.close', 'Close')}
+          </button>
+        </div>
+      )
+    }
+
+    if (encrypted === null) {
+      return (
+        <div className="flex justify-center py-8">
+          {error ? (
+            <p className="text-sm text-status-danger">{error}</p>
+          ) : (
+            <Loader2 className="w-6 h-6 animate-spin text-primary" />
+          )}
+        </div>
+      )
+    }
+
+    return (
+      <form className="space-y-4" onSubmit={handleSubmit}>
+        <p className="text-sm text-content-secondary leading-relaxed">
+          {encrypted
+            ? t(
+                'database.encryption.descriptionEncrypted',
+                'Your app data is encrypted. Enter the current password to choose a new one, or to remove encryption.'
+              )
+            : t(
+                'database.encryption.description',
+                'Encrypt accounts, node credentials, NWC secrets and order history with a password. It is asked for on every start and cannot be recovered if lost.'
+              )}
+        </p>
+
+        {encrypted &&
+          passwordInput(
+            t('database.encryption.currentPassword', 'Current password'),
+            currentPassword,
+            setCurrentPassword,
+            'current-password'
+          )}
+        {passwordInput(
+          t('database.encryption.newPassword', 'New password'),
+          newPassword,
+          setNewPassword,
+          'new-password'
+        )}
+        {passwordInput(
+          t('database.encryption.confirmPassword', 'Confirm new password'),
+          confirmPassword,
+          setConfirmPassword,
+          'new-password'
+        )}
+
+        {error && (
+          <p className="text-sm text-status-danger leading-relaxed">{error}</p>
+        )}
+
+        <div className="space-y-2 pt-2">
+          <button
+            className="w-full flex items-center justify-center gap-2 px-4 py-2.5 bg-primary hover:bg-primary-emphasis text-primary-foreground rounded-md font-semibold transition-colors text-sm disabled:opacity-50 disabled:cursor-not-allowed"
+            disabled={
+              isLoading ||
+              (encrypted && !currentPassword) ||
+              !newPassword ||
+              !confirmPassword
+            }
+            type="submit"
+          >
+            {isLoading ? (
+              <Loader2 className="w-4 h-4 animate-spin" />
+            ) : (
+              <Database className="w-4 h-4" />
+            )}
+            {encrypted
+              ? t('database.encryption.change', 'Change password')
+              : t('database.encryption.enable', 'Encrypt database')}
+          </button>
+          {encrypted && (
+            <button
+              className="w-full flex items-center justify-center gap-2 px-4 py-2.5 rounded-md border border-status-danger/20 bg-status-danger/10 text-sm font-medium text-status-danger hover:bg-status-danger/20 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+              disabled={isLoading || !currentPassword}
+              onClick={handleDisable}
+              type="button"
+            >
+              <LockOpen className="w-4 h-4" />
+              {t('database.encryption.disable', 'Remove encryption')}
+            </button>
+          )}
+        </div>
+      </form>
+    )
+  }
+
+  const dismissable = !isLoading
+
+  return createPortal(
+    <div
+      className={`${pos} inset-0 bg-surface-base/80 backdrop-blur-sm flex items-center justify-center z-50 p-4`}
+      onMouseDown={(e) =>
+        dismissable && e.target === e.currentTarget && handleClose()
+      }
+    >
+      <div className="w-full max-w-lg bg-surface-base rounded-3xl border border-border-subtle/50 shadow-2xl shadow-black/20 overflow-hidden relative">
+        <div className="max-h-[85vh] overflow-y-auto px-8 py-8">
+          <div className="flex items-center gap-3 pb-4 border-b border-divider/10 mb-6">
+            <Database className="w-6 h-6 text-primary" />
+            <h3 className="text-xl font-bold text-white flex-1">
+              {t('database.encryption.title', 'Database Encryption')}
+            </h3>
+            {dismissable && (
+              <button
+                className="p-1.5 rounded-md text-content-secondary hover:text-white hover:bg-surface-overlay/50 transition-colors"
+                onClick={handleClose}
+                type="button"
+              >
+                <X className="w-4 h-4" />
+              </button>
+            )}
+          </div>
+          {renderBody()}
+        </div>
+      </div>
+    </div>,
+    getModalPortalTarget()
+  )
+}
@@ -0,0 +1,133 @@
+import { invoke } from '@tauri-apps/api/core'
+import { Database, Loader2, Lock } from 'lucide-react'
+import { ReactNode, useEffect, useState } from 'react'
+import { useTranslation } from 'react-i18next'
+
+import { Alert, Button, PasswordInput } from '../ui'
+
+export interface DbStatus {
+  encrypted: boolean
+  error?: string
+  unlocked: boolean
+}
+
+interface Props {
+  children: ReactNode
+}
+
+// An encrypted app database stays closed until it is unlocked here; nothing
+// below the gate (accounts, orders, settings) can be read before that.
+export const DatabaseGate = ({ children }: Props) => {
+  const { t } = useTranslation()
+  const [status, setStatus] = useState<DbStatus | null>(null)
+  const [password, setPassword] = useState('')
+  const [showPassword, setShowPassword] = useState(false)
+  const [isUnlocking, setIsUnlocking] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+
+  useEffect(() => {
+    invoke<DbStatus>('db_status')
+      .then((status) => {
+        setStatus(status)
+        setError(status.error ?? null)
+      })
+      .catch((err) => {
+        console.error('db_status failed:', err)
+        // Let the app surface its own errors rather than block on the gate
+        setStatus({ encrypted: false, unlocked: true })
+      })
+  }, [])
+
+  if (!status) {
+    return (
+      <div className="flex h-screen items-center justify-center bg-surface-base">
+        <Loader2 className="h-6 w-6 animate-spin text-primary" />
+      </div>
+    )
+  }
+
+  if (status.unlocked || (!status.encrypted && !status.error)) {
+    return <>{children}</>
+  }
+
+  const handleUnlock = async (e: React.FormEvent) => {
+    e.preventDefault()
+    setError(null)
+    setIsUnlocking(true)
+    try {
+      await invoke('db_unlock', { password })
+      setPassword('')
+      setStatus({ encrypted: true, unlocked: true })
+    } catch (err) {
+      setError(String(err))
+    } finally {
+      setIsUnlocking(false)
+    }
+  }
+
+  if (!status.encrypted) {
+    // A plaintext database that failed to reopen: it opens on the next start
+    return (
+      <div className="flex h-screen items-center justify-center bg-surface-base p-4">
+        <div className="w-full max-w-md space-y-3">
+          <Alert variant="error">{error}</Alert>
+          <p className="text-center text-sm text-content-secondary">
+            {t(
+              'database.unlock.restart',
+              'The app data could not be reopened. Restart the app to continue.'
+            )}
+          </p>
+        </div>
+      </div>
+    )
+  }
+
+  return (
+    <div className="flex h-screen items-center justify-center bg-surface-base p-4">
+      <form
+        className="w-full max-w-md space-y-5 rounded-3xl border border-border-subtle/50 bg-surface-overlay p-8 shadow-2xl shadow-black/20"
+        onSubmit={handleUnlock}
+      >
+        <div className="flex flex-col items-center gap-3 text-center">
+          <div className="flex h-14 w-14 items-center justify-center rounded-2xl border border-primary/20 bg-primary/10">
+            <Database className="h-7 w-7 text-primary" />
+          </div>
+          <h1 className="text-xl font-bold text-white">
+            {t('database.unlock.title', 'Unlock app data')}
+          </h1>
+          <p className="text-sm leading-relaxed text-content-secondary">
+            {t(
+              'database.unlock.description',
+              'Your accounts, orders and settings are encrypted. Enter the database password to continue.'
+            )}
+          </p>
+        </div>
+
+        <PasswordInput
+          autoComplete="current-password"
+          autoFocus
+          disabled={isUnlocking}
+          isVisible={showPassword}
+          onChange={(e) => setPassword(e.target.value)}
+          onToggleVisibility={() => setShowPassword(!showPassword)}
+          placeholder={t('database.unlock.password', 'Database password')}
+          value={password}
+        />
+
+        {error && <Alert variant="error">{error}</Alert>}
+
+        <Button
+          disabled={!password}
+          fullWidth
+          icon={<Lock className="h-4 w-4" />}
+          isLoading={isUnlocking}
+          type="submit"
+        >
+          {isUnlocking
+            ? t('database.unlock.unlocking', 'Unlocking…')
+            : t('database.unlock.submit', 'Unlock')}
+        </Button>
+      </form>
+    </div>
+  )
+}
@@ -0,0 +1,227 @@
+import { invoke } from '@tauri-apps/api/core'
+import { open } from '@tauri-apps/plugin-dialog'
+import { Download, FolderOpen, Terminal, Upload } from 'lucide-react'
+import { useEffect, useState } from 'react'
+import { useTranslation } from 'react-i18next'
+import { toast } from 'react-toastify'
+
+import { getNetworkDisplayName } from '../../constants'
+import { NETWORK_DEFAULTS, getDefaultMakerUrls } from '../../constants/networks'
+
+/** A kaleido-cli environment (`kaleido_cli::CliEnvironment`). */
+interface CliEnvironment {
+  env_name: string
+  account_name: string
+  network: string | null
+  daemon_port: number | null
+  peer_port: number | null
+  node_count: number
+  has_data: boolean
+  account_exists: boolean
+}
+
+// Node environments shared with kaleido-cli: import the ones in a kaleido-cli
+// config directory (~/.kaleido by default) as Docker accounts, or export an
+// account there.
+export const KaleidoCliSettings = () => {
+  const { t } = useTranslation()
+  const [dir, setDir] = useState<string | null>(null)
+  const [environments, setEnvironments] = useState<CliEnvironment[]>([])
+  const [accounts, setAccounts] = useState<string[]>([])
+  const [exportAccount, setExportAccount] = useState('')
+  const [busy, setBusy] = useState<string | null>(null)
+
+  const scan = async (path: string | null) => {
+    try {
+      setEnvironments(
+        await invoke<CliEnvironment[]>('cli_scan_environments', { dir: path })
+      )
+    } catch (err) {
+      setEnvironments([])
+      toast.error(String(err))
+    }
+  }
+
+  const loadAccounts = () =>
+    invoke<{ name: string }[]>('get_accounts')
+      .then((list) => {
+        setAccounts(list.map((account) => account.name))
+        setExportAccount((current) => current || list[0]?.name || '')
+      })
+      .catch((err) => toast.error(String(err)))
+
+  useEffect(() => {
+    scan(null)
+    loadAccounts()
+  }, [])
+
+  const pickDirectory = async () => {
+    const selected = await open({ directory: true, multiple: false })
+    return typeof selected === 'string' ? selected : null
+  }
+
+  const handleBrowse = async () => {
+    const selected = await pickDirectory()
+    if (!selected) return
+    setDir(selected)
+    await scan(selected)
+  }
+
+  const handleImport = async (env: CliEnvironment) => {
+    if (!env.network) return
+    const defaults = NETWORK_DEFAULTS[env.network]
+    setBusy(env.env_name)
+    try {
+      await invoke('cli_import_environment', {
+        account: {
+          default_lsp_url: defaults.default_lsp_url,
+          default_maker_url: defaults.default_maker_url,
+          indexer_url: defaults.indexer_url,
+          maker_urls: getDefaultMakerUrls(env.network).join(','),
+          name: env.account_name,
+          network: env.network,
+          proxy_endpoint: defaults.proxy_endpoint,
+          rpc_connection_url: defaults.rpc_connection_url,
+        },
+        dir,
+        envName: env.env_name,
+      })
+      toast.success(
+        t('kaleidoCli.imported', {
+          defaultValue: 'Imported {{name}}',
+          name: env.account_name,
+        })
+      )
+      await Promise.all([scan(dir), loadAccounts()])
+    } catch (err) {
+      toast.error(String(err))
+    } finally {
+      setBusy(null)
+    }
+  }
+
+  const handleExport = async () => {
+    if (!exportAccount) return
+    const selected = await pickDirectory()
+    if (!selected) return
+    setBusy('export')
+    try {
+      const path = await invoke<string>('cli_export_account', {
+        accountName: exportAccount,
+        dir: selected,
+      })
+      toast.success(
+        t('kaleidoCli.exported', {
+          defaultValue: 'Exported to {{path}}',
+          path,
+        })
+      )
+    } catch (err) {
+      toast.error(String(err))
+    } finally {
+      setBusy(null)
+    }
+  }
+
+  const buttonClass =
+    'inline-flex items-center justify-center gap-2 h-9 px-3 rounded-lg border border-border-default/50 text-sm font-semibold text-white hover:bg-surface-overlay/50 transition-colors disabled:opacity-50 disabled:cursor-not-allowed'
+
+  return (
+    <section className="overflow-hidden rounded-2xl border border-border-subtle bg-surface-overlay">
+      <div className="flex items-center gap-3 px-5 py-4 border-b border-divider/10">
+        <Terminal className="w-5 h-5 text-primary flex-shrink-0" />
+        <h2 className="text-base font-bold text-white">
+          {t('kaleidoCli.title', 'kaleido-cli environments')}
+        </h2>
+      </div>
+
+      <div className="p-5 space-y-5">
+        <div className="space-y-3">
+          <div className="flex items-center justify-between gap-3">
+            <p className="text-xs text-content-tertiary break-all">
+              {dir ??
+                t('kaleidoCli.defaultDir', 'Default kaleido-cli directory')}
+            </p>
+            <button
+              className={buttonClass}
+              disabled={busy !== null}
+              onClick={handleBrowse}
+              type="button"
+            >
+              <FolderOpen className="w-4 h-4" />
+              {t('kaleidoCli.browse', 'Browse…')}
+            </button>
+          </div>
+
+          {environments.length === 0 ? (
+            <p className="text-sm text-content-tertiary">
+              {t('kaleidoCli.empty', 'No environments found')}
+            </p>
+          ) : (
+            environments.map((env) => (
+              <div
+                className="flex items-center justify-between gap-3 rounded-lg border border-border-subtle px-3 py-2"
+                key={env.env_name}
+              >
+                <div className="min-w-0">
+                  <p className="text-sm text-white font-mono truncate">
+                    {env.env_name}
+                  </p>
+                  <p className="text-xs text-content-tertiary">
+                    {env.network
+                      ? getNetworkDisplayName(env.network)
+                      : t('kaleidoCli.unknownNetwork', 'Unknown network')}
+                    {env.daemon_port ? ` · :${env.daemon_port}` : ''}
+                    {env.has_data
+                      ? ''
+                      : ` · ${t('kaleidoCli.noData', 'not initialized')}`}
+                  </p>
+                </div>
+                <button
+                  className={buttonClass}
+                  disabled={
+                    busy !== null || env.account_exists || !env.network
+                  }
+                  onClick={() => handleImport(env)}
+                  type="button"
+                >
+                  <Download className="w-4 h-4" />
+                  {env.account_exists
+                    ? t('kaleidoCli.alreadyImported', 'Imported')
+                    : t('kaleidoCli.import', 'Import')}
+                </button>
+              </div>
+            ))
+          )}
+        </div>
+
+        {accounts.length > 0 && (
+          <div className="flex items-center gap-3 pt-4 border-t border-divider/10">
+            <select
+              className="flex-1 h-9 rounded-lg bg-surface-base border border-border-default/50 px-3 text-sm text-white"
+              onChange={(e) => setExportAccount(e.target.value)}
+              value={exportAccount}
+            >
+              {accounts.map((name) => (
+                <option key={name} value={name}>
+                  {name}
+                </option>
+              ))}
+            </select>
+            <button
+              className={buttonClass}
+              disabled={busy !== null || !exportAccount}
+              onClick={handleExport}
+              type="button"
+            >
+              <Upload className="w-4 h-4" />
+              {busy === 'export'
+                ? t('kaleidoCli.exporting', 'Exporting…')
+                : t('kaleidoCli.export', 'Export…')}
+            </button>
+          </div>
+        )}
+      </div>
+    </section>
+  )
+}
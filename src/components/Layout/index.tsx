@@ -43,11 +43,14 @@ import { uiSliceActions } from '../../slices/ui/ui.slice'
 import { AppVersion } from '../AppVersion'
 import { BackupModal } from '../BackupModal'
 import { useBackup } from '../../hooks/useBackup'
+import { useChannelOrderWatcher } from '../../hooks/useChannelOrderWatcher'
 import { useDcaScheduler } from '../../hooks/useDcaScheduler'
 import { useLimitOrderScheduler } from '../../hooks/useLimitOrderScheduler'
 import { useNwcAutostart } from '../../hooks/useNwcAutostart'
 import { useNodeReachabilityMonitor } from '../../hooks/useNodeReachabilityMonitor'
 import { useNodeReconnect } from '../../hooks/useNodeReconnect'
+import { useTradeLedgerSync } from '../../hooks/useTradeLedgerSync'
+import { useVaultSession } from '../../hooks/useVaultSession'
 import { LogoutModal, LogoutButton } from '../LogoutModal'
 import { useNotification } from '../NotificationSystem'
 import { ShutdownAnimation } from '../ShutdownAnimation'
@@ -591,8 +594,11 @@ export const Layout = (props: Props) => {
 
   useDcaScheduler()
   useLimitOrderScheduler()
+  useChannelOrderWatcher()
   useNwcAutostart()
   useNodeLifecycleEvents()
+  useTradeLedgerSync()
+  useVaultSession()
 
   const [showLogoutModal, setShowLogoutModal] = useState(false)
   const [isLoggingOut, setIsLoggingOut] = useState(false)
@@ -693,6 +699,7 @@ export const Layout = (props: Props) => {
     try {
       setIsLoggingOut(true)
       const lockResponse = await lock().unwrap()
+      await invoke('vault_lock').catch(() => undefined)
       await invoke('stop_node')
 
       if (lockResponse !== undefined || lockResponse === null) {
@@ -54,10 +54,14 @@ export const MnemonicViewerModal: React.FC<MnemonicViewerModalProps> = ({
     setIsLoading(true)
     setError(null)
     try {
-      const decryptedMnemonic = await invoke<string>('get_decrypted_mnemonic', {
+      // The phrase comes from the session vault, which checks the password
+      // again; a vault that locked since is unlocked with it first
+      const vault = await invoke<{ unlocked: boolean }>('vault_status')
+      if (!vault.unlocked) await invoke('vault_unlock', { password })
+      const revealed = await invoke<string>('vault_reveal_mnemonic', {
         password,
       })
-      setMnemonic(decryptedMnemonic)
+      setMnemonic(revealed)
       setStep('display')
       toast.success(t('mnemonicViewer.retrievedSuccess'))
     } catch (err) {
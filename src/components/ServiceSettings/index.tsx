@@ -0,0 +1,211 @@
+import { Save, SlidersHorizontal } from 'lucide-react'
+import { useEffect, useState } from 'react'
+import { useTranslation } from 'react-i18next'
+import { toast } from 'react-toastify'
+
+import {
+  AppSettings,
+  NodeBackendType,
+  useAppSettings,
+} from '../../hooks/useAppSettings'
+
+const inputCls =
+  'w-full px-4 py-2.5 text-sm text-white bg-surface-overlay/30 border border-border-default/50 rounded-lg focus:outline-none focus:ring-2 focus:ring-primary/20 focus:border-primary transition-colors'
+
+interface Draft {
+  pollInterval: string
+  jitter: string
+  relays: string
+  nodeBackend: NodeBackendType
+  logRetention: string
+  vaultIdleTimeout: string
+}
+
+const toDraft = (settings: AppSettings): Draft => ({
+  jitter: String(settings.dca.jitter_secs),
+  logRetention: String(settings.log_retention_days),
+  nodeBackend: settings.node_backend,
+  pollInterval: String(settings.dca.poll_interval_secs),
+  relays: settings.nwc_relays.join('\n'),
+  vaultIdleTimeout: String(settings.vault_idle_timeout_mins),
+})
+
+// Settings stored and validated by the backend: background services (DCA
+// scheduler, NWC service), the default local node backend, log retention and
+// when the session vault locks.
+export const ServiceSettings = () => {
+  const { t } = useTranslation()
+  const { settings, updateSettings } = useAppSettings()
+  const [draft, setDraft] = useState<Draft | null>(null)
+  const [isSaving, setIsSaving] = useState(false)
+
+  useEffect(() => {
+    if (settings) setDraft(toDraft(settings))
+  }, [settings])
+
+  if (!draft) return null
+
+  const set = (field: keyof Draft) => (value: string) =>
+    setDraft({ ...draft, [field]: value })
+
+  const handleSave = async () => {
+    setIsSaving(true)
+    try {
+      await updateSettings({
+        dca: {
+          jitter_secs: Number(draft.jitter),
+          poll_interval_secs: Number(draft.pollInterval),
+        },
+        log_retention_days: Number(draft.logRetention),
+        node_backend: draft.nodeBackend,
+        nwc_relays: draft.relays
+          .split('\n')
+          .map((relay) => relay.trim())
+          .filter(Boolean),
+        vault_idle_timeout_mins: Number(draft.vaultIdleTimeout),
+      })
+      toast.success(t('serviceSettings.saved', 'Settings saved'))
+    } catch (err) {
+      toast.error(String(err))
+    } finally {
+      setIsSaving(false)
+    }
+  }
+
+  const numberField = (
+    label: string,
+    hint: string,
+    value: string,
+    onChange: (v: string) => void
+  ) => (
+    <div className="space-y-1.5">
+      <label className="block text-sm font-medium text-content-secondary">
+        {label}
+      </label>
+      <p className="text-xs text-content-tertiary">{hint}</p>
+      <input
+        className={inputCls}
+        min={0}
+        onChange={(e) => onChange(e.target.value)}
+        type="number"
+        value={value}
+      />
+    </div>
+  )
+
+  return (
+    <section className="overflow-hidden rounded-2xl border border-border-subtle bg-surface-overlay">
+      <div className="flex items-center gap-3 px-5 py-4 border-b border-divider/10">
+        <SlidersHorizontal className="w-5 h-5 text-primary flex-shrink-0" />
+        <h2 className="text-base font-bold text-white">
+          {t('serviceSettings.title', 'Services')}
+        </h2>
+      </div>
+
+      <div className="p-5 space-y-5">
+        <div className="grid grid-cols-1 sm:grid-cols-2 gap-5">
+          {numberField(
+            t(
+              'serviceSettings.dcaPollInterval',
+              'DCA check interval (seconds)'
+            ),
+            t(
+              'serviceSettings.dcaPollIntervalHint',
+              'How often price-based DCA orders are checked (5–3600).'
+            ),
+            draft.pollInterval,
+            set('pollInterval')
+          )}
+          {numberField(
+            t('serviceSettings.dcaJitter', 'DCA jitter (seconds)'),
+            t(
+              'serviceSettings.dcaJitterHint',
+              'Random delay added to each check, at most the interval.'
+            ),
+            draft.jitter,
+            set('jitter')
+          )}
+        </div>
+
+        <div className="space-y-1.5">
+          <label className="block text-sm font-medium text-content-secondary">
+            {t('serviceSettings.nwcRelays', 'NWC relays')}
+          </label>
+          <p className="text-xs text-content-tertiary">
+            {t(
+              'serviceSettings.nwcRelaysHint',
+              'One wss:// URL per line. Leave empty for the default relays. Applies the next time the service starts.'
+            )}
+          </p>
+          <textarea
+            className={`${inputCls} font-mono`}
+            onChange={(e) => set('relays')(e.target.value)}
+            placeholder="wss://relay.example.com"
+            rows={3}
+            value={draft.relays}
+          />
+        </div>
+
+        <div className="space-y-1.5">
+          <label className="block text-sm font-medium text-content-secondary">
+            {t('serviceSettings.nodeBackend', 'Default local node backend')}
+          </label>
+          <div className="flex gap-1 rounded-xl bg-surface-base/35 p-1 w-fit">
+            {(['native', 'docker'] as NodeBackendType[]).map((backend) => (
+              <button
+                className={`inline-flex items-center rounded-lg px-3 py-1.5 text-sm font-medium transition-all duration-200 focus:outline-none ${
+                  draft.nodeBackend === backend
+                    ? 'bg-primary/15 text-primary border border-primary/30'
+                    : 'text-content-secondary hover:text-white border border-transparent'
+                }`}
+                key={backend}
+                onClick={() => setDraft({ ...draft, nodeBackend: backend })}
+                type="button"
+              >
+                {backend === 'native'
+                  ? t('serviceSettings.native', 'Native')
+                  : t('serviceSettings.docker', 'Docker')}
+              </button>
+            ))}
+          </div>
+        </div>
+
+        <div className="grid grid-cols-1 sm:grid-cols-2 gap-5">
+          {numberField(
+            t('serviceSettings.logRetention', 'Keep logs for (days)'),
+            t(
+              'serviceSettings.logRetentionHint',
+              'Older log files are deleted (1–365).'
+            ),
+            draft.logRetention,
+            set('logRetention')
+          )}
+          {numberField(
+            t(
+              'serviceSettings.vaultIdleTimeout',
+              'Lock session after (minutes)'
+            ),
+            t(
+              'serviceSettings.vaultIdleTimeoutHint',
+              'The session locks after this long without use, and on sleep (1–1440).'
+            ),
+            draft.vaultIdleTimeout,
+            set('vaultIdleTimeout')
+          )}
+        </div>
+
+        <button
+          className="w-full inline-flex items-center justify-center gap-2 h-10 rounded-lg bg-primary hover:bg-primary-emphasis text-sm font-semibold text-primary-foreground transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+          disabled={isSaving}
+          onClick={handleSave}
+          type="button"
+        >
+          <Save className="w-4 h-4" />
+          {isSaving
+            ? t('serviceSettings.saving', 'Saving…')
+            : t('serviceSettings.save', 'Save')}
+        </button>
+      </div>
+    </section>
+  )
+}
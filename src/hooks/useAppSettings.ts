@@ -0,0 +1,53 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { useCallback, useEffect, useState } from 'react'
+
+import { logger } from '../utils/logger'
+
+export type NodeBackendType = 'native' | 'docker'
+
+/** Mirrors `settings::Settings` in the backend. */
+export interface AppSettings {
+  dca: {
+    poll_interval_secs: number
+    jitter_secs: number
+  }
+  nwc_relays: string[]
+  node_backend: NodeBackendType
+  log_retention_days: number
+  vault_idle_timeout_mins: number
+}
+
+export type AppSettingsPatch = Partial<Omit<AppSettings, 'dca'>> & {
+  dca?: Partial<AppSettings['dca']>
+}
+
+/**
+ * Backend-stored app settings, kept current through `settings:changed` so
+ * every consumer sees a change made anywhere. `updateSettings` sends only the
+ * changed fields and rejects with the backend's validation error.
+ */
+export function useAppSettings() {
+  const [settings, setSettings] = useState<AppSettings | null>(null)
+
+  useEffect(() => {
+    invoke<AppSettings>('get_settings')
+      .then(setSettings)
+      .catch((err) => logger.error('get_settings failed', err))
+
+    const unlisten = listen<AppSettings>('settings:changed', (event) =>
+      setSettings(event.payload)
+    )
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
+  const updateSettings = useCallback(
+    (patch: AppSettingsPatch) =>
+      invoke<AppSettings>('update_settings', { patch }),
+    []
+  )
+
+  return { settings, updateSettings }
+}
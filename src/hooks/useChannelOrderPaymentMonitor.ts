@@ -4,7 +4,10 @@ import type {
   Lsps1GetOrderRequest,
   Lsps1GetOrderResponse,
 } from '../slices/makerApi/makerApi.slice'
-import { persistChannelOrder } from '../utils/channelOrderPersistence'
+import {
+  persistChannelOrder,
+  updateChannelOrderStatus,
+} from '../utils/channelOrderPersistence'
 import {
   getChannelOrderPaymentSnapshot,
   getChannelOrderTerminalStatus,
@@ -169,11 +172,21 @@ export const useChannelOrderPaymentMonitor = ({
         }
 
         reachedTerminalState = true
-        // Only persist orders that succeeded or where a payment was actually
-        // made. Failed/expired orders with no payment produce empty "Unknown"
-        // rows in history, so we skip saving them.
+        // Orders that succeeded or were paid are saved in full. The order was
+        // stored when created, so one that failed or expired unpaid only gets
+        // its final state (history hides it).
         if (terminalStatus === 'success' || paymentReceivedRef.current) {
           await persistOrder(orderData)
+        } else {
+          await updateChannelOrderStatus(orderId, {
+            payment_state: paymentSnapshot.actualPaymentState,
+            status:
+              terminalStatus === 'expired'
+                ? 'EXPIRED'
+                : (orderData.order_state ?? 'FAILED'),
+          }).catch((error) =>
+            console.error('Error updating channel order status:', error)
+          )
         }
         setPaymentStatus(terminalStatus)
         onTerminalStateRef.current?.(terminalStatus, orderData)
@@ -0,0 +1,84 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { useEffect } from 'react'
+import { useTranslation } from 'react-i18next'
+import { toast } from 'react-toastify'
+
+import { useAppSelector } from '../app/store/hooks'
+import { nodeApi } from '../slices/nodeApi/nodeApi.slice'
+import type { StoredChannelOrder } from '../utils/channelOrderPersistence'
+import { logger } from '../utils/logger'
+
+type ChannelOrderEvent = {
+  order_id: string
+  order: StoredChannelOrder
+}
+
+const shortId = (orderId: string) =>
+  orderId.length > 12 ? `${orderId.slice(0, 8)}…` : orderId
+
+/**
+ * Runs the backend channel-order poller while the node is unlocked. It
+ * refreshes stored LSP orders until they complete, fail or expire, and this
+ * hook tells the user when one waits for payment or expired unpaid.
+ */
+export function useChannelOrderWatcher() {
+  const { t } = useTranslation()
+  const accountName = useAppSelector((s) => s.nodeSettings.data.name)
+
+  const { data: nodeInfoData, isSuccess: nodeInfoSuccess } =
+    nodeApi.endpoints.nodeInfo.useQuery(undefined, {
+      pollingInterval: 30_000,
+      skip: !accountName,
+    })
+  const isNodeReady = nodeInfoSuccess && !!(nodeInfoData as any)?.pubkey
+
+  useEffect(() => {
+    if (isNodeReady && accountName) {
+      invoke('channel_orders_start_poller').catch((err) =>
+        logger.error('channel_orders_start_poller failed', err)
+      )
+    } else {
+      invoke('channel_orders_stop_poller').catch((err) =>
+        logger.error('channel_orders_stop_poller failed', err)
+      )
+    }
+  }, [accountName, isNodeReady])
+
+  useEffect(() => {
+    const unlistenPayment = listen<ChannelOrderEvent>(
+      'channel-order:payment-required',
+      (event) => {
+        const { order, order_id } = event.payload
+        toast.info(
+          order.order_total_sat
+            ? t('channelOrderWatcher.paymentRequiredAmount', {
+                amount: order.order_total_sat.toLocaleString(),
+                id: shortId(order_id),
+              })
+            : t('channelOrderWatcher.paymentRequired', {
+                id: shortId(order_id),
+              }),
+          { toastId: `channel-order-payment-${order_id}` }
+        )
+      }
+    )
+
+    const unlistenExpired = listen<ChannelOrderEvent>(
+      'channel-order:expired',
+      (event) => {
+        const { order_id } = event.payload
+        logger.info(`Channel order ${order_id} expired unpaid`)
+        toast.warn(
+          t('channelOrderWatcher.expired', { id: shortId(order_id) }),
+          { toastId: `channel-order-expired-${order_id}` }
+        )
+      }
+    )
+
+    return () => {
+      unlistenPayment.then((fn) => fn())
+      unlistenExpired.then((fn) => fn())
+    }
+  }, [t])
+}
@@ -1,14 +1,22 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { useEffect, useRef } from 'react'
 import { toast } from 'react-toastify'
 
 import { store } from '../app/store'
 import { useAppDispatch, useAppSelector } from '../app/store/hooks'
 import {
+  completeOrder,
+  DcaAsset,
+  DcaAssetPair,
   DcaOrder,
+  DcaStatusReason,
+  fillLadderStep,
+  normalizeOrder,
   pauseOrder,
   recordExecution,
   setOrders,
+  setTrailingExtreme,
   updateAfterExecution,
 } from '../slices/dcaSlice'
 import { makerApi } from '../slices/makerApi/makerApi.slice'
@@ -17,6 +25,7 @@ import { handleApiError } from '../routes/trade/market-maker/apiUtils'
 import { validateSwapString } from '../routes/trade/market-maker/swapUtils'
 import { SATOSHIS_PER_BTC } from '../helpers/number'
 import { logger } from '../utils/logger'
+import { buildTrade, quoteFee } from '../utils/tradeLedger'
 import { FetchBaseQueryError } from '@reduxjs/toolkit/query'
 import { NiaAsset } from '../slices/nodeApi/nodeApi.slice'
 import { useBitcoinPrice } from './useBitcoinPrice'
@@ -192,11 +201,11 @@ function normalizeDcaError(err: unknown) {
     }
   }
 
-  if (normalized.includes('usdt asset not found')) {
+  if (normalized.includes('asset not found')) {
     return {
       internalMessage: rawMessage,
       userMessage:
-        'USDT channel asset not available. Check wallet/channel state and retry.',
+        'Order asset not available on the node. Check wallet/channel state and retry.',
     }
   }
 
@@ -206,28 +215,49 @@ function normalizeDcaError(err: unknown) {
   }
 }
 
+/** One side of a swap: resolved asset id, ticker, precision, decimal amount */
+interface SwapSide {
+  amount: number
+  assetId: string
+  precision: number
+  ticker: string
+}
+
 /**
- * Convert the fee from a quote response into satoshis.
- * fee_asset == 'BTC' (or empty) → final_fee is in msats → divide by 1000
- * Otherwise (USDT RGB asset) → final_fee is in asset units → convert using btcPrice
+ * The fee from a quote response in the received asset's smallest unit (sats
+ * for BTC). fee_asset 'BTC' (or empty) means final_fee is in msats; a fee
+ * charged in the spent asset is converted at the swap's own rate.
  */
-function computeFeeSats(
+function computeFee(
   fee:
     | { final_fee: number; fee_asset?: string; fee_asset_precision?: number }
     | undefined,
-  btcPriceUsdt: number
+  spent: SwapSide,
+  received: SwapSide
 ): number {
   if (!fee || !fee.final_fee) return 0
   const { final_fee, fee_asset, fee_asset_precision } = fee
-  if (!fee_asset || fee_asset.toUpperCase() === 'BTC') {
-    return Math.round(final_fee / 1000)
+  const inBtc = !fee_asset || fee_asset.toUpperCase() === 'BTC'
+  const chargedIn = (side: SwapSide) =>
+    inBtc
+      ? side.assetId === 'BTC'
+      : fee_asset === side.assetId ||
+        fee_asset.toUpperCase() === side.ticker.toUpperCase()
+  const value = (side: SwapSide) =>
+    inBtc
+      ? final_fee / 1000 / SATOSHIS_PER_BTC
+      : final_fee / Math.pow(10, fee_asset_precision ?? side.precision)
+  const toUnits = (amount: number) =>
+    Math.round(amount * Math.pow(10, received.precision))
+
+  if (chargedIn(received)) return toUnits(value(received))
+  if (chargedIn(spent) && spent.amount > 0) {
+    return toUnits((value(spent) * received.amount) / spent.amount)
   }
-  // USDT RGB asset: convert to sats via BTC price
-  const precision = fee_asset_precision ?? 6
-  const feeUsdt = final_fee / Math.pow(10, precision)
-  return btcPriceUsdt > 0
-    ? Math.round((feeUsdt / btcPriceUsdt) * SATOSHIS_PER_BTC)
-    : 0
+  logger.warn(
+    `DCA: fee charged in ${fee_asset ?? 'BTC'}, which the swap neither spends nor receives`
+  )
+  return 0
 }
 
 function sendNotification(title: string, body: string) {
@@ -242,10 +272,128 @@ function sendNotification(title: string, body: string) {
   }
 }
 
+/** Append an execution attempt to the Rust-side DCA history (DcaExecutions) */
+function persistExecution(execution: {
+  orderId: string
+  quotedPrice: number
+  amountIn: number
+  amountOut: number
+  fee?: number
+  swapId?: string
+  spendAsset: string
+  status: 'success' | 'failed'
+  error?: string
+}) {
+  invoke('dca_record_execution', {
+    execution: {
+      account_id: 0,
+      amount_in: execution.amountIn,
+      amount_out: execution.amountOut,
+      error: execution.error ?? null,
+      executed_at: Math.floor(Date.now() / 1000),
+      fee: execution.fee ?? 0,
+      id: 0,
+      order_id: execution.orderId,
+      quoted_price: execution.quotedPrice,
+      spend_asset: execution.spendAsset,
+      status: execution.status,
+      swap_id: execution.swapId ?? null,
+    },
+  }).catch((err) => logger.error('dca_record_execution failed', err))
+}
+
+// Pair used by orders created before pairs were configurable (mirrors Rust)
+const DEFAULT_PAIR: DcaAssetPair = {
+  base: { asset_id: 'BTC', precision: 8, ticker: 'BTC' },
+  price_source: {
+    coin_id: 'bitcoin',
+    source: 'coin-gecko',
+    vs_currency: 'usd',
+  },
+  quote: { asset_id: null, precision: 6, ticker: 'USDT' },
+}
+
+const isBtc = (asset: DcaAsset) => asset.asset_id === 'BTC'
+
+/** Assets spent (`from`) and received (`to`) by one execution of the order */
+function orderAssets(order: DcaOrder) {
+  const pair = order.pair ?? DEFAULT_PAIR
+  return order.side === 'sell'
+    ? { from: pair.base, pair, to: pair.quote }
+    : { from: pair.quote, pair, to: pair.base }
+}
+
+/** Decimal amount → Rust AssetAmount, rounded down to the asset's precision */
+function toAssetAmount(value: number, precision: number) {
+  return {
+    precision,
+    raw: Math.floor(value * Math.pow(10, precision) + 1e-6),
+  }
+}
+
+/** Maker API amounts: msats for BTC, smallest units for RGB assets */
+function toMakerAmount(value: number, asset: DcaAsset, precision: number) {
+  return isBtc(asset)
+    ? Math.round(value * SATOSHIS_PER_BTC * 1000)
+    : Math.round(value * Math.pow(10, precision))
+}
+
+function fromMakerAmount(raw: number, asset: DcaAsset, precision: number) {
+  return isBtc(asset)
+    ? raw / 1000 / SATOSHIS_PER_BTC
+    : raw / Math.pow(10, precision)
+}
+
+/** Match an order asset to the node's NIA asset (by id, else by ticker) */
+function findNodeAsset(asset: DcaAsset, niassets: NiaAsset[]): any {
+  return niassets.find((a: any) =>
+    asset.asset_id ? a.asset_id === asset.asset_id : a.ticker === asset.ticker
+  )
+}
+
+/** Strategy parameters for the Rust DcaStrategy enum */
+function toRustStrategy(order: DcaOrder) {
+  if (!order.strategy) return null
+  const { from, to } = orderAssets(order)
+  switch (order.strategy.kind) {
+    case 'value-averaging': {
+      // toAmount is the received amount in the base asset's smallest unit
+      const acquired = order.executions
+        .filter((e) => e.status === 'success')
+        .reduce((sum, e) => sum + e.toAmount, 0)
+      return {
+        acquired: { precision: to.precision, raw: acquired },
+        increment: toAssetAmount(order.strategy.increment, from.precision),
+        kind: order.strategy.kind,
+        max_amount: toAssetAmount(order.strategy.max_amount, from.precision),
+      }
+    }
+    case 'dip-ladder':
+      return {
+        kind: order.strategy.kind,
+        steps: order.strategy.steps.map((step) => ({
+          amount: toAssetAmount(step.amount, from.precision),
+          filled: step.filled,
+          price: step.price,
+        })),
+      }
+    default:
+      return order.strategy
+  }
+}
+
 /** Build DcaOrderInfo compatible with the Rust struct */
 function toRustOrder(order: DcaOrder) {
+  const { from, pair } = orderAssets(order)
   return {
-    amount_usdt: order.amountUsdt,
+    amount: toAssetAmount(order.amount, from.precision),
+    budget:
+      order.budget != null
+        ? toAssetAmount(order.budget, from.precision)
+        : null,
+    catch_up: order.catchUp ?? { policy: 'run-once' },
+    created_at: Math.floor(order.createdAt / 1000),
+    end_at: order.endAt != null ? Math.floor(order.endAt / 1000) : null,
     id: order.id,
     interval_secs:
       order.intervalHours != null
@@ -256,31 +404,77 @@ function toRustOrder(order: DcaOrder) {
         ? Math.floor(order.lastExecutedAt / 1000)
         : null,
     order_type: order.type,
+    pair,
+    schedule: order.schedule ?? null,
+    side: order.side ?? 'buy',
     status: order.status,
-    trigger_price_usd: order.triggerPriceBtcUsdt ?? null,
+    strategy: toRustStrategy(order),
+    trigger_price: order.triggerPrice ?? null,
   }
 }
 
-function computeUsdtLnBalance(
+/** Spendable Lightning balance per ticker, as Rust AssetAmounts */
+function computeLnBalances(
   channels: any[] | undefined,
   niAssets: NiaAsset[] | undefined
+) {
+  const balances: Record<string, { raw: number; precision: number }> = {}
+  if (!channels || !niAssets) return balances
+  const ready = channels.filter((channel: any) => channel.ready)
+  balances.BTC = {
+    precision: 8,
+    raw: Math.floor(
+      ready.reduce(
+        (sum: number, channel: any) =>
+          sum + (channel.outbound_balance_msat ?? 0),
+        0
+      ) / 1000
+    ),
+  }
+  for (const asset of niAssets as any[]) {
+    balances[asset.ticker] = {
+      precision: asset.precision ?? 0,
+      raw: ready
+        .filter((channel: any) => channel.asset_id === asset.asset_id)
+        .reduce(
+          (sum: number, channel: any) =>
+            sum + (channel.asset_local_amount ?? 0),
+          0
+        ),
+    }
+  }
+  return balances
+}
+
+/** Spendable Lightning balance of an asset, in decimal units */
+function computeLnBalance(
+  channels: any[] | undefined,
+  asset: DcaAsset,
+  nodeAsset: any
 ): number | null {
-  if (!channels || !niAssets) return null
-  const usdtAsset = niAssets.find(
-    (asset: any) => asset.ticker === 'USDT'
-  ) as any
-  if (!usdtAsset) return null
-  const precisionFactor = Math.pow(10, usdtAsset.precision ?? 6)
-  const balance = channels
-    .filter(
-      (channel: any) => channel.ready && channel.asset_id === usdtAsset.asset_id
+  if (!channels) return null
+  const ready = channels.filter((channel: any) => channel.ready)
+  if (isBtc(asset)) {
+    return ready.reduce(
+      (sum: number, channel: any) =>
+        sum + (channel.outbound_balance_msat ?? 0) / 1000 / SATOSHIS_PER_BTC,
+      0
     )
+  }
+  const precisionFactor = Math.pow(10, nodeAsset.precision ?? asset.precision)
+  return ready
+    .filter((channel: any) => channel.asset_id === nodeAsset.asset_id)
     .reduce(
       (sum: number, channel: any) =>
         sum + (channel.asset_local_amount ?? 0) / precisionFactor,
       0
     )
-  return balance
+}
+
+/** Per-trigger sizing from the Rust scheduler for strategy orders */
+interface ExecutionSizing {
+  amount?: number
+  ladderStep?: number
 }
 
 // Module-level ref so DcaOrderCard can call executeOrderManually without context
@@ -339,7 +533,7 @@ export function useDcaScheduler() {
 
   const isExecuting = useRef(false)
   const executionQueueRef = useRef<
-    Array<{ orderId: string; currentPrice: number }>
+    Array<{ orderId: string; currentPrice: number } & ExecutionSizing>
   >([])
   const queuedOrderIdsRef = useRef<Set<string>>(new Set())
   const runQueueRef = useRef<(() => Promise<void>) | null>(null)
@@ -398,6 +592,18 @@ export function useDcaScheduler() {
     }
   }, [isNodeReady])
 
+  // ── Report Lightning balances for the Rust pre-trade check ──
+  useEffect(() => {
+    if (!isNodeReady || !channelsData || !assetsData) return
+    const balances = computeLnBalances(
+      (channelsData as any)?.channels,
+      (assetsData as any)?.nia
+    )
+    invoke('dca_set_balances', { balances }).catch((err) =>
+      logger.error('dca_set_balances failed', err)
+    )
+  }, [isNodeReady, channelsData, assetsData])
+
   // ── Load orders from DB for the active account (with localStorage migration) ──
   // Only load when the node is unlocked to avoid "No account selected" errors.
   useEffect(() => {
@@ -418,7 +624,8 @@ export function useDcaScheduler() {
       try {
         const payloads = await invoke<string[]>('dca_get_orders')
         if (payloads.length > 0) {
-          const loaded = payloads.map((p) => JSON.parse(p) as DcaOrder)
+          // Orders saved before pairs were configurable use BTC/USDT names
+          const loaded = payloads.map((p) => normalizeOrder(JSON.parse(p)))
           // DB stores oldest-first; reverse to match unshift order (newest first)
           hydratedAccountRef.current = accountName
           isHydratingOrdersRef.current = false
@@ -436,7 +643,7 @@ export function useDcaScheduler() {
       try {
         const raw = localStorage.getItem(LEGACY_STORAGE_KEY)
         if (raw) {
-          const migrated = JSON.parse(raw) as DcaOrder[]
+          const migrated: DcaOrder[] = JSON.parse(raw).map(normalizeOrder)
           if (migrated.length > 0) {
             hydratedAccountRef.current = accountName
             isHydratingOrdersRef.current = false
@@ -513,19 +720,34 @@ export function useDcaScheduler() {
 
   // ── Core execution — registered once, uses refs for live state ──────────
   useEffect(() => {
-    const enqueueExecution = (orderId: string, currentPrice: number) => {
+    const enqueueExecution = (
+      orderId: string,
+      currentPrice: number,
+      sizing: ExecutionSizing = {}
+    ) => {
       if (queuedOrderIdsRef.current.has(orderId)) {
         return
       }
       queuedOrderIdsRef.current.add(orderId)
-      executionQueueRef.current.push({ currentPrice, orderId })
+      executionQueueRef.current.push({ currentPrice, orderId, ...sizing })
       void runQueueRef.current?.()
     }
 
-    const executeOrder = async (order_id: string, current_price: number) => {
+    const executeOrder = async (
+      order_id: string,
+      current_price: number,
+      sizing: ExecutionSizing = {}
+    ) => {
       logger.info(`DCA execute: order=${order_id} price=$${current_price}`)
+      const state = store.getState()
+      const order = state.dca.orders.find(
+        (o) => o.id === order_id && o.status === 'active'
+      )
+      // The frontend only tracks the BTC price, i.e. the default pair's price
       const referencePrice =
-        current_price > 0 ? current_price : (btcPriceRef.current ?? 0)
+        current_price > 0 || order?.pair
+          ? current_price
+          : (btcPriceRef.current ?? 0)
 
       if (!isNodeReadyRef.current) {
         logger.warn(
@@ -535,46 +757,73 @@ export function useDcaScheduler() {
         return
       }
 
-      const state = store.getState()
-      const order = state.dca.orders.find(
-        (o) => o.id === order_id && o.status === 'active'
-      )
       if (!order) {
         logger.warn(`DCA: order ${order_id} not found or not active`)
         return
       }
 
+      // Strategy orders (value averaging, dip ladder) size each trade themselves
+      const amount = sizing.amount ?? order.amount
+      const { from, pair, to } = orderAssets(order)
+      const pairLabel = `${pair.base.ticker}/${pair.quote.ticker}`
+
       isExecuting.current = true
       const toastId = toast.loading('DCA: requesting quote...')
 
       try {
-        // 1. Find USDT asset for precision + asset_id
+        // 1. Resolve the pair's RGB assets for precision + asset_id
         const niassets: NiaAsset[] = (assetsRef.current as any)?.nia ?? []
         logger.info(`DCA: ${niassets.length} NIA assets loaded`)
-        const usdtAsset = niassets.find((a: any) => a.ticker === 'USDT') as any
-        if (!usdtAsset) throw new Error('USDT asset not found in node assets')
+        const resolve = (asset: DcaAsset) => {
+          if (isBtc(asset)) {
+            return { assetId: 'BTC', nodeAsset: null, precision: 8 }
+          }
+          const nodeAsset = findNodeAsset(asset, niassets)
+          if (!nodeAsset) {
+            throw new Error(`${asset.ticker} asset not found in node assets`)
+          }
+          return {
+            assetId: nodeAsset.asset_id as string,
+            nodeAsset,
+            precision: (nodeAsset.precision ?? asset.precision) as number,
+          }
+        }
+        const fromNode = resolve(from)
+        const toNode = resolve(to)
         logger.info(
-          `DCA: USDT precision=${usdtAsset.precision} asset_id=${usdtAsset.asset_id}`
+          `DCA: ${order.side ?? 'buy'} ${pairLabel} from=${fromNode.assetId} to=${toNode.assetId}`
         )
 
-        const availableUsdtLn = computeUsdtLnBalance(
+        const available = computeLnBalance(
           (channelsRef.current as any)?.channels,
-          niassets
+          from,
+          fromNode.nodeAsset
         )
-        if (availableUsdtLn != null && availableUsdtLn < order.amountUsdt) {
-          const pauseReason = `Insufficient USDT LN balance (${availableUsdtLn.toFixed(2)} < ${order.amountUsdt}). Order auto-paused.`
+        if (available != null && available < amount) {
+          const pauseReason = `Insufficient ${from.ticker} LN balance (${available.toFixed(2)} < ${amount}). Order auto-paused.`
           logger.warn(`DCA: ${pauseReason}`)
-          dispatch(pauseOrder(order_id))
+          dispatch(
+            pauseOrder({ orderId: order_id, reason: 'insufficient_funds' })
+          )
           dispatch(
             recordExecution({
               error: pauseReason,
-              fromAmountUsdt: order.amountUsdt,
+              fromAmount: amount,
               orderId: order_id,
-              priceBtcUsdt: referencePrice,
+              price: referencePrice,
               status: 'failed',
-              toAmountSats: 0,
+              toAmount: 0,
             })
           )
+          persistExecution({
+            amountIn: amount,
+            amountOut: 0,
+            error: pauseReason,
+            orderId: order_id,
+            quotedPrice: referencePrice,
+            spendAsset: from.ticker,
+            status: 'failed',
+          })
           toast.update(toastId, {
             autoClose: 6500,
             isLoading: false,
@@ -584,24 +833,23 @@ export function useDcaScheduler() {
           return
         }
 
-        const usdtPrecision: number = usdtAsset.precision ?? 6
-        const rawFromAmount = Math.round(
-          order.amountUsdt * Math.pow(10, usdtPrecision)
-        )
+        const rawFromAmount = toMakerAmount(amount, from, fromNode.precision)
         logger.info(`DCA: rawFromAmount=${rawFromAmount}`)
 
-        // 2. Fetch quote via REST: USDT (RGB_LN) → BTC (BTC_LN)
-        toast.update(toastId, { render: 'DCA: requesting USDT→BTC quote...' })
+        // 2. Fetch quote via REST, e.g. USDT (RGB_LN) → BTC (BTC_LN)
+        toast.update(toastId, {
+          render: `DCA: requesting ${from.ticker}→${to.ticker} quote...`,
+        })
         const quoteResp = await withTimeout(
           getQuoteRef.current({
             from_asset: {
               amount: rawFromAmount,
-              asset_id: usdtAsset.asset_id,
-              layer: 'RGB_LN',
+              asset_id: fromNode.assetId,
+              layer: isBtc(from) ? 'BTC_LN' : 'RGB_LN',
             },
             to_asset: {
-              asset_id: 'BTC',
-              layer: 'BTC_LN',
+              asset_id: toNode.assetId,
+              layer: isBtc(to) ? 'BTC_LN' : 'RGB_LN',
             },
           } as any),
           DCA_QUOTE_TIMEOUT_MS,
@@ -621,15 +869,20 @@ export function useDcaScheduler() {
           `DCA: quote received rfq_id=${quote.rfq_id} to_amount=${quote.to_asset.amount}`
         )
 
-        // 3. Slippage check (msats → sats)
-        const receivedBtcSats = quote.to_asset.amount / 1000
+        // 3. Slippage check against the pair price (quote per base)
+        const received = fromMakerAmount(
+          quote.to_asset.amount,
+          to,
+          toNode.precision
+        )
         if (referencePrice > 0) {
-          const expectedSats =
-            (order.amountUsdt / referencePrice) * SATOSHIS_PER_BTC
-          const slippagePct =
-            ((expectedSats - receivedBtcSats) / expectedSats) * 100
+          const expected =
+            order.side === 'sell'
+              ? amount * referencePrice
+              : amount / referencePrice
+          const slippagePct = ((expected - received) / expected) * 100
           logger.info(
-            `DCA: slippage=${slippagePct.toFixed(3)}% (expected=${expectedSats.toFixed(0)} received=${receivedBtcSats.toFixed(0)})`
+            `DCA: slippage=${slippagePct.toFixed(3)}% (expected=${expected} received=${received} ${to.ticker})`
           )
           if (slippagePct > MAX_SLIPPAGE_PCT) {
             throw new Error(
@@ -641,8 +894,10 @@ export function useDcaScheduler() {
         // 4. Init swap
         toast.update(toastId, { render: '(1/3) Initializing DCA swap...' })
         const fromAssetId: string =
-          quote.from_asset.asset_id ?? usdtAsset.asset_id
-        const toAssetId: string = (quote.to_asset as any).asset_id ?? 'btc'
+          quote.from_asset.asset_id ?? fromNode.assetId
+        const toAssetId: string =
+          (quote.to_asset as any).asset_id ??
+          (isBtc(to) ? 'btc' : toNode.assetId)
         const initPayload = {
           from_amount: rawFromAmount,
           from_asset: fromAssetId,
@@ -693,34 +948,60 @@ export function useDcaScheduler() {
           throw new Error(handleApiError(execResp.error as FetchBaseQueryError))
         logger.info('DCA: execSwap OK')
 
-        // 7. Record success
-        const toAmountSats = Math.round(receivedBtcSats)
-        const impliedPrice =
-          toAmountSats > 0
-            ? (order.amountUsdt / toAmountSats) * SATOSHIS_PER_BTC
-            : referencePrice
-        const feeSats = computeFeeSats(
+        // 7. Record success (received amount and fee in the received
+        //    asset's smallest unit)
+        const toAmount = Math.round(
+          received * Math.pow(10, isBtc(to) ? 8 : toNode.precision)
+        )
+        let impliedPrice = referencePrice
+        if (received > 0) {
+          impliedPrice =
+            order.side === 'sell' ? received / amount : amount / received
+        }
+        const fee = computeFee(
           (quote as any).fee,
-          referencePrice > 0 ? referencePrice : impliedPrice
+          {
+            amount,
+            assetId: fromNode.assetId,
+            precision: fromNode.precision,
+            ticker: from.ticker,
+          },
+          {
+            amount: received,
+            assetId: toNode.assetId,
+            precision: toNode.precision,
+            ticker: to.ticker,
+          }
         )
+        const summary = `${order.side === 'sell' ? 'Sold' : 'Bought'} ${isBtc(to) ? `${toAmount.toLocaleString()} sats` : `${received} ${to.ticker}`} for ${amount} ${from.ticker}`
 
         toast.update(toastId, {
           autoClose: 5000,
           isLoading: false,
-          render: `DCA: bought ${toAmountSats.toLocaleString()} sats for ${order.amountUsdt} USDT`,
+          render: `DCA: ${summary}`,
           type: 'success',
         })
 
         dispatch(
           recordExecution({
-            feeSats: feeSats > 0 ? feeSats : undefined,
-            fromAmountUsdt: order.amountUsdt,
+            fee: fee > 0 ? fee : undefined,
+            fromAmount: amount,
             orderId: order_id,
-            priceBtcUsdt: impliedPrice,
+            price: impliedPrice,
             status: 'success',
-            toAmountSats,
+            toAmount,
           })
         )
+        persistExecution({
+          amountIn: amount,
+          amountOut: toAmount,
+          fee,
+          orderId: order_id,
+          quotedPrice: impliedPrice,
+          spendAsset: from.ticker,
+          status: 'success',
+          swapId: payment_hash,
+        })
 
         if (order.type === 'price-target') {
           const newCreationPrice =
@@ -733,15 +1014,38 @@ export function useDcaScheduler() {
           )
         }
 
+        if (order.type === 'dip-ladder' && sizing.ladderStep != null) {
+          dispatch(
+            fillLadderStep({ orderId: order_id, step: sizing.ladderStep })
+          )
+        } else if (order.type === 'trailing-entry') {
+          dispatch(completeOrder(order_id))
+        }
+
+        const ledgerFee = quoteFee((quote as any).fee)
         await invoke('dca_order_executed', {
+          ladderStep: sizing.ladderStep ?? null,
           orderId: order_id,
           timestamp: Math.floor(Date.now() / 1000),
+          trade: buildTrade({
+            base: pair.base.ticker,
+            fee: ledgerFee?.fee,
+            feeAsset: ledgerFee?.feeAsset,
+            fromAmount: amount,
+            fromAsset: from.ticker,
+            orderId: order_id,
+            quote: pair.quote.ticker,
+            source: 'dca',
+            swapId: payment_hash,
+            toAmount: received,
+            toAsset: to.ticker,
+          }),
         }).catch((err) => logger.error('dca_order_executed failed', err))
 
         // OS notification
         sendNotification(
-          'DCA: Buy executed',
-          `Bought ${toAmountSats.toLocaleString()} sats for ${order.amountUsdt} USDT`
+          `DCA: ${order.side === 'sell' ? 'Sell' : 'Buy'} executed`,
+          summary
         )
       } catch (err) {
         const { internalMessage, userMessage } = normalizeDcaError(err)
@@ -760,13 +1064,22 @@ export function useDcaScheduler() {
         dispatch(
           recordExecution({
             error: userMessage,
-            fromAmountUsdt: order.amountUsdt,
+            fromAmount: amount,
             orderId: order_id,
-            priceBtcUsdt: referencePrice,
+            price: referencePrice,
             status: 'failed',
-            toAmountSats: 0,
+            toAmount: 0,
           })
         )
+        persistExecution({
+          amountIn: amount,
+          amountOut: 0,
+          error: userMessage,
+          orderId: order_id,
+          quotedPrice: referencePrice,
+          spendAsset: from.ticker,
+          status: 'failed',
+        })
       } finally {
         isExecuting.current = false
       }
@@ -781,7 +1094,7 @@ export function useDcaScheduler() {
         return
       }
       queuedOrderIdsRef.current.delete(next.orderId)
-      await executeOrder(next.orderId, next.currentPrice)
+      await executeOrder(next.orderId, next.currentPrice, next)
       if (executionQueueRef.current.length > 0) {
         void runQueue()
       }
@@ -794,7 +1107,7 @@ export function useDcaScheduler() {
       enqueueExecution(orderId, currentPrice)
     }
 
-    // ── Frontend scheduler: check orders every few seconds, no Tauri events needed ──
+    // ── Frontend scheduler: check price-target orders every few seconds ──
     const checkOrders = () => {
       if (!isNodeReadyRef.current) {
         logger.info('DCA: scheduler tick skipped — node not ready')
@@ -802,28 +1115,26 @@ export function useDcaScheduler() {
       }
 
       const state = store.getState()
-      const now = Date.now()
 
       for (const order of state.dca.orders) {
         if (order.status !== 'active') continue
 
-        if (order.type === 'scheduled' && order.intervalHours) {
-          const intervalMs = order.intervalHours * 3600 * 1000
-          const last = order.lastExecutedAt ?? order.createdAt
-          const due = last + intervalMs
-          logger.info(
-            `DCA check: order=${order.id} last=${last} due=${due} now=${now} remaining=${Math.round((due - now) / 1000)}s`
-          )
-          if (now >= due) {
-            logger.info(`DCA: scheduled order ${order.id} is due, queueing`)
-            enqueueExecution(order.id, 0)
-          }
-        } else if (order.type === 'price-target' && order.triggerPriceBtcUsdt) {
+        // Scheduled orders (interval, cron, calendar) and price targets on
+        // other pairs are evaluated in Rust, which emits dca:trigger.
+        if (
+          order.type === 'price-target' &&
+          !order.pair &&
+          order.triggerPrice
+        ) {
           const price = btcPriceRef.current ?? 0
           logger.info(
-            `DCA check: order=${order.id} trigger=$${order.triggerPriceBtcUsdt} current=$${price}`
+            `DCA check: order=${order.id} side=${order.side ?? 'buy'} trigger=$${order.triggerPrice} current=$${price}`
           )
-          if (price > 0 && price <= order.triggerPriceBtcUsdt) {
+          const reached =
+            order.side === 'sell'
+              ? price >= order.triggerPrice
+              : price <= order.triggerPrice
+          if (price > 0 && reached) {
             logger.info(
               `DCA: price-target order ${order.id} triggered at $${price}, queueing`
             )
@@ -835,6 +1146,62 @@ export function useDcaScheduler() {
 
     checkOrders()
 
+    // Everything except BTC/USDT price targets is triggered by the Rust scheduler
+    const unlistenTrigger = listen<{
+      order_id: string
+      current_price: number
+      amount: { raw: number; precision: number } | null
+      ladder_step: number | null
+    }>('dca:trigger', (event) => {
+      const order = store
+        .getState()
+        .dca.orders.find((o) => o.id === event.payload.order_id)
+      const frontendChecked = order?.type === 'price-target' && !order.pair
+      if (!order || frontendChecked || !isNodeReadyRef.current) {
+        return
+      }
+      const { amount } = event.payload
+      enqueueExecution(order.id, event.payload.current_price, {
+        amount: amount
+          ? amount.raw / Math.pow(10, amount.precision)
+          : undefined,
+        ladderStep: event.payload.ladder_step ?? undefined,
+      })
+    })
+
+    // Orders the Rust pre-trade check paused (funds) or completed (budget,
+    // end date)
+    type StatusChange = {
+      order_id: string
+      reason: DcaStatusReason
+      message: string
+    }
+    const unlistenPaused = listen<StatusChange>('dca:paused', (event) => {
+      const { order_id, reason, message } = event.payload
+      dispatch(pauseOrder({ orderId: order_id, reason }))
+      toast.warn(`DCA paused: ${message}`)
+    })
+    const unlistenCompleted = listen<StatusChange>(
+      'dca:completed',
+      (event) => {
+        const { order_id, reason, message } = event.payload
+        dispatch(completeOrder({ orderId: order_id, reason }))
+        toast.info(`DCA order completed: ${message}`)
+      }
+    )
+
+    // Keep the trailing-entry low the scheduler stored, so mirroring the
+    // order back to the DB doesn't overwrite it
+    const unlistenTrailing = listen<{
+      order_id: string
+      extreme_price: number | null
+    }>('dca:trailing', (event) => {
+      const { order_id, extreme_price } = event.payload
+      dispatch(
+        setTrailingExtreme({ extremePrice: extreme_price, orderId: order_id })
+      )
+    })
+
     const intervalId = setInterval(checkOrders, DCA_SCHEDULER_INTERVAL_MS)
     logger.info(
       `DCA: frontend scheduler started (every ${DCA_SCHEDULER_INTERVAL_MS / 1000}s)`
@@ -842,6 +1209,10 @@ export function useDcaScheduler() {
 
     return () => {
       clearInterval(intervalId)
+      unlistenTrigger.then((unlisten) => unlisten())
+      unlistenPaused.then((unlisten) => unlisten())
+      unlistenCompleted.then((unlisten) => unlisten())
+      unlistenTrailing.then((unlisten) => unlisten())
       _executeFnRef.current = null
       runQueueRef.current = null
       executionQueueRef.current = []
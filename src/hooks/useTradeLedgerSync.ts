@@ -0,0 +1,48 @@
+import { invoke } from '@tauri-apps/api/core'
+import { useEffect, useRef } from 'react'
+
+import { useAppSelector } from '../app/store/hooks'
+import { nodeApi } from '../slices/nodeApi/nodeApi.slice'
+import { logger } from '../utils/logger'
+import { settledTradeStatus } from '../utils/tradeLedger'
+
+/**
+ * Settles pending trades in the ledger as the node reports their swaps
+ * succeeded, failed or expired. Trades are recorded as pending when the swap
+ * is executed, whether manually, by a DCA order or by the limit-order engine.
+ */
+export function useTradeLedgerSync() {
+  const accountName = useAppSelector((s) => s.nodeSettings.data.name)
+  // Swaps already reported this session
+  const reported = useRef(new Set<string>())
+
+  const { data } = nodeApi.endpoints.listSwaps.useQuery(undefined, {
+    pollingInterval: 15_000,
+    skip: !accountName,
+  })
+
+  useEffect(() => {
+    reported.current.clear()
+  }, [accountName])
+
+  useEffect(() => {
+    const swaps: { payment_hash?: string | null; status?: string }[] =
+      data?.taker ?? []
+    const updates = swaps.flatMap((swap) => {
+      const status = settledTradeStatus(swap.status)
+      if (!swap.payment_hash || !status) return []
+      if (reported.current.has(swap.payment_hash)) return []
+      return [{ error: null, status, swap_id: swap.payment_hash }]
+    })
+    if (updates.length === 0) return
+
+    invoke<number>('trades_update_status', { updates })
+      .then((changed) => {
+        updates.forEach((u) => reported.current.add(u.swap_id))
+        if (changed > 0) {
+          logger.info(`Trade ledger: ${changed} trade(s) settled`)
+        }
+      })
+      .catch((err) => logger.error('trades_update_status failed', err))
+  }, [data])
+}
@@ -0,0 +1,63 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { useEffect } from 'react'
+import { useTranslation } from 'react-i18next'
+import { toast } from 'react-toastify'
+
+import { logger } from '../utils/logger'
+
+type VaultLockReason = 'manual' | 'idle' | 'sleep' | 'account_changed'
+
+type VaultLockedEvent = {
+  account_name: string
+  reason: VaultLockReason
+}
+
+/** User activity is reported at most this often. */
+const TOUCH_INTERVAL_MS = 30_000
+
+/**
+ * Keeps the backend session vault (`vault.rs`) open while the user is
+ * active, and tells them when it locked on its own, after the idle timeout
+ * or on sleep.
+ */
+export function useVaultSession() {
+  const { t } = useTranslation()
+
+  useEffect(() => {
+    let lastTouch = 0
+    const touch = () => {
+      const now = Date.now()
+      if (now - lastTouch < TOUCH_INTERVAL_MS) return
+      lastTouch = now
+      invoke('vault_touch').catch((err) =>
+        logger.error('vault_touch failed', err)
+      )
+    }
+
+    const events = ['keydown', 'pointerdown', 'wheel'] as const
+    events.forEach((event) =>
+      window.addEventListener(event, touch, { passive: true })
+    )
+    return () =>
+      events.forEach((event) => window.removeEventListener(event, touch))
+  }, [])
+
+  useEffect(() => {
+    const unlisten = listen<VaultLockedEvent>('vault:locked', (event) => {
+      const { reason } = event.payload
+      logger.info(`Session vault locked (${reason})`)
+      if (reason === 'idle' || reason === 'sleep') {
+        toast.info(
+          reason === 'idle'
+            ? t('vaultSession.lockedIdle', 'Session locked after inactivity')
+            : t('vaultSession.lockedSleep', 'Session locked during sleep'),
+          { toastId: 'vault-locked' }
+        )
+      }
+    })
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [t])
+}
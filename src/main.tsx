@@ -3,6 +3,7 @@ import { Provider } from 'react-redux'
 
 import { Router } from './app/router'
 import { store } from './app/store'
+import { DatabaseGate } from './components/DatabaseGate'
 import { I18nProvider } from './components/I18nProvider'
 import { ThemeProvider } from './components/ThemeProvider'
 import { UpdateProvider } from './components/UpdateChecker'
@@ -16,7 +17,9 @@ ReactDOM.createRoot(document.getElementById('root') as HTMLElement).render(
     <ThemeProvider>
       <I18nProvider>
         <UpdateProvider>
-          <Router />
+          <DatabaseGate>
+            <Router />
+          </DatabaseGate>
         </UpdateProvider>
       </I18nProvider>
     </ThemeProvider>
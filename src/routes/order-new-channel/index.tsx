@@ -39,6 +39,7 @@ import {
   validateChannelParams,
   formatRtkQueryError,
 } from '../../utils/channelOrderUtils'
+import { persistChannelOrder } from '../../utils/channelOrderPersistence'
 
 import { Step1 } from './Step1'
 import { Step2 } from './Step2'
@@ -391,6 +392,14 @@ export const Component = () => {
           setOrderId(orderId)
           setAccessToken(orderAccessToken)
           setOrderPayload(payload)
+          // Stored before payment so the backend can track it to the end
+          persistChannelOrder({
+            order: channelResponse.data,
+            orderId,
+            orderPayload: payload,
+          }).catch((dbError) =>
+            console.error('Error saving order to database:', dbError)
+          )
           setStep(3)
         }
       } catch (error) {
@@ -21,6 +21,8 @@ import {
   Lock,
   ArrowRight,
   KeyRound,
+  Database,
+  Archive,
 } from 'lucide-react'
 import React, { useState, useEffect } from 'react'
 import { createPortal } from 'react-dom'
@@ -37,10 +39,15 @@ import {
 import { WALLET_SETUP_PATH } from '../../app/router/paths'
 import { RootState } from '../../app/store'
 import { useAppSelector } from '../../app/store/hooks'
+import { AppBackupModal } from '../../components/AppBackupModal'
 import { AppVersion } from '../../components/AppVersion'
 import { BackupModal } from '../../components/BackupModal'
 import { ChangePasswordModal } from '../../components/ChangePasswordModal'
+import { DataDirectorySettings } from '../../components/DataDirectorySettings'
+import { DatabaseEncryptionModal } from '../../components/DatabaseEncryptionModal'
+import { KaleidoCliSettings } from '../../components/KaleidoCliSettings'
 import { MnemonicViewerModal } from '../../components/MnemonicViewer'
+import { ServiceSettings } from '../../components/ServiceSettings'
 import {
   ModalType,
   ModalTypeValue,
@@ -131,6 +138,8 @@ export const Component: React.FC = () => {
   const [showRestartConfirmation, setShowRestartConfirmation] = useState(false)
   const [showMnemonicModal, setShowMnemonicModal] = useState(false)
   const [showChangePasswordModal, setShowChangePasswordModal] = useState(false)
+  const [showDbEncryptionModal, setShowDbEncryptionModal] = useState(false)
+  const [showAppBackupModal, setShowAppBackupModal] = useState(false)
   const maxLogsFetchRetries = 3
 
   // Replace showModal with unified modal state
@@ -1025,6 +1034,10 @@ export const Component: React.FC = () => {
               </div>
             </div>
           </form>
+
+          <ServiceSettings />
+          <DataDirectorySettings />
+          <KaleidoCliSettings />
         </div>
 
         {/* ── Right column: status + actions ── */}
@@ -1079,6 +1092,28 @@ export const Component: React.FC = () => {
                 </div>
                 <ArrowRight className="w-4 h-4 text-content-secondary group-hover:translate-x-0.5 transition-transform" />
               </button>
+              <button
+                className="w-full group flex items-center justify-between gap-3 p-4 rounded-xl border border-border-default/50 bg-surface-overlay/30 hover:bg-surface-elevated transition-colors text-white"
+                onClick={() => setShowDbEncryptionModal(true)}
+              >
+                <div className="flex items-center gap-3">
+                  <div className="p-2 bg-primary/10 rounded-lg group-hover:bg-primary/15 transition-colors">
+                    <Database className="w-4 h-4 text-primary" />
+                  </div>
+                  <div className="text-left">
+                    <div className="text-sm font-semibold">
+                      {t('settings.databaseEncryption', 'Database Encryption')}
+                    </div>
+                    <div className="text-xs text-content-secondary">
+                      {t(
+                        'settings.databaseEncryptionDescription',
+                        'Protect app data on disk with a password'
+                      )}
+                    </div>
+                  </div>
+                </div>
+                <ArrowRight className="w-4 h-4 text-content-secondary group-hover:translate-x-0.5 transition-transform" />
+              </button>
 
               <button
                 className="w-full group flex items-center justify-between gap-3 p-4 rounded-xl border border-border-default/50 bg-surface-overlay/30 hover:bg-surface-elevated transition-colors text-white"
@@ -1099,6 +1134,28 @@ export const Component: React.FC = () => {
                 </div>
                 <ArrowRight className="w-4 h-4 text-content-secondary group-hover:translate-x-0.5 transition-transform" />
               </button>
+              <button
+                className="w-full group flex items-center justify-between gap-3 p-4 rounded-xl border border-border-default/50 bg-surface-overlay/30 hover:bg-surface-elevated transition-colors text-white"
+                onClick={() => setShowAppBackupModal(true)}
+              >
+                <div className="flex items-center gap-3">
+                  <div className="p-2 bg-primary/10 rounded-lg group-hover:bg-primary/15 transition-colors">
+                    <Archive className="w-4 h-4 text-primary" />
+                  </div>
+                  <div className="text-left">
+                    <div className="text-sm font-semibold">
+                      {t('settings.appBackup', 'App Backup')}
+                    </div>
+                    <div className="text-xs text-content-secondary">
+                      {t(
+                        'settings.appBackupDescription',
+                        'Export or restore all accounts, orders and settings'
+                      )}
+                    </div>
+                  </div>
+                </div>
+                <ArrowRight className="w-4 h-4 text-content-secondary group-hover:translate-x-0.5 transition-transform" />
+              </button>
             </div>
           </section>
 
@@ -1291,11 +1348,20 @@ export const Component: React.FC = () => {
       />
 
       <ChangePasswordModal
-        accountName={currentAccount?.name ?? ''}
         onClose={() => setShowChangePasswordModal(false)}
         showModal={showChangePasswordModal}
       />
 
+      <DatabaseEncryptionModal
+        onClose={() => setShowDbEncryptionModal(false)}
+        showModal={showDbEncryptionModal}
+      />
+
+      <AppBackupModal
+        onClose={() => setShowAppBackupModal(false)}
+        showModal={showAppBackupModal}
+      />
+
       <BackupModal
         backupPath={backupPath}
         control={backupControl}
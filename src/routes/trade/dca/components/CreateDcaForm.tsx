@@ -91,6 +91,8 @@ export function CreateDcaForm({ currentBtcPrice, onCreated }: Props) {
   const [amountUsdt, setAmountUsdt] = useState('')
   const [intervalHours, setIntervalHours] = useState<DcaIntervalHours>(24)
   const [targetDropPercent, setTargetDropPercent] = useState(5)
+  const [budgetUsdt, setBudgetUsdt] = useState('')
+  const [endDate, setEndDate] = useState('')
 
   const amount = parseFloat(amountUsdt)
   const insufficientBalance =
@@ -113,12 +115,33 @@ export function CreateDcaForm({ currentBtcPrice, onCreated }: Props) {
       toast.error(t('dca.errors.noPriceData', 'BTC price not available'))
       return
     }
+    const budget = budgetUsdt ? parseFloat(budgetUsdt) : undefined
+    if (budget != null && !(budget >= amount)) {
+      toast.error(
+        t(
+          'dca.errors.invalidBudget',
+          'Total budget must be at least the amount per buy'
+        )
+      )
+      return
+    }
+    // End of the chosen day, local time
+    const endAt = endDate
+      ? new Date(`${endDate}T23:59:59`).getTime()
+      : undefined
+    if (endAt != null && endAt <= Date.now()) {
+      toast.error(
+        t('dca.errors.invalidEndDate', 'End date must be in the future')
+      )
+      return
+    }
 
     dispatch(
       createOrder({
-        amountUsdt: amount,
-        creationPriceBtcUsdt:
-          type === 'price-target' ? currentBtcPrice : undefined,
+        amount,
+        budget,
+        creationPrice: type === 'price-target' ? currentBtcPrice : undefined,
+        endAt,
         intervalHours: type === 'scheduled' ? intervalHours : undefined,
         targetDropPercent:
           type === 'price-target' ? targetDropPercent : undefined,
@@ -128,6 +151,8 @@ export function CreateDcaForm({ currentBtcPrice, onCreated }: Props) {
 
     toast.success(t('dca.toast.created', 'DCA order created'))
     setAmountUsdt('')
+    setBudgetUsdt('')
+    setEndDate('')
     onCreated?.()
   }
 
@@ -389,6 +414,39 @@ export function CreateDcaForm({ currentBtcPrice, onCreated }: Props) {
           </div>
         )}
 
+        {/* Spend limits */}
+        <div className="grid grid-cols-1 md:grid-cols-2 gap-2">
+          <div className="space-y-1.5">
+            <label className="text-xs text-content-secondary font-medium">
+              {t('dca.form.budgetLabel', 'Total budget (optional)')} (USDT)
+            </label>
+            <input
+              className="w-full bg-surface-overlay border border-border-subtle rounded-lg px-3 py-2.5
+                       text-sm text-content-primary placeholder:text-content-tertiary
+                       focus:outline-none focus:ring-1 focus:border-primary/50 focus:ring-primary/20 transition-colors"
+              min="0"
+              onChange={(e) => setBudgetUsdt(e.target.value)}
+              placeholder={t('dca.form.noLimit', 'No limit')}
+              step="any"
+              type="number"
+              value={budgetUsdt}
+            />
+          </div>
+          <div className="space-y-1.5">
+            <label className="text-xs text-content-secondary font-medium">
+              {t('dca.form.endDateLabel', 'End date (optional)')}
+            </label>
+            <input
+              className="w-full bg-surface-overlay border border-border-subtle rounded-lg px-3 py-2.5
+                       text-sm text-content-primary
+                       focus:outline-none focus:ring-1 focus:border-primary/50 focus:ring-primary/20 transition-colors"
+              onChange={(e) => setEndDate(e.target.value)}
+              type="date"
+              value={endDate}
+            />
+          </div>
+        </div>
+
         <button
           className="w-full py-2.5 rounded-lg bg-primary text-white font-semibold text-sm
                    hover:bg-primary/90 active:scale-[0.98] transition-all duration-200
@@ -21,8 +21,8 @@ export function DcaHistoryChart({ executions }: Props) {
   // Build cumulative BTC sats over time
   let cumSats = 0
   const points = successes.map((e) => {
-    cumSats += e.toAmountSats
-    return { cumSats, price: e.priceBtcUsdt, ts: e.timestamp }
+    cumSats += e.toAmount
+    return { cumSats, price: e.price, ts: e.timestamp }
   })
 
   const chartW = W - PAD.left - PAD.right
@@ -105,20 +105,20 @@ function ExecutionRow({ exec }: { exec: DcaExecution }) {
         {exec.status === 'success' ? (
           <>
             <span className="text-content-secondary">
-              {exec.fromAmountUsdt} USDT
+              {exec.fromAmount} USDT
             </span>
             <span className="text-status-success font-medium">
-              +{exec.toAmountSats.toLocaleString()} sats
+              +{exec.toAmount.toLocaleString()} sats
             </span>
             <span className="text-content-tertiary">
               @$
-              {exec.priceBtcUsdt.toLocaleString('en-US', {
+              {exec.price.toLocaleString('en-US', {
                 maximumFractionDigits: 0,
               })}
             </span>
-            {exec.feeSats != null && exec.feeSats > 0 && (
+            {exec.fee != null && exec.fee > 0 && (
               <span className="text-content-tertiary/70">
-                fee {exec.feeSats.toLocaleString('en-US')} sats
+                fee {exec.fee.toLocaleString('en-US')} sats
               </span>
             )}
           </>
@@ -146,12 +146,12 @@ export function DcaOrderCard({ order, currentBtcPrice }: Props) {
   const successfulExecs = order.executions.filter((e) => e.status === 'success')
   const failedExecs = order.executions.filter((e) => e.status === 'failed')
   const successCount = successfulExecs.length
-  const totalSats = successfulExecs.reduce((s, e) => s + e.toAmountSats, 0)
+  const totalSats = successfulExecs.reduce((s, e) => s + e.toAmount, 0)
   const avgPrice =
     successCount > 0
-      ? successfulExecs.reduce((s, e) => s + e.priceBtcUsdt, 0) / successCount
+      ? successfulExecs.reduce((s, e) => s + e.price, 0) / successCount
       : undefined
-  const totalFeeSats = successfulExecs.reduce((s, e) => s + (e.feeSats ?? 0), 0)
+  const totalFeeSats = successfulExecs.reduce((s, e) => s + (e.fee ?? 0), 0)
 
   const isActive = order.status === 'active'
   const isPaused = order.status === 'paused'
@@ -177,10 +177,10 @@ export function DcaOrderCard({ order, currentBtcPrice }: Props) {
   // Price-target: gap
   const priceGapPct =
     order.type === 'price-target' &&
-    order.triggerPriceBtcUsdt &&
+    order.triggerPrice &&
     currentBtcPrice &&
     currentBtcPrice > 0
-      ? ((currentBtcPrice - order.triggerPriceBtcUsdt) / currentBtcPrice) * 100
+      ? ((currentBtcPrice - order.triggerPrice) / currentBtcPrice) * 100
       : undefined
   const triggerReached = priceGapPct != null && priceGapPct <= 0
 
@@ -216,7 +216,7 @@ export function DcaOrderCard({ order, currentBtcPrice }: Props) {
                   className="w-3 h-3 rounded-full"
                   src={tetherLogo}
                 />
-                <span>{order.amountUsdt} USDT</span>
+                <span>{order.amount} USDT</span>
                 <ArrowRight className="w-3 h-3" />
                 <img
                   alt="BTC"
@@ -328,11 +328,21 @@ export function DcaOrderCard({ order, currentBtcPrice }: Props) {
                 </span>
               )}
               {isPaused && (
-                <span className="text-status-warning text-xs">Paused</span>
+                <span className="text-status-warning text-xs">
+                  {order.statusReason === 'insufficient_funds'
+                    ? 'Paused: insufficient funds'
+                    : 'Paused'}
+                </span>
               )}
               {isDone && (
                 <span className="text-content-secondary text-xs">
-                  {order.status === 'cancelled' ? 'Cancelled' : 'Completed'}
+                  {order.status === 'cancelled'
+                    ? 'Cancelled'
+                    : order.statusReason === 'budget_exhausted'
+                      ? 'Completed: budget spent'
+                      : order.statusReason === 'ended'
+                        ? 'Completed: end date reached'
+                        : 'Completed'}
                 </span>
               )}
             </div>
@@ -346,13 +356,13 @@ export function DcaOrderCard({ order, currentBtcPrice }: Props) {
             )}
           </div>
         ) : order.type === 'price-target' &&
-          order.triggerPriceBtcUsdt != null ? (
+          order.triggerPrice != null ? (
           <div className="flex items-center gap-4 text-xs flex-wrap">
             <span className="text-content-secondary">
               Trigger ≤{' '}
               <span className="text-status-warning font-semibold">
                 $
-                {order.triggerPriceBtcUsdt.toLocaleString(undefined, {
+                {order.triggerPrice.toLocaleString(undefined, {
                   maximumFractionDigits: 0,
                 })}
               </span>
@@ -151,21 +151,23 @@ export const Component = () => {
   )
   const displayedOrders = tab === 'active' ? activeOrders : doneOrders
 
-  const allSuccess = orders.flatMap((o) =>
-    o.executions.filter((e) => e.status === 'success')
-  )
+  // Totals in sats and USD: BTC/USDT buys only, other orders trade other
+  // assets
+  const allSuccess = orders
+    .filter((o) => !o.pair && o.side !== 'sell')
+    .flatMap((o) => o.executions.filter((e) => e.status === 'success'))
   const totalBuys = allSuccess.length
-  const totalSats = allSuccess.reduce((s, e) => s + e.toAmountSats, 0)
+  const totalSats = allSuccess.reduce((s, e) => s + e.toAmount, 0)
   const avgPrice =
     totalBuys > 0
-      ? allSuccess.reduce((s, e) => s + e.priceBtcUsdt, 0) / totalBuys
+      ? allSuccess.reduce((s, e) => s + e.price, 0) / totalBuys
       : undefined
 
   const thirtyDaysAgo = Date.now() - 30 * 24 * 3600 * 1000
   const monthlyBuys = allSuccess.filter(
     (e) => e.timestamp >= thirtyDaysAgo
   ).length
-  const totalFeeSats = allSuccess.reduce((s, e) => s + (e.feeSats ?? 0), 0)
+  const totalFeeSats = allSuccess.reduce((s, e) => s + (e.fee ?? 0), 0)
   const avgPriceDeltaPct =
     avgPrice != null && currentBtcPrice != null && avgPrice > 0
       ? ((currentBtcPrice - avgPrice) / avgPrice) * 100
@@ -115,7 +115,10 @@ export const LimitOrdersView = () => {
     : false
 
   const activeOrders = orders.filter(
-    (o) => o.status === 'active' || o.status === 'paused'
+    (o) =>
+      o.status === 'active' ||
+      o.status === 'paused' ||
+      o.status === 'pending-confirmation'
   )
   const doneOrders = orders.filter(
     (o) =>
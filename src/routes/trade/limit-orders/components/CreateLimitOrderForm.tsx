@@ -6,7 +6,11 @@ import { ArrowUpDown, ShoppingCart } from 'lucide-react'
 
 import { ORDER_CHANNEL_PATH } from '../../../../app/router/paths'
 import { useAppDispatch, useAppSelector } from '../../../../app/store/hooks'
-import { createLimitOrder } from '../../../../slices/limitOrderSlice'
+import {
+  createLimitOrder,
+  createOcoOrder,
+  LimitOrderType,
+} from '../../../../slices/limitOrderSlice'
 import { getAssetId } from '../../../../slices/makerApi/makerApi.slice'
 import { nodeApi } from '../../../../slices/nodeApi/nodeApi.slice'
 import { useSettings } from '../../../../hooks/useSettings'
@@ -31,6 +35,15 @@ const EXPIRATION_OPTIONS: { label: string; value: number | null }[] = [
   { label: 'Never', value: null },
 ]
 
+type FormOrderType = LimitOrderType | 'oco'
+
+const ORDER_TYPE_OPTIONS: { label: string; value: FormOrderType }[] = [
+  { label: 'Limit', value: 'limit' },
+  { label: 'Stop-loss', value: 'stop-loss' },
+  { label: 'Take-profit', value: 'take-profit' },
+  { label: 'OCO', value: 'oco' },
+]
+
 export function CreateLimitOrderForm({ onCreated }: Props) {
   const { t } = useTranslation()
   const dispatch = useAppDispatch()
@@ -86,6 +99,11 @@ export function CreateLimitOrderForm({ onCreated }: Props) {
   const [fromAmountStr, setFromAmountStr] = useState('')
   const [toAmountStr, setToAmountStr] = useState('')
   const [limitPriceStr, setLimitPriceStr] = useState('')
+  const [orderType, setOrderType] = useState<FormOrderType>('limit')
+  // Stop leg of an OCO order; the limit price is its take-profit
+  const [stopPriceStr, setStopPriceStr] = useState('')
+  // Let the engine fill the order across several swaps sized to capacity
+  const [allowPartial, setAllowPartial] = useState(false)
   const [expirationMs, setExpirationMs] = useState<number | null>(
     24 * 3600 * 1000
   )
@@ -126,6 +144,7 @@ export function CreateLimitOrderForm({ onCreated }: Props) {
   }, [currentPrice, limitPriceStr])
 
   const limitPrice = parseFloat(limitPriceStr)
+  const stopPrice = parseFloat(stopPriceStr)
 
   // Asset options formatting
   const fromAssetOptions = useMemo(
@@ -362,6 +381,21 @@ export function CreateLimitOrderForm({ onCreated }: Props) {
       )
       return
     }
+    // The stop of an OCO pair has to be on the losing side of its target
+    if (
+      orderType === 'oco' &&
+      (!stopPrice ||
+        stopPrice <= 0 ||
+        (side === 'sell' ? stopPrice >= limitPrice : stopPrice <= limitPrice))
+    ) {
+      toast.error(
+        t(
+          'limitOrders.errors.invalidStopPrice',
+          'The stop price must be below the take-profit price for sells and above it for buys'
+        )
+      )
+      return
+    }
 
     // Compute base amount in whole units for the order record
     const fromTicker = spendingAssetData?.ticker?.toUpperCase()
@@ -386,20 +420,25 @@ export function CreateLimitOrderForm({ onCreated }: Props) {
       amountRaw = fromAmountRaw
     }
 
-    dispatch(
-      createLimitOrder({
-        amount: baseAmount,
-        amountRaw,
-        baseAssetId,
-        baseAssetTicker: selectedPair.base.ticker,
-        expiresAt: expirationMs ? Date.now() + expirationMs : undefined,
-        limitPrice,
-        pairId: selectedPair.id,
-        quoteAssetId,
-        quoteAssetTicker: selectedPair.quote.ticker,
-        side,
-      })
-    )
+    const fields = {
+      allowPartial,
+      amount: baseAmount,
+      amountRaw,
+      baseAssetId,
+      baseAssetTicker: selectedPair.base.ticker,
+      expiresAt: expirationMs ? Date.now() + expirationMs : undefined,
+      pairId: selectedPair.id,
+      quoteAssetId,
+      quoteAssetTicker: selectedPair.quote.ticker,
+      side,
+    }
+    if (orderType === 'oco') {
+      dispatch(
+        createOcoOrder({ ...fields, stopPrice, takeProfitPrice: limitPrice })
+      )
+    } else {
+      dispatch(createLimitOrder({ ...fields, limitPrice, orderType }))
+    }
 
     toast.success(t('limitOrders.notifications.created', 'Limit order created'))
     onCreated?.()
@@ -555,11 +594,33 @@ export function CreateLimitOrderForm({ onCreated }: Props) {
         value={toAmountStr}
       />
 
+      {/* Order type */}
+      <div className="flex flex-wrap gap-2">
+        {ORDER_TYPE_OPTIONS.map((opt) => (
+          <button
+            className={`rounded-lg border px-3 py-1.5 text-xs font-semibold transition-all duration-200 active:scale-95 ${
+              orderType === opt.value
+                ? 'border-primary/50 bg-primary/20 text-primary shadow-sm'
+                : 'border-border-default/30 bg-surface-base/50 text-content-tertiary hover:text-content-primary hover:border-border-default/60'
+            }`}
+            key={opt.value}
+            onClick={() => setOrderType(opt.value)}
+            type="button"
+          >
+            {t(`limitOrders.orderType.${opt.value}`, opt.label)}
+          </button>
+        ))}
+      </div>
+
       {/* Target Limit Price */}
       <div className="bg-surface-overlay/70 rounded-xl border border-border-default/40 hover:border-border-default/60 transition-all duration-300">
         <div className="px-4 py-2 border-b border-border-default/20 flex items-center justify-between">
           <span className="text-xs font-semibold text-white uppercase tracking-wider">
-            {t('limitOrders.form.limitPrice', 'Limit Price')}
+            {orderType === 'limit'
+              ? t('limitOrders.form.limitPrice', 'Limit Price')
+              : orderType === 'stop-loss'
+                ? t('limitOrders.form.stopPrice', 'Stop Price')
+                : t('limitOrders.form.takeProfitPrice', 'Take-profit Price')}
           </span>
           {selectedPair && currentPrice !== undefined && (
             <button
@@ -634,6 +695,34 @@ export function CreateLimitOrderForm({ onCreated }: Props) {
         </div>
       </div>
 
+      {/* Stop leg of an OCO order */}
+      {orderType === 'oco' && (
+        <div className="bg-surface-overlay/70 rounded-xl border border-border-default/40 hover:border-border-default/60 transition-all duration-300">
+          <div className="px-4 py-2 border-b border-border-default/20">
+            <span className="text-xs font-semibold text-white uppercase tracking-wider">
+              {t('limitOrders.form.stopPrice', 'Stop Price')}
+            </span>
+          </div>
+          <div className="px-4 py-3 flex items-center gap-3 relative">
+            <input
+              className="w-full bg-surface-base/50 rounded-lg border border-border-default/30 text-white text-2xl font-semibold focus:border-primary/60 focus:ring-2 focus:ring-primary/15 placeholder:text-content-tertiary/50 h-14 hover:border-border-default/50 focus:outline-none pl-4 pr-16 transition-all duration-300"
+              inputMode="decimal"
+              onChange={(e) =>
+                setStopPriceStr(e.target.value.replace(/[^\d.]/g, ''))
+              }
+              placeholder="0.00"
+              type="text"
+              value={stopPriceStr}
+            />
+            {selectedPair && (
+              <span className="absolute right-7 top-1/2 -translate-y-1/2 text-content-tertiary text-sm font-semibold pointer-events-none select-none tracking-wide">
+                {selectedPair.quote.ticker}
+              </span>
+            )}
+          </div>
+        </div>
+      )}
+
       {insufficientBalance && (
         <p className="mt-1 text-xs text-status-danger px-1">
           {t(
@@ -643,6 +732,27 @@ export function CreateLimitOrderForm({ onCreated }: Props) {
         </p>
       )}
 
+      {/* Partial fills */}
+      <label className="flex cursor-pointer items-start gap-3 rounded-xl border border-border-default/40 bg-surface-overlay/70 px-4 py-3 transition-all duration-300 hover:border-border-default/60">
+        <input
+          checked={allowPartial}
+          className="mt-0.5 h-4 w-4 accent-primary"
+          onChange={(e) => setAllowPartial(e.target.checked)}
+          type="checkbox"
+        />
+        <span className="space-y-0.5">
+          <span className="block text-xs font-semibold uppercase tracking-wider text-white">
+            {t('limitOrders.form.allowPartial', 'Allow partial fills')}
+          </span>
+          <span className="block text-xs text-content-tertiary">
+            {t(
+              'limitOrders.form.allowPartialHint',
+              'Fill large orders in several swaps sized to your channel capacity.'
+            )}
+          </span>
+        </span>
+      </label>
+
       {/* Expiration */}
       <div className="bg-surface-overlay/70 rounded-xl border border-border-default/40 hover:border-border-default/60 transition-all duration-300">
         <div className="px-4 py-2 border-b border-border-default/20">
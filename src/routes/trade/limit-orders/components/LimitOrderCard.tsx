@@ -1,4 +1,5 @@
 import {
+  AlertTriangle,
   Pause,
   Play,
   Trash2,
@@ -61,6 +62,7 @@ function StatusBadge({ status }: { status: LimitOrder['status'] }) {
       'bg-border-subtle/30 text-content-tertiary border-border-subtle/50',
     filled: 'bg-primary/15 text-primary border-primary/30',
     paused: 'bg-status-warning/15 text-status-warning border-status-warning/30',
+    'pending-confirmation': 'bg-sky-400/15 text-sky-300 border-sky-400/30',
   }
   return (
     <span
@@ -91,6 +93,17 @@ function SideBadge({ side }: { side: LimitOrder['side'] }) {
   )
 }
 
+function TypeBadge({ order }: { order: LimitOrder }) {
+  const { t } = useTranslation()
+  if (!order.orderType || order.orderType === 'limit') return null
+  return (
+    <span className="inline-flex items-center rounded-full border border-sky-400/30 bg-sky-400/15 px-2.5 py-0.5 text-[10px] font-semibold uppercase tracking-[0.16em] text-sky-300">
+      {t(`limitOrders.orderType.${order.orderType}`, order.orderType)}
+      {order.linkedOrderId && ` · ${t('limitOrders.orderType.oco', 'OCO')}`}
+    </span>
+  )
+}
+
 function ExecutionRow({ execution }: { execution: LimitOrderExecution }) {
   return (
     <div className="flex items-center gap-2 rounded-lg border border-border-subtle/50 bg-surface-base/30 px-3 py-2">
@@ -130,11 +143,27 @@ export function LimitOrderCard({ order, currentPrices }: Props) {
     order.status === 'filled' ||
     order.status === 'expired' ||
     order.status === 'cancelled'
+  // The engine holds the order until the node reports how its swap ended
+  const isPending = order.status === 'pending-confirmation'
+  const notice =
+    order.status === 'paused'
+      ? order.pauseReason
+      : order.status === 'active' && order.failures
+        ? t('limitOrders.card.lastError', {
+            defaultValue: 'Failed {{failures}} times in a row: {{error}}',
+            error: order.lastError,
+            failures: order.failures,
+          })
+        : undefined
   const currentPrice = currentPrices?.[order.pairId]
   const priceDeltaPct =
     currentPrice && order.limitPrice > 0
       ? ((currentPrice - order.limitPrice) / order.limitPrice) * 100
       : undefined
+  const filledPct =
+    order.filledRaw && order.amountRaw > 0
+      ? Math.min(100, (order.filledRaw / order.amountRaw) * 100)
+      : undefined
   const timeRemaining =
     order.expiresAt && order.status === 'active'
       ? Math.max(0, order.expiresAt - Date.now())
@@ -147,6 +176,7 @@ export function LimitOrderCard({ order, currentPrices }: Props) {
         <div className="min-w-0 flex-1 space-y-2">
           <div className="flex flex-wrap items-center gap-2">
             <SideBadge side={order.side} />
+            <TypeBadge order={order} />
             <StatusBadge status={order.status} />
             <span className="text-sm font-semibold text-content-primary">
               {order.baseAssetTicker}/{order.quoteAssetTicker}
@@ -156,7 +186,9 @@ export function LimitOrderCard({ order, currentPrices }: Props) {
           <div className="grid gap-2 sm:grid-cols-3">
             <div>
               <p className="text-[10px] uppercase tracking-[0.2em] text-content-tertiary">
-                {t('limitOrders.card.limitPrice', 'Limit')}
+                {order.orderType === 'stop-loss'
+                  ? t('limitOrders.card.stopPrice', 'Stop')
+                  : t('limitOrders.card.limitPrice', 'Limit')}
               </p>
               <p className="mt-0.5 text-sm font-semibold text-content-primary">
                 {order.limitPrice} {order.quoteAssetTicker}
@@ -194,6 +226,31 @@ export function LimitOrderCard({ order, currentPrices }: Props) {
             </div>
           </div>
 
+          {filledPct != null && (
+            <div className="space-y-1">
+              <div className="flex items-center justify-between text-[11px] text-content-secondary">
+                <span>
+                  {t('limitOrders.card.filled', 'Filled {{pct}}%', {
+                    pct: filledPct.toFixed(1),
+                  })}
+                </span>
+                {order.averagePrice != null && (
+                  <span>
+                    {t('limitOrders.card.averagePrice', 'Avg. price')}{' '}
+                    {Number(order.averagePrice.toPrecision(8))}{' '}
+                    {order.quoteAssetTicker}
+                  </span>
+                )}
+              </div>
+              <div className="h-1 overflow-hidden rounded-full bg-surface-overlay">
+                <div
+                  className="h-full rounded-full bg-primary"
+                  style={{ width: `${filledPct}%` }}
+                />
+              </div>
+            </div>
+          )}
+
           <div className="flex flex-wrap items-center gap-3 text-[11px] text-content-secondary">
             <span className="flex items-center gap-1">
               <Clock className="h-3 w-3" />
@@ -218,11 +275,27 @@ export function LimitOrderCard({ order, currentPrices }: Props) {
               </span>
             )}
           </div>
+
+          {isPending && (
+            <p className="flex items-start gap-1.5 text-[11px] text-sky-300">
+              <Clock className="mt-0.5 h-3 w-3 shrink-0" />
+              {t(
+                'limitOrders.card.pendingConfirmation',
+                'Waiting for the node to confirm whether the swap went through'
+              )}
+            </p>
+          )}
+          {notice && (
+            <p className="flex items-start gap-1.5 text-[11px] text-status-warning">
+              <AlertTriangle className="mt-0.5 h-3 w-3 shrink-0" />
+              <span className="break-words">{notice}</span>
+            </p>
+          )}
         </div>
 
         {/* Right: Actions */}
         <div className="flex shrink-0 items-center gap-1.5">
-          {!isTerminal && (
+          {!isTerminal && !isPending && (
             <>
               <button
                 className="rounded-lg p-1.5 text-content-secondary transition-colors hover:bg-primary/15 hover:text-primary"
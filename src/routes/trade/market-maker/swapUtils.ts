@@ -8,6 +8,7 @@ import {
 } from '../../../slices/makerApi/makerApi.slice'
 import type { AssetNIA as NiaAsset } from 'kaleido-sdk/rln'
 import { logger } from '../../../utils/logger'
+import { recordTrade } from '../../../utils/tradeLedger'
 
 import { handleApiError } from './apiUtils'
 import { Fields } from './types'
@@ -294,6 +295,27 @@ export const createSwapExecutor = (
       }
 
       logger.info('Swap executed successfully!')
+
+      // Ledger amounts are in display units, which for BTC is BTC
+      const displayAmount = (amount: string, ticker: string) =>
+        parseAssetAmount(amount, ticker) /
+        Math.pow(
+          10,
+          ticker.toLowerCase() === 'btc'
+            ? 8
+            : (assets.find((asset) => asset.ticker === ticker)?.precision ??
+                0)
+        )
+      recordTrade({
+        base: pair.base_asset!,
+        fromAmount: displayAmount(data.from, data.fromAsset),
+        fromAsset: data.fromAsset,
+        quote: pair.quote_asset!,
+        source: 'manual',
+        swapId: payment_hash,
+        toAmount: displayAmount(data.to, data.toAsset),
+        toAsset: data.toAsset,
+      }).catch((err) => logger.error('Failed to record trade', err))
       toast.update(toastId, {
         autoClose: 5000,
         closeOnClick: true,
@@ -44,6 +44,10 @@ import {
   Lsps1CreateOrderResponse,
 } from '../../../slices/makerApi/makerApi.slice'
 import { nodeApi } from '../../../slices/nodeApi/nodeApi.slice'
+import {
+  isUnpaidClosedOrder,
+  StoredChannelOrder,
+} from '../../../utils/channelOrderPersistence'
 
 interface ChannelOrder {
   id: number
@@ -652,9 +656,10 @@ export const Component = () => {
   const fetchOrders = async () => {
     try {
       console.log('Fetching channel orders from database...')
-      const result = await invoke<ChannelOrder[]>('get_channel_orders')
+      const result = await invoke<StoredChannelOrder[]>('get_channel_orders')
       console.log('Channel orders fetched:', result)
-      setOrders(result)
+      // Orders are stored when created; hide the ones never paid for
+      setOrders(result.filter((order) => !isUnpaidClosedOrder(order)))
       setError(null)
     } catch (err) {
       console.error('Error fetching channel orders:', err)
@@ -902,7 +902,14 @@ export const Component = () => {
   ) => {
     try {
       if (mnemonic.join(' ') !== data.mnemonic.trim()) {
-        setErrors([t('walletInit.verifyStep.mnemonicMismatch')])
+        // Point at the mistyped word when the phrase isn't valid BIP39
+        const invalid = await invoke('validate_mnemonic', {
+          mnemonic: data.mnemonic,
+        }).then(
+          () => null,
+          (err) => String(err)
+        )
+        setErrors([invalid ?? t('walletInit.verifyStep.mnemonicMismatch')])
         return
       }
 
@@ -926,6 +933,12 @@ export const Component = () => {
         return
       }
 
+      // Open the session vault for the new account. Best-effort: the node
+      // unlock below doesn't depend on it.
+      invoke('vault_unlock', { password: nodePassword }).catch((err) =>
+        console.warn('vault_unlock failed', err)
+      )
+
       // Move to unlock step
       handleStepChange('unlock')
 
@@ -35,6 +35,7 @@ import {
   WalletAction,
   IconWrapper,
 } from '../../components/wallet-setup'
+import { NodeBackendType, useAppSettings } from '../../hooks/useAppSettings'
 import { setAppMode, type AppMode } from '../../slices/settings/settings.slice'
 
 const MODE_OPTIONS: {
@@ -81,6 +82,7 @@ export const Component = () => {
   const [localNodeMode, setLocalNodeMode] = useState<
     'native' | 'docker' | null
   >(null)
+  const { settings: appSettings, updateSettings } = useAppSettings()
 
   // Check local node capabilities (native binary + Docker)
   useEffect(() => {
@@ -128,6 +130,24 @@ export const Component = () => {
     checkCapabilities()
   }, [])
 
+  // Use the saved default backend once we know it is available here.
+  const preferredBackend = appSettings?.node_backend
+  useEffect(() => {
+    if (
+      (preferredBackend === 'docker' && isDockerAvailable) ||
+      (preferredBackend === 'native' && isNativeSupported)
+    ) {
+      setLocalNodeMode(preferredBackend)
+    }
+  }, [preferredBackend, isDockerAvailable, isNativeSupported])
+
+  const selectLocalNodeMode = (mode: NodeBackendType) => {
+    setLocalNodeMode(mode)
+    updateSettings({ node_backend: mode }).catch((error) =>
+      console.error('Failed to save default node backend:', error)
+    )
+  }
+
   // Handle transitions
   const handleNodeTypeChange = (type: 'local' | 'remote' | null) => {
     if (type !== nodeType && !isTransitioning) {
@@ -396,7 +416,7 @@ export const Component = () => {
                               : 'border-border-default/40 bg-surface-elevated/20 hover:border-border-default/60'
                         }`}
                         disabled={!isNativeSupported}
-                        onClick={() => setLocalNodeMode('native')}
+                        onClick={() => selectLocalNodeMode('native')}
                         type="button"
                       >
                         <div className="flex items-center gap-3 mb-2">
@@ -427,7 +447,7 @@ export const Component = () => {
                               : 'border-border-default/40 bg-surface-elevated/20 hover:border-border-default/60'
                         }`}
                         disabled={!isDockerAvailable}
-                        onClick={() => setLocalNodeMode('docker')}
+                        onClick={() => selectLocalNodeMode('docker')}
                         type="button"
                       >
                         <div className="flex items-center justify-between mb-2">
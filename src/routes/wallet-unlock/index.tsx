@@ -40,12 +40,13 @@ interface Fields {
 }
 
 const MAX_UNLOCK_RETRIES = 240
+// The vault's error for an account without stored secrets (vault::NO_SECRETS)
+const NO_SECRETS_ERROR = 'No secrets are stored for this account'
 
 export const Component = () => {
   const { t } = useTranslation()
   const nodeSettings = useAppSelector((state) => state.nodeSettings.data)
   const nodeLifecycle = useAppSelector((state) => state.node.lifecycle)
-  const [unlock] = nodeApi.endpoints.unlock.useMutation()
   const [nodeInfo] = nodeApi.endpoints.nodeInfo.useLazyQuery()
 
   const navigate = useNavigate()
@@ -212,7 +213,21 @@ export const Component = () => {
         // Non-network errors (403 locked, 401, etc.) are fine — proceed to unlock
       }
 
-      const rpcConfig = parseRpcUrl(nodeSettings.rpc_connection_url)
+      // The session vault checks the password against the stored secrets
+      // and keeps their keys. Accounts restored without them have nothing to
+      // unlock; the node checks the password below.
+      try {
+        await invoke('vault_unlock', { password: data.password })
+      } catch (err) {
+        if (String(err) !== NO_SECRETS_ERROR) {
+          throw new Error(
+            String(err) === 'Invalid password'
+              ? t('walletUnlock.invalidPassword')
+              : String(err)
+          )
+        }
+      }
+
       const outcome = await unlockNodeWithRetry({
         getNodeInfo: () => nodeInfo(),
         invalidPasswordMessage: t('walletUnlock.invalidPassword'),
@@ -224,19 +239,7 @@ export const Component = () => {
         }),
         onLongUnlock: setUnlockStatusMessage,
         unlock: () =>
-          unlock({
-            announce_addresses: [],
-            announce_alias: 'kaleidoswap-desktop',
-            bitcoind_rpc_host: rpcConfig.host,
-            bitcoind_rpc_password: rpcConfig.password,
-            bitcoind_rpc_port: rpcConfig.port,
-            bitcoind_rpc_username: rpcConfig.username,
-            indexer_url: nodeSettings.indexer_url,
-            password: data.password,
-            proxy_endpoint: nodeSettings.proxy_endpoint,
-          })
-            .unwrap()
-            .then(() => undefined),
+          invoke<void>('vault_unlock_node', { password: data.password }),
         unlockLabel: 'Wallet unlock',
         unlockTimeoutMessage: t('walletUnlock.unlockTimeoutMessage', {
           defaultValue:
@@ -281,6 +284,7 @@ export const Component = () => {
         return
       }
 
+      invoke('vault_lock').catch(() => undefined)
       const errorMessage =
         error instanceof Error ? error.message : t('walletUnlock.unknownError')
       setUnlockError(errorMessage)
@@ -1,18 +1,98 @@
 import { createSlice, PayloadAction } from '@reduxjs/toolkit'
 
-export type DcaOrderType = 'scheduled' | 'price-target'
+export type DcaOrderType =
+  | 'scheduled'
+  | 'price-target'
+  | 'value-averaging'
+  | 'dip-ladder'
+  | 'trailing-entry'
 export type DcaOrderStatus = 'active' | 'paused' | 'completed' | 'cancelled'
 
+// Why the scheduler paused or completed an order (Rust StatusReason)
+export type DcaStatusReason =
+  | 'insufficient_funds'
+  | 'budget_exhausted'
+  | 'ended'
+
 // Stored as fractional hours; 1/60 ≈ 0.01667 = 1 minute
 export type DcaIntervalHours = number
 
+// Cron / calendar schedule, evaluated by the Rust scheduler (dca_schedule.rs)
+export type DcaCalendarRule =
+  | { every: 'day' }
+  | { every: 'week'; weekday: number }
+  | { every: 'month'; day: number }
+
+export type DcaSchedule =
+  | { kind: 'interval'; interval_secs: number }
+  | { kind: 'cron'; expression: string; timezone: string }
+  | {
+      kind: 'calendar'
+      rule: DcaCalendarRule
+      hour: number
+      minute: number
+      timezone: string
+    }
+
+// Prices are in the pair's quote asset; amounts are decimals of the spent asset
+export interface DcaLadderStep {
+  price: number
+  amount: number
+  filled: boolean
+}
+
+// Parameters for the strategy order types (Rust DcaStrategy)
+export type DcaStrategy =
+  | {
+      kind: 'value-averaging'
+      increment: number
+      max_amount: number
+    }
+  | { kind: 'dip-ladder'; steps: DcaLadderStep[] }
+  | {
+      kind: 'trailing-entry'
+      rebound_pct: number
+      activation_price: number | null
+      extreme_price?: number | null
+    }
+
+// asset_id 'BTC' is bitcoin; null resolves the node's asset by ticker
+export interface DcaAsset {
+  asset_id: string | null
+  ticker: string
+  precision: number
+}
+
+export type DcaPriceSource =
+  | { source: 'coin-gecko'; coin_id: string; vs_currency: string }
+  | { source: 'binance'; symbol: string }
+
+// `base` priced in `quote`; orders without a pair trade BTC/USDT
+export interface DcaAssetPair {
+  base: DcaAsset
+  quote: DcaAsset
+  price_source: DcaPriceSource
+  invert_price?: boolean
+}
+
+// Buy spends the quote asset, sell spends the base asset
+export type DcaSide = 'buy' | 'sell'
+
+// What to do with scheduled runs missed while the app was closed or asleep
+export type DcaCatchUpPolicy =
+  | { policy: 'skip' }
+  | { policy: 'run-once' }
+  | { policy: 'run-all'; max_runs: number }
+
+// fromAmount is a decimal of the spent asset; toAmount and fee are in the
+// received asset's smallest unit (sats for BTC). price is quote per base.
 export interface DcaExecution {
   id: string
   timestamp: number
-  fromAmountUsdt: number
-  toAmountSats: number
-  priceBtcUsdt: number
-  feeSats?: number
+  fromAmount: number
+  toAmount: number
+  price: number
+  fee?: number
   status: 'success' | 'failed'
   error?: string
 }
@@ -21,21 +101,87 @@ export interface DcaOrder {
   id: string
   type: DcaOrderType
   status: DcaOrderStatus
-  amountUsdt: number
+  pair?: DcaAssetPair
+  side?: DcaSide
+  // Amount spent per execution, in the spent asset (USDT for default buys)
+  amount: number
   createdAt: number
   executions: DcaExecution[]
 
+  // Spend limits: lifetime total in the spent asset, and end date (ms)
+  budget?: number
+  endAt?: number
+  statusReason?: DcaStatusReason
+
   // Scheduled
   intervalHours?: DcaIntervalHours
+  schedule?: DcaSchedule
+  catchUp?: DcaCatchUpPolicy
   lastExecutedAt?: number
   nextExecutionAt?: number
 
-  // Price-target
-  creationPriceBtcUsdt?: number
+  // Value averaging / dip ladder / trailing entry
+  strategy?: DcaStrategy
+
+  // Price-target; prices are quote per base
+  creationPrice?: number
   targetDropPercent?: number
+  triggerPrice?: number
+}
+
+// Field names of orders saved before pairs were configurable
+type LegacyDcaExecution = Omit<
+  DcaExecution,
+  'fromAmount' | 'price' | 'toAmount'
+> &
+  Partial<Pick<DcaExecution, 'fromAmount' | 'price' | 'toAmount'>> & {
+    feeSats?: number
+    fromAmountUsdt?: number
+    priceBtcUsdt?: number
+    toAmountSats?: number
+  }
+
+type LegacyDcaOrder = Omit<DcaOrder, 'amount' | 'executions'> & {
+  amount?: number
+  amountUsdt?: number
+  creationPriceBtcUsdt?: number
+  executions?: LegacyDcaExecution[]
   triggerPriceBtcUsdt?: number
 }
 
+// A stored order (DB payload or legacy localStorage) under the current names
+export function normalizeOrder(stored: LegacyDcaOrder): DcaOrder {
+  const {
+    amountUsdt,
+    creationPriceBtcUsdt,
+    triggerPriceBtcUsdt,
+    executions,
+    ...order
+  } = stored
+  return {
+    ...order,
+    amount: order.amount ?? amountUsdt ?? 0,
+    creationPrice: order.creationPrice ?? creationPriceBtcUsdt,
+    executions: (executions ?? []).map(
+      ({ feeSats, fromAmountUsdt, priceBtcUsdt, toAmountSats, ...e }) => ({
+        ...e,
+        fee: e.fee ?? feeSats,
+        fromAmount: e.fromAmount ?? fromAmountUsdt ?? 0,
+        price: e.price ?? priceBtcUsdt ?? 0,
+        toAmount: e.toAmount ?? toAmountSats ?? 0,
+      })
+    ),
+    triggerPrice: order.triggerPrice ?? triggerPriceBtcUsdt,
+  }
+}
+
+// Buys wait for a drop of `percent`, sells for a rise of the same size
+function priceTarget(price: number, percent: number, side?: DcaSide) {
+  return side === 'sell'
+    ? price * (1 + percent / 100)
+    : price * (1 - percent / 100)
+}
+
 interface DcaState {
   orders: DcaOrder[]
 }
@@ -55,30 +201,59 @@ export const dcaSlice = createSlice({
       }
     },
 
+    completeOrder(
+      state,
+      action: PayloadAction<
+        string | { orderId: string; reason: DcaStatusReason }
+      >
+    ) {
+      const { orderId, reason } =
+        typeof action.payload === 'string'
+          ? { orderId: action.payload, reason: undefined }
+          : action.payload
+      const order = state.orders.find((o) => o.id === orderId)
+      if (order) {
+        order.status = 'completed'
+        order.statusReason = reason
+      }
+    },
+
     createOrder(
       state,
       action: PayloadAction<{
         type: DcaOrderType
-        amountUsdt: number
+        amount: number
         intervalHours?: DcaIntervalHours
         targetDropPercent?: number
-        creationPriceBtcUsdt?: number
+        creationPrice?: number
+        pair?: DcaAssetPair
+        side?: DcaSide
+        budget?: number
+        endAt?: number
       }>
     ) {
       const {
         type,
-        amountUsdt,
+        amount,
         intervalHours,
         targetDropPercent,
-        creationPriceBtcUsdt,
+        creationPrice,
+        pair,
+        side,
+        budget,
+        endAt,
       } = action.payload
 
       const now = Date.now()
       const order: DcaOrder = {
-        amountUsdt,
+        amount,
+        budget,
         createdAt: now,
+        endAt,
         executions: [],
         id: crypto.randomUUID(),
+        pair,
+        side,
         status: 'active',
         type,
       }
@@ -89,15 +264,14 @@ export const dcaSlice = createSlice({
         order.nextExecutionAt = now + intervalHours * 3600 * 1000
       }
 
-      if (
-        type === 'price-target' &&
-        targetDropPercent &&
-        creationPriceBtcUsdt
-      ) {
+      if (type === 'price-target' && targetDropPercent && creationPrice) {
         order.targetDropPercent = targetDropPercent
-        order.creationPriceBtcUsdt = creationPriceBtcUsdt
-        order.triggerPriceBtcUsdt =
-          creationPriceBtcUsdt * (1 - targetDropPercent / 100)
+        order.creationPrice = creationPrice
+        order.triggerPrice = priceTarget(
+          creationPrice,
+          targetDropPercent,
+          order.side
+        )
       }
 
       state.orders.unshift(order)
@@ -107,10 +281,33 @@ export const dcaSlice = createSlice({
       state.orders = state.orders.filter((o) => o.id !== action.payload)
     },
 
-    pauseOrder(state, action: PayloadAction<string>) {
-      const order = state.orders.find((o) => o.id === action.payload)
+    fillLadderStep(
+      state,
+      action: PayloadAction<{ orderId: string; step: number }>
+    ) {
+      const order = state.orders.find((o) => o.id === action.payload.orderId)
+      if (order?.strategy?.kind !== 'dip-ladder') return
+      const step = order.strategy.steps[action.payload.step]
+      if (step) step.filled = true
+      if (order.strategy.steps.every((s) => s.filled)) {
+        order.status = 'completed'
+      }
+    },
+
+    pauseOrder(
+      state,
+      action: PayloadAction<
+        string | { orderId: string; reason: DcaStatusReason }
+      >
+    ) {
+      const { orderId, reason } =
+        typeof action.payload === 'string'
+          ? { orderId: action.payload, reason: undefined }
+          : action.payload
+      const order = state.orders.find((o) => o.id === orderId)
       if (order && order.status === 'active') {
         order.status = 'paused'
+        order.statusReason = reason
       }
     },
 
@@ -118,20 +315,20 @@ export const dcaSlice = createSlice({
       state,
       action: PayloadAction<{
         orderId: string
-        fromAmountUsdt: number
-        toAmountSats: number
-        priceBtcUsdt: number
-        feeSats?: number
+        fromAmount: number
+        toAmount: number
+        price: number
+        fee?: number
         status: 'success' | 'failed'
         error?: string
       }>
     ) {
       const {
         orderId,
-        fromAmountUsdt,
-        toAmountSats,
-        priceBtcUsdt,
-        feeSats,
+        fromAmount,
+        toAmount,
+        price,
+        fee,
         status,
         error,
       } = action.payload
@@ -140,13 +337,13 @@ export const dcaSlice = createSlice({
 
       const execution: DcaExecution = {
         error,
-        feeSats,
-        fromAmountUsdt,
+        fee,
+        fromAmount,
         id: crypto.randomUUID(),
-        priceBtcUsdt,
+        price,
         status,
         timestamp: Date.now(),
-        toAmountSats,
+        toAmount,
       }
       order.executions.push(execution)
 
@@ -164,6 +361,7 @@ export const dcaSlice = createSlice({
       const order = state.orders.find((o) => o.id === action.payload)
       if (order && order.status === 'paused') {
         order.status = 'active'
+        order.statusReason = undefined
         if (order.type === 'scheduled' && order.intervalHours) {
           const now = Date.now()
           order.lastExecutedAt = now
@@ -176,6 +374,17 @@ export const dcaSlice = createSlice({
       state.orders = action.payload
     },
 
+    // The low (high for sells) a trailing-entry order tracks, as stored by
+    // the Rust scheduler
+    setTrailingExtreme(
+      state,
+      action: PayloadAction<{ orderId: string; extremePrice: number | null }>
+    ) {
+      const order = state.orders.find((o) => o.id === action.payload.orderId)
+      if (order?.strategy?.kind !== 'trailing-entry') return
+      order.strategy.extreme_price = action.payload.extremePrice
+    },
+
     updateAfterExecution(
       state,
       action: PayloadAction<{ orderId: string; newCreationPrice: number }>
@@ -183,9 +392,12 @@ export const dcaSlice = createSlice({
       const { orderId, newCreationPrice } = action.payload
       const order = state.orders.find((o) => o.id === orderId)
       if (order && order.type === 'price-target' && order.targetDropPercent) {
-        order.creationPriceBtcUsdt = newCreationPrice
-        order.triggerPriceBtcUsdt =
-          newCreationPrice * (1 - order.targetDropPercent / 100)
+        order.creationPrice = newCreationPrice
+        order.triggerPrice = priceTarget(
+          newCreationPrice,
+          order.targetDropPercent,
+          order.side
+        )
       }
     },
   },
@@ -193,12 +405,15 @@ export const dcaSlice = createSlice({
 
 export const {
   cancelOrder,
+  completeOrder,
   createOrder,
   deleteOrder,
+  fillLadderStep,
   pauseOrder,
   recordExecution,
   resumeOrder,
   setOrders,
+  setTrailingExtreme,
   updateAfterExecution,
 } = dcaSlice.actions
 export const dcaReducer = dcaSlice.reducer
@@ -1,9 +1,15 @@
 import { createSlice, PayloadAction } from '@reduxjs/toolkit'
 
 export type LimitOrderSide = 'buy' | 'sell'
+// stop-loss fires when the price moves against the order (a sell below the
+// stop), take-profit when it moves in its favour, like a limit
+export type LimitOrderType = 'limit' | 'stop-loss' | 'take-profit'
 export type LimitOrderStatus =
   | 'active'
   | 'paused'
+  // A swap was handed to the maker but may not have gone through; the engine
+  // holds the order until the node reports how it ended
+  | 'pending-confirmation'
   | 'filled'
   | 'expired'
   | 'cancelled'
@@ -21,8 +27,20 @@ export interface LimitOrderExecution {
   error?: string
 }
 
+// The swap of a 'pending-confirmation' order, recorded as a fill if the node
+// reports it succeeded
+export interface LimitOrderPendingSwap {
+  swapId: string
+  fromAmount: number
+  toAmount: number
+  executionPrice: number
+  precisions: [number, number]
+  startedAt: number
+}
+
 export interface LimitOrder {
   id: string
+  orderType?: LimitOrderType
   side: LimitOrderSide
   status: LimitOrderStatus
   createdAt: number
@@ -42,9 +60,29 @@ export interface LimitOrder {
   // Expiration
   expiresAt?: number
 
+  // Partial fills: the order may fill across several swaps sized to the
+  // channels' capacity. Filled and received amounts are raw units of the
+  // spent and received asset; averagePrice is weighted by the base asset.
+  allowPartial?: boolean
+  filledRaw?: number
+  receivedRaw?: number
+  averagePrice?: number
+
+  // One-cancels-other: the sibling leg, and on a cancelled leg the order
+  // whose fill cancelled it
+  linkedOrderId?: string
+  cancelledBy?: string
+
   // Execution tracking
   executions: LimitOrderExecution[]
   filledAt?: number
+
+  // Failed attempts in a row and the last error; the engine backs off
+  // between attempts and pauses the order, with a reason, after too many
+  failures?: number
+  lastError?: string
+  pauseReason?: string
+  pendingSwap?: LimitOrderPendingSwap
 }
 
 interface LimitOrderState {
@@ -55,6 +93,33 @@ const initialState: LimitOrderState = {
   orders: [],
 }
 
+interface NewLimitOrder {
+  side: LimitOrderSide
+  pairId: string
+  baseAssetTicker: string
+  baseAssetId: string
+  quoteAssetTicker: string
+  quoteAssetId: string
+  amount: number
+  amountRaw: number
+  expiresAt?: number
+  allowPartial?: boolean
+}
+
+const newOrder = (
+  fields: NewLimitOrder,
+  limitPrice: number,
+  orderType: LimitOrderType
+): LimitOrder => ({
+  ...fields,
+  createdAt: Date.now(),
+  executions: [],
+  id: crypto.randomUUID(),
+  limitPrice,
+  orderType,
+  status: 'active',
+})
+
 export const limitOrderSlice = createSlice({
   initialState,
   name: 'limitOrders',
@@ -63,55 +128,41 @@ export const limitOrderSlice = createSlice({
       const order = state.orders.find((o) => o.id === action.payload)
       if (order && (order.status === 'active' || order.status === 'paused')) {
         order.status = 'cancelled'
+        // Cancelling one leg of an OCO pair cancels the other
+        const sibling = state.orders.find((o) => o.id === order.linkedOrderId)
+        if (
+          sibling &&
+          (sibling.status === 'active' || sibling.status === 'paused')
+        ) {
+          sibling.status = 'cancelled'
+        }
       }
     },
 
     createLimitOrder(
       state,
-      action: PayloadAction<{
-        side: LimitOrderSide
-        pairId: string
-        baseAssetTicker: string
-        baseAssetId: string
-        quoteAssetTicker: string
-        quoteAssetId: string
-        limitPrice: number
-        amount: number
-        amountRaw: number
-        expiresAt?: number
-      }>
+      action: PayloadAction<
+        NewLimitOrder & { limitPrice: number; orderType?: LimitOrderType }
+      >
     ) {
-      const {
-        side,
-        pairId,
-        baseAssetTicker,
-        baseAssetId,
-        quoteAssetTicker,
-        quoteAssetId,
-        limitPrice,
-        amount,
-        amountRaw,
-        expiresAt,
-      } = action.payload
-
-      const order: LimitOrder = {
-        amount,
-        amountRaw,
-        baseAssetId,
-        baseAssetTicker,
-        createdAt: Date.now(),
-        executions: [],
-        expiresAt,
-        id: crypto.randomUUID(),
-        limitPrice,
-        pairId,
-        quoteAssetId,
-        quoteAssetTicker,
-        side,
-        status: 'active',
-      }
+      const { limitPrice, orderType, ...fields } = action.payload
+      state.orders.unshift(newOrder(fields, limitPrice, orderType ?? 'limit'))
+    },
 
-      state.orders.unshift(order)
+    // A take-profit and a stop-loss on the same amount; the engine cancels
+    // one when the other fills
+    createOcoOrder(
+      state,
+      action: PayloadAction<
+        NewLimitOrder & { takeProfitPrice: number; stopPrice: number }
+      >
+    ) {
+      const { takeProfitPrice, stopPrice, ...fields } = action.payload
+      const takeProfit = newOrder(fields, takeProfitPrice, 'take-profit')
+      const stopLoss = newOrder(fields, stopPrice, 'stop-loss')
+      takeProfit.linkedOrderId = stopLoss.id
+      stopLoss.linkedOrderId = takeProfit.id
+      state.orders.unshift(takeProfit, stopLoss)
     },
 
     deleteLimitOrder(state, action: PayloadAction<string>) {
@@ -184,23 +235,35 @@ export const limitOrderSlice = createSlice({
       const order = state.orders.find((o) => o.id === action.payload)
       if (order && order.status === 'paused') {
         order.status = 'active'
+        order.failures = 0
+        delete order.pauseReason
       }
     },
 
     setLimitOrders(state, action: PayloadAction<LimitOrder[]>) {
       state.orders = action.payload
     },
+
+    // Replace an order with the copy the backend engine just stored
+    updateLimitOrder(state, action: PayloadAction<LimitOrder>) {
+      const index = state.orders.findIndex((o) => o.id === action.payload.id)
+      if (index !== -1) {
+        state.orders[index] = action.payload
+      }
+    },
   },
 })
 
 export const {
   cancelLimitOrder,
   createLimitOrder,
+  createOcoOrder,
   deleteLimitOrder,
   expireLimitOrder,
   pauseLimitOrder,
   recordLimitExecution,
   resumeLimitOrder,
   setLimitOrders,
+  updateLimitOrder,
 } = limitOrderSlice.actions
 export const limitOrderReducer = limitOrderSlice.reducer
@@ -30,3 +30,52 @@ export const persistChannelOrder = async ({
     status: order?.order_state || 'paid',
   })
 }
+
+/** A channel order as stored by the backend (`db::ChannelOrder`). */
+export interface StoredChannelOrder {
+  id: number
+  account_id: number
+  order_id: string
+  created_at: string
+  status: string
+  payload: string
+  lsp_url: string | null
+  access_token: string | null
+  client_balance_sat: number | null
+  lsp_balance_sat: number | null
+  asset_id: string | null
+  client_asset_amount: number | null
+  lsp_asset_amount: number | null
+  payment_state: string | null
+  fee_total_sat: number | null
+  order_total_sat: number | null
+  expires_at: string | null
+  channel_id: string | null
+  updated_at: number | null
+}
+
+/** New state of a stored order; omitted fields keep their stored value. */
+export interface ChannelOrderUpdate {
+  status: string
+  payment_state?: string | null
+  fee_total_sat?: number | null
+  order_total_sat?: number | null
+  expires_at?: string | null
+  channel_id?: string | null
+}
+
+export const updateChannelOrderStatus = (
+  orderId: string,
+  update: ChannelOrderUpdate
+): Promise<StoredChannelOrder> =>
+  invoke<StoredChannelOrder>('update_channel_order_status', {
+    orderId,
+    update,
+  })
+
+const UNPAID_PAYMENT_STATES = ['EXPECT_PAYMENT', 'TIMEOUT', 'EXPIRED']
+
+/** Orders that failed or expired without ever being paid. */
+export const isUnpaidClosedOrder = (order: StoredChannelOrder): boolean =>
+  ['EXPIRED', 'FAILED'].includes(order.status.toUpperCase()) &&
+  UNPAID_PAYMENT_STATES.includes((order.payment_state ?? '').toUpperCase())
@@ -0,0 +1,150 @@
+import { invoke } from '@tauri-apps/api/core'
+
+export type TradeSource = 'manual' | 'dca' | 'limit'
+export type TradeStatus = 'pending' | 'succeeded' | 'failed' | 'expired'
+
+/**
+ * One swap in the backend trade ledger (`db::Trade`). Amounts are in the
+ * asset's display unit (BTC, not sats); `price` is quote per base of `pair`.
+ * Timestamps are unix seconds.
+ */
+export interface Trade {
+  id: number
+  account_id: number
+  source: TradeSource
+  order_id: string | null
+  pair: string
+  side: 'buy' | 'sell'
+  from_asset: string
+  from_amount: number
+  to_asset: string
+  to_amount: number
+  price: number
+  fee: number
+  fee_asset: string | null
+  maker_url: string
+  swap_id: string | null
+  status: TradeStatus
+  error: string | null
+  created_at: number
+  updated_at: number
+}
+
+export interface TradeFilter {
+  source?: TradeSource
+  pair?: string
+  status?: TradeStatus
+  from?: number
+  to?: number
+  limit?: number
+  offset?: number
+}
+
+export interface TradeAssetTotal {
+  asset: string
+  sent: number
+  received: number
+  fees: number
+  trades: number
+}
+
+export interface NewTrade {
+  source: TradeSource
+  orderId?: string
+  /** Base and quote tickers of the pair */
+  base: string
+  quote: string
+  fromAsset: string
+  fromAmount: number
+  toAsset: string
+  toAmount: number
+  fee?: number
+  feeAsset?: string
+  /** Empty for the account's default maker */
+  makerUrl?: string
+  swapId: string
+}
+
+/**
+ * Ledger record of a swap that was just executed. The price is derived from
+ * the amounts, and the trade stays pending until the node settles the swap.
+ */
+export const buildTrade = (trade: NewTrade): Trade => {
+  const side = trade.fromAsset === trade.base ? 'sell' : 'buy'
+  const baseAmount = side === 'sell' ? trade.fromAmount : trade.toAmount
+  const quoteAmount = side === 'sell' ? trade.toAmount : trade.fromAmount
+  const now = Math.floor(Date.now() / 1000)
+  return {
+    account_id: 0,
+    created_at: now,
+    error: null,
+    fee: trade.fee ?? 0,
+    fee_asset: trade.feeAsset ?? null,
+    from_amount: trade.fromAmount,
+    from_asset: trade.fromAsset,
+    id: 0,
+    maker_url: trade.makerUrl ?? '',
+    order_id: trade.orderId ?? null,
+    pair: `${trade.base}/${trade.quote}`,
+    price: baseAmount > 0 ? quoteAmount / baseAmount : 0,
+    side,
+    source: trade.source,
+    status: 'pending',
+    swap_id: trade.swapId,
+    to_amount: trade.toAmount,
+    to_asset: trade.toAsset,
+    updated_at: now,
+  }
+}
+
+/** Record a manual swap; DCA and limit orders record their own. */
+export const recordTrade = (trade: NewTrade): Promise<number> =>
+  invoke<number>('trades_record', { trade: buildTrade(trade) })
+
+/**
+ * Maker fee of a quote in display units. BTC fees are quoted in msat; other
+ * assets only when the quote gives their precision.
+ */
+export const quoteFee = (
+  fee:
+    | { final_fee: number; fee_asset?: string; fee_asset_precision?: number }
+    | undefined
+): { fee: number; feeAsset: string } | undefined => {
+  if (!fee || !fee.final_fee) return undefined
+  if (!fee.fee_asset || fee.fee_asset.toUpperCase() === 'BTC') {
+    return { fee: fee.final_fee / 1000 / 1e8, feeAsset: 'BTC' }
+  }
+  if (fee.fee_asset_precision == null) return undefined
+  return {
+    fee: fee.final_fee / Math.pow(10, fee.fee_asset_precision),
+    feeAsset: fee.fee_asset,
+  }
+}
+
+/** Ledger status of a node swap status; undefined while still in flight. */
+export const settledTradeStatus = (
+  swapStatus: string | undefined
+): TradeStatus | undefined => {
+  switch (swapStatus) {
+    case 'Succeeded':
+      return 'succeeded'
+    case 'Failed':
+      return 'failed'
+    case 'Expired':
+      return 'expired'
+    default:
+      return undefined
+  }
+}
+
+export const listTrades = (filter: TradeFilter = {}): Promise<Trade[]> =>
+  invoke<Trade[]>('trades_list', { filter })
+
+export const getTradeTotals = (
+  from?: number,
+  to?: number
+): Promise<TradeAssetTotal[]> =>
+  invoke<TradeAssetTotal[]>('trades_totals', {
+    from: from ?? null,
+    to: to ?? null,
+  })